@@ -0,0 +1,76 @@
+"""A gym environment controlling one cell of a running evonomics sim.
+
+Start the sim with `--control 127.0.0.1:7878` and run it, then:
+
+    env = EvonomicsEnv()
+    observation = env.reset()
+    observation, reward, done, info = env.step(env.action_space.sample())
+
+An observation holds five inputs for each neighbor and the food and money of the cell, then
+the food and money each neighbor offers while direct exchange is enabled, the age and
+generation while life inputs are enabled, and the fill of the last order while fill inputs are
+enabled, so from 22 to 32 inputs. `observation_space` is only known once `reset` returns.
+"""
+import json
+import socket
+
+import gym
+import numpy as np
+from gym import spaces
+
+DIRECTIONS = ["right", "up", "left", "down"]
+# Two fixed orders give the agent a way to reach the market with a discrete action space.
+ACTIONS = (
+    [{"type": "nothing"}]
+    + [{"type": "move", "dir": d} for d in DIRECTIONS]
+    + [{"type": "divide", "dir": d} for d in DIRECTIONS]
+    + [{"type": "trade", "rate": 2, "food": -1}, {"type": "trade", "rate": 1, "food": 1}]
+)
+
+
+class EvonomicsEnv(gym.Env):
+    action_space = spaces.Discrete(len(ACTIONS))
+
+    def __init__(self, host="127.0.0.1", port=7878):
+        self.socket = socket.create_connection((host, port))
+        self.lines = self.socket.makefile("r")
+        # How many inputs a cell sees depends on the rules of the world, so the observations
+        # are sized by the first one the sim sends.
+        self.observation_space = None
+
+    def _send(self, message):
+        self.socket.sendall((json.dumps(message) + "\n").encode())
+
+    def _receive(self):
+        """Waits for the next observation, or None if the cell was released."""
+        for line in self.lines:
+            report = json.loads(line)
+            if report["type"] == "observation":
+                return report
+            if report["type"] == "released":
+                return None
+            if report["type"] == "error":
+                raise RuntimeError(report["message"])
+        raise ConnectionError("the sim closed the connection")
+
+    def reset(self):
+        self._send({"type": "possess_any"})
+        report = self._receive()
+        if report is None:
+            raise RuntimeError("the agent died before its first decision")
+        inputs = np.array(report["inputs"])
+        self.observation_space = spaces.Box(
+            -np.inf, np.inf, shape=inputs.shape, dtype=np.float64
+        )
+        return inputs
+
+    def step(self, action):
+        self._send(ACTIONS[action])
+        report = self._receive()
+        if report is None:
+            return np.zeros(self.observation_space.shape), 0.0, True, {}
+        info = {"tick": report["tick"], "food": report["food"], "money": report["money"]}
+        return np.array(report["inputs"]), 1.0, False, info
+
+    def close(self):
+        self.socket.close()
@@ -0,0 +1,173 @@
+use crate::sim::{Arrivals, CellType, Comparison, Filter, Flips, Maze, Move, Sim};
+use gridsim::{moore::MooreNeighbors, Neighborhood};
+use rayon::prelude::*;
+use std::time::{Duration, Instant};
+
+/// The largest world the main menu can make.
+const WIDTH: usize = 4096;
+const HEIGHT: usize = 2560;
+/// Times each pass is repeated to average out noise.
+const RUNS: u32 = 10;
+
+/// The mean time of a pass along with its result.
+fn time<T>(mut pass: impl FnMut() -> T) -> (Duration, T) {
+    let start = Instant::now();
+    let mut result = pass();
+    for _ in 1..RUNS {
+        result = pass();
+    }
+    (start.elapsed() / RUNS, result)
+}
+
+fn report(name: &str, scan: Duration, masked: Duration) {
+    println!(
+        "{}: scan {:?}, masks {:?} ({:.1}x)",
+        name,
+        scan,
+        masked,
+        scan.as_secs_f64() / masked.as_secs_f64()
+    );
+}
+
+/// Prints how long passes over the world take by scanning every cell and by using the masks.
+pub fn masks(warmup: usize) {
+    let mut sim = Sim::new(WIDTH, HEIGHT, Maze::new(5), 0.005, None);
+    for _ in 0..warmup {
+        sim = sim.tick();
+    }
+    let cells = WIDTH * HEIGHT;
+    println!(
+        "{}x{} after {} ticks with {} cells",
+        WIDTH,
+        HEIGHT,
+        warmup,
+        sim.masks().occupied.count()
+    );
+
+    let (scan, scanned) = time(|| {
+        (0..cells)
+            .filter(|&index| sim.cell(index).brain.is_some())
+            .count()
+    });
+    let (masked, counted) = time(|| sim.masks().occupied.count());
+    assert_eq!(scanned, counted);
+    report("count cells", scan, masked);
+
+    let (scan, scanned) = time(|| {
+        (0..cells)
+            .filter(|&index| sim.cell(index).ty == CellType::Wall)
+            .map(|index| sim.cell(index).money as u64)
+            .sum::<u64>()
+    });
+    let (masked, summed) = time(|| {
+        sim.masks()
+            .walls
+            .ones()
+            .map(|index| sim.cell(index).money as u64)
+            .sum::<u64>()
+    });
+    assert_eq!(scanned, summed);
+    report("money on walls", scan, masked);
+
+    let filter = Filter::Generation(Comparison::Greater, 0);
+    let (scan, scanned) = time(|| {
+        (0..cells)
+            .into_par_iter()
+            .filter(|&index| filter.matches(sim.cell(index)))
+            .count()
+    });
+    let (masked, matched) = time(|| {
+        sim.masks()
+            .occupied
+            .par_count_where(|index| filter.matches(sim.cell(index)))
+    });
+    assert_eq!(scanned, matched);
+    report("filter matches", scan, masked);
+
+    // Every living brain moves onto the tile to its right if nothing is there, as though all of
+    // them moved at once, which changes two tiles for each brain which moved.
+    let occupied = &sim.masks().occupied;
+    let moved: Vec<usize> = occupied
+        .ones()
+        .filter(|&index| {
+            let right = index + 1;
+            right % WIDTH != 0
+                && sim.cell(right).ty != CellType::Wall
+                && sim.cell(right).brain.is_none()
+        })
+        .collect();
+    let flips = Flips::new(cells);
+    let time_update = |flip: bool| {
+        let mut elapsed = Duration::default();
+        let mut population = 0;
+        for _ in 0..RUNS {
+            let mut masks = sim.masks().clone();
+            if flip {
+                for &index in &moved {
+                    flips.flip(index);
+                    flips.flip(index + 1);
+                }
+            }
+            let start = Instant::now();
+            if flip {
+                masks.flip_occupied(&flips);
+            } else {
+                masks.rescan_occupied(sim.cells());
+            }
+            elapsed += start.elapsed();
+            population = masks.occupied.count();
+        }
+        (elapsed / RUNS, population)
+    };
+    let (scan, rescanned) = time_update(false);
+    let (masked, flipped) = time_update(true);
+    assert_eq!(rescanned, flipped);
+    report("update occupied", scan, masked);
+}
+
+/// Prints how long gathering the moves onto every tile a brain moves onto takes when the moves
+/// are cloned for each thing gathered and when they are consumed in one pass.
+pub fn update(warmup: usize) {
+    let mut sim = Sim::new(WIDTH, HEIGHT, Maze::new(5), 0.005, None);
+    for _ in 0..warmup {
+        sim = sim.tick();
+    }
+    // Every living brain moves onto a tile from one side, as though all of them moved at once.
+    let neighborhoods: Vec<MooreNeighbors<Move>> = sim
+        .masks()
+        .occupied
+        .ones()
+        .map(|index| {
+            let mut brain = sim.cell(index).brain.clone();
+            MooreNeighbors::new(|_| brain.take().map(Move::carrying).unwrap_or_default())
+        })
+        .collect();
+    println!(
+        "{}x{} after {} ticks with {} cells",
+        WIDTH,
+        HEIGHT,
+        warmup,
+        neighborhoods.len()
+    );
+
+    // The moves are used up by each pass, so every run gets a fresh copy made outside the timing.
+    let time_gather = |gather: fn(MooreNeighbors<Move>) -> Arrivals| {
+        let mut elapsed = Duration::default();
+        for _ in 0..RUNS {
+            let moves = neighborhoods.clone();
+            let start = Instant::now();
+            let arrivals: Vec<Arrivals> = moves.into_iter().map(gather).collect();
+            elapsed += start.elapsed();
+            drop(arrivals);
+        }
+        elapsed / RUNS
+    };
+    let cloning = time_gather(Arrivals::gather_by_cloning);
+    let consuming = time_gather(Arrivals::gather);
+    println!(
+        "gather moves: cloning {:?}, consuming {:?} ({:.1}x)",
+        cloning,
+        consuming,
+        cloning.as_secs_f64() / consuming.as_secs_f64()
+    );
+}
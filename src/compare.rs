@@ -0,0 +1,208 @@
+//! Comparing runs from their directories, for sweeps over parameters: charts of the population,
+//! the price and the diversity of genomes of every run over the same ticks, and a table of the
+//! parameters and scenario settings they were run with where those differ.
+
+use crate::database;
+use crate::io;
+use crate::plot;
+use crate::runs::RunDir;
+use crate::statfile::StatsFile;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The columns of the samples read from each run, with the parameters from `FIRST_PARAM` on.
+const COLUMNS: &[&str] = &[
+    "population",
+    "bid",
+    "ask",
+    "unique_genomes",
+    "spawn_chance",
+    "mutation_chance",
+    "general_food_chance",
+    "cornacopia_chance",
+    "cornacopia_bounty",
+];
+const FIRST_PARAM: usize = 4;
+/// The most samples read from a stats file, spread over the whole run, which is plenty for a
+/// chart however long the run was.
+const MAX_POINTS: usize = 10_000;
+
+struct Run {
+    name: String,
+    samples: Vec<(u64, Vec<Option<f64>>)>,
+    /// The settings of the scenario the run was started with, by their dotted keys.
+    scenario: BTreeMap<String, String>,
+}
+
+impl Run {
+    /// Reads a run from its directory, or from a stats file written by a headless run, which
+    /// has no scenario.
+    fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut scenario = BTreeMap::new();
+        let samples = if path.is_file() {
+            StatsFile::open(path)?.sample(COLUMNS, MAX_POINTS)?
+        } else {
+            let dir = RunDir::open(path.to_owned());
+            if let Some(path) = dir.scenario()? {
+                let value: toml::Value = toml::from_str(&fs::read_to_string(path)?)?;
+                flatten("", &value, &mut scenario);
+            }
+            database::read_samples(&dir.database(), COLUMNS)?
+        };
+        let name = path
+            .file_name()
+            .unwrap_or_else(|| path.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+        Ok(Self {
+            name,
+            samples,
+            scenario,
+        })
+    }
+
+    /// A stat of every sample it can be worked out for.
+    fn series(&self, stat: impl Fn(&[Option<f64>]) -> Option<f64>) -> Vec<(u64, f64)> {
+        self.samples
+            .iter()
+            .filter_map(|(tick, values)| Some((*tick, stat(values)?)))
+            .collect()
+    }
+
+    /// A parameter as it was at the start of the run, and as it ended if it was changed.
+    fn param(&self, column: usize) -> String {
+        let values: Vec<f64> = self
+            .samples
+            .iter()
+            .filter_map(|(_, values)| values[column])
+            .collect();
+        match (values.first(), values.last()) {
+            (Some(first), Some(last)) if first != last => format!("{} -> {}", first, last),
+            (Some(first), _) => first.to_string(),
+            _ => String::from("-"),
+        }
+    }
+}
+
+/// Settings of a scenario by their keys joined with dots, such as `tiers.small.chance`. Arrays
+/// are kept whole.
+fn flatten(prefix: &str, value: &toml::Value, settings: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, settings);
+            }
+        }
+        value => {
+            settings.insert(prefix.to_owned(), value.to_string());
+        }
+    }
+}
+
+/// Compares the runs in some directories, writing the charts and the table into `out` and
+/// printing the table.
+pub fn compare(out: &Path, dirs: &[PathBuf]) {
+    if let Err(e) = write_comparison(out, dirs) {
+        panic!("failed to compare runs into {}: {}", out.display(), e);
+    }
+}
+
+fn write_comparison(out: &Path, dirs: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    let mut runs = vec![];
+    for dir in dirs {
+        runs.push(Run::load(dir).map_err(|e| format!("{}: {}", dir.display(), e))?);
+    }
+    fs::create_dir_all(out)?;
+
+    // Every chart spans the ticks of the longest run, so that they line up.
+    let last_tick = runs
+        .iter()
+        .filter_map(|run| run.samples.last().map(|&(tick, _)| tick))
+        .max()
+        .unwrap_or(0);
+    let charts: [(&str, &str, fn(&[Option<f64>]) -> Option<f64>); 3] = [
+        ("population.png", "Population", |values| values[0]),
+        ("price.png", "Mid price", |values| {
+            match (values[1], values[2]) {
+                // No bid or no ask was recorded as 0.
+                (Some(bid), Some(ask)) if bid > 0.0 && ask > 0.0 => Some((bid + ask) / 2.0),
+                _ => None,
+            }
+        }),
+        ("diversity.png", "Unique genomes", |values| values[3]),
+    ];
+    for &(file, caption, stat) in &charts {
+        let series: Vec<(String, Vec<(u64, f64)>)> = runs
+            .iter()
+            .map(|run| (run.name.clone(), run.series(stat)))
+            .collect();
+        plot::save_comparison(&out.join(file), caption, last_tick, &series)?;
+    }
+
+    let mut rows = vec![];
+    for (column, &name) in COLUMNS.iter().enumerate().skip(FIRST_PARAM) {
+        rows.push(
+            std::iter::once(name.to_owned())
+                .chain(runs.iter().map(|run| run.param(column)))
+                .collect::<Vec<_>>(),
+        );
+    }
+    let keys: BTreeMap<&str, ()> = runs
+        .iter()
+        .flat_map(|run| run.scenario.keys().map(|key| (key.as_str(), ())))
+        .collect();
+    for &key in keys.keys() {
+        rows.push(
+            std::iter::once(key.to_owned())
+                .chain(runs.iter().map(|run| {
+                    run.scenario
+                        .get(key)
+                        .cloned()
+                        .unwrap_or_else(|| String::from("-"))
+                }))
+                .collect(),
+        );
+    }
+    // Only what differs between the runs is worth a row.
+    rows.retain(|row| row[2..].iter().any(|value| *value != row[1]));
+
+    let header: Vec<&str> = std::iter::once("parameter")
+        .chain(runs.iter().map(|run| run.name.as_str()))
+        .collect();
+    io::write_csv(&out.join("parameters.csv"), &header, rows.iter().cloned())?;
+    print_table(&header, &rows);
+    println!("compared {} runs into {}", runs.len(), out.display());
+    Ok(())
+}
+
+/// Prints rows under a header in columns padded to line up.
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|name| name.len()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.len());
+        }
+    }
+    let line = |values: Vec<&str>| {
+        let padded: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, &width)| format!("{:width$}", value, width = width))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(header.to_vec());
+    if rows.is_empty() {
+        println!("(the runs were started with the same parameters)");
+    }
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
@@ -0,0 +1,218 @@
+//! An SQLite database the stats are recorded into as they arrive, so that a long run can be
+//! queried with SQL afterwards.
+
+use rusqlite::{params, Connection, OpenFlags, ToSql, NO_PARAMS};
+use std::error::Error;
+use std::path::Path;
+
+/// The schema, one step per entry. A database records in `user_version` how many steps it has
+/// taken, and opening it takes the rest, so a database from an older build keeps working.
+///
+/// Steps are never changed once released, only added, and the columns of `samples` must match
+/// the header of the exported stats.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE samples (
+        tick INTEGER NOT NULL,
+        bid INTEGER,
+        ask INTEGER,
+        reserve INTEGER,
+        buy_volume INTEGER,
+        sell_volume INTEGER,
+        trades INTEGER,
+        reserve_purchases INTEGER,
+        reserve_sales INTEGER,
+        escheatment INTEGER,
+        mean_age INTEGER,
+        max_age INTEGER,
+        spawn_chance REAL,
+        mutation_chance REAL,
+        general_food_chance REAL,
+        cornacopia_chance REAL,
+        cornacopia_bounty INTEGER,
+        sanitized_memory INTEGER,
+        sanitized_signals INTEGER,
+        sanitized_trades INTEGER,
+        halted INTEGER
+    );
+    CREATE TABLE events (
+        tick INTEGER NOT NULL,
+        event TEXT NOT NULL
+    );
+    CREATE TABLE lineages (
+        tick INTEGER NOT NULL,
+        lineage INTEGER NOT NULL,
+        name TEXT,
+        living INTEGER NOT NULL
+    );
+    CREATE INDEX samples_tick ON samples (tick);
+    CREATE INDEX lineages_lineage ON lineages (lineage, tick);
+",
+    "
+    ALTER TABLE samples ADD COLUMN starved INTEGER;
+    ALTER TABLE samples ADD COLUMN died_in_walls INTEGER;
+    ALTER TABLE samples ADD COLUMN died_combining INTEGER;
+    ALTER TABLE samples ADD COLUMN hungry INTEGER;
+",
+    "
+    ALTER TABLE lineages ADD COLUMN ordered INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN expired_orders INTEGER;
+    ALTER TABLE samples ADD COLUMN partial_orders INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN separators REAL;
+",
+    "
+    ALTER TABLE samples ADD COLUMN demurrage INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN mean_genome_length REAL;
+    ALTER TABLE samples ADD COLUMN unreachable_codons REAL;
+    ALTER TABLE samples ADD COLUMN unexecuted_codons REAL;
+",
+    "
+    ALTER TABLE samples ADD COLUMN inheritance_tax INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN choice_move INTEGER;
+    ALTER TABLE samples ADD COLUMN choice_divide INTEGER;
+    ALTER TABLE samples ADD COLUMN choice_buy INTEGER;
+    ALTER TABLE samples ADD COLUMN choice_sell INTEGER;
+    ALTER TABLE samples ADD COLUMN choice_exchange INTEGER;
+    ALTER TABLE samples ADD COLUMN choice_nothing INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN approximated_ticks INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN population INTEGER;
+    ALTER TABLE samples ADD COLUMN unique_genomes INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN signal_food INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN spoiled_food INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN metabolism_food INTEGER;
+",
+    "
+    ALTER TABLE samples ADD COLUMN minted INTEGER;
+",
+];
+
+pub struct StatsDatabase {
+    connection: Connection,
+}
+
+impl StatsDatabase {
+    /// Opens the database at a path, creating it if needed and bringing its schema up to date.
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut connection = Connection::open(path)?;
+        // Rows arrive every frame, so only the write-ahead log is synced rather than every commit.
+        connection.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+        let applied: i64 =
+            connection.query_row("PRAGMA user_version", NO_PARAMS, |row| row.get(0))?;
+        if applied as usize > MIGRATIONS.len() {
+            return Err(format!(
+                "the database has schema version {}, newer than this build knows ({})",
+                applied,
+                MIGRATIONS.len()
+            )
+            .into());
+        }
+        let transaction = connection.transaction()?;
+        for migration in &MIGRATIONS[applied as usize..] {
+            transaction.execute_batch(migration)?;
+        }
+        transaction.execute_batch(&format!("PRAGMA user_version = {}", MIGRATIONS.len()))?;
+        transaction.commit()?;
+        Ok(Self { connection })
+    }
+
+    /// Records a sample of stats, with a value for each column of the header.
+    pub fn record_sample(
+        &self,
+        tick: u64,
+        header: &[&str],
+        values: &[String],
+    ) -> Result<(), Box<dyn Error>> {
+        let sql = format!(
+            "INSERT INTO samples (tick, {}) VALUES (?{})",
+            header.join(", "),
+            ", ?".repeat(header.len())
+        );
+        let tick = tick as i64;
+        let params: Vec<&dyn ToSql> = std::iter::once(&tick as &dyn ToSql)
+            .chain(values.iter().map(|value| value as &dyn ToSql))
+            .collect();
+        // The columns are numeric, so SQLite stores the text of each value as a number.
+        self.connection.prepare_cached(&sql)?.execute(params)?;
+        Ok(())
+    }
+
+    pub fn record_event(&self, tick: u64, event: &str) -> Result<(), Box<dyn Error>> {
+        self.connection
+            .prepare_cached("INSERT INTO events (tick, event) VALUES (?, ?)")?
+            .execute(params![tick as i64, event])?;
+        Ok(())
+    }
+
+    /// Records the size of each lineage in the legend, along with how many of its cells run their
+    /// entries in order.
+    pub fn record_lineages<'a>(
+        &mut self,
+        tick: u64,
+        lineages: impl IntoIterator<Item = (u64, Option<&'a str>, usize, usize)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut insert = transaction.prepare_cached(
+                "INSERT INTO lineages (tick, lineage, name, living, ordered) \
+                 VALUES (?, ?, ?, ?, ?)",
+            )?;
+            for (lineage, name, living, ordered) in lineages {
+                // SQLite integers are signed, so the id is stored by its bits.
+                insert.execute(params![
+                    tick as i64,
+                    lineage as i64,
+                    name,
+                    living as i64,
+                    ordered as i64
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// Reads some columns of every sample recorded in a database, by tick, without changing it. A
+/// column recorded before a build which knew it is missing from the samples of that time.
+pub fn read_samples(
+    path: &Path,
+    columns: &[&str],
+) -> Result<Vec<(u64, Vec<Option<f64>>)>, Box<dyn Error>> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut select = connection.prepare(&format!(
+        "SELECT tick, {} FROM samples ORDER BY tick",
+        columns.join(", ")
+    ))?;
+    let rows = select.query_map(NO_PARAMS, |row| {
+        let tick: i64 = row.get(0)?;
+        let mut values = Vec::with_capacity(columns.len());
+        for i in 1..=columns.len() {
+            let value: Option<f64> = row.get(i)?;
+            values.push(value);
+        }
+        Ok((tick as u64, values))
+    })?;
+    let mut samples = vec![];
+    for row in rows {
+        samples.push(row?);
+    }
+    Ok(samples)
+}
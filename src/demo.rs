@@ -0,0 +1,254 @@
+//! Demo scripts, which drive the window through a fixed sequence of actions for talks and
+//! reproducible walkthroughs.
+//!
+//! A script has one step per line, and `#` starts a comment:
+//!
+//! ```text
+//! scenario talk.toml   # apply a scenario file
+//! set openness 3       # set one scenario parameter
+//! start                # start a world from the main menu
+//! run
+//! wait 2000            # until 2000 more ticks have been sent to the sim
+//! look 256 160 1.5     # center on a column and row at a zoom
+//! view wealth          # species, age, wealth or starvation
+//! sleep 3              # seconds
+//! pause
+//! grid                 # toggle the grid lines
+//! menu                 # go back to the main menu
+//! say grid Hello       # show a callout by the grid, market or controls
+//! hold                 # until the viewer presses Continue on the callout
+//! until trade          # until the first spawn, trade or division of the world
+//! hush                 # take the callout down
+//! ```
+//!
+//! The tutorial offered on the main menu is a script like any other, built into the app.
+
+use crate::scenario::{self, Scenario};
+use crate::sim::ViewMode;
+use std::error::Error;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The script of the guided first run.
+const TUTORIAL: &str = include_str!("../demos/tutorial.demo");
+
+/// The part of the window a callout is shown by.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    Grid,
+    /// The charts of the stats panel, which is opened to show it.
+    Market,
+    /// The controls of the sim menu.
+    Controls,
+}
+
+/// Something happening for the first time in the world a demo is showing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Milestone {
+    Spawn,
+    Trade,
+    Division,
+}
+
+/// The milestones the running world has reached since it started.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Milestones {
+    pub spawn: bool,
+    pub trade: bool,
+    pub division: bool,
+}
+
+impl Milestones {
+    fn reached(&self, milestone: Milestone) -> bool {
+        match milestone {
+            Milestone::Spawn => self.spawn,
+            Milestone::Trade => self.trade,
+            Milestone::Division => self.division,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Step {
+    Scenario(Scenario),
+    Start,
+    Run,
+    Pause,
+    /// Center the grid on a column and row at a zoom.
+    Look(usize, usize, f32),
+    View(ViewMode),
+    Grid,
+    Menu,
+    /// Wait until this many more ticks were sent.
+    Wait(u64),
+    Sleep(Duration),
+    /// Show a callout by a part of the window, replacing any other.
+    Say(Target, String),
+    Hush,
+    /// Wait until the viewer presses Continue.
+    Hold,
+    /// Wait until the world first reaches a milestone.
+    Until(Milestone),
+}
+
+enum Waiting {
+    Ticks(u64),
+    Until(Instant),
+    Viewer,
+    Milestone(Milestone),
+}
+
+pub struct Demo {
+    steps: Vec<Step>,
+    next: usize,
+    waiting: Option<Waiting>,
+}
+
+impl Demo {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text, path.parent().unwrap_or_else(|| Path::new("")))
+    }
+
+    /// The guided first run.
+    pub fn tutorial() -> Self {
+        Self::parse(TUTORIAL, Path::new("")).expect("the tutorial script is broken")
+    }
+
+    /// Parses a script, finding the files it names relative to a directory.
+    fn parse(text: &str, dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let steps = text
+            .lines()
+            .enumerate()
+            .filter_map(|(number, line)| {
+                let line = line.split('#').next().unwrap().trim();
+                if line.is_empty() {
+                    return None;
+                }
+                Some(parse_step(line, dir).map_err(|e| format!("line {}: {}", number + 1, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            steps,
+            next: 0,
+            waiting: None,
+        })
+    }
+
+    /// The next step to take, if the last wait is over, given the ticks sent so far and the
+    /// milestones reached.
+    pub fn next(&mut self, ticks: u64, milestones: Milestones) -> Option<Step> {
+        loop {
+            match self.waiting {
+                Some(Waiting::Ticks(until)) if ticks < until => return None,
+                Some(Waiting::Until(until)) if Instant::now() < until => return None,
+                Some(Waiting::Viewer) => return None,
+                Some(Waiting::Milestone(milestone)) if !milestones.reached(milestone) => {
+                    return None
+                }
+                _ => self.waiting = None,
+            }
+            let step = self.steps.get(self.next)?.clone();
+            self.next += 1;
+            match step {
+                Step::Wait(more) => self.waiting = Some(Waiting::Ticks(ticks + more)),
+                Step::Sleep(duration) => {
+                    self.waiting = Some(Waiting::Until(Instant::now() + duration))
+                }
+                Step::Hold => self.waiting = Some(Waiting::Viewer),
+                Step::Until(milestone) => self.waiting = Some(Waiting::Milestone(milestone)),
+                step => return Some(step),
+            }
+        }
+    }
+
+    /// Whether the demo is waiting for the viewer to press Continue.
+    pub fn holding(&self) -> bool {
+        matches!(self.waiting, Some(Waiting::Viewer))
+    }
+
+    /// Goes on past a hold.
+    pub fn resume(&mut self) {
+        if self.holding() {
+            self.waiting = None;
+        }
+    }
+
+    /// Whether every step was taken.
+    pub fn finished(&self) -> bool {
+        self.waiting.is_none() && self.next == self.steps.len()
+    }
+}
+
+fn parse_step(line: &str, dir: &Path) -> Result<Step, Box<dyn Error>> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap();
+    let mut arg = |name: &str| {
+        words
+            .next()
+            .ok_or_else(|| format!("{} requires {}", command, name))
+    };
+    let step = match command {
+        // Relative to the script, so a demo can be run from anywhere.
+        "scenario" => Step::Scenario(Scenario::load(&dir.join(arg("a path")?))?),
+        "set" => {
+            let key = arg("a parameter")?;
+            let value = words.collect::<Vec<_>>().join(" ");
+            Step::Scenario(Scenario::parse(&format!(
+                "version = {}\n{} = {}",
+                scenario::VERSION,
+                key,
+                value
+            ))?)
+        }
+        "start" => Step::Start,
+        "run" => Step::Run,
+        "pause" => Step::Pause,
+        "look" => Step::Look(
+            arg("a column")?.parse()?,
+            arg("a row")?.parse()?,
+            arg("a zoom")?.parse()?,
+        ),
+        "view" => Step::View(match arg("a mode")? {
+            "species" => ViewMode::Species,
+            "age" => ViewMode::Age,
+            "wealth" => ViewMode::Wealth,
+            "starvation" => ViewMode::Starvation,
+            mode => return Err(format!("unknown view mode {}", mode).into()),
+        }),
+        "grid" => Step::Grid,
+        "menu" => Step::Menu,
+        "wait" => Step::Wait(arg("a number of ticks")?.parse()?),
+        "sleep" => {
+            let seconds: f64 = arg("a number of seconds")?.parse()?;
+            // A duration cannot be negative, infinite or NaN, and making one would panic.
+            if !(seconds.is_finite() && seconds >= 0.0) {
+                return Err("sleep requires a non-negative number of seconds".into());
+            }
+            Step::Sleep(Duration::from_secs_f64(seconds))
+        }
+        "say" => {
+            let target = match arg("a target")? {
+                "grid" => Target::Grid,
+                "market" => Target::Market,
+                "controls" => Target::Controls,
+                target => return Err(format!("unknown target {}", target).into()),
+            };
+            let text = words.collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                return Err("say requires some text".into());
+            }
+            Step::Say(target, text)
+        }
+        "hush" => Step::Hush,
+        "hold" => Step::Hold,
+        "until" => Step::Until(match arg("a milestone")? {
+            "spawn" => Milestone::Spawn,
+            "trade" => Milestone::Trade,
+            "division" => Milestone::Division,
+            milestone => return Err(format!("unknown milestone {}", milestone).into()),
+        }),
+        _ => return Err(format!("unknown step {}", command).into()),
+    };
+    Ok(step)
+}
@@ -1,12 +1,16 @@
+use crate::raster;
 use crate::sim;
+use crate::style;
 use float_ord::FloatOrd;
+use std::cell::Cell;
 use std::collections::VecDeque;
-use std::ops::RangeInclusive;
+use std::ops::{Range, RangeInclusive};
 use std::time::{Duration, Instant};
 
 use iced::{
     canvas::{self, Cache, Canvas, Cursor, Event, Frame, Geometry, Path},
-    mouse, Color, Element, Length, Point, Rectangle, Size, Vector,
+    mouse, Color, Element, HorizontalAlignment, Length, Point, Rectangle, Size, Vector,
+    VerticalAlignment,
 };
 
 const CELL_SIZE: usize = 20;
@@ -14,9 +18,37 @@ const MAX_SCALING: f32 = 2.0;
 
 const AVERAGING_COUNT: usize = 15;
 
+const ROOM_COLOR: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 0.7 };
+const REGION_COLOR: Color = Color { r: 1.0, g: 0.75, b: 0.0, a: 0.9 };
+
+/// Rooms smaller than this many tiles go unlabeled, so that scraps of open tiles walled off by
+/// the maze do not bury the grid in labels.
+const LABELED_ROOM_TILES: usize = 16;
+/// Below this scaling only regions are labeled, since rooms are too small to read them on.
+const ROOM_LABEL_SCALING: f32 = 0.5;
+/// From this scaling up the terrain is drawn with glyphs, which are too small to read below it.
+const GLYPH_SCALING: f32 = 1.5;
+/// Cells smaller than this many pixels to a side are drawn averaged together in square blocks at
+/// least this wide, so that drawing costs about as much as the pixels on screen however many
+/// cells the world has.
+const BLOCK_PIXELS: f32 = 2.0;
+/// The size of the text of the tooltip, and how far it sits from the cursor.
+const TOOLTIP_TEXT: f32 = 14.0;
+const TOOLTIP_OFFSET: f32 = 16.0;
+const TOOLTIP_COLOR: Color = Color { r: 0.1, g: 0.1, b: 0.12, a: 0.85 };
+
 #[derive(Debug)]
 pub enum Message {
     View(sim::View),
+    /// What changed since the last frame, applied to the one on the grid.
+    Delta(sim::ViewDelta),
+    /// The cell at this column and row was clicked.
+    Select(usize, usize),
+    /// The brush was pressed on or dragged to the cell at this column and row, starting a new
+    /// stroke when it was pressed.
+    Paint(usize, usize, bool),
+    /// The cursor came to rest over the cell at this column and row, or left the grid.
+    Hover(Option<(usize, usize)>),
 }
 
 impl From<sim::View> for Message {
@@ -25,6 +57,12 @@ impl From<sim::View> for Message {
     }
 }
 
+impl From<sim::ViewDelta> for Message {
+    fn from(delta: sim::ViewDelta) -> Self {
+        Self::Delta(delta)
+    }
+}
+
 pub struct Grid {
     width: usize,
     height: usize,
@@ -32,12 +70,34 @@ pub struct Grid {
     interaction: Interaction,
     life_cache: Cache,
     grid_cache: Cache,
+    floorplan_cache: Cache,
     translation: Vector,
     scaling: f32,
     show_lines: bool,
+    show_floorplan: bool,
+    /// The outlines of the rooms and regions, worked out once for each floorplan.
+    outlines: Outlines,
+    /// What each tile is in row-major order, as of the last floorplan.
+    tiles: Vec<sim::CellType>,
+    /// Cells outlined so they can be found again, as (column, row).
+    marked: Vec<(usize, usize)>,
+    /// Whether the left button paints with a brush instead of selecting cells.
+    brushing: bool,
     tick_durations: VecDeque<(Duration, usize)>,
     /// When a tick comes in, this is used to measure the elapsed time of the tick.
     tick_start: Instant,
+    /// The size the grid was last drawn at, to know which cells are on screen.
+    bounds: Cell<Size>,
+    /// The cell under the cursor while nothing is held down, as (column, row).
+    hovered: Option<(usize, usize)>,
+    /// What was last learned of a hovered cell, shown beside the cursor while it stays there.
+    tooltip: Option<Tooltip>,
+}
+
+struct Tooltip {
+    x: usize,
+    y: usize,
+    lines: Vec<String>,
 }
 
 impl Grid {
@@ -51,11 +111,20 @@ impl Grid {
             interaction: Interaction::None,
             life_cache: Cache::default(),
             grid_cache: Cache::default(),
+            floorplan_cache: Cache::default(),
             translation: Vector::new(initial_x, initial_y),
             scaling: 1.0,
             show_lines: false,
+            show_floorplan: false,
+            outlines: Outlines::default(),
+            tiles: vec![],
+            marked: vec![],
+            brushing: false,
             tick_durations: vec![].into(),
             tick_start: Instant::now(),
+            bounds: Cell::new(Size::new(0.0, 0.0)),
+            hovered: None,
+            tooltip: None,
         }
     }
 
@@ -72,16 +141,33 @@ impl Grid {
             Message::View(view) => {
                 // Replace our old view with this new view.
                 self.view = view;
-                let tick_duration = self.tick_start.elapsed();
-                self.tick_start = Instant::now();
-                self.tick_durations.push_front( (tick_duration, self.view.ticks) );
-                self.tick_durations.truncate(AVERAGING_COUNT);
-                self.life_cache.clear();
+                self.shown_frame();
             }
+            Message::Delta(delta) => {
+                self.view.apply(delta);
+                self.shown_frame();
+            }
+            Message::Hover(hovered) => self.hovered = hovered,
+            Message::Select(..) | Message::Paint(..) => {}
         }
     }
 
-    pub fn view<'a>(&'a mut self) -> Element<'a, ()> {
+    /// Redraws the cells once a frame came in, and counts its ticks toward the tick rate.
+    fn shown_frame(&mut self) {
+        // Views redrawn without ticking, such as for a new filter, say nothing of the tick rate.
+        if self.view.ticks > 0 {
+            let tick_duration = self.tick_start.elapsed();
+            self.tick_start = Instant::now();
+            self.tick_durations.push_front( (tick_duration, self.view.ticks) );
+            self.tick_durations.truncate(AVERAGING_COUNT);
+        }
+        self.life_cache.clear();
+    }
+
+    /// The frame on the grid.
+    pub fn shown(&self) -> &sim::View { &self.view }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
         Canvas::new(self)
             .width(Length::Fill)
             .height(Length::Fill)
@@ -94,6 +180,129 @@ impl Grid {
 
     pub fn is_showing_lines(&self) -> bool { self.show_lines }
 
+    pub fn set_lines(&mut self, show: bool) {
+        self.show_lines = show;
+        self.grid_cache.clear();
+    }
+
+    pub fn set_show_floorplan(&mut self, show: bool) {
+        self.show_floorplan = show;
+        self.floorplan_cache.clear();
+    }
+
+    /// Outlines the rooms and regions of a floorplan and marks its terrain up close from now on,
+    /// unless it is of a world of another size.
+    pub fn set_floorplan(&mut self, floorplan: &sim::Floorplan) {
+        if (floorplan.width, floorplan.height) != (self.width, self.height) {
+            return;
+        }
+        self.outlines = Outlines::new(floorplan);
+        self.tiles = floorplan.tiles.clone();
+        self.floorplan_cache.clear();
+        self.life_cache.clear();
+    }
+
+    /// Where the view is panned to, as a translation, and how far it is zoomed.
+    pub fn viewport(&self) -> (f32, f32, f32) { (self.translation.x, self.translation.y, self.scaling) }
+
+    /// Pans and zooms to a viewport kept from before.
+    pub fn set_viewport(&mut self, x: f32, y: f32, scaling: f32) {
+        self.translation = Vector::new(x, y);
+        self.scaling = scaling.min(MAX_SCALING);
+        self.life_cache.clear();
+        self.grid_cache.clear();
+        self.floorplan_cache.clear();
+    }
+
+    /// The columns and rows of the world shown.
+    pub fn dimensions(&self) -> (usize, usize) { (self.width, self.height) }
+
+    /// The cells on screen, or the whole world before the grid is first drawn.
+    pub fn visible_cells(&self) -> raster::Rect {
+        let bounds = self.bounds.get();
+        if bounds.width == 0.0 || bounds.height == 0.0 {
+            return raster::Rect { x: 0, y: 0, width: self.width, height: self.height };
+        }
+        let region = self.visible_region(bounds);
+        let (rows, columns) = (region.rows(), region.columns());
+        let (x, y) = ((*columns.start()).min(self.width), (*rows.start()).min(self.height));
+        raster::Rect {
+            x,
+            y,
+            width: (*columns.end() + 1).min(self.width) - x,
+            height: (*rows.end() + 1).min(self.height) - y,
+        }
+    }
+
+    pub fn marked(&self) -> &[(usize, usize)] { &self.marked }
+
+    pub fn set_marked(&mut self, marked: Vec<(usize, usize)>) {
+        self.marked = marked;
+    }
+
+    /// Centers the view on the cell at this column and row, zoomed so each cell is `scaling`
+    /// times its usual size.
+    pub fn look_at(&mut self, x: usize, y: usize, scaling: f32) {
+        self.translation = Vector::new(
+            -((x * CELL_SIZE + CELL_SIZE / 2) as f32),
+            -((y * CELL_SIZE + CELL_SIZE / 2) as f32),
+        );
+        self.scaling = scaling.min(MAX_SCALING);
+        self.life_cache.clear();
+        self.grid_cache.clear();
+        self.floorplan_cache.clear();
+    }
+
+    pub fn set_brushing(&mut self, brushing: bool) {
+        self.brushing = brushing;
+    }
+
+    /// The cell under the brush while it is held down, as (column, row).
+    pub fn painting(&self) -> Option<(usize, usize)> {
+        match self.interaction {
+            Interaction::Painting { x, y } => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    /// The cell under the cursor, as (column, row).
+    pub fn hovered(&self) -> Option<(usize, usize)> { self.hovered }
+
+    /// Shows what was learned of the cell at this column and row beside the cursor, below what
+    /// the tile is, for as long as it stays hovered.
+    pub fn set_tooltip(&mut self, x: usize, y: usize, lines: Vec<String>) {
+        self.tooltip = Some(Tooltip { x, y, lines });
+    }
+
+    /// What the tooltip says of the hovered cell: what the tile is, as of the last floorplan,
+    /// then whatever was last learned of the cell if it is still the one hovered.
+    fn tooltip_lines(&self) -> Vec<String> {
+        let (x, y) = match self.hovered {
+            Some(hovered) => hovered,
+            None => return vec![],
+        };
+        let mut lines = vec![format!("({}, {})", x, y)];
+        if let Some(&ty) = self.tiles.get(y * self.width + x) {
+            lines[0] = format!("{} at {}", tile_name(ty), lines[0]);
+        }
+        match self.tooltip {
+            Some(ref tooltip) if (tooltip.x, tooltip.y) == (x, y) => {
+                lines.extend(tooltip.lines.iter().cloned())
+            }
+            _ => {}
+        }
+        lines
+    }
+
+    fn cell_in_bounds(&self, point: Point) -> Option<(usize, usize)> {
+        let (x, y) = cell_at(point.x, point.y);
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
     // used for grid lines, determining when cells are visible, and accurately placing the mouse
     fn visible_region(&self, size: Size) -> Region {
         let width = size.width / self.scaling;
@@ -117,16 +326,21 @@ impl Grid {
     }
 }
 
-impl canvas::Program<()> for Grid {
-    fn update(&mut self, event: Event, bounds: Rectangle, cursor: Cursor) -> Option<()> {
+impl canvas::Program<Message> for Grid {
+    fn update(&mut self, event: Event, bounds: Rectangle, cursor: Cursor) -> Option<Message> {
         if let Event::Mouse(mouse::Event::ButtonReleased(_)) = event {
             self.interaction = Interaction::None;
         }
 
-        let cursor_position = cursor.position_in(&bounds)?;
+        let cursor_position = match cursor.position_in(&bounds) {
+            Some(position) => position,
+            None if self.hovered.is_some() => return Some(Message::Hover(None)),
+            None => return None,
+        };
         let min_scaling = bounds.width / ( self.width * CELL_SIZE ) as f32;
         if self.scaling < min_scaling { self.scaling = min_scaling; }
 
+        let translation = self.translation;
         let x_offset = -self.translation.x;
         let x_range_half = bounds.width/self.scaling/2.0;
         let right_border_correction = x_offset + x_range_half - (self.width*CELL_SIZE) as f32;
@@ -161,19 +375,26 @@ impl canvas::Program<()> for Grid {
                 }
             }
         }
+        // Only the cells on screen are drawn, so moving the view must draw them anew.
+        if self.translation != translation {
+            self.life_cache.clear();
+            self.grid_cache.clear();
+            self.floorplan_cache.clear();
+        }
 
         match event {
             Event::Mouse(mouse_event) => match mouse_event {
                 mouse::Event::ButtonPressed(button) => match button {
-                    // TODO <CELL INTERACTION>
-                    // mouse::Button::Left => {
-                    //     self.interaction = if is_populated {
-                    //         Interaction::Erasing
-                    //     } else {
-                    //         Interaction::Drawing
-                    //     };
-                    //     populate.or(unpopulate)
-                    // }
+                    mouse::Button::Left => {
+                        let point = self.project(cursor_position, bounds.size());
+                        let (x, y) = self.cell_in_bounds(point)?;
+                        if self.brushing {
+                            self.interaction = Interaction::Painting { x, y };
+                            Some(Message::Paint(x, y, true))
+                        } else {
+                            Some(Message::Select(x, y))
+                        }
+                    }
                     mouse::Button::Right => {
                         self.interaction = Interaction::Panning {
                             translation: self.translation,
@@ -195,10 +416,29 @@ impl canvas::Program<()> for Grid {
 
                             self.life_cache.clear();
                             self.grid_cache.clear();
+                            self.floorplan_cache.clear();
 
                             None
                         }
-                        _ => None,
+                        Interaction::Painting { x, y } => {
+                            let point = self.project(cursor_position, bounds.size());
+                            match self.cell_in_bounds(point) {
+                                Some(cell) if cell != (x, y) => {
+                                    self.interaction = Interaction::Painting { x: cell.0, y: cell.1 };
+                                    Some(Message::Paint(cell.0, cell.1, false))
+                                }
+                                _ => None,
+                            }
+                        }
+                        Interaction::None => {
+                            let point = self.project(cursor_position, bounds.size());
+                            let hovered = self.cell_in_bounds(point);
+                            if hovered != self.hovered {
+                                Some(Message::Hover(hovered))
+                            } else {
+                                None
+                            }
+                        }
                     }
                 }
                 mouse::Event::WheelScrolled { delta } => match delta {
@@ -224,6 +464,7 @@ impl canvas::Program<()> for Grid {
 
                             self.life_cache.clear();
                             self.grid_cache.clear();
+                            self.floorplan_cache.clear();
                         }
 
                         None
@@ -235,6 +476,7 @@ impl canvas::Program<()> for Grid {
     }
 
     fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+        self.bounds.set(bounds.size());
         let center = Vector::new(bounds.width / 2.0, bounds.height / 2.0);
 
         let life = self.life_cache.draw(bounds.size(), |frame| {
@@ -248,41 +490,52 @@ impl canvas::Program<()> for Grid {
                 frame.scale(CELL_SIZE as f32);
 
                 let region = self.visible_region(frame.size());
-
-                if self.scaling >= 1.5 {
-                    for ((y, x), &(color, ancestor_count)) in self.view.colors.indexed_iter() {
-                        if region.contained(x, y) {
+                let (rows, columns) = self.view.colors.dim();
+                let (ys, xs) = region.clamped(rows, columns);
+                let block = (BLOCK_PIXELS / (CELL_SIZE as f32 * self.scaling)).ceil().max(1.0) as usize;
+
+                if block > 1 {
+                    // Blocks line up with the world rather than the screen, so that they keep
+                    // their colors while panning.
+                    for y in (ys.start / block * block..ys.end).step_by(block) {
+                        for x in (xs.start / block * block..xs.end).step_by(block) {
+                            let (bottom, right) = ((y + block).min(rows), (x + block).min(columns));
+                            let color = mean_color(&self.view.colors, y..bottom, x..right);
+                            frame.fill_rectangle(Point::new(x as f32, y as f32), Size::new((right - x) as f32, (bottom - y) as f32), color);
+                        }
+                    }
+                }
+                else if let (true, sim::ViewColors::Full(colors)) = (self.scaling >= 1.5, &self.view.colors) {
+                    for y in ys {
+                        for x in xs.clone() {
+                            let (color, ancestor_count) = colors[(y, x)];
                             frame.fill_rectangle(Point::new(x as f32, y as f32), Size::UNIT, color);
                             // draw ancestry markings
-                            let mut marking: u32 = 0;
-                            let mut x_off = 0.0;
-                            let mut y_off = 0.0;
-                            let mut consumed = 0x0;
-                            
-                            while ancestor_count > consumed { // 0, F, FF, FFF, ...
-                                let c = ( ancestor_count & ( 7 * usize::pow(8, marking) ) ) / usize::pow(8, marking) as usize;
-                                let value = ((7.0-c as f32)/7.0) as f32;
-                                frame.fill_rectangle( Point::new(x as f32 + 0.075 + x_off, y as f32 + 0.075 + y_off) , Size::new(0.1,0.1), Color::from_rgb( color.r * value, color.g * value, color.b * value ) );
-
-                                let band = marking / 11;
-                                let dir = ( if band == 0 { marking / 3 } else if marking%11 == 0 { 0 } else { marking } )%4; // 0123 right, 4567 down, 89AB left, CDEF up, 10;11;12;13 right, ...
-                                match dir {
-                                    0 => { x_off += if marking % 3 == 1 || marking / 12 == 1 {0.25} else {0.2}; }
-                                    1 => { y_off += if marking % 3 == 1 || marking / 12 == 1 {0.25} else {0.2}; }
-                                    2 => { x_off -= if marking % 3 == 1 || marking / 12 == 1 {0.25} else {0.2}; }
-                                    3 => { y_off -= if marking % 3 == 1 || marking / 12 == 1 {0.25} else {0.2}; }
-                                    _ => { panic!("bad modification made to marking operations"); }
-                                }
-                                consumed += 8 * usize::pow(8, marking);
-                                marking += 1;
+                            for (x_off, y_off, value) in raster::markings(ancestor_count) {
+                                frame.fill_rectangle( Point::new(x as f32 + x_off, y as f32 + y_off) , Size::new(0.1,0.1), Color::from_rgb( color.r * value, color.g * value, color.b * value ) );
                             }
                         }
                     }
                 }
                 else {
-                    for ((y, x), &(color, _)) in self.view.colors.indexed_iter() {
-                        if region.contained(x, y) {
-                            frame.fill_rectangle(Point::new(x as f32, y as f32), Size::UNIT, color);
+                    // Compact views have no ancestry to mark even when zoomed in.
+                    for y in ys {
+                        for x in xs.clone() {
+                            frame.fill_rectangle(Point::new(x as f32, y as f32), Size::UNIT, self.view.colors.color(y, x));
+                        }
+                    }
+                }
+
+                // Up close, walls, portals and sources get glyphs of their own, so that the terrain
+                // reads apart from the cells on it.
+                if self.scaling >= GLYPH_SCALING && self.tiles.len() == self.width * self.height && self.view.colors.dim() == (self.height, self.width) {
+                    let terrain = style::skin().terrain();
+                    for y in region.rows().take_while(|&y| y < self.height) {
+                        for x in region.columns().take_while(|&x| x < self.width) {
+                            let ty = self.tiles[y * self.width + x];
+                            if ty != sim::CellType::Empty {
+                                draw_glyph(frame, Point::new(x as f32, y as f32), ty, self.view.colors.color(y, x), &terrain);
+                            }
                         }
                     }
                 }
@@ -297,6 +550,30 @@ impl canvas::Program<()> for Grid {
                 cell_at(point.x, point.y)
             });
 
+            frame.with_save(|frame| {
+                frame.translate(center);
+                frame.scale(self.scaling);
+                frame.translate(self.translation);
+                frame.scale(CELL_SIZE as f32);
+
+                let region = self.visible_region(frame.size());
+                for &(x, y) in self.marked.iter().filter(|&&(x, y)| region.contained(x, y)) {
+                    frame.fill_rectangle(
+                        Point::new(x as f32, y as f32),
+                        Size::UNIT,
+                        Color {
+                            a: 0.6,
+                            ..Color::WHITE
+                        },
+                    );
+                }
+            });
+
+            let lines = self.tooltip_lines();
+            if let (Interaction::None, Some(position), false) = (&self.interaction, cursor.position_in(&bounds), lines.is_empty()) {
+                draw_tooltip(&mut frame, position, &lines);
+            }
+
             if let Some(cell) = hovered_cell {
                 frame.with_save(|frame| {
                     frame.translate(center);
@@ -318,9 +595,8 @@ impl canvas::Program<()> for Grid {
             frame.into_geometry()
         };
 
-        if self.scaling < 0.2 || !self.show_lines {
-            vec![life, overlay]
-        } else {
+        let mut layers = vec![life];
+        if self.scaling >= 0.2 && self.show_lines {
             let grid = self.grid_cache.draw(bounds.size(), |frame| {
                 frame.translate(center);
                 frame.scale(self.scaling);
@@ -353,8 +629,39 @@ impl canvas::Program<()> for Grid {
                 }
             });
 
-            vec![life, grid, overlay]
+            layers.push(grid);
         }
+        if self.show_floorplan {
+            layers.push(self.floorplan_cache.draw(bounds.size(), |frame| {
+                frame.translate(center);
+                frame.scale(self.scaling);
+                frame.translate(self.translation);
+                frame.scale(CELL_SIZE as f32);
+
+                for &(position, size) in &self.outlines.rooms {
+                    frame.fill_rectangle(position, size, ROOM_COLOR);
+                }
+                for &(position, size) in &self.outlines.regions {
+                    frame.fill_rectangle(position, size, REGION_COLOR);
+                }
+                // Labels keep their size however far the grid is zoomed.
+                for label in &self.outlines.labels {
+                    if label.region || self.scaling >= ROOM_LABEL_SCALING {
+                        frame.fill_text(canvas::Text {
+                            content: label.content.clone(),
+                            position: label.position,
+                            color: if label.region { REGION_COLOR } else { ROOM_COLOR },
+                            size: if label.region { 18.0 } else { 14.0 },
+                            horizontal_alignment: HorizontalAlignment::Center,
+                            vertical_alignment: VerticalAlignment::Center,
+                            ..canvas::Text::default()
+                        });
+                    }
+                }
+            }));
+        }
+        layers.push(overlay);
+        layers
     }
 
     fn mouse_interaction(&self, bounds: Rectangle, cursor: Cursor) -> mouse::Interaction {
@@ -362,12 +669,122 @@ impl canvas::Program<()> for Grid {
             // Interaction::Drawing => mouse::Interaction::Crosshair,
             // Interaction::Erasing => mouse::Interaction::Crosshair,
             Interaction::Panning { .. } => mouse::Interaction::Grabbing,
+            Interaction::Painting { .. } => mouse::Interaction::Crosshair,
             Interaction::None if cursor.is_over(&bounds) => mouse::Interaction::Crosshair,
             _ => mouse::Interaction::default(),
         }
     }
 }
 
+/// The mean color of a block of cells, as the rows and columns it spans.
+fn mean_color(colors: &sim::ViewColors, rows: Range<usize>, columns: Range<usize>) -> Color {
+    let (mut r, mut g, mut b, mut count) = (0.0, 0.0, 0.0, 0.0);
+    for y in rows {
+        for x in columns.clone() {
+            let color = colors.color(y, x);
+            r += color.r;
+            g += color.g;
+            b += color.b;
+            count += 1.0;
+        }
+    }
+    if count == 0.0 {
+        return Color::BLACK;
+    }
+    Color::from_rgb(r / count, g / count, b / count)
+}
+
+/// What a tile is, in a word or two.
+fn tile_name(ty: sim::CellType) -> String {
+    match ty {
+        sim::CellType::Wall => "wall".to_owned(),
+        sim::CellType::Source(tier) => format!("{} cornacopia", tier),
+        sim::CellType::Empty => "open tile".to_owned(),
+        sim::CellType::Portal => "portal".to_owned(),
+    }
+}
+
+/// Draws lines of text on a dark box beside the cursor, flipped to the other side of it where
+/// it would run off the grid.
+fn draw_tooltip(frame: &mut Frame, cursor: Point, lines: &[String]) {
+    let line_height = TOOLTIP_TEXT + 2.0;
+    // Canvas text cannot be measured, so the box is sized for characters of about this width.
+    let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let size = Size::new(longest as f32 * TOOLTIP_TEXT * 0.55 + 8.0, lines.len() as f32 * line_height + 6.0);
+    let mut corner = Point::new(cursor.x + TOOLTIP_OFFSET, cursor.y + TOOLTIP_OFFSET);
+    if corner.x + size.width > frame.width() {
+        corner.x = (cursor.x - TOOLTIP_OFFSET - size.width).max(0.0);
+    }
+    if corner.y + size.height > frame.height() {
+        corner.y = (cursor.y - TOOLTIP_OFFSET - size.height).max(0.0);
+    }
+    frame.fill_rectangle(corner, size, TOOLTIP_COLOR);
+    for (n, line) in lines.iter().enumerate() {
+        frame.fill_text(canvas::Text {
+            content: line.clone(),
+            position: Point::new(corner.x + 4.0, corner.y + 3.0 + n as f32 * line_height),
+            color: Color::WHITE,
+            size: TOOLTIP_TEXT,
+            ..canvas::Text::default()
+        });
+    }
+}
+
+/// Draws the glyph of the terrain of a tile over its color: bricks on a wall, a ring on a portal
+/// and a diamond on a source, larger for larger tiers.
+fn draw_glyph(frame: &mut Frame, corner: Point, ty: sim::CellType, tile: Color, terrain: &sim::TerrainColors) {
+    let Point { x, y } = corner;
+    let center = Point::new(x + 0.5, y + 0.5);
+    match ty {
+        sim::CellType::Wall => {
+            let mortar = contrast(terrain.wall);
+            let width = 0.06;
+            for row in 1..3 {
+                frame.fill_rectangle(Point::new(x, y + row as f32 / 3.0 - width / 2.0), Size::new(1.0, width), mortar);
+            }
+            // The joints of each course of bricks are offset from the one below.
+            for &(row, joint) in &[(0, 0.5), (1, 0.25), (1, 0.75), (2, 0.5)] {
+                frame.fill_rectangle(Point::new(x + joint - width / 2.0, y + row as f32 / 3.0), Size::new(width, 1.0 / 3.0), mortar);
+            }
+        }
+        sim::CellType::Portal => {
+            frame.fill(&Path::circle(center, 0.38), contrast(terrain.portal));
+            frame.fill(&Path::circle(center, 0.26), tile);
+        }
+        sim::CellType::Source(tier) => {
+            let radius = [0.15, 0.22, 0.3][tier.index()];
+            let diamond = Path::new(|path| {
+                path.move_to(Point::new(center.x, center.y - radius));
+                path.line_to(Point::new(center.x + radius, center.y));
+                path.line_to(Point::new(center.x, center.y + radius));
+                path.line_to(Point::new(center.x - radius, center.y));
+                path.close();
+            });
+            frame.fill(&diamond, brightest(terrain.sources[tier.index()]));
+        }
+        sim::CellType::Empty => {}
+    }
+}
+
+/// A color which stands out on another, darker on light colors and lighter on dark ones.
+fn contrast(color: Color) -> Color {
+    let luma = 0.3 * color.r + 0.59 * color.g + 0.11 * color.b;
+    if luma > 0.5 {
+        Color::from_rgb(color.r * 0.5, color.g * 0.5, color.b * 0.5)
+    } else {
+        Color::from_rgb(0.5 + color.r * 0.5, 0.5 + color.g * 0.5, 0.5 + color.b * 0.5)
+    }
+}
+
+/// The same hue at full brightness, since the tints of sources are too dark to see on their own.
+fn brightest(color: Color) -> Color {
+    let max = color.r.max(color.g).max(color.b);
+    if max <= 0.0 {
+        return Color::WHITE;
+    }
+    Color::from_rgb(color.r / max, color.g / max, color.b / max)
+}
+
 pub struct Region {
     x: f32,
     y: f32,
@@ -396,11 +813,154 @@ impl Region {
         first_column..=first_column + visible_columns
     }
 
-    fn contained(&self, _i: usize, _j: usize) -> bool {
-        // self.rows().contains(&i) && self.columns().contains(&j)
-        // FIXME
-        true
+    /// Whether the cell at this column and row is on screen.
+    fn contained(&self, x: usize, y: usize) -> bool {
+        self.columns().contains(&x) && self.rows().contains(&y)
+    }
+
+    /// The rows and columns on screen of a grid of this many, empty if none of it is.
+    fn clamped(&self, rows: usize, columns: usize) -> (Range<usize>, Range<usize>) {
+        let (visible_rows, visible_columns) = (self.rows(), self.columns());
+        (
+            *visible_rows.start()..(*visible_rows.end() + 1).min(rows),
+            *visible_columns.start()..(*visible_columns.end() + 1).min(columns),
+        )
+    }
+}
+
+/// The room and region outlines of a floorplan, in tiles, along with where each is labeled.
+#[derive(Default)]
+struct Outlines {
+    rooms: Vec<(Point, Size)>,
+    regions: Vec<(Point, Size)>,
+    labels: Vec<Label>,
+}
+
+struct Label {
+    position: Point,
+    content: String,
+    region: bool,
+}
+
+impl Outlines {
+    fn new(floorplan: &sim::Floorplan) -> Self {
+        let (width, height) = (floorplan.width, floorplan.height);
+        let regions: Vec<u32> = floorplan.regions.iter().map(|&id| id as u32).collect();
+        let mut labels = vec![];
+        for (id, tiles, position) in label_positions(&floorplan.rooms, width) {
+            if tiles >= LABELED_ROOM_TILES {
+                labels.push(Label {
+                    position,
+                    content: format!("room {}", id),
+                    region: false,
+                });
+            }
+        }
+        for (id, _, position) in label_positions(&regions, width) {
+            if let Some(name) = floorplan.region_names.get(id as usize - 1) {
+                labels.push(Label {
+                    position,
+                    content: name.clone(),
+                    region: true,
+                });
+            }
+        }
+        Self {
+            rooms: boundaries(&floorplan.rooms, width, height, 2.0 / CELL_SIZE as f32),
+            regions: boundaries(&regions, width, height, 4.0 / CELL_SIZE as f32),
+            labels,
+        }
+    }
+}
+
+/// Lines `thickness` tiles thick along every edge between tiles of different ids, in row-major
+/// order, as rectangles running as far as they can. Edges along the border of the world are left
+/// out, since rooms wrap around it.
+fn boundaries(ids: &[u32], width: usize, height: usize, thickness: f32) -> Vec<(Point, Size)> {
+    let mut lines = vec![];
+    if ids.len() != width * height {
+        return lines;
+    }
+    let half = thickness / 2.0;
+    for y in 1..height {
+        let mut start = None;
+        for x in 0..=width {
+            let differs = x < width && ids[y * width + x] != ids[(y - 1) * width + x];
+            match (differs, start) {
+                (true, None) => start = Some(x),
+                (false, Some(from)) => {
+                    lines.push((
+                        Point::new(from as f32 - half, y as f32 - half),
+                        Size::new((x - from) as f32 + thickness, thickness),
+                    ));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    for x in 1..width {
+        let mut start = None;
+        for y in 0..=height {
+            let differs = y < height && ids[y * width + x] != ids[y * width + x - 1];
+            match (differs, start) {
+                (true, None) => start = Some(y),
+                (false, Some(from)) => {
+                    lines.push((
+                        Point::new(x as f32 - half, from as f32 - half),
+                        Size::new(thickness, (y - from) as f32 + thickness),
+                    ));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    lines
+}
+
+/// The id, tile count and label position of every nonzero id, in order. Each is labeled on the
+/// center of its tile nearest the middle of all of its tiles, so that a label never sits on
+/// another room when its middle lands in a wall.
+fn label_positions(ids: &[u32], width: usize) -> Vec<(u32, usize, Point)> {
+    let count = ids.iter().copied().max().unwrap_or(0) as usize;
+    let mut sums = vec![(0.0, 0.0, 0); count];
+    for (index, &id) in ids.iter().enumerate() {
+        if id > 0 {
+            let sum = &mut sums[id as usize - 1];
+            sum.0 += (index % width) as f32;
+            sum.1 += (index / width) as f32;
+            sum.2 += 1;
+        }
+    }
+    let middles: Vec<(f32, f32)> = sums
+        .iter()
+        .map(|&(x, y, tiles)| (x / tiles.max(1) as f32, y / tiles.max(1) as f32))
+        .collect();
+    let mut nearest: Vec<Option<(f32, usize)>> = vec![None; count];
+    for (index, &id) in ids.iter().enumerate() {
+        if id > 0 {
+            let (mx, my) = middles[id as usize - 1];
+            let (dx, dy) = ((index % width) as f32 - mx, (index / width) as f32 - my);
+            let distance = dx * dx + dy * dy;
+            let best = &mut nearest[id as usize - 1];
+            if best.map_or(true, |(closest, _)| distance < closest) {
+                *best = Some((distance, index));
+            }
+        }
     }
+    nearest
+        .into_iter()
+        .zip(sums)
+        .enumerate()
+        .filter_map(|(n, (best, (_, _, tiles)))| {
+            best.map(|(_, index)| {
+                let position =
+                    Point::new((index % width) as f32 + 0.5, (index / width) as f32 + 0.5);
+                (n as u32 + 1, tiles, position)
+            })
+        })
+        .collect()
 }
 
 enum Interaction {
@@ -408,6 +968,7 @@ enum Interaction {
     // Drawing,
     // Erasing,
     Panning { translation: Vector, start: Point },
+    Painting { x: usize, y: usize },
 }
 
 pub fn cell_at(x: f32, y: f32) -> (isize, isize) {
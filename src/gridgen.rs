@@ -82,3 +82,24 @@ pub fn generate_walls(rng: &mut impl Rng, shape: (usize, usize)) -> Array2<bool>
     }
     grid
 }
+
+/// Opens every wall with at least five open tiles among its eight neighbors, once for each pass,
+/// which rounds off corners and wears away the ends of walls without ever closing a passage.
+pub fn smooth(walls: &mut Array2<bool>, passes: usize) {
+    let shape = walls.dim();
+    for _ in 0..passes {
+        let before = walls.clone();
+        for (pos, wall) in walls.indexed_iter_mut() {
+            if !*wall {
+                continue;
+            }
+            let open = (-1..=1)
+                .cartesian_product(-1..=1)
+                .filter(|&delta| delta != (0, 0) && !before[dir(pos, shape, delta)])
+                .count();
+            if open >= 5 {
+                *wall = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::sim::{Decision, Limit, Maze, Sim};
+use gridsim::moore::MooreDirection;
+use rand::{seq::SliceRandom, Rng};
+use rand_chacha::ChaCha8Rng;
+
+/// How the world of an environment is generated and how long episodes last.
+pub struct EnvConfig {
+    pub width: usize,
+    pub height: usize,
+    pub openness: usize,
+    pub cornacopia_count_probability: f64,
+    /// Ticks the world runs before the agent is placed so that it competes with a population.
+    pub warmup: usize,
+    /// Steps after which an episode ends even though the agent is still alive.
+    pub max_steps: usize,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            width: 64,
+            height: 40,
+            openness: 5,
+            cornacopia_count_probability: 0.0005,
+            warmup: 1000,
+            max_steps: 1000,
+        }
+    }
+}
+
+pub struct Step {
+    /// The brain inputs of the agent, or empty once it has died.
+    pub observation: Vec<f64>,
+    /// One for every tick the agent survives.
+    pub reward: f64,
+    pub done: bool,
+}
+
+/// A gym-style environment where the caller decides for a single cell in an evolving world.
+pub struct Env {
+    config: EnvConfig,
+    sim: Option<Sim>,
+    agent: usize,
+    steps: usize,
+}
+
+impl Env {
+    pub fn new(config: EnvConfig) -> Self {
+        Self {
+            config,
+            sim: None,
+            agent: 0,
+            steps: 0,
+        }
+    }
+
+    /// Generates a new world and returns the first observation of the agent.
+    pub fn reset(&mut self) -> Vec<f64> {
+        let mut sim = Sim::new(
+            self.config.width,
+            self.config.height,
+            Maze::new(self.config.openness),
+            self.config.cornacopia_count_probability,
+            None,
+        );
+        for _ in 0..self.config.warmup {
+            sim = sim.tick();
+        }
+        self.agent = sim
+            .possess_any()
+            .expect("the world has no open tiles for the agent");
+        self.steps = 0;
+        let observation = sim.observe(self.agent);
+        self.sim = Some(sim);
+        observation
+    }
+
+    /// Numbers for the caller to decide with, drawn from the world so that its seed replays them.
+    pub fn rng(&mut self) -> ChaCha8Rng {
+        self.sim
+            .as_mut()
+            .expect("reset must be called before drawing")
+            .control_rng()
+    }
+
+    /// Advances the world a tick with the agent taking the action, or its own brain deciding with `None`.
+    pub fn step(&mut self, action: Option<Decision>) -> Step {
+        let mut sim = self.sim.take().expect("reset must be called before step");
+        sim.force(self.agent, action);
+        sim = sim.tick();
+        self.steps += 1;
+        let step = match sim.possessed() {
+            Some(agent) => {
+                self.agent = agent;
+                Step {
+                    observation: sim.observe(agent),
+                    reward: 1.0,
+                    done: self.steps >= self.config.max_steps,
+                }
+            }
+            None => Step {
+                observation: vec![],
+                reward: 0.0,
+                done: true,
+            },
+        };
+        self.sim = Some(sim);
+        step
+    }
+}
+
+pub fn random_action(rng: &mut impl Rng) -> Decision {
+    let dir = *[
+        MooreDirection::Right,
+        MooreDirection::Up,
+        MooreDirection::Left,
+        MooreDirection::Down,
+    ]
+    .choose(rng)
+    .unwrap();
+    match rng.gen_range(0, 4) {
+        0 => Decision::Move(dir),
+        1 => Decision::Divide(dir),
+        2 => Decision::Trade(
+            rng.gen_range(1, 50),
+            rng.gen_range(-10, 10),
+            Limit::default(),
+        ),
+        _ => Decision::Nothing,
+    }
+}
+
+/// Decides for the agent from numbers drawn from the world and its observation, or leaves the
+/// decision to its own brain.
+type Policy = fn(&mut ChaCha8Rng, &[f64]) -> Option<Decision>;
+
+/// Prints the mean episode return of a random policy and of the evolved brains themselves.
+pub fn baseline(episodes: usize) {
+    let mut env = Env::new(EnvConfig::default());
+    let policies: [(&str, Policy); 2] = [
+        ("random", |rng, _| Some(random_action(rng))),
+        ("evolved", |_, _| None),
+    ];
+    for &(name, policy) in &policies {
+        let mut total = 0.0;
+        for _ in 0..episodes {
+            let mut observation = env.reset();
+            loop {
+                let action = policy(&mut env.rng(), &observation);
+                let step = env.step(action);
+                total += step.reward;
+                if step.done {
+                    break;
+                }
+                observation = step.observation;
+            }
+        }
+        println!(
+            "{}: mean return {:.1} over {} episodes",
+            name,
+            total / episodes as f64,
+            episodes
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Runs a world without a window for a number of ticks, writing the market of every tick as a
+//! row of a CSV table, or of a stats file for runs too long to analyze from a table, so that long
+//! experiments can run on machines without a display.
+//!
+//! Nothing is drawn or sampled, so a tick costs about what it costs the sim alone.
+
+use crate::sim::Sim;
+use crate::statfile::StatsWriter;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Ticks between reports of how far the run has got.
+const PROGRESS_TICKS: usize = 100_000;
+
+const HEADER: &[&str] = &[
+    "tick",
+    "bid",
+    "ask",
+    "reserve",
+    "buy_volume",
+    "sell_volume",
+    "trades",
+    "reserve_purchases",
+    "reserve_sales",
+    "escheatment",
+    "demurrage",
+    "inheritance_tax",
+    "minted",
+    "halted",
+    "expired_orders",
+    "partial_orders",
+    "population",
+];
+
+/// The market of the last tick under `HEADER` after the tick, with no price when there was no
+/// order on its side.
+fn values(sim: &Sim) -> Vec<Option<f64>> {
+    let market = sim.market();
+    let price = |price: Option<i32>| price.map(f64::from);
+    vec![
+        price(market.last_bid),
+        price(market.last_ask),
+        Some(market.reserve.into()),
+        Some(market.buy_volume.into()),
+        Some(market.sell_volume.into()),
+        Some(market.flows.trades.into()),
+        Some(market.flows.reserve_purchases.into()),
+        Some(market.flows.reserve_sales.into()),
+        Some(market.flows.escheatment.into()),
+        Some(market.flows.demurrage.into()),
+        Some(market.flows.inheritance_tax.into()),
+        Some(market.flows.minted.into()),
+        Some(market.halted as u8 as f64),
+        Some(market.orders.expired.into()),
+        Some(market.orders.partial.into()),
+        Some(sim.population() as f64),
+    ]
+}
+
+/// Where the rows of a run go.
+enum Output {
+    Csv(BufWriter<File>),
+    Stats(StatsWriter),
+}
+
+impl Output {
+    /// A stats file for a path ending in `.stats`, and a CSV table for any other.
+    fn create(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "stats")
+        {
+            return Ok(Output::Stats(StatsWriter::create(path, &HEADER[1..])?));
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADER.join(","))?;
+        Ok(Output::Csv(writer))
+    }
+
+    /// Writes a row, leaving a missing value empty in a table.
+    fn push(&mut self, tick: usize, values: &[Option<f64>]) -> Result<(), Box<dyn Error>> {
+        match self {
+            Output::Csv(writer) => {
+                let row: Vec<String> = std::iter::once(tick.to_string())
+                    .chain(
+                        values
+                            .iter()
+                            .map(|value| value.map(|value| value.to_string()).unwrap_or_default()),
+                    )
+                    .collect();
+                writeln!(writer, "{}", row.join(","))?;
+            }
+            Output::Stats(writer) => writer.push(tick as u64, values)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Output::Csv(mut writer) => writer.flush()?,
+            Output::Stats(writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+fn write(mut sim: Sim, ticks: usize, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut output = Output::create(path)?;
+    for tick in 1..=ticks {
+        sim = sim.tick();
+        output.push(tick, &values(&sim))?;
+        if tick % PROGRESS_TICKS == 0 {
+            println!("ran {} of {} ticks", tick, ticks);
+        }
+    }
+    output.finish()
+}
+
+/// Runs a world for a number of ticks without a window, writing its market every tick.
+pub fn run(sim: Sim, ticks: usize, path: &Path) -> Result<(), Box<dyn Error>> {
+    write(sim, ticks, path)
+        .map_err(|e| format!("failed to write the run to {}: {}", path.display(), e))?;
+    println!("wrote {} ticks to {}", ticks, path.display());
+    Ok(())
+}
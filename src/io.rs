@@ -0,0 +1,164 @@
+//! The files the user picks with native dialogs, shared by every save, load and export.
+
+use crate::sim::{BrainTrace, Genome, Postmortem};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio::task::spawn_blocking;
+
+/// What a file holds, which decides the extension offered in dialogs.
+#[derive(Copy, Clone, Debug)]
+pub enum FileKind {
+    World,
+    Genome,
+    Csv,
+    /// A CSV table of lineages.
+    Lineages,
+    /// A CSV table of where a followed cell went and what it had.
+    Trace,
+    /// JSON of how a followed cell came to its decisions, codon by codon.
+    BrainTrace,
+    /// The family tree of the brains as a Newick tree.
+    Newick,
+    /// The family tree of the brains as a GraphViz graph.
+    Graphviz,
+    /// A CSV table of how much of the orders in each bucket of an audit were filled.
+    Audit,
+    /// An SQLite database the stats are recorded into.
+    Database,
+    /// A zstd compressed CSV table of every trade executed.
+    Tape,
+    /// A PNG image of part of the world.
+    Render,
+}
+
+impl FileKind {
+    fn extension(self) -> &'static str {
+        match self {
+            FileKind::World => "snap",
+            FileKind::Genome | FileKind::BrainTrace => "json",
+            FileKind::Csv | FileKind::Lineages | FileKind::Trace | FileKind::Audit => "csv",
+            FileKind::Database => "sqlite",
+            FileKind::Tape => "zst",
+            FileKind::Render => "png",
+            FileKind::Newick => "nwk",
+            FileKind::Graphviz => "dot",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            FileKind::World => "Evonomics worlds",
+            FileKind::Genome => "Genomes",
+            FileKind::BrainTrace => "Brain traces",
+            FileKind::Csv | FileKind::Lineages | FileKind::Trace | FileKind::Audit => "CSV tables",
+            FileKind::Database => "SQLite databases",
+            FileKind::Tape => "Compressed CSV tables",
+            FileKind::Render => "PNG images",
+            FileKind::Newick => "Newick trees",
+            FileKind::Graphviz => "GraphViz graphs",
+        }
+    }
+
+    pub fn default_name(self) -> &'static str {
+        match self {
+            FileKind::World => "evonomics.snap",
+            FileKind::Genome => "genome.json",
+            FileKind::Csv => "stats.csv",
+            FileKind::Lineages => "lineages.csv",
+            FileKind::Trace => "trace.csv",
+            FileKind::BrainTrace => "brain_trace.json",
+            FileKind::Audit => "audit.csv",
+            FileKind::Database => "stats.sqlite",
+            FileKind::Tape => "trades.zst",
+            FileKind::Render => "render.png",
+            FileKind::Newick => "ancestry.nwk",
+            FileKind::Graphviz => "ancestry.dot",
+        }
+    }
+}
+
+/// Asks where to write a file, giving `None` if the dialog is cancelled.
+///
+/// The dialog blocks, so it runs on its own thread to keep the sim and the window responsive.
+pub async fn pick_save(kind: FileKind) -> Option<PathBuf> {
+    spawn_blocking(move || {
+        let pattern = format!("*.{}", kind.extension());
+        tinyfiledialogs::save_file_dialog_with_filter(
+            "Save",
+            kind.default_name(),
+            &[&pattern],
+            kind.description(),
+        )
+    })
+    .await
+    .ok()
+    .flatten()
+    .map(PathBuf::from)
+}
+
+/// Asks which file to read, giving `None` if the dialog is cancelled.
+pub async fn pick_open(kind: FileKind) -> Option<PathBuf> {
+    spawn_blocking(move || {
+        let pattern = format!("*.{}", kind.extension());
+        tinyfiledialogs::open_file_dialog(
+            "Open",
+            kind.default_name(),
+            Some((&[&pattern], kind.description())),
+        )
+    })
+    .await
+    .ok()
+    .flatten()
+    .map(PathBuf::from)
+}
+
+/// Asks for a folder, giving `None` if the dialog is cancelled.
+pub async fn pick_folder() -> Option<PathBuf> {
+    spawn_blocking(|| tinyfiledialogs::select_folder_dialog("Choose a folder", ""))
+        .await
+        .ok()
+        .flatten()
+        .map(PathBuf::from)
+}
+
+pub fn write_genome(path: &Path, genome: &Genome) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, genome)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_genome(path: &Path) -> Result<Genome, Box<dyn Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+pub fn write_postmortem(path: &Path, postmortem: &Postmortem) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, postmortem)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_brain_traces(path: &Path, traces: &[BrainTrace]) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, traces)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes a table under a header row, without quoting since no field holds a comma.
+pub fn write_csv(
+    path: &Path,
+    header: &[&str],
+    rows: impl IntoIterator<Item = Vec<String>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", header.join(","))?;
+    for row in rows {
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -1,22 +1,92 @@
+mod bench;
+mod compare;
+mod database;
+mod demo;
 mod grid;
 pub mod gridgen;
+mod gym;
+mod headless;
+mod io;
+mod phylogeny;
 mod plot;
+mod raster;
+mod runs;
+mod sanity;
+mod scenario;
+mod session;
 pub mod sim;
+mod sonify;
+mod spectate;
+mod statfile;
 mod style;
+mod tape;
+mod timelapse;
+mod watch;
 
 use futures::{
     channel::mpsc::{Receiver, Sender},
     prelude::*,
 };
 use iced::{
-    button, executor, image, scrollable, slider, time, Align, Application, Button, Column, Command,
-    Container, Element, HorizontalAlignment, Length, Radio, Row, Scrollable, Settings, Slider,
-    Subscription, Text, VerticalAlignment,
+    button, executor, image, scrollable, slider, text_input, time, Align, Application, Button, Checkbox, Color,
+    Column, Command, Container, Element, HorizontalAlignment, Length, Radio, Row, Scrollable, Settings, Slider,
+    Subscription, Text, TextInput, VerticalAlignment,
 };
+use iced_native::{subscription, window, Event};
 use rand::SeedableRng;
-use std::{collections::VecDeque, time::Duration};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 const MAX_GRAPH_TIMES: usize = 300;
+/// How many of the latest samples the price candles cover unless the slider says otherwise.
+const DEFAULT_CANDLE_WINDOW: usize = 60;
+const MAX_EVENTS: usize = 8;
+/// How often the scenario and theme files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the layout of the window is written out if it changed.
+const SESSION_INTERVAL: Duration = Duration::from_secs(2);
+/// How often the running world is saved into its run.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(600);
+/// How long exiting waits for the sim to finish its recordings and save itself.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often a demo script checks whether its next step is due.
+const DEMO_INTERVAL: Duration = Duration::from_millis(50);
+/// The most differing codons listed when comparing genomes.
+const MAX_DIFF_ROWS: usize = 64;
+/// The most codons of the inspected cell listed.
+const MAX_CODON_ROWS: usize = 64;
+/// The most genes of the followed cell listed with how often they ran.
+const MAX_EXPRESSION_ROWS: usize = 64;
+/// Ticks of the followed cell kept for the graph and for export.
+const MAX_TRACE: usize = 100_000;
+/// Decisions of the followed cell kept with how its brain came to them, for export.
+const MAX_BRAIN_TRACES: usize = 1000;
+/// The most codons listed of the last decision traced.
+const MAX_BRAIN_TRACE_ROWS: usize = 64;
+/// Ticks of the followed cell shown in its graph.
+const TRACE_GRAPH_TICKS: usize = 1000;
+/// Portal tiles opened in each of two linked worlds.
+const PORTALS: usize = 16;
+/// Ticks over which an audit of the matching of orders tallies fill rates.
+const AUDIT_TICKS: usize = 1000;
+/// How often work deferred from the messages of the sim is done, about once a displayed frame.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// The longest each frame spends taking in stats, leaving the rest to later frames so that
+/// panning and zooming stay smooth.
+const STATS_BUDGET: Duration = Duration::from_millis(4);
+/// The least time between redraws of the graphs, which are slow to draw.
+const GRAPH_INTERVAL: Duration = Duration::from_millis(250);
+/// Stats waiting to be taken in beyond which nothing more is taken from the sim until they are,
+/// so that a sim sending faster than they can be taken in is held back.
+const MAX_PENDING_SAMPLES: usize = 1024;
+/// The least time between questions to the sim about the cell under the cursor, so that sweeping
+/// the cursor across the grid does not flood it.
+const HOVER_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the phylogeny is asked for while it is shown.
+const PHYLOGENY_INTERVAL: Duration = Duration::from_secs(2);
 
 std::thread_local! {
     pub static RNG: rand_chacha::ChaCha8Rng = rand_chacha::ChaCha8Rng::from_entropy();
@@ -27,25 +97,354 @@ unsafe fn rng() -> &'static mut rand_chacha::ChaCha8Rng {
 }
 
 pub fn main() {
+    let mut flags = Flags::default();
+    // Runs without a window are made once every flag is read, as the scenario may come after
+    // them.
+    let mut headless = false;
+    let mut ticks = None;
+    let mut out: Option<PathBuf> = None;
+    let mut timelapse = None;
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--scenario") => {
+                flags.scenario = Some(args.next().expect("--scenario requires a path").into())
+            }
+            Some("--theme") => {
+                flags.theme = Some(args.next().expect("--theme requires a path").into())
+            }
+            Some("--stats-db") => {
+                flags.database = Some(args.next().expect("--stats-db requires a path").into())
+            }
+            Some("--demo") => {
+                flags.demo = Some(args.next().expect("--demo requires a path").into())
+            }
+            Some("--session") => {
+                flags.session = Some(args.next().expect("--session requires a path").into())
+            }
+            Some("--runs") => {
+                flags.runs = Some(args.next().expect("--runs requires a directory").into())
+            }
+            Some("--control") => {
+                let addr = args.next().expect("--control requires an address");
+                let link = sim::ControlLink::listen(addr.to_string_lossy().as_ref())
+                    .expect("failed to listen for a controller");
+                flags.control = Some(std::sync::Arc::new(link));
+            }
+            Some("--spectators") => {
+                let addr = args.next().expect("--spectators requires an address");
+                let broadcast = spectate::Broadcast::listen(addr.to_string_lossy().as_ref())
+                    .expect("failed to listen for spectators");
+                flags.broadcast = Some(broadcast);
+            }
+            Some("--spectate") => {
+                let addr = args.next().expect("--spectate requires an address");
+                flags.spectate = Some(addr.to_string_lossy().into_owned());
+            }
+            Some("--gym-baseline") => {
+                let episodes = args
+                    .next()
+                    .and_then(|n| n.to_str()?.parse().ok())
+                    .expect("--gym-baseline requires a number of episodes");
+                gym::baseline(episodes);
+                return;
+            }
+            Some("--bench-masks") => {
+                let warmup = args
+                    .next()
+                    .and_then(|n| n.to_str()?.parse().ok())
+                    .expect("--bench-masks requires a number of warmup ticks");
+                bench::masks(warmup);
+                return;
+            }
+            Some("--bench-update") => {
+                let warmup = args
+                    .next()
+                    .and_then(|n| n.to_str()?.parse().ok())
+                    .expect("--bench-update requires a number of warmup ticks");
+                bench::update(warmup);
+                return;
+            }
+            Some("--compare") => {
+                let out = args.next().expect("--compare requires an output directory");
+                let dirs: Vec<PathBuf> = args.by_ref().map(PathBuf::from).collect();
+                if dirs.is_empty() {
+                    panic!("--compare requires the run directories to compare");
+                }
+                compare::compare(Path::new(&out), &dirs);
+                return;
+            }
+            Some("--headless") => headless = true,
+            Some("--ticks") => {
+                ticks = Some(
+                    args.next()
+                        .and_then(|n| n.to_str()?.parse().ok())
+                        .unwrap_or_else(|| exit_with("--ticks requires a number of ticks")),
+                )
+            }
+            Some("--out") => {
+                out = Some(
+                    args.next()
+                        .unwrap_or_else(|| exit_with("--out requires a CSV or stats file path"))
+                        .into(),
+                )
+            }
+            Some("--timelapse") => {
+                let dir = args
+                    .next()
+                    .unwrap_or_else(|| exit_with("--timelapse requires a directory"));
+                let mut number = |name| {
+                    args.next()
+                        .and_then(|n| n.to_str()?.parse().ok())
+                        .unwrap_or_else(|| {
+                            exit_with(format!("--timelapse requires a number of {}", name))
+                        })
+                };
+                let every = number("ticks between frames");
+                let downscale = number("tiles per pixel");
+                let ticks = number("ticks to run");
+                timelapse = Some((
+                    timelapse::Timelapse::new(dir.into(), every, downscale),
+                    ticks,
+                ));
+            }
+            _ => panic!("unrecognized argument {:?}", arg),
+        }
+    }
+    if headless {
+        let ticks = ticks.unwrap_or_else(|| exit_with("--headless requires --ticks"));
+        let out = out.unwrap_or_else(|| exit_with("--headless requires --out"));
+        if let Err(e) = headless_sim(flags).and_then(|sim| headless::run(sim, ticks, &out)) {
+            exit_with(e);
+        }
+        return;
+    }
+    if ticks.is_some() || out.is_some() {
+        exit_with("--ticks and --out are only for --headless");
+    }
+    if let Some((timelapse, ticks)) = timelapse {
+        if let Err(e) =
+            headless_sim(flags).and_then(|sim| timelapse::headless(sim, timelapse, ticks))
+        {
+            exit_with(e);
+        }
+        return;
+    }
+    if flags.session.is_none() {
+        flags.session = session::Session::default_path();
+    }
+    // The window opens at the size it was left at, while anything wrong with the session is
+    // reported once the window is up.
+    let window = match flags
+        .session
+        .as_deref()
+        .and_then(|path| session::Session::load(path).ok())
+        .and_then(|session| session.window)
+    {
+        Some(size) => iced::window::Settings {
+            size,
+            ..Default::default()
+        },
+        None => Default::default(),
+    };
     EvonomicsWorld::run(Settings {
         antialiasing: true,
+        window,
+        flags,
         ..Settings::default()
     })
 }
 
+/// Reports what stops the program from running and exits with a failure.
+fn exit_with(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
+
+/// The world the window would start under the scenario of `flags`, for the runs which have no
+/// window. Unlike in the window, a scenario which fails to load stops the run.
+fn headless_sim(flags: Flags) -> Result<sim::Sim, Box<dyn std::error::Error>> {
+    let scenario = match flags.scenario {
+        Some(ref path) => Some(
+            scenario::Scenario::load(path)
+                .map_err(|e| format!("failed to load {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let (mut world, _) = EvonomicsWorld::new(Flags {
+        control: flags.control,
+        ..Default::default()
+    });
+    if let Some(scenario) = scenario {
+        world.apply_scenario(scenario);
+    }
+    let mut sim = world.new_sim();
+    world.carry_settings(&mut sim);
+    Ok(sim)
+}
+
+#[derive(Default)]
+struct Flags {
+    /// A scenario file which is applied at startup and whenever it changes.
+    scenario: Option<PathBuf>,
+    /// A theme file which is applied at startup and whenever it changes.
+    theme: Option<PathBuf>,
+    /// A database the stats are recorded into from startup.
+    database: Option<PathBuf>,
+    /// Where a directory is made for each run to hold everything it writes.
+    runs: Option<PathBuf>,
+    /// Where the size of the window and the layout of its panels are kept between sessions.
+    session: Option<PathBuf>,
+    /// A demo script to play from startup.
+    demo: Option<PathBuf>,
+    /// Where external controllers connect to possess cells.
+    control: Option<std::sync::Arc<sim::ControlLink>>,
+    /// Where spectators connect to watch the worlds this window runs.
+    broadcast: Option<spectate::Broadcast>,
+    /// The address of a running window to watch instead of running a world.
+    spectate: Option<String>,
+}
+
 struct EvonomicsWorld {
     grid: Option<grid::Grid>,
     sim_tx: Option<Sender<sim::ToSim>>,
+    sim_stop: Option<sim::Stop>,
+    /// Start new worlds linked through portals to a second world.
+    linked_worlds: bool,
+    /// The seed the maze and sources of the next world are generated from.
+    world_seed: u64,
+    reseed_button: button::State,
+    preview_button: button::State,
+    /// The world the main menu would start, once previewed.
+    preview: Option<WorldPreview>,
+    /// The linked world, which is ticked along with the shown one.
+    neighbor_tx: Option<Sender<sim::ToSim>>,
+    neighbor_stop: Option<sim::Stop>,
     run_simulation_button: button::State,
+    /// Why the settings would stall a new world, shown until it is started anyway or they are
+    /// clamped.
+    warnings: Vec<String>,
+    start_anyway_button: button::State,
+    clamp_settings_button: button::State,
     load_save_button: button::State,
     save_simulation_button: button::State,
+    export_genome_button: button::State,
+    raise_spawn_button: button::State,
+    reseed_now_button: button::State,
+    plant_button: button::State,
+    /// The genomes saved or added to plant by clicking, with the files they were read from, which
+    /// the session keeps.
+    genome_library: Vec<(PathBuf, sim::Genome)>,
+    /// The genome of the library planted on the open tiles clicked.
+    planting: Option<usize>,
+    add_genome_button: button::State,
+    forget_genome_button: button::State,
+    stop_planting_button: button::State,
+    export_stats_button: button::State,
+    export_lineages_button: button::State,
+    /// Whether the sim tracks the family tree of the brains.
+    tracking_ancestry: bool,
+    /// Whether the phylogeny of the family tree is shown while it is tracked.
+    showing_phylogeny: bool,
+    phylogeny: phylogeny::Tree,
+    export_newick_button: button::State,
+    export_graphviz_button: button::State,
+    dismiss_alert_button: button::State,
+    /// A failure shown above everything else until dismissed.
+    alert: Option<String>,
     toggle_spawn_rate_type_button: button::State,
     is_inverse_rate_type: bool,
     spawn_slider: slider::State,
     spawn_rate: f64,
     spawn_chance: f64,
     toggle_run_button: button::State,
+    step_button: button::State,
     toggle_grid_button: button::State,
+    toggle_stats_button: button::State,
+    filter_input: text_input::State,
+    /// The filter expression as typed, which is only parsed when submitted.
+    filter_text: String,
+    view_settings: sim::ViewSettings,
+    /// What dragging over the grid drops, or `None` to select cells instead.
+    brush: Option<sim::Brush>,
+    brush_radius_slider: slider::State,
+    brush_radius: usize,
+    /// The brush was pressed since the last dab which reached the sim, so the next one starts a
+    /// new stroke.
+    stroke_pending: bool,
+    /// The manual interventions in the running world, as of the last change to them.
+    journal: Option<sim::JournalUpdate>,
+    undo_button: button::State,
+    redo_button: button::State,
+    /// Cells matching the filter in the last view.
+    filter_matches: Option<usize>,
+    /// The largest lineages and every named one, as of the last frame.
+    lineage_legend: Vec<sim::LineageSummary>,
+    /// The lineage the name input renames.
+    selected_lineage: Option<u64>,
+    lineage_name_input: text_input::State,
+    lineage_name_text: String,
+    /// Whether the stat panels are shown in the sim menu.
+    show_stats: bool,
+    /// The layout of the window as it is now, and as it was last written.
+    session: session::Session,
+    saved_session: session::Session,
+    session_path: Option<PathBuf>,
+    /// Take one sample of stats per frame rather than every `sample_ticks` ticks.
+    sample_each_frame: bool,
+    sample_ticks_slider: slider::State,
+    sample_ticks: usize,
+    /// Whether the sim is saving frames for a timelapse.
+    recording_timelapse: bool,
+    timelapse_button: button::State,
+    regenerate_button: button::State,
+    /// Regenerating the terrain places new cornacopias as well as new walls.
+    regenerate_sources: bool,
+    timelapse_every_slider: slider::State,
+    timelapse_every: usize,
+    timelapse_downscale_slider: slider::State,
+    timelapse_downscale: usize,
+    /// Whether the sim is recording its trades onto a tape.
+    recording_tape: bool,
+    tape_button: button::State,
+    tape_every_slider: slider::State,
+    /// Ticks between clearings recorded onto the next tape.
+    tape_every: u64,
+    /// Whether the most recent trades scroll by under the grid.
+    show_ticker: bool,
+    /// The most recent trades, oldest first.
+    ticker: VecDeque<sim::Print>,
+    render_button: button::State,
+    render_pixels_slider: slider::State,
+    /// How many pixels wide each tile of a render is.
+    render_pixels: usize,
+    /// Whether renders include the grid lines and the marked lineage.
+    render_overlays: bool,
+    /// Where the stats are recorded as they arrive, if anywhere.
+    database: Option<database::StatsDatabase>,
+    database_button: button::State,
+    /// Where a directory is made for each run, if anywhere.
+    runs_root: Option<PathBuf>,
+    /// The directory of the running sim, which saves and exports go into without asking.
+    run: Option<runs::RunDir>,
+    /// The window has no area, so nothing in it can be seen.
+    minimized: bool,
+    /// Stats arrived since the graphs were last drawn.
+    graphs_stale: bool,
+    /// When the graphs were last drawn.
+    graphs_drawn: Option<Instant>,
+    /// The newest frame from the sim not yet put on the grid, which replaces any older one.
+    pending_view: Option<sim::View>,
+    /// What changed since the frame on the grid, when no whole frame is pending to apply it to.
+    pending_delta: Option<sim::ViewDelta>,
+    /// Stats from the sim not yet taken in, oldest first.
+    pending_samples: VecDeque<sim::Stats>,
+    /// The hovered cell to ask the sim about once the last question is old enough.
+    pending_hover: Option<(usize, usize)>,
+    /// When the sim was last asked about a hovered cell.
+    hover_asked: Option<Instant>,
+    /// The messages of the sim, left untaken while too many stats are pending.
+    parked: Option<Receiver<sim::FromSim>>,
     frame_rate_slider: slider::State,
     frames_per_second: usize,
     ms_per_frame: usize,
@@ -56,6 +455,13 @@ struct EvonomicsWorld {
     width: usize,
     grid_openness_slider: slider::State,
     openness: usize,
+    corridor_width_slider: slider::State,
+    corridor_width: usize,
+    smoothing_slider: slider::State,
+    smoothing: usize,
+    /// Food growing or draining along the edges of new worlds.
+    edges: sim::Edges,
+    edge_width_slider: slider::State,
     cornacopia_probability_slider: slider::State,
     cornacopia_probability: f64,
     cornacopia_bounty_slider: slider::State,
@@ -64,8 +470,82 @@ struct EvonomicsWorld {
     cell_food_probability: f64,
     mutation_probability_slider: slider::State,
     mutation_chance: f64,
+    direct_exchange: bool,
+    sense_life: bool,
+    sense_fills: bool,
+    /// Ticks a cell may live at zero food before it starves.
+    starvation_grace: u32,
+    starvation_grace_slider: slider::State,
+    /// The percentage of the cells with brains stepped each tick, where 100 steps every one.
+    stepped_percent: f64,
+    stepped_slider: slider::State,
+    pipeline: sim::Pipeline,
+    sanitization: sim::Sanitization,
+    /// What becomes of the money of a cell which starves right after trading.
+    proceeds: sim::Proceeds,
+    /// The part of the world held still while the rest goes on.
+    freeze: sim::Freeze,
+    /// Whether the rules include a circuit breaker as set by the sliders below.
+    circuit_breaker: bool,
+    breaker_move_slider: slider::State,
+    breaker_move_percent: f64,
+    breaker_window_slider: slider::State,
+    breaker_window: usize,
+    breaker_halt_slider: slider::State,
+    breaker_halt: usize,
+    /// Whether the rules include demurrage as set by the controls below.
+    demurrage: bool,
+    demurrage_slider: slider::State,
+    demurrage_percent: f64,
+    demurrage_carried: bool,
+    /// The percentage of the money passed to offspring which goes to the reserve.
+    inheritance_tax: f64,
+    inheritance_tax_slider: slider::State,
+    /// What the reserve does when it has too little money for the food offered to it.
+    exhaustion: sim::Exhaustion,
+    /// Food a cell pays each tick its signal is not zero.
+    signal_cost: f64,
+    signal_cost_slider: slider::State,
+    /// Whether what cells carry is capped at the capacity below.
+    capped: bool,
+    capacity_slider: slider::State,
+    capacity: u32,
+    /// The share of stockpiles beyond `spoils_above` which spoils each tick, as a percentage.
+    spoilage_slider: slider::State,
+    spoilage_percent: f64,
+    spoils_above_slider: slider::State,
+    spoils_above: u32,
+    /// Food a cell pays for each codon its brain runs to decide.
+    metabolism: f64,
+    metabolism_slider: slider::State,
+    /// Whether a world in which every cell died is started over as set below.
+    recovery: bool,
+    recovery_population_slider: slider::State,
+    recovery_population: usize,
+    recover_from_hall: bool,
+    /// Noise in what brains sense and remember, from none by default.
+    input_noise_slider: slider::State,
+    input_noise: f64,
+    forgetting_slider: slider::State,
+    forgetting: f64,
+    /// The chance of each kind of mutation, with a slider for each.
+    mutations: sim::Mutations,
+    mutation_sliders: [slider::State; 4],
     cornacopia_count_probability_slider: slider::State,
     cornacopia_count_probability: f64,
+    /// A text box for each chance, indexed by `Chance`.
+    chance_inputs: [ChanceInput; 4],
+    /// Tiles of the running world which grow food by the general chance and by the cornacopia
+    /// chance, as of the last sample.
+    fertile_tiles: usize,
+    /// Of each tier of cornacopia, from the smallest.
+    source_tiles: [usize; 3],
+    /// How common each tier of cornacopia is in new worlds and what it yields.
+    tiers: sim::Tiers,
+    /// Parts of the world the scenario gave parameters of their own, for the next world started.
+    regions: Vec<sim::Region>,
+    /// Living cells in each region of the running world, as of the last sample.
+    region_populations: Vec<(sim::Area, usize)>,
     menu_state: MenuState,
     is_running_sim: bool,
     next_speed: Option<usize>,
@@ -76,18 +556,112 @@ struct EvonomicsWorld {
     reserves: VecDeque<u32>,
     buy_volumes: VecDeque<u32>,
     sell_volumes: VecDeque<u32>,
+    money_flows: VecDeque<sim::MoneyFlows>,
+    params: VecDeque<sim::Params>,
     mean_ages: VecDeque<u64>,
     max_ages: VecDeque<u64>,
+    sanitized: VecDeque<sim::Sanitized>,
+    death_causes: VecDeque<sim::DeathCauses>,
+    /// Whether the circuit breaker halted trading during each sample.
+    halted: VecDeque<bool>,
+    order_counts: VecDeque<sim::OrderCounts>,
+    /// The mean number of separator codons in a genome in each sample.
+    separators: VecDeque<f64>,
+    /// How much of the genomes was junk in each sample.
+    junk: VecDeque<sim::Junk>,
+    /// Living cells which made each kind of decision in each sample, by `Choice::index`.
+    choices: VecDeque<[u32; 6]>,
+    /// The parameters and rules the sim last said were in effect, if it has said since it started.
+    applied: Option<(sim::Params, sim::Rules)>,
+    /// Ticks the world had run with approximate stepping as of each sample.
+    approximated: VecDeque<u64>,
+    /// Living cells at the end of each sample.
+    populations: VecDeque<usize>,
+    /// Distinct genomes among the living at the end of each sample.
+    unique_genome_counts: VecDeque<usize>,
+    /// Food paid for holding signals per tick in each sample.
+    signaling: VecDeque<u32>,
+    spoiled: VecDeque<u32>,
+    /// Food paid for running brains per tick in each sample.
+    metabolized: VecDeque<u32>,
+    depth: VecDeque<Vec<(i32, f32)>>,
+    /// How traits correlated with fitness as of the last stats.
+    selection: sim::Selection,
+    lineage_prices: VecDeque<Vec<sim::LineagePrices>>,
+    candles: VecDeque<Option<sim::Candle>>,
+    /// Plays the prices and volume of trades as they come, while the market is sonified.
+    sonifier: Option<sonify::Sonifier>,
+    candle_window_slider: slider::State,
+    /// How many of the latest samples are drawn as price candles.
+    candle_window: usize,
+    population: usize,
+    unique_genomes: usize,
+    interned_genomes: usize,
+    /// The last cell clicked, with its position, as it was when clicked.
+    inspected: Option<(usize, usize, sim::CellDetail)>,
+    /// The last two genomes clicked, with the position of their cell.
+    compared: VecDeque<(usize, usize, sim::Genome)>,
+    genome_diff: Vec<sim::DiffRow>,
+    /// What the running world holds and the memory it takes, as last measured.
+    footprint: Option<sim::Footprint>,
+    /// The cell being followed at each tick since it was clicked.
+    trace: VecDeque<sim::TracePoint>,
+    /// The followed cell died or combined, so the trace stopped.
+    trace_lost: bool,
+    /// Which genes of the followed cell ran, as last sent.
+    expression: Option<sim::Expression>,
+    /// Whether the brain of the followed cell is traced codon by codon.
+    tracing_brain: bool,
+    /// How the followed cell came to its decisions, oldest first.
+    brain_traces: VecDeque<sim::BrainTrace>,
+    export_brain_trace_button: button::State,
+    trace_graph: image::Handle,
+    stop_following_button: button::State,
+    export_trace_button: button::State,
+    /// An audit of the matching of orders is underway.
+    auditing: bool,
+    /// The last audit to finish.
+    audit: Option<sim::Audit>,
+    audit_button: button::State,
+    export_audit_button: button::State,
     bid_ask_graph: image::Handle,
+    depth_graph: image::Handle,
+    lineage_price_graph: image::Handle,
+    price_candle_graph: image::Handle,
     reserve_graph: image::Handle,
     volume_graph: image::Handle,
+    money_flow_graph: image::Handle,
+    params_graph: image::Handle,
     mean_max_age_graph: image::Handle,
+    junk_graph: image::Handle,
+    choices_graph: image::Handle,
+    /// Living cells by age in the last sample.
+    ages: sim::AgePyramid,
+    age_pyramid_graph: image::Handle,
     scroll: scrollable::State,
+    scenario_watcher: Option<watch::FileWatcher>,
+    theme_watcher: Option<watch::FileWatcher>,
+    jitter: scenario::Jitters,
+    events: VecDeque<String>,
+    control: Option<std::sync::Arc<sim::ControlLink>>,
+    broadcast: Option<spectate::Broadcast>,
+    /// The address being watched and the last sample of stats received from it, if any.
+    spectating: Option<(String, Option<(u64, Vec<String>, Vec<String>)>)>,
+    /// The demo script being played, if any.
+    demo: Option<demo::Demo>,
+    /// What the demo is explaining, by the part of the window it is about.
+    callout: Option<(demo::Target, String)>,
+    continue_button: button::State,
+    tutorial_button: button::State,
+    /// What the running world has done for the first time, which a demo can wait for.
+    milestones: demo::Milestones,
 }
 
 enum MenuState {
     MainMenu,
     SimMenu,
+    /// Watching a world run by another window.
+    Spectating,
 }
 
 impl std::default::Default for MenuState {
@@ -99,7 +673,21 @@ impl std::default::Default for MenuState {
 #[derive(Debug)]
 enum Message {
     FromSim(sim::FromSim, Receiver<sim::FromSim>),
+    /// Messages from the linked world, which runs unseen.
+    FromNeighbor(sim::FromSim, Receiver<sim::FromSim>),
+    /// Frames from the window being watched.
+    Spectated(spectate::Received, Receiver<spectate::Received>),
+    LinkedWorldsToggled(bool),
+    Reseed,
+    /// Generate the world the main menu would start, to show what it looks like.
+    PreviewWorld,
+    WorldPreviewed(Option<WorldPreview>),
+    Grid(grid::Message),
     SimView,
+    /// Start a new world despite the warnings about its settings.
+    StartAnyway,
+    /// Bring the settings warned about within bounds and start a new world with them.
+    ClampSettings,
     MainView,
     SpeedChanged(f32),
     FrameRateChanged(f32),
@@ -108,14 +696,146 @@ enum Message {
     DimensionSet(f32),
     AspectChanged(AspectRatio),
     OpennessSet(f32),
+    CorridorWidthSet(f32),
+    SmoothingSet(f32),
+    EdgePolicyChanged(sim::EdgePolicy),
+    EdgeWidthSet(f32),
+    /// Set everything about the edges at once, such as from a scenario.
+    EdgesSet(sim::Edges),
+    /// Set the tiers of cornacopia, such as from a scenario.
+    TiersSet(sim::Tiers),
     CornacopiaProbabilityChanged(f32),
     CornacopiaBountyChanged(f32),
     GeneralFoodProbabilityChanged(f32),
     MutationChanceChanged(f32),
     CornacopiaCountProbabilityChanged(f32),
+    DirectExchangeToggled(bool),
+    SenseLifeToggled(bool),
+    SenseFillsToggled(bool),
+    StarvationGraceChanged(f32),
+    SteppedChanged(f32),
+    PipelineChanged(sim::Pipeline),
+    SanitizationChanged(sim::Sanitization),
+    ProceedsChanged(sim::Proceeds),
+    ExhaustionChanged(sim::Exhaustion),
+    FreezeChanged(sim::Freeze),
+    CircuitBreakerToggled(bool),
+    BreakerMoveChanged(f32),
+    BreakerWindowChanged(f32),
+    BreakerHaltChanged(f32),
+    /// Set every control of the circuit breaker at once, such as from a scenario.
+    CircuitBreakerSet(Option<sim::CircuitBreaker>),
+    DemurrageToggled(bool),
+    DemurrageChanged(f32),
+    DemurrageCarriedToggled(bool),
+    /// Set every control of demurrage at once, such as from a scenario.
+    DemurrageSet(Option<sim::Demurrage>),
+    InheritanceTaxChanged(f32),
+    SignalCostChanged(f32),
+    CappedToggled(bool),
+    CapacityChanged(f32),
+    SpoilageChanged(f32),
+    SpoilsAboveChanged(f32),
+    /// Set every control of spoilage at once, such as from a scenario.
+    SpoilageSet(sim::Spoilage),
+    MetabolismChanged(f32),
+    RecoveryToggled(bool),
+    RecoveryPopulationChanged(f32),
+    RecoverFromHallToggled(bool),
+    /// Set every control of extinction recovery at once, such as from a scenario.
+    RecoverySet(sim::Recovery),
+    InputNoiseChanged(f32),
+    ForgettingChanged(f32),
+    /// Set both kinds of noise at once, such as from a scenario, which is logged.
+    NoiseSet(sim::Noise),
+    MutationsChanged(sim::Mutations),
     ToggleSim,
+    /// Run exactly one tick of a paused world.
+    StepTick,
     ToggleGrid,
+    /// Outline the rooms and regions of the world over the grid.
+    FloorplanToggled(bool),
+    ToggleStats,
+    FilterEdited(String),
+    ApplyFilter,
+    ChanceEdited(Chance, String),
+    /// Set a chance to the probability typed into its text box.
+    ChanceSubmitted(Chance),
+    ViewModeChanged(sim::ViewMode),
+    ViewMemoryChanged(sim::ViewMemory),
+    BrushChanged(Option<sim::Brush>),
+    BrushRadiusChanged(f32),
+    LineageSelected(u64),
+    LineageNameEdited(String),
+    NameLineage,
+    SampleEachFrameToggled(bool),
+    SampleTicksChanged(f32),
+    /// Start a timelapse by asking for its folder, or stop the one being recorded.
+    ToggleTimelapse,
+    TimelapseFolderPicked(Option<PathBuf>),
+    TimelapseEveryChanged(f32),
+    TimelapseDownscaleChanged(f32),
+    /// Start recording trades onto a tape, or stop the one being recorded.
+    ToggleTape,
+    TapeEveryChanged(f32),
+    TickerToggled(bool),
+    /// Raise the spawn rate a step, such as when the world is empty.
+    RaiseSpawnRate,
+    /// Spawn as many cells as recovery would right away, from the hall of fame if it is chosen.
+    ReseedNow,
+    CandleWindowChanged(f32),
+    RenderPixelsChanged(f32),
+    RenderOverlaysToggled(bool),
+    /// Replace the walls of the running world with a new maze.
+    RegenerateTerrain,
+    /// Reverse the last manual intervention still in effect.
+    Undo,
+    /// Make the last undone intervention again.
+    Redo,
+    RegenerateSourcesToggled(bool),
+    /// Start recording stats by asking for a database, or stop recording them.
+    ToggleDatabase,
+    /// Stop recording the trace of the followed cell.
+    StopFollowing,
+    /// Plant a genome of the library on the open tiles clicked.
+    PlantingSelected(usize),
+    /// Go back to inspecting the tiles clicked.
+    StopPlanting,
+    /// Remove the genome being planted from the library, leaving its file.
+    ForgetGenome,
+    /// Play the market as sound, or stop.
+    SonifyToggled(bool),
+    /// Trace how the followed cell decides codon by codon, or stop.
+    TraceBrainToggled(bool),
+    TrackAncestryToggled(bool),
+    PhylogenyToggled(bool),
+    /// Ask the sim for the phylogeny again.
+    AskPhylogeny,
+    Phylogeny(phylogeny::Message),
+    /// Audit how fairly orders are matched over the next `AUDIT_TICKS` ticks.
+    AuditMatching,
+    WindowResized(u32, u32),
     Tick,
+    /// Do the work deferred from the messages of the sim which fits in a frame.
+    Frame,
+    /// Check the scenario and theme files for changes.
+    PollFiles,
+    /// Write the layout of the window out if it changed.
+    SaveSession,
+    /// Save the running world into its run, replacing the last autosave.
+    Autosave,
+    /// Shut the sim down gracefully and exit, on Ctrl-C.
+    Quit,
+    /// Take the steps of the demo script which are due.
+    DemoStep,
+    /// Go on with a demo held until the viewer was ready.
+    ContinueDemo,
+    /// Play the guided first run.
+    StartTutorial,
+    /// Ask for a file to use for this action.
+    PickFile(FileAction),
+    FilePicked(FileAction, Option<PathBuf>),
+    DismissAlert,
     Null,
 }
 
@@ -123,17 +843,65 @@ impl Clone for Message {
     fn clone(&self) -> Self {
         match self {
             Self::SimView => Self::SimView,
+            Self::StartAnyway => Self::StartAnyway,
+            Self::ClampSettings => Self::ClampSettings,
             Self::MainView => Self::MainView,
             Self::ToggleSim => Self::ToggleSim,
+            Self::StepTick => Self::StepTick,
             Self::ToggleGrid => Self::ToggleGrid,
+            Self::ToggleStats => Self::ToggleStats,
+            Self::ApplyFilter => Self::ApplyFilter,
+            Self::ChanceSubmitted(chance) => Self::ChanceSubmitted(*chance),
+            Self::NameLineage => Self::NameLineage,
+            Self::LineageSelected(lineage) => Self::LineageSelected(*lineage),
+            Self::PlantingSelected(index) => Self::PlantingSelected(*index),
+            Self::StopPlanting => Self::StopPlanting,
+            Self::ForgetGenome => Self::ForgetGenome,
             Self::Tick => Self::Tick,
+            Self::Frame => Self::Frame,
+            Self::PollFiles => Self::PollFiles,
+            Self::SaveSession => Self::SaveSession,
+            Self::Autosave => Self::Autosave,
+            Self::Quit => Self::Quit,
+            Self::DemoStep => Self::DemoStep,
+            Self::ContinueDemo => Self::ContinueDemo,
+            Self::StartTutorial => Self::StartTutorial,
+            Self::PickFile(action) => Self::PickFile(*action),
+            Self::ToggleTimelapse => Self::ToggleTimelapse,
+            Self::ToggleTape => Self::ToggleTape,
+            Self::RaiseSpawnRate => Self::RaiseSpawnRate,
+            Self::ReseedNow => Self::ReseedNow,
+            Self::Reseed => Self::Reseed,
+            Self::RegenerateTerrain => Self::RegenerateTerrain,
+            Self::Undo => Self::Undo,
+            Self::Redo => Self::Redo,
+            Self::PreviewWorld => Self::PreviewWorld,
+            Self::ToggleDatabase => Self::ToggleDatabase,
+            Self::StopFollowing => Self::StopFollowing,
+            Self::AuditMatching => Self::AuditMatching,
+            Self::AskPhylogeny => Self::AskPhylogeny,
+            Self::DismissAlert => Self::DismissAlert,
             Self::ToggleRateType => Self::ToggleRateType,
             Self::SpawnRateChanged(spwn) => Message::SpawnRateChanged(spwn.clone()),
             Self::AspectChanged(aspect) => Message::AspectChanged(aspect.clone()),
+            Self::PipelineChanged(pipeline) => Message::PipelineChanged(pipeline.clone()),
+            Self::SanitizationChanged(sanitization) => {
+                Message::SanitizationChanged(sanitization.clone())
+            }
+            Self::ProceedsChanged(proceeds) => Message::ProceedsChanged(*proceeds),
+            Self::ExhaustionChanged(exhaustion) => Message::ExhaustionChanged(*exhaustion),
+            Self::FreezeChanged(freeze) => Message::FreezeChanged(*freeze),
+            Self::ViewModeChanged(mode) => Message::ViewModeChanged(mode.clone()),
+            Self::ViewMemoryChanged(memory) => Message::ViewMemoryChanged(memory.clone()),
+            Self::BrushChanged(brush) => Message::BrushChanged(brush.clone()),
             Self::SpeedChanged(spd) => Message::SpeedChanged(spd.clone()),
             Self::FrameRateChanged(rt) => Message::FrameRateChanged(rt.clone()),
             Self::DimensionSet(dm) => Message::DimensionSet(dm.clone()),
             Self::OpennessSet(openness) => Message::OpennessSet(openness.clone()),
+            Self::CorridorWidthSet(width) => Message::CorridorWidthSet(width.clone()),
+            Self::SmoothingSet(passes) => Message::SmoothingSet(passes.clone()),
+            Self::EdgePolicyChanged(policy) => Message::EdgePolicyChanged(policy.clone()),
+            Self::EdgeWidthSet(width) => Message::EdgeWidthSet(width.clone()),
             _ => panic!("do not try to clone messages with data in them"),
         }
     }
@@ -146,7 +914,58 @@ fn reciever_command(rx: Receiver<sim::FromSim>) -> Command<Message> {
     })
 }
 
+/// How many cornacopias there are of each tier, or nothing when they are all of one tier.
+fn describe_tiers(sources: [usize; 3]) -> String {
+    if sources.iter().filter(|&&count| count > 0).count() < 2 {
+        return String::new();
+    }
+    let counts: Vec<String> = sim::Tier::ALL
+        .iter()
+        .map(|&tier| format!("{} {}", sources[tier.index()], tier))
+        .collect();
+    format!(" ({})", counts.join(", "))
+}
+
+/// Stops a sim, waiting until a deadline for it to answer with anything which failed. The sim
+/// takes the stop even while it waits on the GUI, which takes nothing while it waits here.
+fn stop_sim(
+    stop: sim::Stop,
+    autosave: Option<PathBuf>,
+    deadline: Instant,
+) -> Option<Vec<String>> {
+    let (done, acknowledged) = std::sync::mpsc::channel();
+    stop.send(sim::Shutdown { autosave, done }).ok()?;
+    acknowledged
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+/// Quits gracefully on Ctrl-C rather than being killed with the sim mid-tick.
+fn quit_on_ctrl_c() -> Command<Message> {
+    Command::perform(tokio::signal::ctrl_c(), |result| match result {
+        Ok(()) => Message::Quit,
+        // Without the handler Ctrl-C still kills the app, only without shutting down.
+        Err(_) => Message::Null,
+    })
+}
+
+fn neighbor_command(rx: Receiver<sim::FromSim>) -> Command<Message> {
+    Command::perform(rx.into_future(), |(item, stream)| {
+        item.map(|item| Message::FromNeighbor(item, stream))
+            .unwrap_or(Message::Null)
+    })
+}
+
+fn spectated_command(rx: Receiver<spectate::Received>) -> Command<Message> {
+    Command::perform(rx.into_future(), |(item, stream)| {
+        item.map(|item| Message::Spectated(item, stream))
+            .unwrap_or(Message::Null)
+    })
+}
+
 const SPAWN_CURVE: f64 = 0.000000001;
+/// How far the spawn rate is raised by the button shown over an empty world.
+const SPAWN_RATE_STEP: f64 = 0.1;
 
 fn spawn_rate(
     is_inverse_rate_type: bool,
@@ -167,20 +986,48 @@ impl<'a> Application for EvonomicsWorld {
     // run commands and subscriptions
     type Executor = executor::Default;
     // initialization data for application
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_: ()) -> (EvonomicsWorld, Command<Self::Message>) {
+    fn new(flags: Flags) -> (EvonomicsWorld, Command<Self::Message>) {
         const INITIAL_SPAWN_RATE: f64 = 0.5;
         const INITIAL_IS_INVERSE_RATE: bool = true;
         const INITIAL_WIDTH: usize = 512;
         const INITIAL_ASPECT: AspectRatio = AspectRatio::SixteenToTen;
-        (
-            EvonomicsWorld {
+        let mut world = EvonomicsWorld {
                 grid: None,
                 sim_tx: None,
+                sim_stop: None,
+                linked_worlds: false,
+                world_seed: rand::random(),
+                reseed_button: Default::default(),
+                preview_button: Default::default(),
+                preview: None,
+                neighbor_tx: None,
+                neighbor_stop: None,
                 run_simulation_button: Default::default(),
+                warnings: vec![],
+                start_anyway_button: Default::default(),
+                clamp_settings_button: Default::default(),
                 load_save_button: Default::default(),
                 save_simulation_button: Default::default(),
+                export_genome_button: Default::default(),
+                raise_spawn_button: Default::default(),
+                reseed_now_button: Default::default(),
+                plant_button: Default::default(),
+                genome_library: vec![],
+                planting: None,
+                add_genome_button: Default::default(),
+                forget_genome_button: Default::default(),
+                stop_planting_button: Default::default(),
+                export_stats_button: Default::default(),
+                export_lineages_button: Default::default(),
+                tracking_ancestry: false,
+                showing_phylogeny: false,
+                phylogeny: Default::default(),
+                export_newick_button: Default::default(),
+                export_graphviz_button: Default::default(),
+                dismiss_alert_button: Default::default(),
+                alert: None,
                 toggle_spawn_rate_type_button: Default::default(),
                 is_inverse_rate_type: INITIAL_IS_INVERSE_RATE,
                 spawn_slider: Default::default(),
@@ -192,7 +1039,65 @@ impl<'a> Application for EvonomicsWorld {
                     INITIAL_SPAWN_RATE,
                 ),
                 toggle_run_button: Default::default(),
+                step_button: Default::default(),
                 toggle_grid_button: Default::default(),
+                toggle_stats_button: Default::default(),
+                filter_input: Default::default(),
+                filter_text: String::new(),
+                filter_matches: None,
+                view_settings: sim::ViewSettings {
+                    terrain: style::skin().terrain(),
+                    ..Default::default()
+                },
+                brush: None,
+                brush_radius_slider: Default::default(),
+                brush_radius: 2,
+                stroke_pending: false,
+                journal: None,
+                undo_button: Default::default(),
+                redo_button: Default::default(),
+                lineage_legend: vec![],
+                selected_lineage: None,
+                lineage_name_input: Default::default(),
+                lineage_name_text: String::new(),
+                show_stats: true,
+                session: Default::default(),
+                saved_session: Default::default(),
+                session_path: flags.session,
+                sample_each_frame: false,
+                sample_ticks_slider: Default::default(),
+                sample_ticks: 1,
+                recording_timelapse: false,
+                timelapse_button: Default::default(),
+                regenerate_button: Default::default(),
+                regenerate_sources: false,
+                timelapse_every_slider: Default::default(),
+                timelapse_every: 100,
+                timelapse_downscale_slider: Default::default(),
+                timelapse_downscale: 1,
+                recording_tape: false,
+                tape_button: Default::default(),
+                tape_every_slider: Default::default(),
+                tape_every: 1,
+                show_ticker: false,
+                ticker: VecDeque::new(),
+                render_button: Default::default(),
+                render_pixels_slider: Default::default(),
+                render_pixels: 16,
+                render_overlays: true,
+                database: None,
+                database_button: Default::default(),
+                runs_root: flags.runs,
+                run: None,
+                minimized: false,
+                graphs_stale: false,
+                graphs_drawn: None,
+                pending_view: None,
+                pending_delta: None,
+                pending_hover: None,
+                hover_asked: None,
+                pending_samples: VecDeque::new(),
+                parked: None,
                 speed_slider: Default::default(),
                 speed: 1,
                 cell_count: 0,
@@ -203,16 +1108,74 @@ impl<'a> Application for EvonomicsWorld {
                 width: INITIAL_WIDTH,
                 grid_openness_slider: Default::default(),
                 openness: 5,
+                corridor_width_slider: Default::default(),
+                corridor_width: 0,
+                smoothing_slider: Default::default(),
+                smoothing: 0,
+                edges: sim::Edges::default(),
+                edge_width_slider: Default::default(),
                 cornacopia_probability_slider: Default::default(),
-                cornacopia_probability: 0.1,
+                cornacopia_probability: 0.01,
                 cornacopia_bounty_slider: Default::default(),
                 cornacopia_bounty: 16,
                 general_food_slider: Default::default(),
                 cell_food_probability: 0.1,
                 mutation_probability_slider: Default::default(),
-                mutation_chance: 0.01,
+                mutation_chance: 0.0002,
+                direct_exchange: false,
+                sense_life: false,
+                sense_fills: false,
+                starvation_grace: 0,
+                starvation_grace_slider: Default::default(),
+                stepped_percent: 100.0,
+                stepped_slider: Default::default(),
+                pipeline: sim::Pipeline::default(),
+                sanitization: sim::Sanitization::default(),
+                proceeds: sim::Proceeds::default(),
+                freeze: sim::Freeze::default(),
+                circuit_breaker: false,
+                breaker_move_slider: Default::default(),
+                breaker_move_percent: 20.0,
+                breaker_window_slider: Default::default(),
+                breaker_window: 10,
+                breaker_halt_slider: Default::default(),
+                breaker_halt: 20,
+                demurrage: false,
+                demurrage_slider: Default::default(),
+                demurrage_percent: 0.1,
+                demurrage_carried: false,
+                inheritance_tax: 0.0,
+                inheritance_tax_slider: Default::default(),
+                exhaustion: sim::Exhaustion::default(),
+                signal_cost: 0.0,
+                signal_cost_slider: Default::default(),
+                capped: false,
+                capacity_slider: Default::default(),
+                capacity: 500,
+                spoilage_slider: Default::default(),
+                spoilage_percent: 0.0,
+                spoils_above_slider: Default::default(),
+                spoils_above: 200,
+                metabolism: 0.0,
+                metabolism_slider: Default::default(),
+                recovery: false,
+                recovery_population_slider: Default::default(),
+                recovery_population: 100,
+                input_noise_slider: Default::default(),
+                input_noise: 0.0,
+                forgetting_slider: Default::default(),
+                forgetting: 0.0,
+                mutations: Default::default(),
+                mutation_sliders: Default::default(),
+                recover_from_hall: false,
                 cornacopia_count_probability_slider: Default::default(),
-                cornacopia_count_probability: 0.005,
+                cornacopia_count_probability: 0.0005,
+                chance_inputs: Default::default(),
+                fertile_tiles: 0,
+                source_tiles: [0; 3],
+                tiers: sim::Tiers::default(),
+                regions: vec![],
+                region_populations: vec![],
                 menu_state: MenuState::MainMenu,
                 is_running_sim: false,
                 next_speed: None,
@@ -223,16 +1186,104 @@ impl<'a> Application for EvonomicsWorld {
                 reserves: VecDeque::new(),
                 buy_volumes: VecDeque::new(),
                 sell_volumes: VecDeque::new(),
+                money_flows: VecDeque::new(),
+                params: VecDeque::new(),
                 mean_ages: VecDeque::new(),
                 max_ages: VecDeque::new(),
+                sanitized: VecDeque::new(),
+                death_causes: VecDeque::new(),
+                halted: VecDeque::new(),
+                order_counts: VecDeque::new(),
+                separators: VecDeque::new(),
+                junk: VecDeque::new(),
+                choices: VecDeque::new(),
+                approximated: VecDeque::new(),
+                populations: VecDeque::new(),
+                unique_genome_counts: VecDeque::new(),
+                signaling: VecDeque::new(),
+                spoiled: VecDeque::new(),
+                metabolized: VecDeque::new(),
+                applied: None,
+                depth: VecDeque::new(),
+                selection: Default::default(),
+                lineage_prices: VecDeque::new(),
+                candles: VecDeque::new(),
+                sonifier: None,
+                candle_window_slider: Default::default(),
+                candle_window: DEFAULT_CANDLE_WINDOW,
+                population: 0,
+                unique_genomes: 0,
+                interned_genomes: 0,
+                inspected: None,
+                compared: VecDeque::new(),
+                genome_diff: vec![],
+                footprint: None,
+                trace: VecDeque::new(),
+                trace_lost: false,
+                expression: None,
+                tracing_brain: false,
+                brain_traces: VecDeque::new(),
+                export_brain_trace_button: Default::default(),
+                trace_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                stop_following_button: Default::default(),
+                export_trace_button: Default::default(),
+                auditing: false,
+                audit: None,
+                audit_button: Default::default(),
+                export_audit_button: Default::default(),
                 bid_ask_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                depth_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                lineage_price_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                price_candle_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
                 reserve_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
                 volume_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                money_flow_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                params_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
                 mean_max_age_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                junk_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                choices_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
+                ages: Default::default(),
+                age_pyramid_graph: image::Handle::from_pixels(1, 1, vec![255; 4]),
                 scroll: scrollable::State::new(),
-            },
-            Command::none(),
-        )
+                scenario_watcher: flags.scenario.map(watch::FileWatcher::new),
+                theme_watcher: flags.theme.map(watch::FileWatcher::new),
+                jitter: Default::default(),
+                events: VecDeque::new(),
+                control: flags.control,
+                broadcast: flags.broadcast,
+                spectating: None,
+                demo: None,
+                callout: None,
+                continue_button: Default::default(),
+                tutorial_button: Default::default(),
+                milestones: Default::default(),
+            };
+        world.poll_scenario();
+        // The sim started later is sent the colors of the theme along with the other settings.
+        world.poll_theme();
+        world.restore_session();
+        if let Some(path) = flags.database {
+            world.open_database(path);
+        }
+        if let Some(path) = flags.demo {
+            match demo::Demo::load(&path) {
+                Ok(demo) => {
+                    world.log_event(format!("playing {}", path.display()));
+                    world.demo = Some(demo);
+                }
+                Err(e) => world.alert(format!("failed to load {}: {}", path.display(), e)),
+            }
+        }
+        if let Some(addr) = flags.spectate {
+            world.menu_state = MenuState::Spectating;
+            let frames = spectate::connect(addr.clone());
+            world.spectating = Some((addr, None));
+            return (
+                world,
+                Command::batch(vec![spectated_command(frames), quit_on_ctrl_c()]),
+            );
+        }
+        (world, quit_on_ctrl_c())
     }
 
     fn title(&self) -> String {
@@ -244,56 +1295,279 @@ impl<'a> Application for EvonomicsWorld {
         match message {
             Message::FromSim(from_sim, stream) => {
                 match from_sim {
-                    sim::FromSim::View(view) => match self.grid {
-                        Some(ref mut grd) => {
-                            self.cell_count = view.cells;
-                            grd.update(view.into())
+                    // Frames are put on the grid at most once a displayed frame, so a sim
+                    // sending them faster only has its older ones skipped.
+                    sim::FromSim::View(view) => {
+                        self.pending_view = Some(view);
+                        self.pending_delta = None;
+                    }
+                    // Changes build on each other, so none may be skipped.
+                    sim::FromSim::ViewDelta(delta) => {
+                        match (&mut self.pending_view, &mut self.pending_delta) {
+                            (Some(view), _) => view.apply(delta),
+                            (None, Some(pending)) => pending.then(delta),
+                            (None, None) => self.pending_delta = Some(delta),
+                        }
+                    }
+                    sim::FromSim::ParamApplied { params, rules } => {
+                        self.applied = Some((params, rules));
+                    }
+                    sim::FromSim::FullView {
+                        view,
+                        params,
+                        rules,
+                    } => {
+                        self.applied = Some((params, rules));
+                        self.pending_view = Some(view);
+                        self.pending_delta = None;
+                        // The spawn chance is sent every frame from the spawn rate, so only the
+                        // other sliders are moved to match.
+                        self.mutation_chance = params.mutation_chance;
+                        self.cell_food_probability = params.general_food_chance;
+                        self.cornacopia_probability = params.cornacopia_chance;
+                        self.cornacopia_bounty = params.cornacopia_bounty;
+                        self.direct_exchange = rules.direct_exchange;
+                        self.sense_life = rules.sense_life;
+                        self.sense_fills = rules.sense_fills;
+                        self.starvation_grace = rules.starvation_grace;
+                        self.show_approximation(rules.approximation);
+                        self.pipeline = rules.pipeline;
+                        self.sanitization = rules.sanitization;
+                        self.proceeds = rules.proceeds;
+                        self.freeze = rules.freeze;
+                        self.show_circuit_breaker(rules.circuit_breaker);
+                        self.show_demurrage(rules.demurrage);
+                        self.inheritance_tax = rules.inheritance_tax;
+                        self.exhaustion = rules.exhaustion;
+                        self.signal_cost = rules.signal_cost;
+                        self.show_spoilage(rules.spoilage);
+                        self.metabolism = rules.metabolism;
+                        self.tiers = rules.tiers;
+                    }
+                    sim::FromSim::CellSummary { x, y, summary } => {
+                        if let Some(ref mut grd) = self.grid {
+                            let mut lines = vec![];
+                            if let Some(lineage) = summary.lineage {
+                                lines.push(label_lineage(&self.lineage_legend, lineage));
+                            }
+                            lines.push(format!("food {}, money {}", summary.food, summary.money));
+                            grd.set_tooltip(x, y, lines);
+                        }
+                    }
+                    sim::FromSim::CellDetail { x, y, detail } => match detail {
+                        Some(detail) => {
+                            // The sim starts following the cell, sending its trace after this.
+                            self.trace.clear();
+                            self.trace_lost = false;
+                            self.expression = None;
+                            self.brain_traces.clear();
+                            self.log_event(format!(
+                                "selected ({}, {}) of {}",
+                                x,
+                                y,
+                                label_lineage(&self.lineage_legend, detail.lineage)
+                            ));
+                            self.select_lineage(detail.lineage);
+                            self.compared.push_back((x, y, detail.genome.clone()));
+                            self.inspected = Some((x, y, detail));
+                            if self.compared.len() > 2 {
+                                self.compared.pop_front();
+                            }
+                            if self.compared.len() == 2 {
+                                self.genome_diff =
+                                    sim::diff(&self.compared[0].2, &self.compared[1].2);
+                            }
+                            if let Some(ref mut grd) = self.grid {
+                                grd.set_marked(self.compared.iter().map(|&(x, y, _)| (x, y)).collect());
+                            }
                         }
-                        None => {}
+                        None => match (self.planting, &mut self.sim_tx) {
+                            (Some(index), Some(tx)) => {
+                                let genome = self.genome_library[index].1.clone();
+                                tx.try_send(sim::ToSim::Plant {
+                                    genome,
+                                    population: 1,
+                                    pos: Some((x, y)),
+                                })
+                                .ok();
+                            }
+                            _ => self.log_event(format!("no cell to inspect at ({}, {})", x, y)),
+                        },
                     },
-                    sim::FromSim::Stats {
-                        ask,
-                        bid,
-                        reserve,
-                        buy_volume,
-                        sell_volume,
-                        mean_age,
-                        max_age,
+                    sim::FromSim::Lineages { events, legend } => {
+                        for event in events {
+                            if let sim::LineageEvent::Extinct {
+                                postmortem: Some(ref postmortem),
+                                ..
+                            } = event
+                            {
+                                self.save_postmortem(postmortem);
+                            }
+                            self.log_event(describe_lineage_event(event));
+                        }
+                        self.lineage_legend = legend;
+                    }
+                    sim::FromSim::Phylogeny(phylogeny) => self.phylogeny.set(phylogeny),
+                    sim::FromSim::Saved { path, error: None } => {
+                        self.log_event(format!("saved to {}", path.display()))
+                    }
+                    sim::FromSim::Saved {
+                        path,
+                        error: Some(e),
+                    } => self.alert(format!("failed to save to {}: {}", path.display(), e)),
+                    sim::FromSim::AncestryExported {
+                        path,
+                        result: Ok(brains),
+                    } => self.log_event(format!(
+                        "exported the ancestry of {} brains to {}",
+                        brains,
+                        path.display()
+                    )),
+                    sim::FromSim::AncestryExported {
+                        path,
+                        result: Err(e),
+                    } => self.alert(format!(
+                        "failed to export the ancestry to {}: {}",
+                        path.display(),
+                        e
+                    )),
+                    sim::FromSim::Rendered { path, error: None } => {
+                        self.log_event(format!("rendered to {}", path.display()))
+                    }
+                    sim::FromSim::Rendered {
+                        path,
+                        error: Some(e),
+                    } => self.alert(format!("failed to render to {}: {}", path.display(), e)),
+                    sim::FromSim::Halted(halt) => self.log_event(format!(
+                        "trading halted for {} ticks after prices ranged from {} to {}",
+                        halt.ticks, halt.low, halt.high
+                    )),
+                    sim::FromSim::ReserveShort(shortfall) => {
+                        self.log_event(match shortfall.exhaustion {
+                            sim::Exhaustion::Halt => format!(
+                                "the reserve ran out of money and stopped buying, leaving {} food unsold to it",
+                                shortfall.food
+                            ),
+                            sim::Exhaustion::Mint => format!(
+                                "the reserve ran out of money and minted {} to buy the food offered to it",
+                                shortfall.food
+                            ),
+                            sim::Exhaustion::Ration => format!(
+                                "the reserve ran short of money and rationed it, leaving {} food unsold to it",
+                                shortfall.food
+                            ),
+                        })
+                    }
+                    sim::FromSim::Recovered(recovered) => self.log_event(format!(
+                        "every cell died, so {} cells were spawned from {}",
+                        recovered.population, recovered.seeds
+                    )),
+                    sim::FromSim::Reseeded(reseeded) => self.log_event(format!(
+                        "spawned {} cells from {}",
+                        reseeded.population, reseeded.seeds
+                    )),
+                    sim::FromSim::Planted { population: 0 } => {
+                        self.log_event("there was no open tile to plant the genome on".to_owned())
+                    }
+                    sim::FromSim::Planted { population } => {
+                        self.log_event(format!("planted {} copies of the genome", population))
+                    }
+                    sim::FromSim::Journal(update) => {
+                        // New interventions are logged as they are, or not at all for strokes
+                        // of a brush, while the journal keeps every one.
+                        if update.reversed {
+                            self.log_event(update.event.clone());
+                        }
+                        if let Some(ref run) = self.run {
+                            if let Err(e) = run.journal(update.tick, &update.event) {
+                                self.alert(format!("failed to write the journal: {}", e));
+                            }
+                        }
+                        self.journal = Some(update);
+                    }
+                    sim::FromSim::Floorplan(floorplan) => {
+                        if let Some(ref mut grid) = self.grid {
+                            grid.set_floorplan(&floorplan);
+                        }
+                    }
+                    sim::FromSim::Regenerated(regenerated) => {
+                        self.log_event(format!(
+                            "regenerated the terrain from seed {}, moving {} cells off new walls",
+                            regenerated.seed, regenerated.relocated
+                        ));
+                        if regenerated.lost > 0 {
+                            self.log_event(format!(
+                                "{} cells found no open tile and died",
+                                regenerated.lost
+                            ));
+                        }
+                    }
+                    sim::FromSim::TimelapseStopped { frames, error } => {
+                        self.recording_timelapse = false;
+                        match error {
+                            None => self.log_event(format!(
+                                "timelapse stopped after {} frames",
+                                frames
+                            )),
+                            Some(e) => self.alert(format!(
+                                "timelapse stopped after {} frames: {}",
+                                frames, e
+                            )),
+                        }
+                    }
+                    sim::FromSim::TapeStopped {
+                        path,
+                        trades,
+                        error,
                     } => {
-                        self.bids.push_back(bid.unwrap_or(0));
-                        self.asks.push_back(ask.unwrap_or(0));
-                        self.reserves.push_back(reserve);
-                        self.buy_volumes.push_back(buy_volume);
-                        self.sell_volumes.push_back(sell_volume);
-                        self.mean_ages.push_back(mean_age);
-                        self.max_ages.push_back(max_age);
-                        if self.bids.len() > MAX_GRAPH_TIMES {
-                            self.bids.pop_front();
-                            self.asks.pop_front();
-                            self.reserves.pop_front();
-                            self.buy_volumes.pop_front();
-                            self.sell_volumes.pop_front();
-                            self.mean_ages.pop_front();
-                            self.max_ages.pop_front();
+                        self.recording_tape = false;
+                        match error {
+                            None => self.log_event(format!(
+                                "recorded {} trades to {}",
+                                trades,
+                                path.display()
+                            )),
+                            Some(e) => self.alert(format!(
+                                "tape {} stopped after {} trades: {}",
+                                path.display(),
+                                trades,
+                                e
+                            )),
+                        }
+                    }
+                    sim::FromSim::Stats(samples) => self.pending_samples.extend(samples),
+                    sim::FromSim::Footprint(footprint) => self.footprint = Some(footprint),
+                    sim::FromSim::Audited(audit) => self.audited(audit),
+                    sim::FromSim::Expression(expression) => self.expression = Some(expression),
+                    sim::FromSim::BrainTrace(traces) => {
+                        self.brain_traces.extend(traces);
+                        while self.brain_traces.len() > MAX_BRAIN_TRACES {
+                            self.brain_traces.pop_front();
+                        }
+                    }
+                    sim::FromSim::Ticker(prints) => {
+                        self.ticker.extend(prints);
+                        while self.ticker.len() > sim::TICKER_PRINTS {
+                            self.ticker.pop_front();
+                        }
+                    }
+                    sim::FromSim::Trace { points, lost } => {
+                        self.trace.extend(points);
+                        while self.trace.len() > MAX_TRACE {
+                            self.trace.pop_front();
+                        }
+                        if lost {
+                            self.trace_lost = true;
+                            self.log_event("the followed cell died or combined".to_owned());
                         }
-                        // Update the bid/ask graph.
-                        let bids: Vec<i32> = self.bids.clone().into();
-                        let asks: Vec<i32> = self.asks.clone().into();
-                        let reserves: Vec<u32> = self.reserves.clone().into();
-                        let buy_volumes: Vec<u32> = self.buy_volumes.clone().into();
-                        let sell_volumes: Vec<u32> = self.sell_volumes.clone().into();
-                        let mean_ages: Vec<u64> = self.mean_ages.clone().into();
-                        let max_ages: Vec<u64> = self.max_ages.clone().into();
-                        self.bid_ask_graph = plot::graph_bids_asks(&bids, &asks)
-                            .expect("failed to create bid/ask graph");
-                        self.reserve_graph = plot::graph_reserves(&reserves)
-                            .expect("failed to create reserves graph");
-                        self.volume_graph = plot::graph_volumes(&buy_volumes, &sell_volumes)
-                            .expect("failed to create volume graph");
-                        self.mean_max_age_graph = plot::graph_mean_max_age(&mean_ages, &max_ages)
-                            .expect("failed to create mean max age graph");
+                        self.graphs_stale = true;
                     }
                 }
+                // A sim sending stats faster than they are taken in waits until they are.
+                if self.pending_samples.len() >= MAX_PENDING_SAMPLES {
+                    self.parked = Some(stream);
+                    return Command::none();
+                }
                 return reciever_command(stream);
             }
             Message::AspectChanged(new_aspect) => {
@@ -302,49 +1576,179 @@ impl<'a> Application for EvonomicsWorld {
             Message::OpennessSet(new_openness) => {
                 self.openness = new_openness as usize;
             }
+            Message::CorridorWidthSet(width) => {
+                self.corridor_width = width as usize;
+            }
+            Message::SmoothingSet(passes) => {
+                self.smoothing = passes as usize;
+            }
+            Message::EdgePolicyChanged(policy) => self.edges.policy = policy,
+            Message::EdgeWidthSet(width) => self.edges.width = width as usize,
+            Message::EdgesSet(edges) => self.edges = edges,
+            Message::TiersSet(tiers) => {
+                self.tiers = tiers;
+                self.send_rules();
+            }
             Message::CornacopiaProbabilityChanged(val) => {
                 self.cornacopia_probability = val as f64;
-                match self.sim_tx {
-                    Some(ref mut tx) => {
-                        // If the channel is full, dont send it.
-                        tx.try_send(sim::ToSim::SetCornacopiaChance(val as f64 / 10.0))
-                            .ok();
-                    }
-                    None => {}
-                }
+                self.send_controls(move || sim::ToSim::SetCornacopiaChance(val as f64));
             }
             Message::CornacopiaBountyChanged(val) => {
                 self.cornacopia_bounty = val as u32;
-                match self.sim_tx {
-                    Some(ref mut tx) => {
-                        // If the channel is full, dont send it.
-                        tx.try_send(sim::ToSim::SetCornacopiaBounty(val as u32))
-                            .ok();
-                    }
-                    None => {}
-                }
+                self.send_controls(move || sim::ToSim::SetCornacopiaBounty(val as u32));
             }
             Message::GeneralFoodProbabilityChanged(val) => {
                 self.cell_food_probability = val as f64;
-                match self.sim_tx {
-                    Some(ref mut tx) => {
-                        // If the channel is full, dont send it.
-                        tx.try_send(sim::ToSim::SetGeneralFoodChance(val as f64))
-                            .ok();
-                    }
-                    None => {}
-                }
+                self.send_controls(move || sim::ToSim::SetGeneralFoodChance(val as f64));
+            }
+            Message::DirectExchangeToggled(enabled) => {
+                self.direct_exchange = enabled;
+                self.send_rules();
+            }
+            Message::SenseLifeToggled(enabled) => {
+                self.sense_life = enabled;
+                self.send_rules();
+            }
+            Message::SenseFillsToggled(enabled) => {
+                self.sense_fills = enabled;
+                self.send_rules();
+            }
+            Message::StarvationGraceChanged(grace) => {
+                self.starvation_grace = grace as u32;
+                self.send_rules();
+            }
+            Message::SteppedChanged(percent) => {
+                self.stepped_percent = (percent as f64).round();
+                self.send_rules();
+            }
+            Message::PipelineChanged(pipeline) => {
+                self.pipeline = pipeline;
+                self.send_rules();
+            }
+            Message::SanitizationChanged(sanitization) => {
+                self.sanitization = sanitization;
+                self.send_rules();
+            }
+            Message::ProceedsChanged(proceeds) => {
+                self.proceeds = proceeds;
+                self.send_rules();
+            }
+            Message::ExhaustionChanged(exhaustion) => {
+                self.exhaustion = exhaustion;
+                self.send_rules();
+            }
+            Message::FreezeChanged(freeze) => {
+                self.freeze = freeze;
+                self.send_rules();
+            }
+            Message::CircuitBreakerToggled(enabled) => {
+                self.circuit_breaker = enabled;
+                self.send_rules();
+            }
+            Message::BreakerMoveChanged(percent) => {
+                self.breaker_move_percent = percent as f64;
+                self.send_rules();
+            }
+            Message::BreakerWindowChanged(window) => {
+                self.breaker_window = window as usize;
+                self.send_rules();
+            }
+            Message::BreakerHaltChanged(halt) => {
+                self.breaker_halt = halt as usize;
+                self.send_rules();
+            }
+            Message::CircuitBreakerSet(breaker) => {
+                self.show_circuit_breaker(breaker);
+                self.send_rules();
+            }
+            Message::DemurrageToggled(enabled) => {
+                self.demurrage = enabled;
+                self.send_rules();
+            }
+            Message::DemurrageChanged(percent) => {
+                self.demurrage_percent = percent as f64;
+                self.send_rules();
+            }
+            Message::DemurrageCarriedToggled(carried) => {
+                self.demurrage_carried = carried;
+                self.send_rules();
+            }
+            Message::DemurrageSet(demurrage) => {
+                self.show_demurrage(demurrage);
+                self.send_rules();
+            }
+            Message::InheritanceTaxChanged(percent) => {
+                self.inheritance_tax = percent as f64;
+                self.send_rules();
+            }
+            Message::SignalCostChanged(cost) => {
+                self.signal_cost = cost as f64;
+                self.send_rules();
+            }
+            Message::CappedToggled(capped) => {
+                self.capped = capped;
+                self.send_rules();
+            }
+            Message::CapacityChanged(capacity) => {
+                self.capacity = capacity as u32;
+                self.send_rules();
+            }
+            Message::SpoilageChanged(percent) => {
+                self.spoilage_percent = percent as f64;
+                self.send_rules();
+            }
+            Message::SpoilsAboveChanged(above) => {
+                self.spoils_above = above as u32;
+                self.send_rules();
+            }
+            Message::SpoilageSet(spoilage) => {
+                self.show_spoilage(spoilage);
+                self.send_rules();
+            }
+            Message::MetabolismChanged(cost) => {
+                self.metabolism = cost as f64;
+                let metabolism = self.metabolism;
+                self.send_controls(move || sim::ToSim::SetMetabolismCost(metabolism));
+            }
+            Message::RecoveryToggled(enabled) => {
+                self.recovery = enabled;
+                self.send_recovery();
+            }
+            Message::RecoveryPopulationChanged(population) => {
+                self.recovery_population = population as usize;
+                self.send_recovery();
+            }
+            Message::RecoverFromHallToggled(enabled) => {
+                self.recover_from_hall = enabled;
+                self.send_recovery();
+            }
+            Message::RecoverySet(recovery) => {
+                self.recovery = true;
+                self.recovery_population = recovery.population;
+                self.recover_from_hall = recovery.seeds == sim::Seeds::HallOfFame;
+                self.send_recovery();
+            }
+            Message::InputNoiseChanged(noise) => {
+                self.input_noise = noise as f64;
+                self.send_noise();
+            }
+            Message::ForgettingChanged(forgetting) => {
+                self.forgetting = forgetting as f64;
+                self.send_noise();
+            }
+            Message::MutationsChanged(mutations) => {
+                self.mutations = mutations;
+                self.send_controls(move || sim::ToSim::SetMutations(mutations));
+            }
+            Message::NoiseSet(noise) => {
+                self.input_noise = noise.input;
+                self.forgetting = noise.forgetting;
+                self.send_noise();
+                self.log_noise();
             }
             Message::MutationChanceChanged(val) => {
                 self.mutation_chance = val as f64;
-                match self.sim_tx {
-                    Some(ref mut tx) => {
-                        // If the channel is full, dont send it.
-                        tx.try_send(sim::ToSim::SetMutationChance(val as f64 / 50.0))
-                            .ok();
-                    }
-                    None => {}
-                }
+                self.send_controls(move || sim::ToSim::SetMutationChance(val as f64));
             }
             Message::CornacopiaCountProbabilityChanged(val) => {
                 self.cornacopia_count_probability = val as f64;
@@ -357,14 +1761,8 @@ impl<'a> Application for EvonomicsWorld {
                     self.aspect_ratio.get_height(self.width),
                     self.spawn_rate,
                 );
-                match self.sim_tx {
-                    Some(ref mut tx) => {
-                        // If the channel is full, dont send it.
-                        tx.try_send(sim::ToSim::SetSpawnChance(self.spawn_chance))
-                            .ok();
-                    }
-                    None => {}
-                }
+                let chance = self.spawn_chance;
+                self.send_controls(move || sim::ToSim::SetSpawnChance(chance));
             }
             Message::ToggleRateType => {
                 self.is_inverse_rate_type = !self.is_inverse_rate_type;
@@ -374,39 +1772,401 @@ impl<'a> Application for EvonomicsWorld {
                     self.aspect_ratio.get_height(self.width),
                     self.spawn_rate,
                 );
-                match self.sim_tx {
-                    Some(ref mut tx) => {
-                        // If the channel is full, dont send it.
-                        tx.try_send(sim::ToSim::SetSpawnChance(self.spawn_chance))
-                            .ok();
-                    }
-                    None => {}
-                }
+                let chance = self.spawn_chance;
+                self.send_controls(move || sim::ToSim::SetSpawnChance(chance));
+            }
+            Message::SimView if !sanity::warnings(&self.settings()).is_empty() => {
+                self.warnings = sanity::warnings(&self.settings());
             }
-            Message::SimView => {
-                self.menu_state = MenuState::SimMenu;
+            Message::ClampSettings => {
+                let mut settings = self.settings();
+                sanity::clamp(&mut settings);
+                self.spawn_chance = settings.params.spawn_chance;
+                self.mutation_chance = settings.params.mutation_chance;
+                self.cell_food_probability = settings.params.general_food_chance;
+                self.width = settings.width;
+                self.warnings.clear();
+                return self.update(Message::StartAnyway);
+            }
+            Message::SimView | Message::StartAnyway => {
+                self.warnings.clear();
+                let shape = self.world_shape();
+                let mut sim = self.new_sim();
+                if !self.linked_worlds {
+                    let start = self.start_sim(sim);
+                    self.log_edges();
+                    return start;
+                }
 
-                let (sim_tx, sim_rx, sim_runner) = sim::run_sim(
-                    3,
-                    3,
-                    self.width,
-                    self.aspect_ratio.get_height(self.width),
-                    self.openness,
-                    self.cornacopia_count_probability / 10.0,
-                );
+                let neighbor_shape = WorldShape {
+                    seed: rand::random(),
+                    ..shape
+                };
+                let mut neighbor = sim::Sim::from_layout(&neighbor_shape.generate(), None);
+                neighbor.set_seed(neighbor_shape.seed);
+                // Each world runs under its own parameters, so the neighbor is given the same.
+                neighbor.set_rules(self.rules());
+                neighbor.set_params(self.params());
+                neighbor.set_noise(self.noise());
+                neighbor.set_mutations(self.mutations);
+                let (link, neighbor_link, coordinator) = sim::link_worlds();
+                sim.open_portals(link, PORTALS);
+                neighbor.open_portals(neighbor_link, PORTALS);
+                let start = self.start_sim(sim);
+                self.log_edges();
 
-                self.sim_tx = Some(sim_tx);
-                self.grid = Some(grid::Grid::new(
-                    self.width,
-                    self.aspect_ratio.get_height(self.width),
+                let (mut neighbor_tx, neighbor_rx, neighbor_stop, neighbor_runner) =
+                    sim::run_sim(3, 3, neighbor);
+                // Nothing graphs the neighbor, and its fresh channel has room for this.
+                neighbor_tx.try_send(sim::ToSim::SubscribeStats(false)).ok();
+                self.neighbor_tx = Some(neighbor_tx);
+                self.neighbor_stop = Some(neighbor_stop);
+                self.log_event(format!(
+                    "linked to a neighboring world through {} portals",
+                    PORTALS
                 ));
-
                 return Command::batch(vec![
-                    Command::perform(sim_runner, |_| Message::Null),
-                    reciever_command(sim_rx),
+                    start,
+                    Command::perform(neighbor_runner, |_| Message::Null),
+                    Command::perform(coordinator, |_| Message::Null),
+                    neighbor_command(neighbor_rx),
                 ]);
             }
+            Message::FromNeighbor(from_sim, stream) => {
+                if let sim::FromSim::Lineages { events, .. } = from_sim {
+                    for event in events {
+                        self.log_event(format!(
+                            "neighbor: {}",
+                            describe_lineage_event(event)
+                        ));
+                    }
+                }
+                return neighbor_command(stream);
+            }
+            Message::Spectated(received, stream) => match received {
+                Ok(spectate::Frame::View {
+                    width,
+                    height,
+                    colors,
+                    cells,
+                    ticks,
+                }) => {
+                    // The watched window may start a world of another size at any time.
+                    let resized = match self.grid {
+                        Some(ref grd) => grd.dimensions() != (width, height),
+                        None => true,
+                    };
+                    if resized {
+                        self.grid = Some(grid::Grid::new(width, height));
+                    }
+                    self.cell_count = cells;
+                    if let Some(ref mut grd) = self.grid {
+                        grd.update(spectate::to_view(width, height, colors, cells, ticks).into());
+                    }
+                    return spectated_command(stream);
+                }
+                Ok(spectate::Frame::Stats {
+                    tick,
+                    header,
+                    values,
+                }) => {
+                    if let Some((_, ref mut stats)) = self.spectating {
+                        *stats = Some((tick, header, values));
+                    }
+                    return spectated_command(stream);
+                }
+                // The connection is over, so nothing more will come.
+                Err(e) => self.alert(e),
+            },
+            Message::LinkedWorldsToggled(linked) => self.linked_worlds = linked,
+            Message::Reseed => self.world_seed = rand::random(),
+            Message::PreviewWorld => {
+                let shape = self.world_shape();
+                return Command::perform(
+                    tokio::task::spawn_blocking(move || WorldPreview::generate(shape)),
+                    |preview| Message::WorldPreviewed(preview.ok()),
+                );
+            }
+            Message::WorldPreviewed(preview) => {
+                // A preview finished after the controls moved on is of a world nobody asked for.
+                if let Some(preview) = preview.filter(|p| p.shape == self.world_shape()) {
+                    self.preview = Some(preview);
+                }
+            }
+            Message::PickFile(action) => {
+                let kind = action.kind();
+                if let (false, Some(ref run)) = (action.reads(), &self.run) {
+                    let path = run.artifact(kind, self.total_tick_count);
+                    return self.update(Message::FilePicked(action, Some(path)));
+                }
+                return if action.reads() {
+                    Command::perform(io::pick_open(kind), move |path| {
+                        Message::FilePicked(action, path)
+                    })
+                } else {
+                    Command::perform(io::pick_save(kind), move |path| {
+                        Message::FilePicked(action, path)
+                    })
+                };
+            }
+            Message::FilePicked(_, None) => {}
+            Message::FilePicked(FileAction::PlantGenome, Some(path)) => {
+                match io::read_genome(&path) {
+                    Ok(genome) => {
+                        self.log_event(format!("loaded the genome {}", path.display()));
+                        return self.send_must(sim::ToSim::Plant {
+                            genome,
+                            population: self.recovery_population,
+                            pos: None,
+                        });
+                    }
+                    Err(e) => {
+                        self.alert(format!("failed to load the genome {}: {}", path.display(), e))
+                    }
+                }
+            }
+            Message::FilePicked(FileAction::AddGenome, Some(path)) => {
+                match io::read_genome(&path) {
+                    Ok(genome) => self.add_to_library(path, genome),
+                    Err(e) => {
+                        self.alert(format!("failed to load the genome {}: {}", path.display(), e))
+                    }
+                }
+            }
+            Message::PlantingSelected(index) => self.planting = Some(index),
+            Message::StopPlanting => self.planting = None,
+            Message::ForgetGenome => {
+                if let Some(index) = self.planting.take() {
+                    self.genome_library.remove(index);
+                    self.session.genomes.remove(index);
+                }
+            }
+            Message::FilePicked(FileAction::LoadWorld, Some(path)) => {
+                match sim::Sim::load(&path, self.control.clone()) {
+                    Ok(sim) => {
+                        self.direct_exchange = sim.rules().direct_exchange;
+                        self.sense_life = sim.rules().sense_life;
+                        self.sense_fills = sim.rules().sense_fills;
+                        self.starvation_grace = sim.rules().starvation_grace;
+                        self.show_approximation(sim.rules().approximation);
+                        self.pipeline = sim.rules().pipeline;
+                        self.sanitization = sim.rules().sanitization;
+                        self.proceeds = sim.rules().proceeds;
+                        self.freeze = sim.rules().freeze;
+                        self.show_circuit_breaker(sim.rules().circuit_breaker);
+                        self.show_demurrage(sim.rules().demurrage);
+                        self.inheritance_tax = sim.rules().inheritance_tax;
+                        self.exhaustion = sim.rules().exhaustion;
+                        self.signal_cost = sim.rules().signal_cost;
+                        self.show_spoilage(sim.rules().spoilage);
+                        self.metabolism = sim.rules().metabolism;
+                        self.tiers = sim.rules().tiers;
+                        self.width = sim.width();
+                        self.aspect_ratio = if sim.height() == sim.width() {
+                            AspectRatio::OneToOne
+                        } else {
+                            AspectRatio::SixteenToTen
+                        };
+                        self.log_event(format!("loaded {}", path.display()));
+                        return self.start_sim(sim);
+                    }
+                    Err(e) => self.alert(format!("failed to load {}: {}", path.display(), e)),
+                }
+            }
+            Message::FilePicked(FileAction::SaveWorld, Some(path)) => match self.sim_tx {
+                Some(ref tx) => {
+                    // Unlike the sliders this must not be dropped when the channel is full.
+                    let mut tx = tx.clone();
+                    self.log_event(format!("saving to {}", path.display()));
+                    return Command::perform(
+                        async move { tx.send(sim::ToSim::Save(path)).await.ok() },
+                        |_| Message::Null,
+                    );
+                }
+                None => {}
+            },
+            Message::FilePicked(FileAction::ExportGenome, Some(path)) => {
+                match self.compared.back() {
+                    Some((_, _, genome)) => match io::write_genome(&path, genome) {
+                        Ok(()) => {
+                            let genome = genome.clone();
+                            self.log_event(format!("exported genome to {}", path.display()));
+                            self.add_to_library(path, genome);
+                        }
+                        Err(e) => self.alert(format!(
+                            "failed to export genome to {}: {}",
+                            path.display(),
+                            e
+                        )),
+                    },
+                    None => self.alert("click a cell to choose the genome to export".to_owned()),
+                }
+            }
+            Message::FilePicked(FileAction::ExportLineages, Some(path)) => {
+                match io::write_csv(&path, LINEAGES_HEADER, self.lineage_rows()) {
+                    Ok(()) => self.log_event(format!("exported lineages to {}", path.display())),
+                    Err(e) => self.alert(format!(
+                        "failed to export lineages to {}: {}",
+                        path.display(),
+                        e
+                    )),
+                }
+            }
+            Message::FilePicked(FileAction::ExportStats, Some(path)) => {
+                self.flush_samples();
+                match io::write_csv(&path, STATS_HEADER, self.stats_rows()) {
+                    Ok(()) => self.log_event(format!("exported stats to {}", path.display())),
+                    Err(e) => self.alert(format!(
+                        "failed to export stats to {}: {}",
+                        path.display(),
+                        e
+                    )),
+                }
+            }
+            Message::FilePicked(FileAction::ExportTrace, Some(path)) => {
+                match io::write_csv(&path, TRACE_HEADER, self.trace_rows()) {
+                    Ok(()) => self.log_event(format!("exported trace to {}", path.display())),
+                    Err(e) => self.alert(format!(
+                        "failed to export trace to {}: {}",
+                        path.display(),
+                        e
+                    )),
+                }
+            }
+            Message::FilePicked(FileAction::ExportBrainTrace, Some(path)) => {
+                let traces: Vec<_> = self.brain_traces.iter().cloned().collect();
+                match io::write_brain_traces(&path, &traces) {
+                    Ok(()) => self.log_event(format!(
+                        "exported {} traced decisions to {}",
+                        traces.len(),
+                        path.display()
+                    )),
+                    Err(e) => self.alert(format!(
+                        "failed to export the brain trace to {}: {}",
+                        path.display(),
+                        e
+                    )),
+                }
+            }
+            Message::FilePicked(FileAction::ExportAudit, Some(path)) => self.export_audit(&path),
+            Message::FilePicked(FileAction::ExportNewick, Some(path)) => {
+                return self.export_ancestry(path, sim::AncestryFormat::Newick)
+            }
+            Message::FilePicked(FileAction::ExportGraphviz, Some(path)) => {
+                return self.export_ancestry(path, sim::AncestryFormat::Graphviz)
+            }
+            Message::FilePicked(FileAction::RecordStats, Some(path)) => self.open_database(path),
+            Message::FilePicked(FileAction::RenderRegion, Some(path)) => {
+                if let (Some(tx), Some(grid)) = (&self.sim_tx, &self.grid) {
+                    // Unlike the sliders this must not be dropped when the channel is full.
+                    let mut tx = tx.clone();
+                    let rect = grid.visible_cells();
+                    let overlays = if self.render_overlays {
+                        raster::Overlays {
+                            lines: grid.is_showing_lines(),
+                            marked: grid.marked().to_vec(),
+                        }
+                    } else {
+                        raster::Overlays::default()
+                    };
+                    let pixels = self.render_pixels;
+                    self.log_event(format!(
+                        "rendering {} by {} tiles at ({}, {}) to {}",
+                        rect.width,
+                        rect.height,
+                        rect.x,
+                        rect.y,
+                        path.display()
+                    ));
+                    return Command::perform(
+                        async move {
+                            tx.send(sim::ToSim::RenderRegion {
+                                path,
+                                rect,
+                                pixels,
+                                overlays,
+                            })
+                            .await
+                            .ok()
+                        },
+                        |_| Message::Null,
+                    );
+                }
+            }
+            Message::FilePicked(FileAction::RecordTape, Some(path)) => {
+                match tape::Tape::create(&path, self.tape_every) {
+                    Ok(tape) => {
+                        self.log_event(format!("recording trades to {}", path.display()));
+                        self.recording_tape = true;
+                        return self.send_tape(Some(tape));
+                    }
+                    Err(e) => self.alert(format!(
+                        "failed to create the tape {}: {}",
+                        path.display(),
+                        e
+                    )),
+                }
+            }
+            Message::StopFollowing => {
+                self.trace.clear();
+                self.trace_lost = false;
+                self.expression = None;
+                self.brain_traces.clear();
+                self.graphs_stale = true;
+                if let Some(ref tx) = self.sim_tx {
+                    // Unlike the sliders this must not be dropped when the channel is full.
+                    let mut tx = tx.clone();
+                    return Command::perform(
+                        async move { tx.send(sim::ToSim::StopFollowing).await.ok() },
+                        |_| Message::Null,
+                    );
+                }
+            }
+            Message::SonifyToggled(sonify) => {
+                self.sonifier = None;
+                if sonify {
+                    match sonify::Sonifier::new() {
+                        Ok(sonifier) => self.sonifier = Some(sonifier),
+                        Err(e) => self.alert(format!("failed to play the market as sound: {}", e)),
+                    }
+                }
+            }
+            Message::TraceBrainToggled(tracing) => {
+                self.tracing_brain = tracing;
+                return self.send_must(sim::ToSim::TraceBrain(tracing));
+            }
+            Message::TrackAncestryToggled(tracking) => {
+                self.tracking_ancestry = tracking;
+                self.phylogeny.clear();
+                return self.send_must(sim::ToSim::TrackAncestry(tracking));
+            }
+            Message::PhylogenyToggled(showing) => {
+                self.showing_phylogeny = showing;
+                if showing {
+                    return self.update(Message::AskPhylogeny);
+                }
+            }
+            Message::AskPhylogeny => {
+                if let (true, Some(ref mut tx)) = (self.tracking_ancestry, &mut self.sim_tx) {
+                    // A sim too busy to answer is asked again next time.
+                    tx.try_send(sim::ToSim::AskPhylogeny).ok();
+                }
+            }
+            Message::Phylogeny(message) => self.phylogeny.update(message),
+            Message::AuditMatching => {
+                if let Some(ref tx) = self.sim_tx {
+                    self.auditing = true;
+                    // Unlike the sliders this must not be dropped when the channel is full.
+                    let mut tx = tx.clone();
+                    return Command::perform(
+                        async move { tx.send(sim::ToSim::Audit(AUDIT_TICKS)).await.ok() },
+                        |_| Message::Null,
+                    );
+                }
+            }
+            Message::DismissAlert => self.alert = None,
             Message::MainView => {
+                self.remember_viewport();
                 self.menu_state = MenuState::MainMenu;
                 self.is_running_sim = false;
             }
@@ -423,10 +2183,43 @@ impl<'a> Application for EvonomicsWorld {
             Message::ToggleSim => {
                 self.is_running_sim = !self.is_running_sim;
             }
-            Message::ToggleGrid => match self.grid {
-                Some(ref mut grd) => grd.toggle_lines(),
-                None => {}
-            },
+            // A running world is already ticking, so a step would only be lost among its ticks.
+            Message::StepTick if !self.is_running_sim && self.sim_tx.is_some() => {
+                self.total_tick_count += 1;
+                if let Some(ref mut neighbor_tx) = self.neighbor_tx {
+                    neighbor_tx.try_send(sim::ToSim::Tick(1)).ok();
+                }
+                self.update(Message::SpawnRateChanged(self.spawn_rate as f32));
+                // Unlike the ticks of a running world, a step is asked for and must not be dropped.
+                return self.send_must(sim::ToSim::Tick(1));
+            }
+            Message::StepTick => {}
+            Message::DemoStep => return self.step_demo(),
+            Message::ContinueDemo => {
+                if let Some(ref mut demo) = self.demo {
+                    demo.resume();
+                }
+                return self.step_demo();
+            }
+            Message::StartTutorial => {
+                self.log_event("playing the tutorial".to_owned());
+                self.demo = Some(demo::Demo::tutorial());
+                return self.step_demo();
+            }
+            Message::ToggleGrid => match self.grid {
+                Some(ref mut grd) => {
+                    grd.toggle_lines();
+                    self.session.grid_lines = grd.is_showing_lines();
+                }
+                None => {}
+            },
+            Message::FloorplanToggled(shown) => {
+                self.session.floorplan = shown;
+                if let Some(ref mut grid) = self.grid {
+                    grid.set_show_floorplan(shown);
+                }
+            }
+            Message::Frame => return self.frame(),
             Message::Tick => {
                 match self.sim_tx {
                     Some(ref mut tx) => {
@@ -437,11 +2230,270 @@ impl<'a> Application for EvonomicsWorld {
                             }
                             None => {}
                         }
+                        if let Some(ref mut neighbor_tx) = self.neighbor_tx {
+                            // If the channel is full, dont send it.
+                            neighbor_tx.try_send(sim::ToSim::Tick(self.speed)).ok();
+                        }
                         self.update(Message::SpawnRateChanged(self.spawn_rate as f32));
+                        self.jitter_params();
+                        // A held brush keeps painting even when the cursor stays still.
+                        if let Some((x, y)) = self.grid.as_ref().and_then(grid::Grid::painting) {
+                            self.paint(x, y);
+                        }
                     }
                     None => {}
                 }
             }
+            Message::ToggleStats => {
+                let was_visible = self.stats_visible();
+                self.show_stats = !self.show_stats;
+                self.session.show_stats = self.show_stats;
+                return self.stats_visibility_changed(was_visible);
+            }
+            Message::WindowResized(width, height) => {
+                let was_visible = self.stats_visible();
+                self.minimized = width == 0 || height == 0;
+                if !self.minimized {
+                    self.session.window = Some((width, height));
+                }
+                return self.stats_visibility_changed(was_visible);
+            }
+            Message::FilterEdited(text) => self.filter_text = text,
+            Message::ChanceEdited(chance, text) => self.chance_inputs[chance as usize].text = text,
+            Message::ChanceSubmitted(chance) => {
+                let text = std::mem::take(&mut self.chance_inputs[chance as usize].text);
+                match text.trim().parse::<f64>() {
+                    Ok(p) if (0.0..=1.0).contains(&p) => {
+                        return self.update(chance.set(p as f32));
+                    }
+                    _ => self.alert(format!(
+                        "the {} must be a probability from 0 to 1, not \"{}\"",
+                        chance.name(),
+                        text.trim()
+                    )),
+                }
+            }
+            Message::ApplyFilter => {
+                let filter = if self.filter_text.trim().is_empty() {
+                    None
+                } else {
+                    match self.filter_text.parse::<sim::Filter>() {
+                        Ok(filter) => Some(filter),
+                        Err(e) => {
+                            self.log_event(format!("invalid filter: {}", e));
+                            return Command::none();
+                        }
+                    }
+                };
+                match filter {
+                    Some(filter) => self.log_event(format!("filtering by {}", filter)),
+                    None => self.log_event("cleared filter".to_owned()),
+                }
+                self.view_settings.filter = filter;
+                return self.send_view_settings();
+            }
+            Message::BrushChanged(brush) => {
+                self.brush = brush;
+                if let Some(ref mut grd) = self.grid {
+                    grd.set_brushing(brush.is_some());
+                }
+            }
+            Message::BrushRadiusChanged(radius) => self.brush_radius = radius as usize,
+            Message::LineageSelected(lineage) => self.select_lineage(lineage),
+            Message::LineageNameEdited(text) => self.lineage_name_text = text,
+            Message::NameLineage => match (self.selected_lineage, self.sim_tx.clone()) {
+                (Some(lineage), Some(mut tx)) => {
+                    let name = match self.lineage_name_text.trim() {
+                        "" => None,
+                        name => Some(name.to_owned()),
+                    };
+                    match name {
+                        Some(ref name) => self.log_event(format!(
+                            "named {} \"{}\"",
+                            label_lineage(&self.lineage_legend, lineage),
+                            name
+                        )),
+                        None => self.log_event(format!(
+                            "cleared the name of {}",
+                            label_lineage(&self.lineage_legend, lineage)
+                        )),
+                    }
+                    // Unlike the sliders this must not be dropped when the channel is full.
+                    return Command::perform(
+                        async move { tx.send(sim::ToSim::NameLineage(lineage, name)).await.ok() },
+                        |_| Message::Null,
+                    );
+                }
+                (None, _) => {
+                    self.log_event("pick a lineage from the legend to name it".to_owned())
+                }
+                (_, None) => {}
+            },
+            Message::SampleEachFrameToggled(each_frame) => {
+                self.sample_each_frame = each_frame;
+                return self.send_sampling();
+            }
+            Message::SampleTicksChanged(ticks) => {
+                let ticks = ticks as usize;
+                if ticks == self.sample_ticks {
+                    return Command::none();
+                }
+                self.sample_ticks = ticks;
+                return self.send_sampling();
+            }
+            Message::ToggleTimelapse => {
+                if self.recording_timelapse {
+                    return self.send_timelapse(None);
+                }
+                if let Some(ref run) = self.run {
+                    let dir = run.timelapse();
+                    return self.update(Message::TimelapseFolderPicked(Some(dir)));
+                }
+                return Command::perform(io::pick_folder(), Message::TimelapseFolderPicked);
+            }
+            Message::TimelapseFolderPicked(None) => {}
+            Message::TimelapseFolderPicked(Some(dir)) => {
+                self.log_event(format!("saving a timelapse to {}", dir.display()));
+                self.recording_timelapse = true;
+                return self.send_timelapse(Some(timelapse::Timelapse::new(
+                    dir,
+                    self.timelapse_every,
+                    self.timelapse_downscale,
+                )));
+            }
+            Message::ToggleTape => {
+                if self.recording_tape {
+                    return self.send_tape(None);
+                }
+                return self.update(Message::PickFile(FileAction::RecordTape));
+            }
+            Message::TapeEveryChanged(every) => self.tape_every = every as u64,
+            Message::RaiseSpawnRate => {
+                let rate = (self.spawn_rate + SPAWN_RATE_STEP).min(1.0);
+                self.log_event(format!("raised the spawn rate to {:.2}", rate));
+                return self.update(Message::SpawnRateChanged(rate as f32));
+            }
+            Message::ReseedNow => {
+                let recovery = sim::Recovery {
+                    population: self.recovery_population,
+                    seeds: if self.recover_from_hall {
+                        sim::Seeds::HallOfFame
+                    } else {
+                        sim::Seeds::Random
+                    },
+                };
+                return self.send_must(sim::ToSim::Reseed(recovery));
+            }
+            Message::TickerToggled(shown) => {
+                self.show_ticker = shown;
+                self.ticker.clear();
+                return self.send_ticker();
+            }
+            Message::CandleWindowChanged(window) => {
+                self.candle_window = window as usize;
+                self.draw_candles();
+            }
+            Message::RenderPixelsChanged(pixels) => self.render_pixels = pixels as usize,
+            Message::RenderOverlaysToggled(enabled) => self.render_overlays = enabled,
+            Message::ToggleDatabase => {
+                if self.database.take().is_some() {
+                    self.log_event("stopped recording stats".to_owned());
+                    return Command::none();
+                }
+                if let Some(ref run) = self.run {
+                    let path = run.database();
+                    self.open_database(path);
+                    return Command::none();
+                }
+                return Command::perform(io::pick_save(io::FileKind::Database), |path| {
+                    Message::FilePicked(FileAction::RecordStats, path)
+                });
+            }
+            Message::RegenerateTerrain => match self.sim_tx {
+                // Unlike the sliders this must not be dropped when the channel is full.
+                Some(ref tx) => {
+                    let mut tx = tx.clone();
+                    let regenerate = sim::ToSim::RegenerateTerrain {
+                        maze: self.maze(),
+                        cornacopia_count_probability: if self.regenerate_sources {
+                            Some(self.cornacopia_count_probability)
+                        } else {
+                            None
+                        },
+                        seed: rand::random(),
+                    };
+                    return Command::perform(
+                        async move { tx.send(regenerate).await.ok() },
+                        |_| Message::Null,
+                    );
+                }
+                None => {}
+            },
+            Message::Undo | Message::Redo => match self.sim_tx {
+                // Unlike the sliders this must not be dropped when the channel is full.
+                Some(ref tx) => {
+                    let mut tx = tx.clone();
+                    let reverse = match message {
+                        Message::Undo => sim::ToSim::Undo,
+                        _ => sim::ToSim::Redo,
+                    };
+                    return Command::perform(
+                        async move { tx.send(reverse).await.ok() },
+                        |_| Message::Null,
+                    );
+                }
+                None => {}
+            },
+            Message::RegenerateSourcesToggled(enabled) => self.regenerate_sources = enabled,
+            Message::TimelapseEveryChanged(every) => self.timelapse_every = every as usize,
+            Message::TimelapseDownscaleChanged(downscale) => {
+                self.timelapse_downscale = downscale as usize
+            }
+            Message::Grid(grid::Message::Paint(x, y, stroke)) => {
+                self.stroke_pending |= stroke;
+                self.paint(x, y);
+            }
+            Message::ViewModeChanged(mode) => {
+                self.view_settings.mode = mode;
+                return self.send_view_settings();
+            }
+            Message::ViewMemoryChanged(memory) => {
+                self.view_settings.memory = memory;
+                return self.send_view_settings();
+            }
+            Message::PollFiles => {
+                self.poll_scenario();
+                if self.poll_theme() {
+                    return self.send_view_settings();
+                }
+            }
+            Message::SaveSession => self.save_session(),
+            Message::Autosave => {
+                if let (Some(ref mut tx), Some(ref run)) = (&mut self.sim_tx, &self.run) {
+                    // Skipping an autosave while the sim is busy only waits for the next one.
+                    tx.try_send(sim::ToSim::Save(run.autosave())).ok();
+                }
+            }
+            Message::Quit => {
+                self.shut_down();
+                std::process::exit(0);
+            }
+            Message::Grid(grid::Message::Select(x, y)) => match self.sim_tx {
+                Some(ref mut tx) => {
+                    tx.try_send(sim::ToSim::Inspect(x, y)).ok();
+                }
+                None => {}
+            },
+            Message::Grid(grid::Message::Hover(hovered)) => {
+                self.pending_hover = hovered;
+                if let Some(ref mut grd) = self.grid {
+                    grd.update(grid::Message::Hover(hovered));
+                }
+            }
+            Message::Grid(message) => match self.grid {
+                Some(ref mut grd) => grd.update(message),
+                None => {}
+            },
             Message::Null => {}
         }
         Command::none()
@@ -449,21 +2501,96 @@ impl<'a> Application for EvonomicsWorld {
 
     // queue tick in update function regularly
     fn subscription(&self) -> Subscription<Message> {
-        if self.is_running_sim {
+        let tick = if self.is_running_sim {
             time::every(Duration::from_millis(self.ms_per_frame as u64)).map(|_| Message::Tick)
         } else {
             Subscription::none()
-        }
+        };
+        let files = if self.scenario_watcher.is_some() || self.theme_watcher.is_some() {
+            time::every(POLL_INTERVAL).map(|_| Message::PollFiles)
+        } else {
+            Subscription::none()
+        };
+        // Minimizing shrinks the window to nothing on some platforms.
+        let resize = subscription::events().map(|event| match event {
+            Event::Window(window::Event::Resized { width, height }) => {
+                Message::WindowResized(width, height)
+            }
+            _ => Message::Null,
+        });
+        let session = if self.session_path.is_some() {
+            time::every(SESSION_INTERVAL).map(|_| Message::SaveSession)
+        } else {
+            Subscription::none()
+        };
+        let demo = if self.demo.is_some() {
+            time::every(DEMO_INTERVAL).map(|_| Message::DemoStep)
+        } else {
+            Subscription::none()
+        };
+        // A paused world has nothing new to save.
+        let autosave = if self.is_running_sim && self.run.is_some() {
+            time::every(AUTOSAVE_INTERVAL).map(|_| Message::Autosave)
+        } else {
+            Subscription::none()
+        };
+        let frame = if self.pending_view.is_some()
+            || self.pending_delta.is_some()
+            || self.pending_hover.is_some()
+            || !self.pending_samples.is_empty()
+            || (self.graphs_stale && self.stats_visible())
+        {
+            time::every(FRAME_INTERVAL).map(|_| Message::Frame)
+        } else {
+            Subscription::none()
+        };
+        let phylogeny =
+            if self.is_running_sim && self.tracking_ancestry && self.showing_phylogeny {
+                time::every(PHYLOGENY_INTERVAL).map(|_| Message::AskPhylogeny)
+            } else {
+                Subscription::none()
+            };
+        Subscription::batch(vec![
+            tick, frame, files, resize, session, demo, autosave, phylogeny,
+        ])
     }
 
     fn view(&mut self) -> Element<Self::Message> {
         let speed = self.next_speed.unwrap_or(self.speed);
+        let shape = self.world_shape();
+        let buffer_bytes = self.buffer_bytes();
+        let source_food = self.source_food();
+        let unapplied = self.unapplied();
+
+        let alert = match self.alert {
+            Some(ref alert) => Some(
+                Container::new(
+                    Row::new()
+                        .padding(style::padding())
+                        .spacing(style::spacing())
+                        .align_items(Align::Center)
+                        .push(
+                            Text::new(alert.as_str())
+                                .color(Color::from_rgb(0.8, 0.0, 0.0))
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            Button::new(&mut self.dismiss_alert_button, Text::new("Dismiss"))
+                                .style(style::Theme::Default)
+                                .on_press(Message::DismissAlert),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .width(Length::Fill),
+            ),
+            None => None,
+        };
 
-        match self.menu_state {
+        let content: Element<Self::Message> = match self.menu_state {
             MenuState::MainMenu => {
-                let new_run_column = Column::new()
+                let mut new_run_column = Column::new()
                     .spacing(10)
-                    .max_width(style::MAIN_MENU_COLLUMN_WIDTH)
+                    .max_width(style::menu_column_width())
                     .align_items(Align::Center)
                     .push(
                         Button::new(
@@ -472,13 +2599,44 @@ impl<'a> Application for EvonomicsWorld {
                                 .horizontal_alignment(HorizontalAlignment::Center),
                         )
                         .style(style::Theme::Default)
-                        .min_width(style::MAIN_MENU_COLLUMN_WIDTH)
+                        .min_width(style::menu_column_width())
                         .on_press(Message::SimView),
-                    )
+                    );
+                if !self.warnings.is_empty() {
+                    for warning in &self.warnings {
+                        new_run_column = new_run_column.push(
+                            Text::new(warning.as_str())
+                                .size(16)
+                                .color(Color::from_rgb(0.8, 0.0, 0.0))
+                                .width(Length::Fill),
+                        );
+                    }
+                    new_run_column = new_run_column.push(
+                        Row::new()
+                            .spacing(style::spacing())
+                            .push(
+                                Button::new(
+                                    &mut self.start_anyway_button,
+                                    Text::new("Start Anyway"),
+                                )
+                                .style(style::Theme::Default)
+                                .on_press(Message::StartAnyway),
+                            )
+                            .push(
+                                Button::new(
+                                    &mut self.clamp_settings_button,
+                                    Text::new("Clamp and Start"),
+                                )
+                                .style(style::Theme::Default)
+                                .on_press(Message::ClampSettings),
+                            ),
+                    );
+                }
+                let new_run_column = new_run_column
                     .push(
                         Row::new()
                             .width(Length::Fill)
-                            .spacing(style::SPACING)
+                            .spacing(style::spacing())
                             .push(Radio::new(
                                 AspectRatio::OneToOne,
                                 "1:1",
@@ -511,6 +2669,86 @@ impl<'a> Application for EvonomicsWorld {
                         .horizontal_alignment(HorizontalAlignment::Center)
                         .width(Length::Fill),
                     )
+                    .push(
+                        Slider::new(
+                            &mut self.corridor_width_slider,
+                            0.0..=10.0,
+                            self.corridor_width as f32,
+                            Message::CorridorWidthSet,
+                        )
+                        .style(style::Theme::Default),
+                    )
+                    .push(
+                        Text::new(format!(
+                            "Corridor Width +{:<4} (Carve wider passages.)",
+                            self.corridor_width,
+                        ))
+                        .size(16)
+                        .vertical_alignment(VerticalAlignment::Bottom)
+                        .horizontal_alignment(HorizontalAlignment::Center)
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        Slider::new(
+                            &mut self.smoothing_slider,
+                            0.0..=5.0,
+                            self.smoothing as f32,
+                            Message::SmoothingSet,
+                        )
+                        .style(style::Theme::Default),
+                    )
+                    .push(
+                        Text::new(format!(
+                            "Smoothing {:<4} (Round off corners and wall ends.)",
+                            self.smoothing,
+                        ))
+                        .size(16)
+                        .vertical_alignment(VerticalAlignment::Bottom)
+                        .horizontal_alignment(HorizontalAlignment::Center)
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        Row::new()
+                            .width(Length::Fill)
+                            .spacing(style::spacing())
+                            .push(Radio::new(
+                                sim::EdgePolicy::Neutral,
+                                "Neutral",
+                                Some(self.edges.policy),
+                                Message::EdgePolicyChanged,
+                            ))
+                            .push(Radio::new(
+                                sim::EdgePolicy::River,
+                                "River",
+                                Some(self.edges.policy),
+                                Message::EdgePolicyChanged,
+                            ))
+                            .push(Radio::new(
+                                sim::EdgePolicy::Hostile,
+                                "Hostile",
+                                Some(self.edges.policy),
+                                Message::EdgePolicyChanged,
+                            )),
+                    )
+                    .push(
+                        Slider::new(
+                            &mut self.edge_width_slider,
+                            0.0..=64.0,
+                            self.edges.width as f32,
+                            Message::EdgeWidthSet,
+                        )
+                        .style(style::Theme::Default),
+                    )
+                    .push(
+                        Text::new(format!(
+                            "Edge Band {:<4} (Tiles the edge reaches in.)",
+                            self.edges.width,
+                        ))
+                        .size(16)
+                        .vertical_alignment(VerticalAlignment::Bottom)
+                        .horizontal_alignment(HorizontalAlignment::Center)
+                        .width(Length::Fill),
+                    )
                     .push(
                         Slider::new(
                             &mut self.dimension_slider,
@@ -535,32 +2773,101 @@ impl<'a> Application for EvonomicsWorld {
                     .push(
                         Slider::new(
                             &mut self.cornacopia_count_probability_slider,
-                            0.0..=1.0,
+                            0.0..=0.1,
                             self.cornacopia_count_probability as f32,
                             Message::CornacopiaCountProbabilityChanged,
                         )
                         .style(style::Theme::Default),
                     )
+                    .push(self.chance_inputs[Chance::CornacopiaCount as usize].view(
+                        Chance::CornacopiaCount,
+                        self.cornacopia_count_probability,
+                    ))
                     .push(
                         Text::new(format!(
                             "Cornacopias Likely {}",
-                            (self.width as f32
-                                * self.aspect_ratio.get_height(self.width) as f32
-                                * self.cornacopia_count_probability as f32
-                                / 10.0) as usize
+                            (self.width as f64
+                                * self.aspect_ratio.get_height(self.width) as f64
+                                * self.cornacopia_count_probability) as usize
                         ))
                         .size(16)
                         .vertical_alignment(VerticalAlignment::Bottom)
                         .horizontal_alignment(HorizontalAlignment::Center)
                         .width(Length::Fill),
+                    )
+                    .push(Checkbox::new(
+                        self.linked_worlds,
+                        "Link a neighboring world through portals",
+                        Message::LinkedWorldsToggled,
+                    ));
+
+                let mut preview_column = Column::new()
+                    .spacing(10)
+                    .width(Length::Units(PREVIEW_WIDTH as u16))
+                    .align_items(Align::Center)
+                    .push(
+                        Button::new(
+                            &mut self.preview_button,
+                            Text::new("Preview World")
+                                .horizontal_alignment(HorizontalAlignment::Center),
+                        )
+                        .style(style::Theme::Default)
+                        .min_width(PREVIEW_WIDTH as u32)
+                        .on_press(Message::PreviewWorld),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(style::spacing())
+                            .align_items(Align::Center)
+                            .push(
+                                Text::new(format!("Seed {}", self.world_seed))
+                                    .size(16)
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Button::new(&mut self.reseed_button, Text::new("New Seed"))
+                                    .style(style::Theme::Default)
+                                    .on_press(Message::Reseed),
+                            ),
                     );
+                // A preview of other settings would show a world the run button will not start.
+                if let Some(preview) = self.preview.as_ref().filter(|p| p.shape == shape) {
+                    preview_column = preview_column
+                        .push(image::Image::new(preview.thumbnail.clone()))
+                        .push(
+                            Text::new(format!(
+                                "{} open tiles in {} rooms, {} cornacopias{}",
+                                preview.stats.open,
+                                preview.stats.rooms,
+                                preview.stats.sources.iter().sum::<usize>(),
+                                describe_tiers(preview.stats.sources)
+                            ))
+                            .size(16)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        );
+                }
 
-                let load_save_column = Button::new(
-                    &mut self.load_save_button,
-                    Text::new("Load Save").horizontal_alignment(HorizontalAlignment::Center),
-                )
-                .style(style::Theme::Default)
-                .min_width(style::MAIN_MENU_COLLUMN_WIDTH);
+                let load_save_column = Column::new()
+                    .spacing(10)
+                    .push(
+                        Button::new(
+                            &mut self.load_save_button,
+                            Text::new("Load Save").horizontal_alignment(HorizontalAlignment::Center),
+                        )
+                        .style(style::Theme::Default)
+                        .on_press(Message::PickFile(FileAction::LoadWorld))
+                        .min_width(style::menu_column_width()),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.tutorial_button,
+                            Text::new("Tutorial").horizontal_alignment(HorizontalAlignment::Center),
+                        )
+                        .style(style::Theme::Default)
+                        .on_press(Message::StartTutorial)
+                        .min_width(style::menu_column_width()),
+                    );
 
                 Container::new(
                     Column::new()
@@ -569,11 +2876,12 @@ impl<'a> Application for EvonomicsWorld {
                         .padding(60)
                         .spacing(100)
                         .align_items(Align::Center)
-                        .push(Text::new("Evonomics").size(50).color(style::COLOR_GOLD))
+                        .push(Text::new("Evonomics").size(50).color(style::color(style::skin().accent)))
                         .push(
                             Row::new()
                                 .spacing(100)
                                 .push(new_run_column)
+                                .push(preview_column)
                                 .push(load_save_column),
                         ),
                 )
@@ -587,7 +2895,7 @@ impl<'a> Application for EvonomicsWorld {
             MenuState::SimMenu => {
                 let fps_controls = Container::new(
                     Column::new()
-                        .padding(style::PADDING)
+                        .padding(style::padding())
                         .push(
                             Slider::new(
                                 &mut self.speed_slider,
@@ -634,270 +2942,4122 @@ impl<'a> Application for EvonomicsWorld {
                             .width(Length::Fill),
                         )
                         .push(
-                            Text::new(format!("Total Ticks: {}", self.total_tick_count))
+                            Text::new(match self.approximated.back() {
+                                Some(&approximated) if approximated > 0 => format!(
+                                    "Total Ticks: {} ({} approximate)",
+                                    self.total_tick_count, approximated
+                                ),
+                                _ => format!("Total Ticks: {}", self.total_tick_count),
+                            })
                                 .size(16)
                                 .vertical_alignment(VerticalAlignment::Bottom)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Fill),
+                        )
+                        .push(
+                            Text::new(match self.applied {
+                                Some((_, ref rules)) => {
+                                    format!("features: {}", sim::FeatureSet::of(rules))
+                                }
+                                None => String::from("features: ..."),
+                            })
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
                         ),
                 )
                 .style(style::Theme::Nested);
 
+                let mutations = self.mutations;
+                let [indel_slider, point_slider, duplication_slider, transposition_slider] =
+                    &mut self.mutation_sliders;
+                let mutation_slider = |state, chance: f64, set: fn(&mut sim::Mutations, f64)| {
+                    Slider::new(state, 0.0..=1.0, chance as f32, move |chance| {
+                        let mut changed = mutations;
+                        set(&mut changed, chance as f64);
+                        Message::MutationsChanged(changed)
+                    })
+                    .style(style::Theme::Default)
+                };
+                let mutation_text = |name, chance| {
+                    Text::new(format!("{}: {:.2} per mutation", name, chance))
+                        .size(16)
+                        .horizontal_alignment(HorizontalAlignment::Center)
+                        .width(Length::Fill)
+                };
+                let [mutation_input, food_input, cornacopia_input, _] = &mut self.chance_inputs;
                 let mutation_controls = Container::new(
                     Column::new()
-                        .padding(style::PADDING)
+                        .padding(style::padding())
                         .push(
                             Slider::new(
                                 &mut self.mutation_probability_slider,
-                                0.0..=1.0,
+                                0.0..=0.02,
                                 self.mutation_chance as f32,
                                 Message::MutationChanceChanged,
                             )
                             .style(style::Theme::Default),
                         )
+                        .push(mutation_input.view(Chance::Mutation, self.mutation_chance))
                         .push(
                             Text::new(format!(
-                                "ave tick to mutation: {:.1}",
-                                50.0 / self.mutation_chance
+                                "mutation chance per cell per tick\nave tick to mutation: {:.1}",
+                                1.0 / self.mutation_chance
                             ))
                             .size(16)
                             .vertical_alignment(VerticalAlignment::Bottom)
                             .horizontal_alignment(HorizontalAlignment::Center)
                             .width(Length::Fill),
-                        ),
+                        )
+                        .push(mutation_slider(indel_slider, mutations.indel, |m, chance| {
+                            m.indel = chance
+                        }))
+                        .push(mutation_text("insert or remove a codon", mutations.indel))
+                        .push(mutation_slider(point_slider, mutations.point, |m, chance| {
+                            m.point = chance
+                        }))
+                        .push(mutation_text("point mutation", mutations.point))
+                        .push(mutation_slider(
+                            duplication_slider,
+                            mutations.duplication,
+                            |m, chance| m.duplication = chance,
+                        ))
+                        .push(mutation_text("duplicate a gene", mutations.duplication))
+                        .push(mutation_slider(
+                            transposition_slider,
+                            mutations.transposition,
+                            |m, chance| m.transposition = chance,
+                        ))
+                        .push(mutation_text("move a gene", mutations.transposition)),
                 )
                 .style(style::Theme::Nested);
 
-                let food_controls = Container::new(
+                let breaker_controls = Container::new(
                     Column::new()
-                        .padding(style::PADDING)
+                        .padding(style::padding())
+                        .push(Checkbox::new(
+                            self.circuit_breaker,
+                            "Circuit breaker",
+                            Message::CircuitBreakerToggled,
+                        ))
                         .push(
                             Slider::new(
-                                &mut self.general_food_slider,
-                                0.0..=1.0,
-                                self.cell_food_probability as f32,
-                                Message::GeneralFoodProbabilityChanged,
+                                &mut self.breaker_move_slider,
+                                1.0..=100.0,
+                                self.breaker_move_percent as f32,
+                                Message::BreakerMoveChanged,
                             )
                             .style(style::Theme::Default),
                         )
                         .push(
-                            Text::new(format!(
-                                "ave tick to food: {:.1}",
-                                1.0 / self.cell_food_probability
-                            ))
-                            .size(16)
-                            .vertical_alignment(VerticalAlignment::Bottom)
-                            .horizontal_alignment(HorizontalAlignment::Center)
-                            .width(Length::Fill),
+                            Text::new(format!("largest move: {:.0}%", self.breaker_move_percent))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
                         )
                         .push(
                             Slider::new(
-                                &mut self.cornacopia_bounty_slider,
-                                0.0..=100.0,
-                                self.cornacopia_bounty as f32,
-                                Message::CornacopiaBountyChanged,
+                                &mut self.breaker_window_slider,
+                                1.0..=100.0,
+                                self.breaker_window as f32,
+                                Message::BreakerWindowChanged,
                             )
                             .style(style::Theme::Default),
                         )
                         .push(
-                            Text::new(format!("bounty: {:<3}", self.cornacopia_bounty))
+                            Text::new(format!("over ticks: {}", self.breaker_window))
                                 .size(16)
-                                .vertical_alignment(VerticalAlignment::Bottom)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Fill),
                         )
                         .push(
                             Slider::new(
-                                &mut self.cornacopia_probability_slider,
-                                0.0..=1.0,
-                                self.cornacopia_probability as f32,
-                                Message::CornacopiaProbabilityChanged,
+                                &mut self.breaker_halt_slider,
+                                1.0..=100.0,
+                                self.breaker_halt as f32,
+                                Message::BreakerHaltChanged,
                             )
                             .style(style::Theme::Default),
                         )
                         .push(
-                            Text::new(format!(
-                                "ave tick for cornacopia: {:.1}",
-                                10.0 / self.cornacopia_probability
-                            ))
-                            .size(16)
-                            .vertical_alignment(VerticalAlignment::Bottom)
-                            .horizontal_alignment(HorizontalAlignment::Center)
-                            .width(Length::Fill),
-                        ),
+                            Text::new(format!("halt ticks: {}", self.breaker_halt))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            Text::new("Money of cells starving after a trade")
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Radio::new(
+                            sim::Proceeds::Tile,
+                            "Stays on the tile",
+                            Some(self.proceeds),
+                            Message::ProceedsChanged,
+                        ))
+                        .push(Radio::new(
+                            sim::Proceeds::Reserve,
+                            "Goes to the reserve",
+                            Some(self.proceeds),
+                            Message::ProceedsChanged,
+                        ))
+                        .push(Radio::new(
+                            sim::Proceeds::Cancel,
+                            "Cancel orders which would starve",
+                            Some(self.proceeds),
+                            Message::ProceedsChanged,
+                        ))
+                        .push(
+                            Text::new("Hold still")
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Radio::new(
+                            sim::Freeze::Nothing,
+                            "Nothing",
+                            Some(self.freeze),
+                            Message::FreezeChanged,
+                        ))
+                        .push(Radio::new(
+                            sim::Freeze::Market,
+                            "The market, so no orders match",
+                            Some(self.freeze),
+                            Message::FreezeChanged,
+                        ))
+                        .push(Radio::new(
+                            sim::Freeze::Ecology,
+                            "The ecology, so only resting orders clear",
+                            Some(self.freeze),
+                            Message::FreezeChanged,
+                        )),
                 )
                 .style(style::Theme::Nested);
 
-                let spawn_controls = Container::new(
+                let monetary_controls = Container::new(
                     Column::new()
-                        .padding(style::PADDING)
+                        .padding(style::padding())
                         .push(
-                            Button::new(
-                                &mut self.toggle_spawn_rate_type_button,
-                                Text::new(if self.is_inverse_rate_type {
-                                    "Currently Dynamic"
-                                } else {
-                                    "Currently Constant"
-                                }),
+                            Text::new("Monetary Policy")
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Checkbox::new(
+                            self.demurrage,
+                            "Demurrage",
+                            Message::DemurrageToggled,
+                        ))
+                        .push(
+                            Slider::new(
+                                &mut self.demurrage_slider,
+                                0.0..=2.0,
+                                self.demurrage_percent as f32,
+                                Message::DemurrageChanged,
                             )
-                            .style(style::Theme::Nested)
-                            .width(Length::Fill)
-                            .on_press(Message::ToggleRateType),
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("decay: {:.2}% per tick", self.demurrage_percent))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
                         )
+                        .push(Checkbox::new(
+                            self.demurrage_carried,
+                            "Carried money decays too",
+                            Message::DemurrageCarriedToggled,
+                        ))
                         .push(
                             Slider::new(
-                                &mut self.spawn_slider,
-                                0.0..=1.0,
-                                self.spawn_rate as f32,
-                                Message::SpawnRateChanged,
+                                &mut self.inheritance_tax_slider,
+                                0.0..=100.0,
+                                self.inheritance_tax as f32,
+                                Message::InheritanceTaxChanged,
                             )
                             .style(style::Theme::Default),
                         )
                         .push(
                             Text::new(format!(
-                                "Estimated RNG Cells/Tick\n{:.3}",
-                                self.spawn_chance
-                                    * self.width as f64
-                                    * self.aspect_ratio.get_height(self.width) as f64
+                                "inheritance tax: {:.0}% of money passed on",
+                                self.inheritance_tax
+                            ))
+                            .size(16)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        )
+                        .push(
+                            Text::new("When the reserve runs out of money")
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Radio::new(
+                            sim::Exhaustion::Halt,
+                            "It stops buying",
+                            Some(self.exhaustion),
+                            Message::ExhaustionChanged,
+                        ))
+                        .push(Radio::new(
+                            sim::Exhaustion::Mint,
+                            "It mints what it lacks",
+                            Some(self.exhaustion),
+                            Message::ExhaustionChanged,
+                        ))
+                        .push(Radio::new(
+                            sim::Exhaustion::Ration,
+                            "It rations what it has",
+                            Some(self.exhaustion),
+                            Message::ExhaustionChanged,
+                        ))
+                        .push(
+                            Slider::new(
+                                &mut self.signal_cost_slider,
+                                0.0..=2.0,
+                                self.signal_cost as f32,
+                                Message::SignalCostChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!(
+                                "signal cost: {:.2} food per tick",
+                                self.signal_cost
                             ))
                             .size(16)
-                            .vertical_alignment(VerticalAlignment::Bottom)
                             .horizontal_alignment(HorizontalAlignment::Center)
                             .width(Length::Fill),
                         ),
                 )
                 .style(style::Theme::Nested);
 
-                let bid_ask_ui = Container::new(
+                let recovery_controls = Container::new(
                     Column::new()
-                        .padding(2)
+                        .padding(style::padding())
+                        .push(Checkbox::new(
+                            self.recovery,
+                            "Start over after extinction",
+                            Message::RecoveryToggled,
+                        ))
+                        .push(
+                            Slider::new(
+                                &mut self.recovery_population_slider,
+                                1.0..=1000.0,
+                                self.recovery_population as f32,
+                                Message::RecoveryPopulationChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
                         .push(
-                            Text::new("Bid/Ask")
+                            Text::new(format!("cells spawned: {}", self.recovery_population))
+                                .size(16)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Fill),
                         )
-                        .push(image::Image::new(self.bid_ask_graph.clone())),
+                        .push(Checkbox::new(
+                            self.recover_from_hall,
+                            "From the hall of fame",
+                            Message::RecoverFromHallToggled,
+                        )),
                 )
-                .style(style::Theme::Nested)
-                .height(Length::Shrink)
-                .width(Length::Fill);
+                .style(style::Theme::Nested);
 
-                // let reserve_ui = Container::new(
-                //     Column::new()
-                //         .padding(2)
-                //         .push(
-                //             Text::new("Reserve")
-                //                 .horizontal_alignment(HorizontalAlignment::Center)
-                //                 .width(Length::Fill),
-                //         )
-                //         .push(image::Image::new(self.reserve_graph.clone())),
-                // )
-                // .style(style::Theme::Nested)
-                // .height(Length::Shrink)
-                // .width(Length::Fill);
-
-                let age_ui = Container::new(
+                let noise_controls = Container::new(
                     Column::new()
-                        .padding(2)
+                        .padding(style::padding())
+                        .push(
+                            Slider::new(
+                                &mut self.input_noise_slider,
+                                0.0..=1.0,
+                                self.input_noise as f32,
+                                Message::InputNoiseChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
                         .push(
-                            Text::new("Generation (mean/max)")
+                            Text::new(format!("input noise: {:.3}", self.input_noise))
+                                .size(16)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Fill),
                         )
-                        .push(image::Image::new(self.mean_max_age_graph.clone())),
+                        .push(
+                            Slider::new(
+                                &mut self.forgetting_slider,
+                                0.0..=0.1,
+                                self.forgetting as f32,
+                                Message::ForgettingChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("forgetting chance: {:.4}", self.forgetting))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        ),
                 )
-                .style(style::Theme::Nested)
-                .height(Length::Shrink)
-                .width(Length::Fill);
+                .style(style::Theme::Nested);
 
-                let volume_ui = Container::new(
+
+                let food_controls = Container::new(
                     Column::new()
-                        .padding(2)
+                        .padding(style::padding())
+                        .push(
+                            Slider::new(
+                                &mut self.general_food_slider,
+                                0.0..=1.0,
+                                self.cell_food_probability as f32,
+                                Message::GeneralFoodProbabilityChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(food_input.view(Chance::GeneralFood, self.cell_food_probability))
+                        .push(
+                            Text::new(format!(
+                                "food chance per tile per tick\nave tick to food: {:.1}\n\
+                                 food/tick on {} tiles: {:.1}",
+                                1.0 / self.cell_food_probability,
+                                self.fertile_tiles,
+                                self.cell_food_probability * self.fertile_tiles as f64
+                            ))
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.cornacopia_bounty_slider,
+                                0.0..=100.0,
+                                self.cornacopia_bounty as f32,
+                                Message::CornacopiaBountyChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
                         .push(
-                            Text::new("Volume (buy/sell)")
+                            Text::new(format!("bounty: {:<3}", self.cornacopia_bounty))
+                                .size(16)
+                                .vertical_alignment(VerticalAlignment::Bottom)
                                 .horizontal_alignment(HorizontalAlignment::Center)
                                 .width(Length::Fill),
                         )
-                        .push(image::Image::new(self.volume_graph.clone())),
-                )
-                .style(style::Theme::Nested)
-                .height(Length::Shrink)
-                .width(Length::Fill);
-
-                let grid_controls = Column::new()
-                    .spacing(style::SPACING)
-                    .padding(style::PADDING)
-                    .max_width(style::BUTTON_SIZE + style::PADDING as u32)
-                    .push(
-                        Button::new(&mut self.save_simulation_button, Text::new("save"))
-                            .style(style::Theme::Default)
-                            .min_width(style::BUTTON_SIZE),
-                    )
-                    .push(
-                        Button::new(
-                            &mut self.toggle_run_button,
-                            if self.is_running_sim {
-                                Text::new("Pause")
+                        .push(
+                            Slider::new(
+                                &mut self.cornacopia_probability_slider,
+                                0.0..=0.1,
+                                self.cornacopia_probability as f32,
+                                Message::CornacopiaProbabilityChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(cornacopia_input.view(Chance::Cornacopia, self.cornacopia_probability))
+                        .push(
+                            Text::new(format!(
+                                "cornacopia chance per source per tick\n\
+                                 ave tick for cornacopia: {:.1}\n\
+                                 food/tick from {} sources{}: {:.1}",
+                                1.0 / self.cornacopia_probability,
+                                self.source_tiles.iter().sum::<usize>(),
+                                describe_tiers(self.source_tiles),
+                                source_food
+                            ))
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.starvation_grace_slider,
+                                0.0..=64.0,
+                                self.starvation_grace as f32,
+                                Message::StarvationGraceChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("starvation grace: {} ticks", self.starvation_grace))
+                                .size(16)
+                                .vertical_alignment(VerticalAlignment::Bottom)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Checkbox::new(
+                            self.capped,
+                            "Cap the food cells carry",
+                            Message::CappedToggled,
+                        ))
+                        .push(
+                            Slider::new(
+                                &mut self.capacity_slider,
+                                40.0..=2000.0,
+                                self.capacity as f32,
+                                Message::CapacityChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("carrying capacity: {} food", self.capacity))
+                                .size(16)
+                                .vertical_alignment(VerticalAlignment::Bottom)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.spoilage_slider,
+                                0.0..=10.0,
+                                self.spoilage_percent as f32,
+                                Message::SpoilageChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.spoils_above_slider,
+                                0.0..=2000.0,
+                                self.spoils_above as f32,
+                                Message::SpoilsAboveChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(if self.spoilage_percent > 0.0 {
+                                format!(
+                                    "spoilage: {:.2}% per tick of food above {}",
+                                    self.spoilage_percent, self.spoils_above
+                                )
                             } else {
-                                Text::new("Run")
-                            },
+                                "spoilage: none".to_owned()
+                            })
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
                         )
-                        .style(style::Theme::Default)
-                        .min_width(style::BUTTON_SIZE)
-                        .on_press(Message::ToggleSim),
-                    )
-                    .push(fps_controls)
-                    .push(spawn_controls)
-                    .push(food_controls)
-                    .push(mutation_controls)
-                    .push(
-                        Button::new(
-                            &mut self.toggle_grid_button,
-                            Text::new(match self.grid {
-                                Some(ref grd) => {
-                                    if grd.is_showing_lines() {
-                                        "Hide Grid"
-                                    } else {
-                                        "Show Grid"
-                                    }
-                                }
-                                None => panic!(
-                                    "grid doesn't exist when attempting to draw grid controls!"
-                                ),
-                            }),
+                        .push(
+                            Slider::new(
+                                &mut self.metabolism_slider,
+                                0.0..=0.1,
+                                self.metabolism as f32,
+                                Message::MetabolismChanged,
+                            )
+                            .style(style::Theme::Default),
                         )
-                        .style(style::Theme::Default)
-                        .min_width(style::BUTTON_SIZE)
-                        .on_press(Message::ToggleGrid),
-                    )
-                    .push(age_ui)
-                    .push(bid_ask_ui)
-                    .push(volume_ui);
-
-                let scrollable = Scrollable::new(&mut self.scroll).push(grid_controls);
-
-                Container::new(
-                    Row::new().push(
-                        Row::new()
-                            .push(scrollable)
-                            // TODO, .push( Text::new("Click a cell to see its genome or save it.\n\nClick an empty spot to plant a cell from the save files.\n\nUse the wheel to zoom | right click to pan.") ) )
-                            //        requires tracking number of marked ancestors in EvonomicsWorld: .push( table with rows of cell ancestors, collumns of color, hide/show radio button, delete button )
-                            .push(match self.grid {
-                                Some(ref mut grd) => grd.view().map(|_| Message::Null),
-                                None => {
-                                    panic!("unexpected entry to view without initializing grid")
-                                }
-                            }),
-                    ),
+                        .push(
+                            Text::new(format!(
+                                "metabolism: {:.3} food per codon run",
+                                self.metabolism
+                            ))
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.stepped_slider,
+                                1.0..=100.0,
+                                self.stepped_percent as f32,
+                                Message::SteppedChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(if self.stepped_percent < 100.0 {
+                                format!(
+                                    "stepped: {:.0}% of cells per tick (approximate)",
+                                    self.stepped_percent
+                                )
+                            } else {
+                                "stepped: every cell per tick".to_owned()
+                            })
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        ),
                 )
-                .style(style::Theme::Default)
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .center_x()
-                .center_y()
-                .into()
+                .style(style::Theme::Nested);
+
+                let spawn_controls = Container::new(
+                    Column::new()
+                        .padding(style::padding())
+                        .push(
+                            Button::new(
+                                &mut self.toggle_spawn_rate_type_button,
+                                Text::new(if self.is_inverse_rate_type {
+                                    "Currently Dynamic"
+                                } else {
+                                    "Currently Constant"
+                                }),
+                            )
+                            .style(style::Theme::Nested)
+                            .width(Length::Fill)
+                            .on_press(Message::ToggleRateType),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.spawn_slider,
+                                0.0..=1.0,
+                                self.spawn_rate as f32,
+                                Message::SpawnRateChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!(
+                                "spawn chance per tile per tick\n{:.3e}\nEstimated RNG Cells/Tick\n{:.3}",
+                                self.spawn_chance,
+                                self.spawn_chance
+                                    * self.width as f64
+                                    * self.aspect_ratio.get_height(self.width) as f64
+                            ))
+                            .size(16)
+                            .vertical_alignment(VerticalAlignment::Bottom)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        ),
+                )
+                .style(style::Theme::Nested);
+
+                // Hidden panels are not built at all, so their formatting costs nothing.
+                let stats_ui = if self.show_stats {
+                    let bid_ask_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Bid/Ask")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.bid_ask_graph.clone())),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let depth_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Order depth")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.depth_graph.clone()))
+                            .push(
+                                Text::new(match self.order_counts.back() {
+                                    Some(orders) => format!(
+                                        "orders per tick: {} expired, {} filled in part",
+                                        orders.expired, orders.partial
+                                    ),
+                                    None => String::from("orders per tick: none yet"),
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Row::new()
+                                    .spacing(style::spacing())
+                                    .push({
+                                        let button = Button::new(
+                                            &mut self.audit_button,
+                                            Text::new(if self.auditing {
+                                                "auditing..."
+                                            } else {
+                                                "audit matching"
+                                            }),
+                                        )
+                                        .style(style::Theme::Default)
+                                        .min_width(style::button_size());
+                                        if self.auditing {
+                                            button
+                                        } else {
+                                            button.on_press(Message::AuditMatching)
+                                        }
+                                    })
+                                    .push({
+                                        let button = Button::new(
+                                            &mut self.export_audit_button,
+                                            Text::new("export audit"),
+                                        )
+                                        .style(style::Theme::Default)
+                                        .min_width(style::button_size());
+                                        match self.audit {
+                                            Some(_) => button
+                                                .on_press(Message::PickFile(FileAction::ExportAudit)),
+                                            None => button,
+                                        }
+                                    }),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let price_candle_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Trade prices")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.price_candle_graph.clone()))
+                            .push(
+                                Slider::new(
+                                    &mut self.candle_window_slider,
+                                    10.0..=MAX_GRAPH_TIMES as f32,
+                                    self.candle_window as f32,
+                                    Message::CandleWindowChanged,
+                                )
+                                .style(style::Theme::Default),
+                            )
+                            .push(
+                                Text::new(format!(
+                                    "candles of the last {} samples",
+                                    self.candle_window
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(Checkbox::new(
+                                self.sonifier.is_some(),
+                                "Play as sound",
+                                Message::SonifyToggled,
+                            )),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let legend = &self.lineage_legend;
+                    let lineage_price_ui = Container::new(
+                        self.lineage_prices.back().into_iter().flatten().fold(
+                            Column::new()
+                                .padding(2)
+                                .push(
+                                    Text::new("Lineage prices")
+                                        .horizontal_alignment(HorizontalAlignment::Center)
+                                        .width(Length::Fill),
+                                )
+                                .push(image::Image::new(self.lineage_price_graph.clone())),
+                            |column, lineage| {
+                                column.push(
+                                    Text::new(describe_lineage_prices(
+                                        &label_lineage(legend, lineage.lineage),
+                                        lineage,
+                                    ))
+                                        .color(lineage.color)
+                                        .size(14)
+                                        .width(Length::Fill),
+                                )
+                            },
+                        ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    // let reserve_ui = Container::new(
+                    //     Column::new()
+                    //         .padding(2)
+                    //         .push(
+                    //             Text::new("Reserve")
+                    //                 .horizontal_alignment(HorizontalAlignment::Center)
+                    //                 .width(Length::Fill),
+                    //         )
+                    //         .push(image::Image::new(self.reserve_graph.clone())),
+                    // )
+                    // .style(style::Theme::Nested)
+                    // .height(Length::Shrink)
+                    // .width(Length::Fill);
+
+                    let age_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Generation (mean/max)")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.mean_max_age_graph.clone())),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let pyramid_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Population by age")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.age_pyramid_graph.clone()))
+                            .push(
+                                Text::new(format!(
+                                    "cells in bands of {} ticks, youngest at the bottom",
+                                    self.ages.width
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let choices_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Decisions")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.choices_graph.clone()))
+                            .push(
+                                Text::new(match self.choices.back() {
+                                    Some(choices) => sim::Choice::ALL
+                                        .iter()
+                                        .zip(plot::CHOICE_COLOR_NAMES.iter())
+                                        .map(|(&choice, color)| {
+                                            format!(
+                                                "{} ({}): {}",
+                                                choice.name(),
+                                                color,
+                                                choices[choice.index()]
+                                            )
+                                        })
+                                        .collect::<Vec<_>>()
+                                        .join("\n"),
+                                    None => String::from("no decisions yet"),
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let trace_ui = Container::new(match self.trace.back() {
+                        None => Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Followed Cell")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new("Click a cell to follow its food and money.").size(14),
+                            ),
+                        Some(point) => Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Followed Cell (food/money)")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.trace_graph.clone()))
+                            .push(
+                                Text::new(format!(
+                                    "{} at ({}, {}) on tick {}
+food: {}
+money: {}",
+                                    if self.trace_lost { "last seen" } else { "now" },
+                                    point.x,
+                                    point.y,
+                                    point.tick,
+                                    point.food,
+                                    point.money
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Row::new()
+                                    .spacing(style::spacing())
+                                    .push(
+                                        Button::new(
+                                            &mut self.export_trace_button,
+                                            Text::new("export trace"),
+                                        )
+                                        .style(style::Theme::Default)
+                                        .min_width(style::button_size())
+                                        .on_press(Message::PickFile(FileAction::ExportTrace)),
+                                    )
+                                    .push(
+                                        Button::new(
+                                            &mut self.stop_following_button,
+                                            Text::new("stop following"),
+                                        )
+                                        .style(style::Theme::Default)
+                                        .min_width(style::button_size())
+                                        .on_press(Message::StopFollowing),
+                                    ),
+                            ),
+                    })
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let genome_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Genomes")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(format!(
+                                    "population: {}\nunique genomes: {}\nmerged last pass: {}\nseparators per genome: {:.2}",
+                                    self.population, self.unique_genomes, self.interned_genomes,
+                                    self.separators.back().copied().unwrap_or(0.0),
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(match self.junk.back() {
+                                    Some(junk) => format!(
+                                        "codons per genome: {:.1}, {:.1}% unreachable, {:.1}% \
+                                         not run",
+                                        junk.mean_length,
+                                        junk.unreachable * 100.0,
+                                        junk.unexecuted * 100.0
+                                    ),
+                                    None => String::from("codons per genome: none yet"),
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.junk_graph.clone()))
+                            .push(
+                                Text::new(if self.region_populations.is_empty() {
+                                    String::from("population by region: no regions")
+                                } else {
+                                    self.region_populations.iter().enumerate().fold(
+                                        String::from("population by region:"),
+                                        |text, (i, (area, population))| {
+                                            format!("{} {}. {} {}", text, i + 1, area, population)
+                                        },
+                                    )
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(match self.sanitized.back() {
+                                    Some(sanitized) => format!(
+                                        "sanitized per tick: {} memory, {} signals, {} trades",
+                                        sanitized.memory, sanitized.signals, sanitized.trades
+                                    ),
+                                    None => String::from("sanitized per tick: none yet"),
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(format!(
+                                    "food lost per tick: {} paid for signals, {} spoiled, {} \
+                                     metabolized",
+                                    self.signaling.back().copied().unwrap_or(0),
+                                    self.spoiled.back().copied().unwrap_or(0),
+                                    self.metabolized.back().copied().unwrap_or(0)
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(match self.death_causes.back() {
+                                    Some(deaths) => format!(
+                                        "deaths per tick: {} starved, {} in walls, {} combined; \
+                                         {} living on the starvation grace",
+                                        deaths.starved,
+                                        deaths.walls,
+                                        deaths.combined,
+                                        deaths.hungry
+                                    ),
+                                    None => String::from("deaths per tick: none yet"),
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let volume_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Volume (buy/sell)")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.volume_graph.clone())),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let last_flows = self.money_flows.back().copied().unwrap_or_default();
+                    let money_flow_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Money Flows")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.money_flow_graph.clone()))
+                            .push(
+                                Text::new(format!(
+                                    "trades (blue): {}\nreserve buys (green): {}\nreserve sells (magenta): {}\nescheated (red): {}\ndemurrage (cyan): {}\ninheritance tax (yellow): {}\nminted by the reserve: {}\nnet issued: {}",
+                                    last_flows.trades,
+                                    last_flows.reserve_purchases,
+                                    last_flows.reserve_sales,
+                                    last_flows.escheatment,
+                                    last_flows.demurrage,
+                                    last_flows.inheritance_tax,
+                                    last_flows.minted,
+                                    last_flows.net(),
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let last_params = self.params.back().copied().unwrap_or_default();
+                    let params_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Parameters (relative)")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(image::Image::new(self.params_graph.clone()))
+                            .push(
+                                Text::new(format!(
+                                    "spawn (blue): {:.6}\nmutation (green): {:.6}\nfood (magenta): {:.4}\ncornacopia (red): {:.4}\nbounty (cyan): {}",
+                                    last_params.spawn_chance,
+                                    last_params.mutation_chance,
+                                    last_params.general_food_chance,
+                                    last_params.cornacopia_chance,
+                                    last_params.cornacopia_bounty,
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let telemetry_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Telemetry")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(match self.footprint {
+                                    Some(footprint) => format!(
+                                        "cells: {} ({})\nbrains: {}\ngenomes: {} ({})\n\
+                                         lineages: {} ({})\nlast frame: {}\nsim total: {}",
+                                        footprint.cells,
+                                        format_bytes(footprint.cell_bytes),
+                                        footprint.brains,
+                                        footprint.genomes,
+                                        format_bytes(footprint.genome_bytes),
+                                        footprint.lineages,
+                                        format_bytes(footprint.lineage_bytes),
+                                        format_bytes(footprint.view_bytes),
+                                        format_bytes(footprint.total_bytes()),
+                                    ),
+                                    None => String::from("not measured yet"),
+                                })
+                                .size(16)
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                Text::new(format!(
+                                    "stats buffers: {} samples ({})\nfollowed cell: {} ticks",
+                                    self.bids.len(),
+                                    format_bytes(buffer_bytes),
+                                    self.trace.len(),
+                                ))
+                                .size(16)
+                                .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    let selection = self.selection;
+                    let traits = sim::TRAITS.iter().fold(
+                        Column::new().push(Text::new("").size(14)),
+                        |column, name| column.push(Text::new(*name).size(14)),
+                    );
+                    let table = sim::FITNESS.iter().enumerate().fold(
+                        Row::new().spacing(8).push(traits),
+                        |row, (f, name)| {
+                            row.push(selection.correlations.iter().fold(
+                                Column::new().push(Text::new(*name).size(14)),
+                                |column, correlations| {
+                                    column.push(correlation_text(correlations[f]).size(14))
+                                },
+                            ))
+                        },
+                    );
+                    let selection_ui = Container::new(
+                        Column::new()
+                            .padding(2)
+                            .push(
+                                Text::new("Selection")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            )
+                            .push(table)
+                            .push(
+                                Text::new(describe_selection(&selection))
+                                    .size(14)
+                                    .width(Length::Fill),
+                            ),
+                    )
+                    .style(style::Theme::Nested)
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                    Some(
+                        Column::new()
+                            .spacing(style::spacing())
+                            .push(age_ui)
+                            .push(pyramid_ui)
+                            .push(choices_ui)
+                            .push(selection_ui)
+                            .push(genome_ui)
+                            .push(trace_ui)
+                            .push(bid_ask_ui)
+                            .push(depth_ui)
+                            .push(lineage_price_ui)
+                            .push(price_candle_ui)
+                            .push(volume_ui)
+                            .push(money_flow_ui)
+                            .push(params_ui)
+                            .push(telemetry_ui),
+                    )
+                } else {
+                    None
+                };
+
+                let diff_ui = Container::new(if self.compared.len() < 2 {
+                    Column::new()
+                        .padding(2)
+                        .push(
+                            Text::new("Genome Diff")
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Text::new("Click two cells to compare their genomes.").size(14))
+                } else {
+                    let genome_diff = &self.genome_diff;
+                    let count = |edit| genome_diff.iter().filter(|row| row.edit == edit).count();
+                    let (a_x, a_y, _) = self.compared[0];
+                    let (b_x, b_y, _) = self.compared[1];
+                    let summary = format!(
+                        "({}, {}) to ({}, {})\nsame: {}\nsubstituted: {}\ninserted: {}\ndeleted: {}",
+                        a_x,
+                        a_y,
+                        b_x,
+                        b_y,
+                        count(sim::Edit::Same),
+                        count(sim::Edit::Substituted),
+                        count(sim::Edit::Inserted),
+                        count(sim::Edit::Deleted),
+                    );
+                    // Entry points are marked with '>'.
+                    let locus = |locus: &Option<sim::Locus>| match locus {
+                        Some(locus) => format!(
+                            "{}{} {}",
+                            if locus.entry { ">" } else { "" },
+                            locus.index,
+                            locus.codon
+                        ),
+                        None => String::from("-"),
+                    };
+                    self.genome_diff
+                        .iter()
+                        .filter(|row| row.is_change())
+                        .take(MAX_DIFF_ROWS)
+                        .fold(
+                            Column::new()
+                                .padding(2)
+                                .push(
+                                    Text::new("Genome Diff")
+                                        .horizontal_alignment(HorizontalAlignment::Center)
+                                        .width(Length::Fill),
+                                )
+                                .push(Text::new(summary).size(16)),
+                            |column, row| {
+                                let color = match row.edit {
+                                    sim::Edit::Same => Color::BLACK,
+                                    sim::Edit::Substituted => Color::from_rgb(0.6, 0.5, 0.0),
+                                    sim::Edit::Inserted => Color::from_rgb(0.0, 0.5, 0.0),
+                                    sim::Edit::Deleted => Color::from_rgb(0.7, 0.0, 0.0),
+                                };
+                                column.push(
+                                    Text::new(format!("{} | {}", locus(&row.a), locus(&row.b)))
+                                        .size(14)
+                                        .color(color),
+                                )
+                            },
+                        )
+                })
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let inspector_ui = Container::new(match self.inspected {
+                    None => Column::new()
+                        .padding(2)
+                        .push(
+                            Text::new("Inspector")
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Text::new("Click a cell to see its genome and memory.").size(14)),
+                    Some((x, y, ref detail)) => {
+                        let summary = format!(
+                            "({}, {}) of {} when clicked
+generation: {}
+ticks lived: {}
+                             food: {}
+money: {}
+signal: {:.3}
+memory: {}",
+                            x,
+                            y,
+                            label_lineage(&self.lineage_legend, detail.lineage),
+                            detail.generation,
+                            detail.ticks,
+                            detail.food,
+                            detail.money,
+                            detail.signal,
+                            detail
+                                .memory
+                                .iter()
+                                .map(|value| format!("{:.3}", value))
+                                .collect::<Vec<_>>()
+                                .join(" "),
+                        );
+                        detail.codons.iter().take(MAX_CODON_ROWS).fold(
+                            Column::new()
+                                .padding(2)
+                                .push(
+                                    Text::new("Inspector")
+                                        .horizontal_alignment(HorizontalAlignment::Center)
+                                        .width(Length::Fill),
+                                )
+                                .push(Text::new(summary).size(16))
+                                .push(
+                                    Text::new(format!("{} codons:", detail.codons.len()))
+                                        .size(16),
+                                ),
+                            // Entry points are marked with '>', as in the genome diff.
+                            |column, locus| {
+                                column.push(
+                                    Text::new(format!(
+                                        "{}{} {}",
+                                        if locus.entry { ">" } else { "" },
+                                        locus.index,
+                                        locus.codon
+                                    ))
+                                    .size(14),
+                                )
+                            },
+                        )
+                    }
+                })
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let expression_ui = Container::new(match self.expression {
+                    None => Column::new()
+                        .padding(2)
+                        .push(
+                            Text::new("Gene Expression")
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Text::new("Click a cell to see which of its genes run.").size(14)),
+                    Some(ref expression) => {
+                        let genes = &expression.genes;
+                        let junk = genes.iter().filter(|gene| gene.is_junk()).count();
+                        let summary = format!(
+                            "over {} ticks\ngenes run: {}\njunk genes: {}",
+                            expression.ticks,
+                            genes.len() - junk,
+                            junk
+                        );
+                        let ticks = expression.ticks.max(1) as f32;
+                        genes.iter().take(MAX_EXPRESSION_ROWS).fold(
+                            Column::new()
+                                .padding(2)
+                                .push(
+                                    Text::new("Gene Expression")
+                                        .horizontal_alignment(HorizontalAlignment::Center)
+                                        .width(Length::Fill),
+                                )
+                                .push(Text::new(summary).size(16)),
+                            |column, gene| {
+                                // Junk is grey, and genes go from blue to red the more they run.
+                                let heat = gene.ran as f32 / ticks;
+                                let color = if gene.is_junk() {
+                                    Color::from_rgb(0.5, 0.5, 0.5)
+                                } else {
+                                    Color::from_rgb(0.8 * heat, 0.0, 0.8 * (1.0 - heat))
+                                };
+                                // Entry points are marked with '>', as in the genome diff, and
+                                // codons which never ran are in parentheses.
+                                let codons = gene
+                                    .codons
+                                    .iter()
+                                    .map(|(codon, ran)| {
+                                        if *ran == 0 {
+                                            format!("({})", codon)
+                                        } else {
+                                            codon.clone()
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join(" ");
+                                column.push(
+                                    Text::new(format!(
+                                        "{}{} ran {:.0}% acted {} decided {} | {}",
+                                        if gene.entry { ">" } else { "" },
+                                        gene.start,
+                                        heat * 100.0,
+                                        gene.acted,
+                                        gene.decided,
+                                        codons
+                                    ))
+                                    .size(14)
+                                    .color(color),
+                                )
+                            },
+                        )
+                    }
+                })
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                // The last decision traced, entry by entry, with the stack each codon ran on.
+                let brain_trace_ui = Container::new(
+                    self.brain_traces.back().map_or(
+                        Column::new().padding(2).push(
+                            Text::new(if self.tracing_brain {
+                                "Click a cell to see how it decides."
+                            } else {
+                                "Trace a followed cell to see how it decides."
+                            })
+                            .size(14),
+                        ),
+                        |trace| {
+                            let summary = format!(
+                                "tick {}: {}\nrotation: {}\nmemory: {}",
+                                trace.tick,
+                                trace.decision,
+                                trace.rotation,
+                                format_values(&trace.memory)
+                            );
+                            let rows = trace.entries.iter().flat_map(|entry| {
+                                std::iter::once(format!(">{} gave {}", entry.entry, entry.action)).chain(
+                                    entry.steps.iter().map(|step| {
+                                        format!(
+                                            "  {} {} on {}",
+                                            step.at,
+                                            step.codon,
+                                            format_values(&step.stack)
+                                        )
+                                    }),
+                                )
+                            });
+                            rows.take(MAX_BRAIN_TRACE_ROWS).fold(
+                                Column::new().padding(2).push(Text::new(summary).size(16)),
+                                |column, row| column.push(Text::new(row).size(14)),
+                            )
+                        },
+                    ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+                let brain_trace_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .push(
+                            Text::new("Brain Execution")
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Checkbox::new(
+                            self.tracing_brain,
+                            "Trace decisions",
+                            Message::TraceBrainToggled,
+                        ))
+                        .push(brain_trace_ui)
+                        .push(
+                            Button::new(
+                                &mut self.export_brain_trace_button,
+                                Text::new("export brain trace"),
+                            )
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::PickFile(FileAction::ExportBrainTrace)),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let current_mode = self.view_settings.mode;
+                let view_mode = |mode, label| {
+                    Radio::new(mode, label, Some(current_mode), Message::ViewModeChanged)
+                };
+                let current_memory = self.view_settings.memory;
+                let view_memory = |memory, label| {
+                    Radio::new(memory, label, Some(current_memory), Message::ViewMemoryChanged)
+                };
+                let filter_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .push(view_mode(sim::ViewMode::Species, "Species"))
+                        .push(view_mode(sim::ViewMode::Age, "Age"))
+                        .push(view_mode(sim::ViewMode::Wealth, "Wealth"))
+                        .push(view_mode(sim::ViewMode::Starvation, "Starvation"))
+                        .push(view_memory(sim::ViewMemory::Auto, "Low memory for large worlds"))
+                        .push(view_memory(sim::ViewMemory::Full, "Always show ancestry"))
+                        .push(view_memory(sim::ViewMemory::Compact, "Always use low memory"))
+                        .push(
+                            TextInput::new(
+                                &mut self.filter_input,
+                                "money > 50",
+                                &self.filter_text,
+                                Message::FilterEdited,
+                            )
+                            .padding(4)
+                            .on_submit(Message::ApplyFilter),
+                        )
+                        .push(
+                            Text::new(match self.filter_matches {
+                                Some(matches) => format!("matching cells: {}", matches),
+                                None => String::from("Enter a filter to highlight cells."),
+                            })
+                            .size(14),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let brush_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .push(Radio::new(None, "Select", Some(self.brush), Message::BrushChanged))
+                        .push(Radio::new(
+                            Some(sim::Brush::Food),
+                            "Food brush",
+                            Some(self.brush),
+                            Message::BrushChanged,
+                        ))
+                        .push(Radio::new(
+                            Some(sim::Brush::Money),
+                            "Money brush",
+                            Some(self.brush),
+                            Message::BrushChanged,
+                        ))
+                        .push(
+                            Slider::new(
+                                &mut self.brush_radius_slider,
+                                0.0..=10.0,
+                                self.brush_radius as f32,
+                                Message::BrushRadiusChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("brush radius: {}", self.brush_radius))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let selected_lineage = self.selected_lineage;
+                let lineage_ui = Container::new(
+                    self.lineage_legend
+                        .iter()
+                        .fold(
+                            Column::new().padding(2).spacing(2).push(
+                                Text::new("Lineages")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            ),
+                            |column, summary| {
+                                column.push(
+                                    Row::new()
+                                        .spacing(4)
+                                        .push(Text::new("■").color(summary.color))
+                                        .push(Radio::new(
+                                            summary.lineage,
+                                            format!(
+                                                "{}: {} ({}% ordered)",
+                                                describe_lineage(
+                                                    summary.lineage,
+                                                    summary.name.clone()
+                                                ),
+                                                summary.living,
+                                                100 * summary.ordered / summary.living.max(1)
+                                            ),
+                                            selected_lineage,
+                                            Message::LineageSelected,
+                                        )),
+                                )
+                            },
+                        )
+                        .push(
+                            TextInput::new(
+                                &mut self.lineage_name_input,
+                                "name the selected lineage",
+                                &self.lineage_name_text,
+                                Message::LineageNameEdited,
+                            )
+                            .padding(4)
+                            .on_submit(Message::NameLineage),
+                        )
+                        .push(Checkbox::new(
+                            self.tracking_ancestry,
+                            "Track ancestry",
+                            Message::TrackAncestryToggled,
+                        ))
+                        .push(
+                            Row::new()
+                                .spacing(2)
+                                .push(
+                                    Button::new(
+                                        &mut self.export_newick_button,
+                                        Text::new("export Newick"),
+                                    )
+                                    .style(style::Theme::Default)
+                                    .on_press(Message::PickFile(FileAction::ExportNewick)),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.export_graphviz_button,
+                                        Text::new("export GraphViz"),
+                                    )
+                                    .style(style::Theme::Default)
+                                    .on_press(Message::PickFile(FileAction::ExportGraphviz)),
+                                ),
+                        )
+                        .push(Checkbox::new(
+                            self.showing_phylogeny,
+                            "Show phylogeny",
+                            Message::PhylogenyToggled,
+                        ))
+                        .push(
+                            if self.showing_phylogeny
+                                && self.tracking_ancestry
+                                && !self.phylogeny.is_empty()
+                            {
+                                self.phylogeny.view().map(Message::Phylogeny)
+                            } else {
+                                Column::new().into()
+                            },
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let planting = self.planting;
+                let library_ui = Container::new(
+                    self.genome_library
+                        .iter()
+                        .enumerate()
+                        .fold(
+                            Column::new().padding(2).spacing(2).push(
+                                Text::new("Genome Library")
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                            ),
+                            |column, (index, (path, genome))| {
+                                column.push(Radio::new(
+                                    index,
+                                    format!(
+                                        "{} ({} codons)",
+                                        path.file_stem().unwrap_or_default().to_string_lossy(),
+                                        genome.codon_count()
+                                    ),
+                                    planting,
+                                    Message::PlantingSelected,
+                                ))
+                            },
+                        )
+                        .push(
+                            Text::new(if planting.is_some() {
+                                "click an open tile to plant the genome"
+                            } else {
+                                "pick a genome to plant it by clicking"
+                            })
+                            .size(16),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(2)
+                                .push(
+                                    Button::new(&mut self.add_genome_button, Text::new("add"))
+                                        .style(style::Theme::Default)
+                                        .on_press(Message::PickFile(FileAction::AddGenome)),
+                                )
+                                .push(
+                                    Button::new(&mut self.forget_genome_button, Text::new("forget"))
+                                        .style(style::Theme::Default)
+                                        .on_press(Message::ForgetGenome),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.stop_planting_button,
+                                        Text::new("stop planting"),
+                                    )
+                                    .style(style::Theme::Default)
+                                    .on_press(Message::StopPlanting),
+                                ),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let sampling_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .spacing(2)
+                        .push(Checkbox::new(
+                            self.sample_each_frame,
+                            "Sample once per frame",
+                            Message::SampleEachFrameToggled,
+                        ))
+                        .push(
+                            Slider::new(
+                                &mut self.sample_ticks_slider,
+                                1.0..=100.0,
+                                self.sample_ticks as f32,
+                                Message::SampleTicksChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("ticks per sample: {}", self.sample_ticks))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                // The last few interventions which can be undone, newest first.
+                let journal_ui = Container::new(
+                    self.journal
+                        .iter()
+                        .flat_map(|journal| journal.done.iter().rev().take(5))
+                        .fold(
+                            Column::new().padding(2).spacing(2).push(
+                                Row::new()
+                                    .spacing(4)
+                                    .push(
+                                        Button::new(&mut self.undo_button, Text::new("undo"))
+                                            .style(style::Theme::Default)
+                                            .on_press(Message::Undo),
+                                    )
+                                    .push(
+                                        Button::new(&mut self.redo_button, Text::new("redo"))
+                                            .style(style::Theme::Default)
+                                            .on_press(Message::Redo),
+                                    )
+                                    .push(
+                                        Text::new(match self.journal {
+                                            Some(ref journal) => format!(
+                                                "{} to undo, {} to redo",
+                                                journal.done.len(),
+                                                journal.undone.len()
+                                            ),
+                                            None => String::from("no interventions yet"),
+                                        })
+                                        .size(14),
+                                    ),
+                            ),
+                            |column, intervention| column.push(Text::new(intervention).size(14)),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let terrain_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .spacing(2)
+                        .push(
+                            Button::new(
+                                &mut self.regenerate_button,
+                                Text::new("regenerate terrain"),
+                            )
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::RegenerateTerrain),
+                        )
+                        .push(Checkbox::new(
+                            self.regenerate_sources,
+                            "New cornacopias",
+                            Message::RegenerateSourcesToggled,
+                        ))
+                        .push(
+                            Text::new(format!(
+                                "openness {}, corridors +{} and smoothing {} from the main menu",
+                                self.openness, self.corridor_width, self.smoothing
+                            ))
+                            .size(16)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .width(Length::Fill),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let timelapse_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .spacing(2)
+                        .push(
+                            Button::new(
+                                &mut self.timelapse_button,
+                                Text::new(if self.recording_timelapse {
+                                    "stop timelapse"
+                                } else {
+                                    "start timelapse"
+                                }),
+                            )
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::ToggleTimelapse),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.timelapse_every_slider,
+                                1.0..=1000.0,
+                                self.timelapse_every as f32,
+                                Message::TimelapseEveryChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("ticks per frame: {}", self.timelapse_every))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.timelapse_downscale_slider,
+                                1.0..=8.0,
+                                self.timelapse_downscale as f32,
+                                Message::TimelapseDownscaleChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("tiles per pixel: {}", self.timelapse_downscale))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        ),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let tape_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .spacing(2)
+                        .push(
+                            Button::new(
+                                &mut self.tape_button,
+                                Text::new(if self.recording_tape {
+                                    "stop trade tape"
+                                } else {
+                                    "record trade tape"
+                                }),
+                            )
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::ToggleTape),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.tape_every_slider,
+                                1.0..=1000.0,
+                                self.tape_every as f32,
+                                Message::TapeEveryChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("ticks per clearing taped: {}", self.tape_every))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Checkbox::new(
+                            self.show_ticker,
+                            "Trade ticker under the grid",
+                            Message::TickerToggled,
+                        )),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let render_ui = Container::new(
+                    Column::new()
+                        .padding(2)
+                        .spacing(2)
+                        .push(
+                            Button::new(&mut self.render_button, Text::new("export region render"))
+                                .style(style::Theme::Default)
+                                .min_width(style::button_size())
+                                .on_press(Message::PickFile(FileAction::RenderRegion)),
+                        )
+                        .push(
+                            Slider::new(
+                                &mut self.render_pixels_slider,
+                                1.0..=64.0,
+                                self.render_pixels as f32,
+                                Message::RenderPixelsChanged,
+                            )
+                            .style(style::Theme::Default),
+                        )
+                        .push(
+                            Text::new(format!("pixels per tile: {}", self.render_pixels))
+                                .size(16)
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Checkbox::new(
+                            self.render_overlays,
+                            "Lines and marks",
+                            Message::RenderOverlaysToggled,
+                        )),
+                )
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let mut event_column = Column::new().padding(2).push(
+                    Text::new("Events")
+                        .horizontal_alignment(HorizontalAlignment::Center)
+                        .width(Length::Fill),
+                );
+                if let Some(ref run) = self.run {
+                    event_column = event_column
+                        .push(Text::new(format!("run: {}", run.path().display())).size(14));
+                }
+                let event_ui = Container::new(self.events.iter().fold(
+                    event_column,
+                    |column, event| column.push(Text::new(event.as_str()).size(14)),
+                ))
+                .style(style::Theme::Nested)
+                .height(Length::Shrink)
+                .width(Length::Fill);
+
+                let holding = self.demo.as_ref().map_or(false, demo::Demo::holding);
+                let mut callout = match self.callout {
+                    Some((target, ref text)) => {
+                        Some((target, callout_ui(text, holding, &mut self.continue_button)))
+                    }
+                    None => None,
+                };
+                let mut grid_controls = Column::new()
+                    .spacing(style::spacing())
+                    .padding(style::padding())
+                    .max_width(style::button_size() + style::padding() as u32);
+                if let Some((demo::Target::Controls, _)) = callout {
+                    grid_controls = grid_controls.push(callout.take().unwrap().1);
+                }
+                let mut grid_controls = grid_controls
+                    .push(
+                        Button::new(&mut self.save_simulation_button, Text::new("save"))
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::PickFile(FileAction::SaveWorld)),
+                    )
+                    .push(
+                        Button::new(&mut self.export_genome_button, Text::new("export genome"))
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::PickFile(FileAction::ExportGenome)),
+                    )
+                    .push(
+                        Button::new(&mut self.export_stats_button, Text::new("export stats"))
+                            .style(style::Theme::Default)
+                            .min_width(style::button_size())
+                            .on_press(Message::PickFile(FileAction::ExportStats)),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.export_lineages_button,
+                            Text::new("export lineages"),
+                        )
+                        .style(style::Theme::Default)
+                        .min_width(style::button_size())
+                        .on_press(Message::PickFile(FileAction::ExportLineages)),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.database_button,
+                            Text::new(if self.database.is_some() {
+                                "stop recording"
+                            } else {
+                                "record stats"
+                            }),
+                        )
+                        .style(style::Theme::Default)
+                        .min_width(style::button_size())
+                        .on_press(Message::ToggleDatabase),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.toggle_run_button,
+                            if self.is_running_sim {
+                                Text::new("Pause")
+                            } else {
+                                Text::new("Run")
+                            },
+                        )
+                        .style(style::Theme::Default)
+                        .min_width(style::button_size())
+                        .on_press(Message::ToggleSim),
+                    );
+                let step = Button::new(&mut self.step_button, Text::new("Step"))
+                    .style(style::Theme::Default)
+                    .min_width(style::button_size());
+                grid_controls = grid_controls.push(if self.is_running_sim {
+                    step
+                } else {
+                    step.on_press(Message::StepTick)
+                });
+                // A control whose change never reached the sim is named until it is moved again.
+                if !unapplied.is_empty() {
+                    grid_controls = grid_controls.push(
+                        Text::new(format!("not in effect: {}", unapplied.join(", ")))
+                            .size(14)
+                            .color(Color::from_rgb(0.6, 0.5, 0.0))
+                            .width(Length::Fill),
+                    );
+                }
+                let grid_controls = grid_controls
+                    .push(fps_controls)
+                    .push(spawn_controls)
+                    .push(food_controls)
+                    .push(mutation_controls)
+                    .push(Checkbox::new(
+                        self.direct_exchange,
+                        "Direct exchange",
+                        Message::DirectExchangeToggled,
+                    ))
+                    .push(Checkbox::new(
+                        self.sense_life,
+                        "Sense age and generation",
+                        Message::SenseLifeToggled,
+                    ))
+                    .push(Checkbox::new(
+                        self.sense_fills,
+                        "Sense order fills",
+                        Message::SenseFillsToggled,
+                    ))
+                    .push(Radio::new(
+                        sim::Pipeline::MARKET_AFTER_CONSUMPTION,
+                        "Market after consumption",
+                        Some(self.pipeline),
+                        Message::PipelineChanged,
+                    ))
+                    .push(Radio::new(
+                        sim::Pipeline::MARKET_BEFORE_CONSUMPTION,
+                        "Market before consumption",
+                        Some(self.pipeline),
+                        Message::PipelineChanged,
+                    ))
+                    .push(Radio::new(
+                        sim::Sanitization::Off,
+                        "Keep NaN and infinity",
+                        Some(self.sanitization),
+                        Message::SanitizationChanged,
+                    ))
+                    .push(Radio::new(
+                        sim::Sanitization::Zero,
+                        "Zero NaN and infinity",
+                        Some(self.sanitization),
+                        Message::SanitizationChanged,
+                    ))
+                    .push(Radio::new(
+                        sim::Sanitization::Clamp,
+                        "Zero NaN and clamp memory",
+                        Some(self.sanitization),
+                        Message::SanitizationChanged,
+                    ))
+                    .push(breaker_controls)
+                    .push(monetary_controls)
+                    .push(recovery_controls)
+                    .push(noise_controls)
+                    .push(
+                        Button::new(
+                            &mut self.toggle_grid_button,
+                            Text::new(match self.grid {
+                                Some(ref grd) => {
+                                    if grd.is_showing_lines() {
+                                        "Hide Grid"
+                                    } else {
+                                        "Show Grid"
+                                    }
+                                }
+                                None => panic!(
+                                    "grid doesn't exist when attempting to draw grid controls!"
+                                ),
+                            }),
+                        )
+                        .style(style::Theme::Default)
+                        .min_width(style::button_size())
+                        .on_press(Message::ToggleGrid),
+                    )
+                    .push(Checkbox::new(
+                        self.session.floorplan,
+                        "Rooms and regions",
+                        Message::FloorplanToggled,
+                    ))
+                    .push(filter_ui)
+                    .push(brush_ui)
+                    .push(terrain_ui)
+                    .push(journal_ui)
+                    .push(lineage_ui)
+                    .push(library_ui)
+                    .push(timelapse_ui)
+                    .push(tape_ui)
+                    .push(render_ui)
+                    .push(
+                        Button::new(
+                            &mut self.toggle_stats_button,
+                            Text::new(if self.show_stats {
+                                "Hide Stats"
+                            } else {
+                                "Show Stats"
+                            }),
+                        )
+                        .style(style::Theme::Default)
+                        .min_width(style::button_size())
+                        .on_press(Message::ToggleStats),
+                    )
+                    .push(sampling_ui);
+                let grid_controls = match stats_ui {
+                    Some(stats_ui) => match callout {
+                        Some((demo::Target::Market, _)) => grid_controls
+                            .push(callout.take().unwrap().1)
+                            .push(stats_ui),
+                        _ => grid_controls.push(stats_ui),
+                    },
+                    None => grid_controls,
+                }
+                .push(inspector_ui)
+                .push(diff_ui)
+                .push(expression_ui)
+                .push(brain_trace_ui)
+                .push(event_ui);
+
+                let scrollable = Scrollable::new(&mut self.scroll).push(grid_controls);
+
+                let grid_ui: Element<_> = match self.grid {
+                    Some(ref mut grd) => grd.view().map(Message::Grid),
+                    None => {
+                        panic!("unexpected entry to view without initializing grid")
+                    }
+                };
+                // An empty world says so, with ways to bring life back, rather than staying dark.
+                let empty_ui = if self.cell_count == 0 {
+                    let raise = Button::new(&mut self.raise_spawn_button, Text::new("raise spawn rate"))
+                        .style(style::Theme::Default)
+                        .min_width(style::button_size());
+                    Some(
+                        Container::new(
+                            Column::new()
+                                .padding(style::padding())
+                                .spacing(style::spacing())
+                                .push(
+                                    Text::new("The world is empty")
+                                        .horizontal_alignment(HorizontalAlignment::Center)
+                                        .width(Length::Fill),
+                                )
+                                .push(
+                                    Text::new(if self.recovery {
+                                        format!(
+                                            "Once it has had life and dies out, it starts over with {} cells on its own.",
+                                            self.recovery_population
+                                        )
+                                    } else {
+                                        String::from("Turn on recovery to start it over on its own once it dies out.")
+                                    })
+                                    .size(16)
+                                    .horizontal_alignment(HorizontalAlignment::Center)
+                                    .width(Length::Fill),
+                                )
+                                .push(
+                                    Row::new()
+                                        .spacing(style::spacing())
+                                        .push(if self.spawn_rate < 1.0 {
+                                            raise.on_press(Message::RaiseSpawnRate)
+                                        } else {
+                                            raise
+                                        })
+                                        .push(
+                                            Button::new(
+                                                &mut self.reseed_now_button,
+                                                Text::new(if self.recover_from_hall {
+                                                    format!("spawn {} from the hall of fame", self.recovery_population)
+                                                } else {
+                                                    format!("spawn {} random cells", self.recovery_population)
+                                                }),
+                                            )
+                                            .style(style::Theme::Default)
+                                            .min_width(style::button_size())
+                                            .on_press(Message::ReseedNow),
+                                        )
+                                        .push(
+                                            Button::new(&mut self.plant_button, Text::new("plant genome"))
+                                                .style(style::Theme::Default)
+                                                .min_width(style::button_size())
+                                                .on_press(Message::PickFile(FileAction::PlantGenome)),
+                                        ),
+                                )
+                                .push(Checkbox::new(
+                                    self.recovery,
+                                    "Recover from extinction",
+                                    Message::RecoveryToggled,
+                                ))
+                                .push(Checkbox::new(
+                                    self.recover_from_hall,
+                                    "From the hall of fame",
+                                    Message::RecoverFromHallToggled,
+                                )),
+                        )
+                        .style(style::Theme::Nested)
+                        .width(Length::Fill),
+                    )
+                } else {
+                    None
+                };
+                // Whatever is left of a callout is shown by the grid, so that it is never lost.
+                let callout = callout.map(|(_, callout)| callout);
+                let stacked = self.show_ticker || empty_ui.is_some() || callout.is_some();
+                let grid_ui: Element<_> = if stacked {
+                    let column = Column::new().width(Length::Fill).height(Length::Fill);
+                    let column = match callout {
+                        Some(callout) => column.push(callout),
+                        None => column,
+                    };
+                    let column = match empty_ui {
+                        Some(empty_ui) => column.push(empty_ui),
+                        None => column,
+                    }
+                    .push(grid_ui);
+                    if self.show_ticker {
+                        column.push(ticker_row(&self.ticker)).into()
+                    } else {
+                        column.into()
+                    }
+                } else {
+                    grid_ui
+                };
+
+                Container::new(
+                    Row::new().push(
+                        Row::new()
+                            .push(scrollable)
+                            // TODO, .push( Text::new("Click a cell to see its genome or save it.\n\nClick an empty spot to plant a cell from the save files.\n\nUse the wheel to zoom | right click to pan.") ) )
+                            //        requires tracking number of marked ancestors in EvonomicsWorld: .push( table with rows of cell ancestors, collumns of color, hide/show radio button, delete button )
+                            .push(grid_ui),
+                    ),
+                )
+                .style(style::Theme::Default)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x()
+                .center_y()
+                .into()
+            }
+            MenuState::Spectating => {
+                let (addr, stats) = self
+                    .spectating
+                    .as_ref()
+                    .expect("spectating without an address");
+                let stats_text = match stats {
+                    Some((tick, header, values)) => header.iter().zip(values).fold(
+                        format!("tick: {}", tick),
+                        |text, (column, value)| format!("{}\n{}: {}", text, column, value),
+                    ),
+                    None => String::from("waiting for stats"),
+                };
+                let info = Container::new(
+                    Column::new()
+                        .padding(style::padding())
+                        .spacing(style::spacing())
+                        .push(
+                            Text::new("Spectating")
+                                .horizontal_alignment(HorizontalAlignment::Center)
+                                .width(Length::Fill),
+                        )
+                        .push(Text::new(format!("watching {}\ncells: {}", addr, self.cell_count)).size(16))
+                        .push(Text::new(stats_text).size(16)),
+                )
+                .style(style::Theme::Nested)
+                .max_width(style::menu_column_width());
+                let scrollable = Scrollable::new(&mut self.scroll).push(info);
+
+                Container::new(
+                    Row::new().push(scrollable).push(match self.grid {
+                        Some(ref mut grd) => grd.view().map(Message::Grid),
+                        None => Text::new("waiting for the first view")
+                            .width(Length::Fill)
+                            .horizontal_alignment(HorizontalAlignment::Center)
+                            .into(),
+                    }),
+                )
+                .style(style::Theme::Default)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+            }
+        };
+
+        match alert {
+            Some(alert) => Column::new().push(alert).push(content).into(),
+            None => content,
+        }
+    }
+}
+
+/// A callout of a demo, with a button to go on if the demo is held until the viewer is ready.
+fn callout_ui<'a>(text: &str, holding: bool, button: &'a mut button::State) -> Element<'a, Message> {
+    let column = Column::new()
+        .spacing(style::spacing())
+        .push(Text::new(text).size(16).width(Length::Fill));
+    let column = if holding {
+        column.push(
+            Button::new(button, Text::new("Continue"))
+                .style(style::Theme::Default)
+                .on_press(Message::ContinueDemo),
+        )
+    } else {
+        column
+    };
+    Container::new(column)
+        .padding(style::padding())
+        .style(style::Callout)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Everything the layout of a new world is generated from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct WorldShape {
+    width: usize,
+    height: usize,
+    maze: sim::Maze,
+    cornacopia_count_probability: f64,
+    tiers: sim::Tiers,
+    seed: u64,
+}
+
+impl WorldShape {
+    fn generate(&self) -> sim::Layout {
+        sim::Layout::generate(
+            self.width,
+            self.height,
+            self.maze,
+            self.cornacopia_count_probability,
+            &self.tiers,
+            self.seed,
+        )
+    }
+}
+
+/// The widest a preview of a world is drawn, in pixels.
+const PREVIEW_WIDTH: usize = 256;
+
+/// A world generated on the main menu to look at before starting it.
+#[derive(Debug)]
+struct WorldPreview {
+    shape: WorldShape,
+    layout: sim::Layout,
+    stats: sim::LayoutStats,
+    thumbnail: image::Handle,
+}
+
+impl WorldPreview {
+    fn generate(shape: WorldShape) -> Self {
+        let layout = shape.generate();
+        let (width, height, pixels) = layout.thumbnail(PREVIEW_WIDTH);
+        Self {
+            shape,
+            stats: layout.stats(),
+            layout,
+            thumbnail: image::Handle::from_pixels(width, height, pixels),
+        }
+    }
+}
+
+/// Something done with a file the user picks.
+#[derive(Copy, Clone, Debug)]
+enum FileAction {
+    SaveWorld,
+    LoadWorld,
+    ExportGenome,
+    /// Spawn copies of a genome from a file into the world.
+    PlantGenome,
+    /// Put a genome from a file into the library.
+    AddGenome,
+    ExportStats,
+    ExportLineages,
+    ExportTrace,
+    /// Write how the followed cell came to its decisions, codon by codon.
+    ExportBrainTrace,
+    /// Write the family tree of the brains as a Newick tree.
+    ExportNewick,
+    /// Write the family tree of the brains as a GraphViz graph.
+    ExportGraphviz,
+    ExportAudit,
+    /// Record the stats into a database as they arrive.
+    RecordStats,
+    /// Record every trade onto a tape as it is executed.
+    RecordTape,
+    /// Render the part of the world on screen into an image.
+    RenderRegion,
+}
+
+impl FileAction {
+    fn kind(self) -> io::FileKind {
+        match self {
+            FileAction::SaveWorld | FileAction::LoadWorld => io::FileKind::World,
+            FileAction::ExportGenome | FileAction::PlantGenome | FileAction::AddGenome => {
+                io::FileKind::Genome
+            }
+            FileAction::ExportStats => io::FileKind::Csv,
+            FileAction::ExportLineages => io::FileKind::Lineages,
+            FileAction::ExportTrace => io::FileKind::Trace,
+            FileAction::ExportBrainTrace => io::FileKind::BrainTrace,
+            FileAction::ExportNewick => io::FileKind::Newick,
+            FileAction::ExportGraphviz => io::FileKind::Graphviz,
+            FileAction::ExportAudit => io::FileKind::Audit,
+            FileAction::RecordStats => io::FileKind::Database,
+            FileAction::RecordTape => io::FileKind::Tape,
+            FileAction::RenderRegion => io::FileKind::Render,
+        }
+    }
+
+    fn reads(self) -> bool {
+        matches!(
+            self,
+            FileAction::LoadWorld | FileAction::PlantGenome | FileAction::AddGenome
+        )
+    }
+}
+
+const STATS_HEADER: &[&str] = &[
+    "bid",
+    "ask",
+    "reserve",
+    "buy_volume",
+    "sell_volume",
+    "trades",
+    "reserve_purchases",
+    "reserve_sales",
+    "escheatment",
+    "mean_age",
+    "max_age",
+    "spawn_chance",
+    "mutation_chance",
+    "general_food_chance",
+    "cornacopia_chance",
+    "cornacopia_bounty",
+    "sanitized_memory",
+    "sanitized_signals",
+    "sanitized_trades",
+    "halted",
+    "starved",
+    "died_in_walls",
+    "died_combining",
+    "hungry",
+    "expired_orders",
+    "partial_orders",
+    "separators",
+    "demurrage",
+    "mean_genome_length",
+    "unreachable_codons",
+    "unexecuted_codons",
+    "inheritance_tax",
+    "choice_move",
+    "choice_divide",
+    "choice_buy",
+    "choice_sell",
+    "choice_exchange",
+    "choice_nothing",
+    "approximated_ticks",
+    "population",
+    "unique_genomes",
+    "signal_food",
+    "spoiled_food",
+    "metabolism_food",
+    "minted",
+];
+
+const LINEAGES_HEADER: &[&str] = &["lineage", "name", "living", "ordered"];
+
+const TRACE_HEADER: &[&str] = &["tick", "x", "y", "food", "money"];
+
+const AUDIT_HEADER: &[&str] = &[
+    "dimension",
+    "bucket",
+    "side",
+    "orders",
+    "placed",
+    "filled",
+    "fill_rate",
+    "mean_order_fill",
+    "z",
+];
+
+/// A lineage by the name it has in the legend.
+fn label_lineage(legend: &[sim::LineageSummary], lineage: u64) -> String {
+    let name = legend
+        .iter()
+        .find(|summary| summary.lineage == lineage)
+        .and_then(|summary| summary.name.clone());
+    describe_lineage(lineage, name)
+}
+
+/// A lineage by its name if it has one, along with the id of its founder.
+fn describe_lineage(lineage: u64, name: Option<String>) -> String {
+    match name {
+        Some(name) => format!("\"{}\" (lineage {})", name, lineage),
+        None => format!("lineage {}", lineage),
+    }
+}
+
+/// The mean buying and selling prices of a lineage, and the spread between them.
+fn describe_lineage_prices(label: &str, lineage: &sim::LineagePrices) -> String {
+    let prices = match lineage.prices {
+        Some(prices) => prices,
+        None => return format!("{}: no trades", label),
+    };
+    let side = |price: Option<f64>| match price {
+        Some(price) => format!("{:.2}", price),
+        None => String::from("-"),
+    };
+    let spread = match (prices.buy, prices.sell) {
+        (Some(buy), Some(sell)) => format!(", spread {:+.2}", sell - buy),
+        _ => String::new(),
+    };
+    format!(
+        "{}: buys at {}, sells at {}{}",
+        label,
+        side(prices.buy),
+        side(prices.sell),
+        spread
+    )
+}
+
+/// The most recent trades as price by size, newest first, each green when the buyer took it and
+/// red when the seller did.
+fn ticker_row(ticker: &VecDeque<sim::Print>) -> Element<'static, Message> {
+    let row = Row::new()
+        .padding(style::padding())
+        .spacing(style::spacing() * 2)
+        .align_items(Align::Center);
+    let row = if ticker.is_empty() {
+        row.push(Text::new("no trades yet").size(14))
+    } else {
+        ticker.iter().rev().fold(row, |row, print| {
+            row.push(
+                Text::new(format!("{}\u{d7}{}", print.price, print.food))
+                    .size(14)
+                    .color(if print.taker_buys {
+                        Color::from_rgb(0.4, 1.0, 0.4)
+                    } else {
+                        Color::from_rgb(1.0, 0.4, 0.4)
+                    }),
+            )
+        })
+    };
+    Container::new(row)
+        .style(style::Theme::Nested)
+        .width(Length::Fill)
+        .into()
+}
+
+/// A correlation colored green when positive and red when negative.
+fn correlation_text(correlation: Option<f64>) -> Text {
+    match correlation {
+        Some(r) => Text::new(format!("{:+.2}", r)).color(if r >= 0.0 {
+            Color::from_rgb(0.4, 1.0, 0.4)
+        } else {
+            Color::from_rgb(1.0, 0.4, 0.4)
+        }),
+        None => Text::new("-"),
+    }
+}
+
+/// The strongest correlation in words, as what evolution currently rewards or punishes.
+fn describe_selection(selection: &sim::Selection) -> String {
+    let strongest = selection
+        .correlations
+        .iter()
+        .enumerate()
+        .flat_map(|(t, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(f, r)| r.map(|r| (t, f, r)))
+        })
+        .max_by(|a, b| a.2.abs().partial_cmp(&b.2.abs()).unwrap());
+    match strongest {
+        Some((t, f, r)) => format!(
+            "over the last {} deaths, a higher {} goes with {} {} (r = {:+.2})",
+            selection.deaths,
+            sim::TRAITS[t],
+            if r >= 0.0 { "higher" } else { "lower" },
+            sim::FITNESS[f],
+            r
+        ),
+        None => format!("{} deaths so far, too few to compare", selection.deaths),
+    }
+}
+
+/// A number of bytes in the largest unit which keeps it at least one.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Numbers of a brain, such as its memory or stack, short enough to read at a glance.
+fn format_values(values: &[f64]) -> String {
+    let values: Vec<String> = values.iter().map(|value| format!("{:.3}", value)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+fn describe_lineage_event(event: sim::LineageEvent) -> String {
+    match event {
+        sim::LineageEvent::Originated { lineage, name } => {
+            format!("{} originated", describe_lineage(lineage, name))
+        }
+        sim::LineageEvent::Extinct {
+            lineage,
+            name,
+            postmortem: None,
+        } => format!("{} went extinct", describe_lineage(lineage, name)),
+        sim::LineageEvent::Extinct {
+            lineage,
+            name,
+            postmortem: Some(postmortem),
+        } => format!(
+            "{} went extinct after a peak of {} cells, mostly by {}",
+            describe_lineage(lineage, name),
+            postmortem.peak,
+            match postmortem.dominant_cause {
+                sim::Cause::Starvation => "starving",
+                sim::Cause::WallsOrCombining => "walls or combining",
+            }
+        ),
+    }
+}
+
+// Closing the window drops the app, which is the only hook iced gives for it.
+impl Drop for EvonomicsWorld {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
+
+impl EvonomicsWorld {
+    /// Shows a failure the user should not miss, keeping it in the event log as well.
+    fn alert(&mut self, alert: String) {
+        self.log_event(alert.clone());
+        self.alert = Some(alert);
+    }
+
+    /// The maze the main menu would draw walls with.
+    fn maze(&self) -> sim::Maze {
+        sim::Maze {
+            openness: self.openness,
+            corridor_width: self.corridor_width,
+            smoothing: self.smoothing,
+        }
+    }
+
+    /// The shape of the world the main menu would start.
+    /// The food the cornacopias of the running world are expected to yield each tick.
+    fn source_food(&self) -> f64 {
+        sim::Tier::ALL
+            .iter()
+            .map(|&tier| {
+                self.tiers.expected_food(
+                    tier,
+                    self.cornacopia_probability,
+                    self.cornacopia_bounty,
+                ) * self.source_tiles[tier.index()] as f64
+            })
+            .sum()
+    }
+
+    fn world_shape(&self) -> WorldShape {
+        WorldShape {
+            width: self.width,
+            height: self.aspect_ratio.get_height(self.width),
+            maze: self.maze(),
+            cornacopia_count_probability: self.cornacopia_count_probability,
+            tiers: self.tiers,
+            seed: self.world_seed,
+        }
+    }
+
+    /// Picks the lineage to rename, filling in its current name.
+    fn select_lineage(&mut self, lineage: u64) {
+        self.selected_lineage = Some(lineage);
+        self.lineage_name_text = self
+            .lineage_legend
+            .iter()
+            .find(|summary| summary.lineage == lineage)
+            .and_then(|summary| summary.name.clone())
+            .unwrap_or_default();
+    }
+
+    /// The legend as rows under `LINEAGES_HEADER`, largest lineage first.
+    fn lineage_rows(&self) -> Vec<Vec<String>> {
+        self.lineage_legend
+            .iter()
+            .map(|summary| {
+                vec![
+                    summary.lineage.to_string(),
+                    summary.name.clone().unwrap_or_default(),
+                    summary.living.to_string(),
+                    summary.ordered.to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    /// The trace of the followed cell as rows under `TRACE_HEADER`, oldest first.
+    fn trace_rows(&self) -> Vec<Vec<String>> {
+        self.trace
+            .iter()
+            .map(|point| {
+                vec![
+                    point.tick.to_string(),
+                    point.x.to_string(),
+                    point.y.to_string(),
+                    point.food.to_string(),
+                    point.money.to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    /// Roughly the memory taken by the samples kept for the graphs and the trace of the followed
+    /// cell, which are what grow as the GUI runs.
+    fn buffer_bytes(&self) -> usize {
+        use std::mem::size_of;
+        let samples = self.bids.len();
+        let per_sample = 2 * size_of::<i32>()
+            + 3 * size_of::<u32>()
+            + size_of::<sim::MoneyFlows>()
+            + size_of::<sim::Params>()
+            + 2 * size_of::<u64>()
+            + size_of::<sim::Sanitized>()
+            + size_of::<sim::DeathCauses>()
+            + size_of::<bool>()
+            + size_of::<sim::OrderCounts>()
+            + size_of::<Vec<(i32, f32)>>()
+            + size_of::<Vec<sim::LineagePrices>>();
+        let depth: usize = self.depth.iter().map(Vec::capacity).sum();
+        let prices: usize = self.lineage_prices.iter().map(Vec::capacity).sum();
+        samples * per_sample
+            + depth * size_of::<(i32, f32)>()
+            + prices * size_of::<sim::LineagePrices>()
+            + self.trace.len() * size_of::<sim::TracePoint>()
+    }
+
+    /// The recorded stats as rows under `STATS_HEADER`, oldest first.
+    fn stats_rows(&self) -> Vec<Vec<String>> {
+        (0..self.bids.len()).map(|i| self.stats_row(i)).collect()
+    }
+
+    /// The `i`th recorded sample as a row under `STATS_HEADER`.
+    fn stats_row(&self, i: usize) -> Vec<String> {
+        let flows = self.money_flows[i];
+        let params = self.params[i];
+        let sanitized = self.sanitized[i];
+        let deaths = self.death_causes[i];
+        let mut row = vec![
+            self.bids[i].to_string(),
+            self.asks[i].to_string(),
+            self.reserves[i].to_string(),
+            self.buy_volumes[i].to_string(),
+            self.sell_volumes[i].to_string(),
+            flows.trades.to_string(),
+            flows.reserve_purchases.to_string(),
+            flows.reserve_sales.to_string(),
+            flows.escheatment.to_string(),
+            self.mean_ages[i].to_string(),
+            self.max_ages[i].to_string(),
+            params.spawn_chance.to_string(),
+            params.mutation_chance.to_string(),
+            params.general_food_chance.to_string(),
+            params.cornacopia_chance.to_string(),
+            params.cornacopia_bounty.to_string(),
+            sanitized.memory.to_string(),
+            sanitized.signals.to_string(),
+            sanitized.trades.to_string(),
+            (self.halted[i] as u8).to_string(),
+            deaths.starved.to_string(),
+            deaths.walls.to_string(),
+            deaths.combined.to_string(),
+            deaths.hungry.to_string(),
+            self.order_counts[i].expired.to_string(),
+            self.order_counts[i].partial.to_string(),
+            self.separators[i].to_string(),
+            flows.demurrage.to_string(),
+            self.junk[i].mean_length.to_string(),
+            self.junk[i].unreachable.to_string(),
+            self.junk[i].unexecuted.to_string(),
+            flows.inheritance_tax.to_string(),
+        ];
+        row.extend(self.choices[i].iter().map(|count| count.to_string()));
+        row.push(self.approximated[i].to_string());
+        row.push(self.populations[i].to_string());
+        row.push(self.unique_genome_counts[i].to_string());
+        row.push(self.signaling[i].to_string());
+        row.push(self.spoiled[i].to_string());
+        row.push(self.metabolized[i].to_string());
+        row.push(flows.minted.to_string());
+        row
+    }
+
+    /// Starts recording the stats into a database, replacing any being recorded into.
+    fn open_database(&mut self, path: PathBuf) {
+        self.database = None;
+        match database::StatsDatabase::open(&path) {
+            Ok(database) => {
+                self.database = Some(database);
+                self.log_event(format!("recording stats to {}", path.display()));
+            }
+            Err(e) => self.alert(format!(
+                "failed to open the database {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    /// Keeps a sample of stats for the graphs, dropping the oldest once they are full, and
+    /// records it into the database if there is one.
+    fn add_sample(&mut self, stats: sim::Stats) {
+        let sim::Stats {
+            tick,
+            ask,
+            bid,
+            reserve,
+            buy_volume,
+            sell_volume,
+            money_flows,
+            mean_age,
+            max_age,
+            params,
+            population,
+            unique_genomes,
+            interned_genomes,
+            separators,
+            junk,
+            choices,
+            sanitized,
+            deaths,
+            selection,
+            ages,
+            lineage_prices,
+            candle,
+            depth,
+            halted,
+            orders,
+            fertile,
+            sources,
+            region_populations,
+            approximated,
+            signaling,
+            spoiled,
+            metabolized,
+        } = stats;
+        self.ages = ages;
+        self.fertile_tiles = fertile;
+        self.source_tiles = sources;
+        self.region_populations = region_populations;
+        self.selection = selection;
+        self.population = population;
+        self.unique_genomes = unique_genomes;
+        self.interned_genomes = interned_genomes;
+        self.bids.push_back(bid.unwrap_or(0));
+        self.asks.push_back(ask.unwrap_or(0));
+        self.reserves.push_back(reserve);
+        self.buy_volumes.push_back(buy_volume);
+        self.sell_volumes.push_back(sell_volume);
+        self.money_flows.push_back(money_flows);
+        self.params.push_back(params);
+        self.mean_ages.push_back(mean_age);
+        self.max_ages.push_back(max_age);
+        self.sanitized.push_back(sanitized);
+        self.death_causes.push_back(deaths);
+        self.lineage_prices.push_back(lineage_prices);
+        if let (Some(sonifier), Some(candle)) = (&mut self.sonifier, candle) {
+            sonifier.play(candle);
+        }
+        self.candles.push_back(candle);
+        self.halted.push_back(halted);
+        self.order_counts.push_back(orders);
+        self.separators.push_back(separators);
+        self.junk.push_back(junk);
+        self.choices.push_back(choices);
+        self.approximated.push_back(approximated);
+        self.populations.push_back(population);
+        self.unique_genome_counts.push_back(unique_genomes);
+        self.signaling.push_back(signaling);
+        self.spoiled.push_back(spoiled);
+        self.metabolized.push_back(metabolized);
+        self.depth.push_back(depth);
+        if self.bids.len() > MAX_GRAPH_TIMES {
+            self.bids.pop_front();
+            self.asks.pop_front();
+            self.reserves.pop_front();
+            self.buy_volumes.pop_front();
+            self.sell_volumes.pop_front();
+            self.money_flows.pop_front();
+            self.params.pop_front();
+            self.mean_ages.pop_front();
+            self.max_ages.pop_front();
+            self.sanitized.pop_front();
+            self.death_causes.pop_front();
+            self.lineage_prices.pop_front();
+            self.candles.pop_front();
+            self.halted.pop_front();
+            self.order_counts.pop_front();
+            self.separators.pop_front();
+            self.junk.pop_front();
+            self.choices.pop_front();
+            self.approximated.pop_front();
+            self.populations.pop_front();
+            self.unique_genome_counts.pop_front();
+            self.signaling.pop_front();
+            self.spoiled.pop_front();
+            self.metabolized.pop_front();
+            self.depth.pop_front();
+        }
+        self.milestones.spawn |= population > 0;
+        self.milestones.trade |= self.candles.back().map_or(false, Option::is_some);
+        // Only a division makes a brain of a later generation than those spawned.
+        self.milestones.division |= max_age > 0;
+        self.record_sample(tick);
+        if let Some(ref broadcast) = self.broadcast {
+            if broadcast.watched() {
+                broadcast.send(spectate::Frame::Stats {
+                    tick,
+                    header: STATS_HEADER.iter().map(|&column| column.into()).collect(),
+                    values: self.stats_row(self.bids.len() - 1),
+                });
+            }
+        }
+    }
+
+    /// Records the newest sample, which ended on `tick` of the world, and the legend into the
+    /// database, if there is one.
+    fn record_sample(&mut self, tick: u64) {
+        if self.database.is_none() {
+            return;
+        }
+        let row = self.stats_row(self.bids.len() - 1);
+        let legend = &self.lineage_legend;
+        let result = match self.database {
+            Some(ref mut database) => database
+                .record_sample(tick, STATS_HEADER, &row)
+                .and_then(|()| {
+                    database.record_lineages(
+                        tick,
+                        legend.iter().map(|summary| {
+                            (
+                                summary.lineage,
+                                summary.name.as_deref(),
+                                summary.living,
+                                summary.ordered,
+                            )
+                        }),
+                    )
+                }),
+            None => return,
+        };
+        if let Err(e) = result {
+            self.database_failed(e);
+        }
+    }
+
+    /// Stops recording into a database which can no longer be written.
+    fn database_failed(&mut self, e: Box<dyn std::error::Error>) {
+        self.database = None;
+        self.alert(format!("stopped recording stats: {}", e));
+    }
+
+    /// Writes the postmortem of a major lineage into the run, if there is one.
+    fn save_postmortem(&mut self, postmortem: &sim::Postmortem) {
+        let path = match self.run {
+            Some(ref run) => run.postmortem(postmortem.lineage, postmortem.extinct),
+            None => return,
+        };
+        if let Err(e) = io::write_postmortem(&path, postmortem) {
+            self.alert(format!(
+                "failed to write a postmortem to {}: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+
+    fn log_event(&mut self, event: String) {
+        if let Some(ref database) = self.database {
+            if let Err(e) = database.record_event(self.total_tick_count, &event) {
+                self.database_failed(e);
+            }
+        }
+        if let Some(ref run) = self.run {
+            // Not through `alert`, which would log the failure in turn.
+            if let Err(e) = run.log(self.total_tick_count, &event) {
+                self.alert = Some(format!("failed to log to {}: {}", run.path().display(), e));
+            }
+        }
+        self.events
+            .push_back(format!("tick {}: {}", self.total_tick_count, event));
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    fn rules(&self) -> sim::Rules {
+        sim::Rules {
+            direct_exchange: self.direct_exchange,
+            sense_life: self.sense_life,
+            sense_fills: self.sense_fills,
+            starvation_grace: self.starvation_grace,
+            pipeline: self.pipeline,
+            sanitization: self.sanitization,
+            circuit_breaker: if self.circuit_breaker {
+                Some(sim::CircuitBreaker {
+                    move_percent: self.breaker_move_percent,
+                    window: self.breaker_window,
+                    halt: self.breaker_halt,
+                })
+            } else {
+                None
+            },
+            demurrage: if self.demurrage {
+                Some(sim::Demurrage {
+                    percent: self.demurrage_percent,
+                    carried: self.demurrage_carried,
+                })
+            } else {
+                None
+            },
+            inheritance_tax: self.inheritance_tax,
+            tiers: self.tiers,
+            approximation: if self.stepped_percent < 100.0 {
+                Some(self.stepped_percent / 100.0)
+            } else {
+                None
+            },
+            proceeds: self.proceeds,
+            exhaustion: self.exhaustion,
+            signal_cost: self.signal_cost,
+            metabolism: self.metabolism,
+            freeze: self.freeze,
+            spoilage: sim::Spoilage {
+                capacity: if self.capped {
+                    Some(self.capacity)
+                } else {
+                    None
+                },
+                above: self.spoils_above,
+                percent: self.spoilage_percent,
+            },
+        }
+    }
+
+    /// Moves the approximation slider to match the rules.
+    fn show_approximation(&mut self, approximation: Option<f64>) {
+        self.stepped_percent = approximation.map_or(100.0, |stepped| stepped * 100.0);
+    }
+
+    /// Moves the spoilage controls to match, leaving the capacity slider where it was if there is
+    /// no cap.
+    fn show_spoilage(&mut self, spoilage: sim::Spoilage) {
+        self.capped = spoilage.capacity.is_some();
+        if let Some(capacity) = spoilage.capacity {
+            self.capacity = capacity;
+        }
+        self.spoilage_percent = spoilage.percent;
+        self.spoils_above = spoilage.above;
+    }
+
+    /// Moves the demurrage controls to match, leaving the slider where it was if there is none.
+    fn show_demurrage(&mut self, demurrage: Option<sim::Demurrage>) {
+        self.demurrage = demurrage.is_some();
+        if let Some(demurrage) = demurrage {
+            self.demurrage_percent = demurrage.percent;
+            self.demurrage_carried = demurrage.carried;
+        }
+    }
+
+    /// Moves the circuit breaker controls to match a breaker, leaving the sliders where they were
+    /// if there is none.
+    fn show_circuit_breaker(&mut self, breaker: Option<sim::CircuitBreaker>) {
+        self.circuit_breaker = breaker.is_some();
+        if let Some(breaker) = breaker {
+            self.breaker_move_percent = breaker.move_percent;
+            self.breaker_window = breaker.window;
+            self.breaker_halt = breaker.halt;
+        }
+    }
+
+    /// What a world is started over with once every cell died, as the controls set it.
+    fn recovery(&self) -> Option<sim::Recovery> {
+        if self.recovery {
+            Some(sim::Recovery {
+                population: self.recovery_population,
+                seeds: if self.recover_from_hall {
+                    sim::Seeds::HallOfFame
+                } else {
+                    sim::Seeds::Random
+                },
+            })
+        } else {
+            None
+        }
+    }
+
+    fn send_recovery(&mut self) {
+        let recovery = self.recovery();
+        match self.sim_tx {
+            Some(ref mut tx) => {
+                // If the channel is full, dont send it.
+                tx.try_send(sim::ToSim::SetRecovery(recovery)).ok();
+            }
+            None => {}
+        }
+    }
+
+    fn noise(&self) -> sim::Noise {
+        sim::Noise {
+            input: self.input_noise,
+            forgetting: self.forgetting,
+        }
+    }
+
+    /// Sends a change of the controls to the shown world and to the neighbor linked to it, which
+    /// runs under the same parameters. A world whose channel is full does not get it.
+    fn send_controls(&mut self, message: impl Fn() -> sim::ToSim) {
+        for tx in self.sim_tx.iter_mut().chain(self.neighbor_tx.iter_mut()) {
+            tx.try_send(message()).ok();
+        }
+    }
+
+    fn send_noise(&mut self) {
+        let noise = self.noise();
+        self.send_controls(move || sim::ToSim::SetNoise(noise));
+    }
+
+    /// Reports a finished audit of the matching of orders, keeping its tallies in the run.
+    fn audited(&mut self, audit: sim::Audit) {
+        let favored = audit.favored();
+        self.log_event(format!(
+            "audited the matching of {} orders over {} ticks: {}",
+            audit.orders(),
+            audit.ticks(),
+            if favored.is_empty() {
+                "no tenth by arrival position or wealth was favored".to_owned()
+            } else {
+                format!("favored or disfavored were {}", favored.join(", "))
+            }
+        ));
+        self.auditing = false;
+        self.audit = Some(audit);
+        if let Some(ref run) = self.run {
+            let path = run.artifact(io::FileKind::Audit, self.total_tick_count);
+            self.export_audit(&path);
+        }
+    }
+
+    /// Asks the sim to write the family tree it tracks, which it answers once written.
+    fn export_ancestry(&mut self, path: PathBuf, format: sim::AncestryFormat) -> Command<Message> {
+        if !self.tracking_ancestry {
+            self.alert("check Track ancestry to track the family tree first".to_owned());
+            return Command::none();
+        }
+        self.log_event(format!(
+            "exporting the ancestry as {} to {}",
+            format,
+            path.display()
+        ));
+        self.send_must(sim::ToSim::ExportAncestry { path, format })
+    }
+
+    fn export_audit(&mut self, path: &Path) {
+        let rows = match self.audit {
+            Some(ref audit) => audit.rows(),
+            None => return,
+        };
+        match io::write_csv(path, AUDIT_HEADER, rows) {
+            Ok(()) => self.log_event(format!("exported audit to {}", path.display())),
+            Err(e) => self.alert(format!(
+                "failed to export audit to {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    /// Records what the edges of a new world do with its run, since it is not kept in saves.
+    fn log_edges(&mut self) {
+        if !self.edges.is_neutral() {
+            self.log_event(format!(
+                "{} edges {} tiles wide with {} chance at the edge",
+                self.edges.policy, self.edges.width, self.edges.chance
+            ));
+        }
+    }
+
+    /// Records the noise in effect with the run, since it is not kept in saves.
+    fn log_noise(&mut self) {
+        self.log_event(format!(
+            "noise is {} on inputs and {} chance of forgetting",
+            self.input_noise, self.forgetting
+        ));
+    }
+
+    /// The parameters as the sliders set them, in the units of the sim.
+    fn settings(&self) -> sanity::Settings {
+        sanity::Settings {
+            params: self.params(),
+            width: self.width,
+            height: self.aspect_ratio.get_height(self.width),
+            cornacopias: self.cornacopia_count_probability,
+        }
+    }
+
+    fn params(&self) -> sim::Params {
+        sim::Params {
+            spawn_chance: self.spawn_chance,
+            mutation_chance: self.mutation_chance,
+            general_food_chance: self.cell_food_probability,
+            cornacopia_chance: self.cornacopia_probability,
+            cornacopia_bounty: self.cornacopia_bounty,
+        }
+    }
+
+    /// The controls which show something other than what the sim last said was in effect, such as
+    /// when a change was dropped because the channel to the sim was full.
+    fn unapplied(&self) -> Vec<&'static str> {
+        let (params, rules) = match self.applied {
+            Some(applied) => applied,
+            None => return vec![],
+        };
+        let shown = (self.params(), self.rules());
+        let mut unapplied = vec![];
+        let mut check = |same: bool, name| {
+            if !same {
+                unapplied.push(name);
+            }
+        };
+        check(params.spawn_chance == shown.0.spawn_chance, "spawn rate");
+        check(params.mutation_chance == shown.0.mutation_chance, "mutation chance");
+        check(
+            params.general_food_chance == shown.0.general_food_chance,
+            "food chance",
+        );
+        check(
+            params.cornacopia_chance == shown.0.cornacopia_chance,
+            "cornacopia chance",
+        );
+        check(
+            params.cornacopia_bounty == shown.0.cornacopia_bounty,
+            "cornacopia bounty",
+        );
+        check(
+            rules.direct_exchange == shown.1.direct_exchange,
+            "direct exchange",
+        );
+        check(rules.sense_life == shown.1.sense_life, "sense age");
+        check(rules.sense_fills == shown.1.sense_fills, "sense fills");
+        check(
+            rules.starvation_grace == shown.1.starvation_grace,
+            "starvation grace",
+        );
+        check(rules.approximation == shown.1.approximation, "stepped");
+        check(rules.pipeline == shown.1.pipeline, "phases");
+        check(rules.sanitization == shown.1.sanitization, "sanitization");
+        check(rules.proceeds == shown.1.proceeds, "proceeds");
+        check(rules.exhaustion == shown.1.exhaustion, "reserve exhaustion");
+        check(rules.freeze == shown.1.freeze, "held still");
+        check(
+            rules.circuit_breaker == shown.1.circuit_breaker,
+            "circuit breaker",
+        );
+        check(rules.demurrage == shown.1.demurrage, "demurrage");
+        check(
+            rules.inheritance_tax == shown.1.inheritance_tax,
+            "inheritance tax",
+        );
+        check(rules.tiers == shown.1.tiers, "tiers");
+        check(rules.signal_cost == shown.1.signal_cost, "signal cost");
+        check(rules.spoilage == shown.1.spoilage, "spoilage");
+        check(rules.metabolism == shown.1.metabolism, "metabolism");
+        unapplied
+    }
+
+    fn send_rules(&mut self) {
+        let rules = self.rules();
+        self.send_controls(move || sim::ToSim::SetRules(rules));
+    }
+
+    /// Switches to the sim menu and starts running the world in the background.
+    /// A world of the shape, rules and parameters picked in the menu, from the preview of it if
+    /// there is one.
+    fn new_sim(&mut self) -> sim::Sim {
+        let shape = self.world_shape();
+        let layout = match self.preview.take() {
+            Some(preview) if preview.shape == shape => preview.layout,
+            _ => shape.generate(),
+        };
+        let mut sim = sim::Sim::from_layout(&layout, self.control.clone());
+        // The seed of the layout also draws what happens in the world, so that picking it
+        // again replays the run.
+        sim.set_seed(self.world_seed);
+        // The next world is a different one unless a seed is picked again.
+        self.world_seed = rand::random();
+        sim.set_rules(self.rules());
+        sim.set_params(self.params());
+        sim.set_regions(self.regions.clone());
+        sim.set_edges(self.edges);
+        sim
+    }
+
+    /// Gives a world the settings which carry over from one run to the next.
+    fn carry_settings(&self, sim: &mut sim::Sim) {
+        sim.set_recovery(self.recovery());
+        sim.set_noise(self.noise());
+        sim.set_mutations(self.mutations);
+    }
+
+    fn start_sim(&mut self, mut sim: sim::Sim) -> Command<Message> {
+        self.carry_settings(&mut sim);
+        // Nothing is known to be in effect in the new world until the sim says so.
+        self.applied = None;
+        self.menu_state = MenuState::SimMenu;
+        self.remember_viewport();
+        let mut grid = grid::Grid::new(sim.width(), sim.height());
+        // A world the same size as one seen before opens where that one was left.
+        if let Some(viewport) = self.session.viewport(sim.width(), sim.height()) {
+            grid.set_viewport(viewport.x, viewport.y, viewport.scaling);
+        }
+        grid.set_lines(self.session.grid_lines);
+        // The rooms and regions only change when the terrain or the regions do, which the sim
+        // reports, so they are outlined from the world as it starts.
+        grid.set_floorplan(&sim.floorplan());
+        grid.set_show_floorplan(self.session.floorplan);
+        grid.set_brushing(self.brush.is_some());
+        self.grid = Some(grid);
+        self.lineage_legend.clear();
+        self.selected_lineage = None;
+        // What the old sim sent and was not yet taken in belongs to its run.
+        self.flush_samples();
+        self.pending_view = None;
+        self.pending_delta = None;
+        self.pending_hover = None;
+        self.parked = None;
+        self.ticker.clear();
+        self.brain_traces.clear();
+        self.milestones = Default::default();
+        // Interventions in the old world cannot be undone in the new one.
+        self.journal = None;
+        self.stroke_pending = false;
+        // The timelapse and the footprint belonged to the old sim.
+        self.recording_timelapse = false;
+        self.footprint = None;
+        self.auditing = false;
+        self.audit = None;
+        self.start_run();
+        // The run's log says which optional mechanics it started with.
+        self.log_event(format!("running with features: {}", sim.features()));
+        if self.noise() != sim::Noise::default() {
+            self.log_noise();
+        }
+
+        let (sim_tx, sim_rx, sim_stop, sim_runner) = sim::run_sim(3, 3, sim);
+        self.sim_tx = Some(sim_tx);
+        self.sim_stop = Some(sim_stop);
+        // Dropping the neighbor ends its sim, which in turn ends the coordinator of their link.
+        self.neighbor_tx = None;
+        self.neighbor_stop = None;
+
+        Command::batch(vec![
+            Command::perform(sim_runner, |_| Message::Null),
+            reciever_command(sim_rx),
+            self.subscribe_stats(),
+            self.send_sampling(),
+            self.send_view_settings(),
+            self.send_ticker(),
+            self.send_must(sim::ToSim::TraceBrain(self.tracing_brain)),
+            self.send_must(sim::ToSim::TrackAncestry(self.tracking_ancestry)),
+            // A loaded world brings its own parameters and rules, which the controls then show.
+            self.request_full_view(),
+        ])
+    }
+
+    /// Reads the layout the window was left in, which the window size was already restored from.
+    fn restore_session(&mut self) {
+        let path = match self.session_path {
+            Some(ref path) => path.clone(),
+            None => return,
+        };
+        match session::Session::load(&path) {
+            Ok(session) => {
+                self.show_stats = session.show_stats;
+                self.load_library(&session.genomes);
+                self.saved_session = session.clone();
+                self.session = session;
+            }
+            Err(e) => {
+                // Writing over a session which could not be read would lose it.
+                self.session_path = None;
+                self.alert(format!(
+                    "failed to read the session {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    /// Reads the genomes of the library kept by the session, leaving out any which can no longer
+    /// be read.
+    fn load_library(&mut self, paths: &[PathBuf]) {
+        for path in paths {
+            match io::read_genome(path) {
+                Ok(genome) => self.genome_library.push((path.clone(), genome)),
+                Err(e) => self.alert(format!(
+                    "left the genome {} out of the library: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+    }
+
+    /// Puts a genome read from or written to a file into the library, replacing what the library
+    /// had from the same file, and picks it to plant.
+    fn add_to_library(&mut self, path: PathBuf, genome: sim::Genome) {
+        match self.genome_library.iter().position(|(p, _)| *p == path) {
+            Some(index) => {
+                self.genome_library[index].1 = genome;
+                self.planting = Some(index);
+            }
+            None => {
+                self.log_event(format!("added {} to the genome library", path.display()));
+                self.genome_library.push((path, genome));
+                self.planting = Some(self.genome_library.len() - 1);
+            }
+        }
+        self.session.genomes = self
+            .genome_library
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+    }
+
+    /// Keeps where the grid is panned and zoomed to for the next world of the same size.
+    fn remember_viewport(&mut self) {
+        if let Some(ref grid) = self.grid {
+            let (width, height) = grid.dimensions();
+            let (x, y, scaling) = grid.viewport();
+            self.session.remember(session::Viewport {
+                width,
+                height,
+                x,
+                y,
+                scaling,
+            });
+        }
+    }
+
+    fn save_session(&mut self) {
+        self.remember_viewport();
+        if self.session == self.saved_session {
+            return;
+        }
+        if let Some(ref path) = self.session_path {
+            if let Err(e) = self.session.save(path) {
+                let path = self.session_path.take().unwrap();
+                self.alert(format!(
+                    "failed to write the session {}: {}",
+                    path.display(),
+                    e
+                ));
+                return;
+            }
+        }
+        self.saved_session = self.session.clone();
+    }
+
+    /// Ends the sim before the app exits: the session is written, the sim finishes its tape and
+    /// saves itself into the run, and the stats database is closed. Nothing can be shown any
+    /// more, so failures are printed.
+    fn shut_down(&mut self) {
+        self.save_session();
+        self.flush_samples();
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        // Only the shown world is saved, but the neighbor still finishes whatever it was doing.
+        if let Some(stop) = self.neighbor_stop.take() {
+            if stop_sim(stop, None, deadline).is_none() {
+                eprintln!("the neighboring sim did not shut down in time, so it was dropped");
+            }
+        }
+        if let Some(stop) = self.sim_stop.take() {
+            let autosave = self.run.as_ref().map(runs::RunDir::autosave);
+            match stop_sim(stop, autosave.clone(), deadline) {
+                Some(errors) => {
+                    for e in &errors {
+                        eprintln!("{}", e);
+                    }
+                    if let (Some(path), true) = (autosave, errors.is_empty()) {
+                        self.log_event(format!("shut down, saved to {}", path.display()));
+                    }
+                }
+                None => eprintln!("the sim did not shut down in time, so it was dropped"),
+            }
+        }
+        self.sim_tx = None;
+        self.neighbor_tx = None;
+        // Closing the database checkpoints its write-ahead log into it.
+        self.database = None;
+    }
+
+    /// Makes a directory for a new run if runs are kept, recording its stats there and keeping
+    /// the scenario it starts with.
+    fn start_run(&mut self) {
+        self.run = None;
+        let root = match self.runs_root {
+            Some(ref root) => root.clone(),
+            None => return,
+        };
+        let run = match runs::RunDir::create(&root) {
+            Ok(run) => run,
+            Err(e) => {
+                self.alert(format!(
+                    "failed to make a run directory in {}: {}",
+                    root.display(),
+                    e
+                ));
+                return;
+            }
+        };
+        let copied = match self.scenario_watcher {
+            Some(ref watcher) => run.copy_scenario(watcher.path()).map_err(|e| {
+                format!(
+                    "failed to copy {} into {}: {}",
+                    watcher.path().display(),
+                    run.path().display(),
+                    e
+                )
+            }),
+            None => Ok(()),
+        };
+        let database = run.database();
+        let started = format!("started run in {}", run.path().display());
+        self.run = Some(run);
+        self.log_event(started);
+        self.open_database(database);
+        if let Err(e) = copied {
+            self.alert(e);
+        }
+    }
+
+    /// Asks the sim for a fresh frame and what is in effect, to correct anything stale.
+    fn request_full_view(&self) -> Command<Message> {
+        match self.sim_tx {
+            // Unlike the sliders this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                Command::perform(
+                    async move { tx.send(sim::ToSim::RequestFullView).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Shows a frame from the sim on the grid.
+    fn show_view(&mut self, view: sim::View) {
+        self.show_frame(view.cells, view.matches, view.into());
+    }
+
+    fn show_view_delta(&mut self, delta: sim::ViewDelta) {
+        self.show_frame(delta.cells, delta.matches, delta.into());
+    }
+
+    /// Puts a frame on the grid, whole or as what changed, and on to any spectators.
+    fn show_frame(&mut self, cells: usize, matches: Option<usize>, frame: grid::Message) {
+        match self.grid {
+            Some(ref mut grd) => {
+                self.cell_count = cells;
+                self.filter_matches = matches;
+                grd.update(frame);
+                if let Some(ref broadcast) = self.broadcast {
+                    if broadcast.watched() {
+                        broadcast.send(spectate::Frame::view(grd.shown()));
+                    }
+                }
+                // What is under the cursor may have changed along with the frame.
+                if let Some(hovered) = grd.hovered() {
+                    self.pending_hover = Some(hovered);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Does the work deferred from the messages of the sim which fits in a frame: the newest
+    /// frame goes on the grid, stats are taken in until the budget runs out, and the graphs are
+    /// redrawn if they are due. Then the sim is listened to again if it was held back and the
+    /// stats have caught up.
+    fn frame(&mut self) -> Command<Message> {
+        if let Some(view) = self.pending_view.take() {
+            self.show_view(view);
+        }
+        if let Some(delta) = self.pending_delta.take() {
+            self.show_view_delta(delta);
+        }
+        self.ask_hover();
+        let start = Instant::now();
+        // At least one sample is taken in each frame, however slow, so the backlog always shrinks.
+        while let Some(stats) = self.pending_samples.pop_front() {
+            self.add_sample(stats);
+            self.graphs_stale = true;
+            if start.elapsed() >= STATS_BUDGET {
+                break;
+            }
+        }
+        let due = self
+            .graphs_drawn
+            .map_or(true, |drawn| drawn.elapsed() >= GRAPH_INTERVAL);
+        if self.graphs_stale && self.stats_visible() && due {
+            self.draw_graphs();
+        }
+        // Waiting for half the backlog to go keeps the sim from being held back every message.
+        match self.parked.take() {
+            Some(stream) if self.pending_samples.len() < MAX_PENDING_SAMPLES / 2 => {
+                reciever_command(stream)
+            }
+            parked => {
+                self.parked = parked;
+                Command::none()
+            }
+        }
+    }
+
+    /// Asks the sim what is on the hovered cell, unless it was asked too recently, in which case a
+    /// later frame asks instead.
+    fn ask_hover(&mut self) {
+        let due = self
+            .hover_asked
+            .map_or(true, |asked| asked.elapsed() >= HOVER_INTERVAL);
+        let (x, y) = match self.pending_hover {
+            Some(hovered) if due => hovered,
+            _ => return,
+        };
+        let sent = match self.sim_tx {
+            Some(ref mut tx) => tx.try_send(sim::ToSim::Hover(x, y)).is_ok(),
+            None => true,
+        };
+        // A question the sim is too busy to take is asked again with the next frame.
+        if sent {
+            self.pending_hover = None;
+            self.hover_asked = Some(Instant::now());
+        }
+    }
+
+    /// Takes in every stat still pending, so that none is lost or recorded into the wrong run.
+    fn flush_samples(&mut self) {
+        if self.pending_samples.is_empty() {
+            return;
+        }
+        while let Some(stats) = self.pending_samples.pop_front() {
+            self.add_sample(stats);
+        }
+        self.graphs_stale = true;
+    }
+
+    /// Drops whatever the brush holds around the cell at this column and row.
+    fn paint(&mut self, x: usize, y: usize) {
+        let brush = match self.brush {
+            Some(brush) => brush,
+            None => return,
+        };
+        let radius = self.brush_radius;
+        match self.sim_tx {
+            Some(ref mut tx) => {
+                // If the channel is full, dont send it, though the stroke still starts with
+                // the next dab which is sent.
+                let sent = tx.try_send(sim::ToSim::Brush {
+                    x,
+                    y,
+                    radius,
+                    brush,
+                    stroke: self.stroke_pending,
+                });
+                if sent.is_ok() {
+                    self.stroke_pending = false;
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn send_view_settings(&self) -> Command<Message> {
+        match self.sim_tx {
+            // Unlike the sliders this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                let settings = self.view_settings.clone();
+                Command::perform(
+                    async move { tx.send(sim::ToSim::SetView(settings)).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Tells the sim how many ticks each sample of stats covers.
+    fn send_sampling(&self) -> Command<Message> {
+        match self.sim_tx {
+            // Unlike the sliders this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                let sampling = if self.sample_each_frame {
+                    sim::Sampling::Frame
+                } else {
+                    sim::Sampling::Ticks(self.sample_ticks)
+                };
+                Command::perform(
+                    async move { tx.send(sim::ToSim::SetSampling(sampling)).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Tells the sim to start saving frames for a timelapse, or to stop with `None`.
+    fn send_timelapse(&self, timelapse: Option<timelapse::Timelapse>) -> Command<Message> {
+        match self.sim_tx {
+            // Unlike the sliders this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                Command::perform(
+                    async move { tx.send(sim::ToSim::SetTimelapse(timelapse)).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Sends a message which must not be dropped when the channel is full, unlike the sliders.
+    fn send_must(&self, message: sim::ToSim) -> Command<Message> {
+        match self.sim_tx {
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                Command::perform(async move { tx.send(message).await.ok() }, |_| Message::Null)
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Asks the sim for the trades of the ticker, or tells it to stop sending them.
+    fn send_ticker(&self) -> Command<Message> {
+        match self.sim_tx {
+            // Like the tape, this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                let shown = self.show_ticker;
+                Command::perform(
+                    async move { tx.send(sim::ToSim::SetTicker(shown)).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    fn send_tape(&self, tape: Option<tape::Tape>) -> Command<Message> {
+        match self.sim_tx {
+            // Unlike the sliders this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                Command::perform(
+                    async move { tx.send(sim::ToSim::SetTape(tape)).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Stats are only worth collecting and graphing while someone can see them.
+    fn stats_visible(&self) -> bool {
+        self.show_stats && !self.minimized
+    }
+
+    fn stats_visibility_changed(&mut self, was_visible: bool) -> Command<Message> {
+        if self.stats_visible() == was_visible {
+            return Command::none();
+        }
+        if self.stats_visible() && self.graphs_stale {
+            self.draw_graphs();
+        }
+        self.subscribe_stats()
+    }
+
+    /// Tells the sim whether to send stats.
+    fn subscribe_stats(&self) -> Command<Message> {
+        match self.sim_tx {
+            // Unlike the sliders this must not be dropped when the channel is full.
+            Some(ref tx) => {
+                let mut tx = tx.clone();
+                let subscribe = self.stats_visible();
+                Command::perform(
+                    async move { tx.send(sim::ToSim::SubscribeStats(subscribe)).await.ok() },
+                    |_| Message::Null,
+                )
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Redraws every graph from the recorded stats.
+    fn draw_graphs(&mut self) {
+        let bids: Vec<i32> = self.bids.clone().into();
+        let asks: Vec<i32> = self.asks.clone().into();
+        let reserves: Vec<u32> = self.reserves.clone().into();
+        let buy_volumes: Vec<u32> = self.buy_volumes.clone().into();
+        let sell_volumes: Vec<u32> = self.sell_volumes.clone().into();
+        let money_flows: Vec<sim::MoneyFlows> = self.money_flows.clone().into();
+        let params: Vec<sim::Params> = self.params.clone().into();
+        let mean_ages: Vec<u64> = self.mean_ages.clone().into();
+        let max_ages: Vec<u64> = self.max_ages.clone().into();
+        let lineage_prices: Vec<Vec<sim::LineagePrices>> = self.lineage_prices.clone().into();
+        let halted: Vec<bool> = self.halted.clone().into();
+        let depth: Vec<Vec<(i32, f32)>> = self.depth.clone().into();
+        self.bid_ask_graph = plot::graph_bids_asks(&bids, &asks, &halted)
+            .expect("failed to create bid/ask graph");
+        self.depth_graph = plot::graph_depth(&depth).expect("failed to create depth graph");
+        self.lineage_price_graph = plot::graph_lineage_prices(&lineage_prices)
+            .expect("failed to create lineage price graph");
+        self.reserve_graph =
+            plot::graph_reserves(&reserves).expect("failed to create reserves graph");
+        self.volume_graph = plot::graph_volumes(&buy_volumes, &sell_volumes)
+            .expect("failed to create volume graph");
+        self.money_flow_graph =
+            plot::graph_money_flows(&money_flows).expect("failed to create money flow graph");
+        self.params_graph = plot::graph_params(&params).expect("failed to create parameter graph");
+        self.mean_max_age_graph = plot::graph_mean_max_age(&mean_ages, &max_ages)
+            .expect("failed to create mean max age graph");
+        let junk: Vec<sim::Junk> = self.junk.clone().into();
+        self.junk_graph = plot::graph_junk(&junk).expect("failed to create junk graph");
+        let choices: Vec<[u32; 6]> = self.choices.clone().into();
+        self.choices_graph =
+            plot::graph_choices(&choices).expect("failed to create decisions graph");
+        self.age_pyramid_graph =
+            plot::graph_age_pyramid(&self.ages).expect("failed to create age pyramid graph");
+        self.draw_candles();
+        let recent = self.trace.len().saturating_sub(TRACE_GRAPH_TICKS);
+        let trace: Vec<sim::TracePoint> = self.trace.iter().skip(recent).copied().collect();
+        self.trace_graph = plot::graph_trace(&trace).expect("failed to create trace graph");
+        self.graphs_stale = false;
+        self.graphs_drawn = Some(Instant::now());
+    }
+
+    fn draw_candles(&mut self) {
+        let candles: Vec<Option<sim::Candle>> = self.candles.clone().into();
+        self.price_candle_graph = plot::graph_price_candles(&candles, self.candle_window)
+            .expect("failed to create price candle graph");
+    }
+
+    /// Applies the scenario file if it changed since it was last read.
+    fn poll_scenario(&mut self) {
+        let watcher = match self.scenario_watcher {
+            Some(ref mut watcher) => watcher,
+            None => return,
+        };
+        let path = watcher.path().display().to_string();
+        match watcher.poll(scenario::Scenario::load) {
+            Ok(Some(scenario)) => {
+                self.log_event(format!(
+                    "applied {} ({})",
+                    path,
+                    scenario.names().join(", ")
+                ));
+                self.apply_scenario(scenario);
+            }
+            Ok(None) => {}
+            Err(e) => self.log_event(format!("failed to load {}: {}", path, e)),
+        }
+    }
+
+    /// Applies the theme file if it changed since it was last read, returning whether it did so
+    /// that the sim can be sent the colors of the terrain.
+    fn poll_theme(&mut self) -> bool {
+        let watcher = match self.theme_watcher {
+            Some(ref mut watcher) => watcher,
+            None => return false,
+        };
+        let path = watcher.path().display().to_string();
+        match watcher.poll(style::Skin::load) {
+            Ok(Some(skin)) => {
+                style::set_skin(skin);
+                self.view_settings.terrain = skin.terrain();
+                self.log_event(format!("applied theme {}", path));
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                self.log_event(format!("failed to load {}: {}", path, e));
+                false
+            }
+        }
+    }
+
+    /// Takes every step of the demo script which is due, as if its control had been used.
+    fn step_demo(&mut self) -> Command<Message> {
+        let mut commands = vec![];
+        loop {
+            let (ticks, milestones) = (self.total_tick_count, self.milestones);
+            let step = match self.demo.as_mut().and_then(|demo| demo.next(ticks, milestones)) {
+                Some(step) => step,
+                None => break,
+            };
+            let message = match step {
+                demo::Step::Scenario(scenario) => {
+                    self.apply_scenario(scenario);
+                    continue;
+                }
+                // Nobody is there to confirm the settings of a demo.
+                demo::Step::Start => Message::StartAnyway,
+                demo::Step::Run if !self.is_running_sim => Message::ToggleSim,
+                demo::Step::Pause if self.is_running_sim => Message::ToggleSim,
+                demo::Step::Run | demo::Step::Pause => continue,
+                demo::Step::Look(x, y, zoom) => {
+                    if let Some(ref mut grd) = self.grid {
+                        grd.look_at(x, y, zoom);
+                    }
+                    continue;
+                }
+                demo::Step::View(mode) => Message::ViewModeChanged(mode),
+                demo::Step::Grid => Message::ToggleGrid,
+                demo::Step::Menu => Message::MainView,
+                demo::Step::Say(target, text) => {
+                    self.callout = Some((target, text));
+                    // The charts a callout is about have to be showing.
+                    if target == demo::Target::Market && !self.show_stats {
+                        Message::ToggleStats
+                    } else {
+                        continue;
+                    }
+                }
+                demo::Step::Hush => {
+                    self.callout = None;
+                    continue;
+                }
+                // Waits are kept by the demo itself.
+                demo::Step::Wait(_)
+                | demo::Step::Sleep(_)
+                | demo::Step::Hold
+                | demo::Step::Until(_) => continue,
+            };
+            commands.push(self.update(message));
+        }
+        if self.demo.as_ref().map_or(false, demo::Demo::finished) {
+            self.demo = None;
+            self.callout = None;
+            self.log_event("demo finished".to_owned());
+        }
+        Command::batch(commands)
+    }
+
+    /// Sets every parameter in the scenario as if its control had been moved.
+    fn apply_scenario(&mut self, scenario: scenario::Scenario) {
+        let mut messages = vec![];
+        if let Some(width) = scenario.width {
+            messages.push(Message::DimensionSet(width as f32));
+        }
+        if let Some(openness) = scenario.openness {
+            messages.push(Message::OpennessSet(openness as f32));
+        }
+        if let Some(width) = scenario.corridor_width {
+            messages.push(Message::CorridorWidthSet(width as f32));
+        }
+        if let Some(passes) = scenario.smoothing {
+            messages.push(Message::SmoothingSet(passes as f32));
+        }
+        if let Some(edges) = scenario.edges {
+            messages.push(Message::EdgesSet(edges));
+        }
+        if let Some(tiers) = scenario.tiers {
+            messages.push(Message::TiersSet(tiers));
+        }
+        if let Some(probability) = scenario.cornacopia_count_probability {
+            messages.push(Message::CornacopiaCountProbabilityChanged(
+                probability as f32,
+            ));
+        }
+        if let Some(speed) = scenario.speed {
+            messages.push(Message::SpeedChanged(speed as f32));
+        }
+        if let Some(fps) = scenario.frames_per_second {
+            messages.push(Message::FrameRateChanged(fps as f32));
+        }
+        if let Some(rate) = scenario.spawn_rate {
+            messages.push(Message::SpawnRateChanged(rate as f32));
+        }
+        if let Some(chance) = scenario.mutation_chance {
+            messages.push(Message::MutationChanceChanged(chance as f32));
+        }
+        if let Some(chance) = scenario.general_food_chance {
+            messages.push(Message::GeneralFoodProbabilityChanged(chance as f32));
+        }
+        if let Some(chance) = scenario.cornacopia_chance {
+            messages.push(Message::CornacopiaProbabilityChanged(chance as f32));
+        }
+        if let Some(bounty) = scenario.cornacopia_bounty {
+            messages.push(Message::CornacopiaBountyChanged(bounty as f32));
+        }
+        if let Some(enabled) = scenario.direct_exchange {
+            messages.push(Message::DirectExchangeToggled(enabled));
+        }
+        if let Some(enabled) = scenario.sense_life {
+            messages.push(Message::SenseLifeToggled(enabled));
+        }
+        if let Some(enabled) = scenario.sense_fills {
+            messages.push(Message::SenseFillsToggled(enabled));
+        }
+        if let Some(grace) = scenario.starvation_grace {
+            messages.push(Message::StarvationGraceChanged(grace as f32));
+        }
+        if let Some(sanitization) = scenario.sanitization {
+            messages.push(Message::SanitizationChanged(sanitization));
+        }
+        if let Some(proceeds) = scenario.proceeds {
+            messages.push(Message::ProceedsChanged(proceeds));
+        }
+        if let Some(exhaustion) = scenario.exhaustion {
+            messages.push(Message::ExhaustionChanged(exhaustion));
+        }
+        if let Some(freeze) = scenario.freeze {
+            messages.push(Message::FreezeChanged(freeze));
+        }
+        if let Some(breaker) = scenario.circuit_breaker {
+            messages.push(Message::CircuitBreakerSet(Some(breaker)));
+        }
+        if let Some(demurrage) = scenario.demurrage {
+            messages.push(Message::DemurrageSet(Some(demurrage)));
+        }
+        if let Some(tax) = scenario.inheritance_tax {
+            messages.push(Message::InheritanceTaxChanged(tax as f32));
+        }
+        if let Some(cost) = scenario.signal_cost {
+            messages.push(Message::SignalCostChanged(cost as f32));
+        }
+        if let Some(spoilage) = scenario.spoilage {
+            messages.push(Message::SpoilageSet(spoilage));
+        }
+        if let Some(cost) = scenario.metabolism {
+            messages.push(Message::MetabolismChanged(cost as f32));
+        }
+        if let Some(percent) = scenario.stepped_percent {
+            messages.push(Message::SteppedChanged(percent as f32));
+        }
+        if let Some(recovery) = scenario.recovery {
+            messages.push(Message::RecoverySet(recovery));
+        }
+        if let Some(noise) = scenario.noise {
+            messages.push(Message::NoiseSet(noise));
+        }
+        if let Some(mutations) = scenario.mutations {
+            messages.push(Message::MutationsChanged(mutations));
+        }
+        if let Some(ref phases) = scenario.phases {
+            match sim::Pipeline::new(phases) {
+                Some(pipeline) => messages.push(Message::PipelineChanged(pipeline)),
+                None => self.log_event(
+                    "scenario phases must name every phase exactly once".to_owned(),
+                ),
             }
         }
+        if let Some(regions) = scenario.regions {
+            self.log_event(format!(
+                "regions overriding parameters: {}",
+                regions.len()
+            ));
+            if let Some(ref mut tx) = self.sim_tx {
+                // If the channel is full, dont send it.
+                tx.try_send(sim::ToSim::SetRegions(regions.clone())).ok();
+            }
+            self.regions = regions;
+        }
+        self.jitter = scenario.jitter;
+        for message in messages {
+            self.update(message);
+        }
+    }
+
+    /// Takes a random step with every parameter the scenario asked to drift.
+    fn jitter_params(&mut self) {
+        let rng = unsafe { rng() };
+        let mut messages = vec![];
+        if let Some(jitter) = self.jitter.spawn_rate {
+            let rate = jitter.walk(rng, self.spawn_rate);
+            messages.push(Message::SpawnRateChanged(rate as f32));
+        }
+        if let Some(jitter) = self.jitter.mutation_chance {
+            let chance = jitter.walk(rng, self.mutation_chance);
+            messages.push(Message::MutationChanceChanged(chance as f32));
+        }
+        if let Some(jitter) = self.jitter.general_food_chance {
+            let chance = jitter.walk(rng, self.cell_food_probability);
+            messages.push(Message::GeneralFoodProbabilityChanged(chance as f32));
+        }
+        if let Some(jitter) = self.jitter.cornacopia_chance {
+            let chance = jitter.walk(rng, self.cornacopia_probability);
+            messages.push(Message::CornacopiaProbabilityChanged(chance as f32));
+        }
+        if let Some(jitter) = self.jitter.cornacopia_bounty {
+            let bounty = jitter.walk(rng, self.cornacopia_bounty as f64).round();
+            messages.push(Message::CornacopiaBountyChanged(bounty as f32));
+        }
+        for message in messages {
+            self.update(message);
+        }
+    }
+}
+
+/// A chance which can be typed in exactly as well as set with its slider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chance {
+    Mutation,
+    GeneralFood,
+    Cornacopia,
+    CornacopiaCount,
+}
+
+impl Chance {
+    fn name(self) -> &'static str {
+        match self {
+            Chance::Mutation => "mutation chance",
+            Chance::GeneralFood => "food chance",
+            Chance::Cornacopia => "cornacopia chance",
+            Chance::CornacopiaCount => "cornacopia count probability",
+        }
+    }
+
+    /// The message its slider sends for a probability.
+    fn set(self, p: f32) -> Message {
+        match self {
+            Chance::Mutation => Message::MutationChanceChanged(p),
+            Chance::GeneralFood => Message::GeneralFoodProbabilityChanged(p),
+            Chance::Cornacopia => Message::CornacopiaProbabilityChanged(p),
+            Chance::CornacopiaCount => Message::CornacopiaCountProbabilityChanged(p),
+        }
+    }
+}
+
+/// The text box of a chance, which is only read when submitted.
+#[derive(Default)]
+struct ChanceInput {
+    state: text_input::State,
+    text: String,
+}
+
+impl ChanceInput {
+    /// Shows the probability in effect until something is typed.
+    fn view(&mut self, chance: Chance, p: f64) -> TextInput<Message> {
+        TextInput::new(&mut self.state, &p.to_string(), &self.text, move |text| {
+            Message::ChanceEdited(chance, text)
+        })
+        .size(16)
+        .padding(4)
+        .on_submit(Message::ChanceSubmitted(chance))
     }
 }
 
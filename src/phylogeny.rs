@@ -0,0 +1,326 @@
+//! The phylogeny of the largest clades of the family tree, drawn as a tree which can be zoomed,
+//! panned and have its clades collapsed, so that the history of a run can be browsed while it
+//! goes on.
+//!
+//! Time runs from left to right, from the oldest clade shown to the tick the phylogeny was taken
+//! on, and each clade is a branch from its parent as wide as the living brains it stands for.
+
+use crate::sim;
+use float_ord::FloatOrd;
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Event, Frame, Geometry},
+    mouse, Color, Element, Length, Point, Rectangle, Size, Vector,
+};
+use std::collections::HashSet;
+
+/// The height of the panel.
+const HEIGHT: u16 = 300;
+/// Pixels left around the tree.
+const PADDING: f32 = 8.0;
+/// The width of the branch of the clade with the most living brains.
+const MAX_BRANCH: f32 = 8.0;
+/// How close to a clade a click must land to collapse or expand it, in pixels.
+const PICK_RADIUS: f32 = 6.0;
+const MAX_SCALING: f32 = 64.0;
+const CONNECTOR_COLOR: Color = Color {
+    r: 0.6,
+    g: 0.6,
+    b: 0.6,
+    a: 1.0,
+};
+const LABEL_SIZE: f32 = 14.0;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// The clade of the brain with this id was clicked, collapsing it or expanding it again.
+    Toggle(u64),
+}
+
+/// Where a clade is drawn, in the units of the tree: across from the oldest clade at zero to
+/// the tick of the phylogeny at one, and down by the rows of the leaves.
+struct Placed {
+    /// The clade, as an index into the phylogeny.
+    clade: usize,
+    /// Where its branch leaves its parent.
+    start: f32,
+    x: f32,
+    y: f32,
+    /// The rows of its first and last child, if they are shown.
+    children: Option<(f32, f32)>,
+    collapsed: bool,
+}
+
+pub struct Tree {
+    phylogeny: sim::Phylogeny,
+    /// The ids of the brains whose clades are collapsed, kept across new phylogenies.
+    collapsed: HashSet<u64>,
+    placed: Vec<Placed>,
+    /// Rows taken by the leaves and collapsed clades.
+    rows: usize,
+    cache: Cache,
+    translation: Vector,
+    scaling: f32,
+    /// The translation and cursor position when the right button was pressed, while panning.
+    panning: Option<(Vector, Point)>,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Self {
+            phylogeny: sim::Phylogeny::default(),
+            collapsed: HashSet::new(),
+            placed: vec![],
+            rows: 0,
+            cache: Cache::default(),
+            translation: Vector::new(0.0, 0.0),
+            scaling: 1.0,
+            panning: None,
+        }
+    }
+}
+
+impl Tree {
+    /// Shows a new phylogeny, keeping the zoom and which clades are collapsed.
+    pub fn set(&mut self, phylogeny: sim::Phylogeny) {
+        self.phylogeny = phylogeny;
+        self.lay_out();
+    }
+
+    /// Forgets the phylogeny, such as when tracking stops.
+    pub fn clear(&mut self) {
+        self.set(sim::Phylogeny::default());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phylogeny.clades.is_empty()
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Toggle(id) => {
+                if !self.collapsed.remove(&id) {
+                    self.collapsed.insert(id);
+                }
+                self.lay_out();
+            }
+        }
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Units(HEIGHT))
+            .into()
+    }
+
+    /// Places every clade not hidden in a collapsed one, giving each leaf a row of its own and
+    /// centering each parent on its children.
+    fn lay_out(&mut self) {
+        let clades = &self.phylogeny.clades;
+        let first = clades.iter().map(|clade| clade.born).min().unwrap_or(0);
+        let span = self.phylogeny.tick.saturating_sub(first).max(1) as f32;
+        let across = |born: u64| born.saturating_sub(first) as f32 / span;
+        let mut children = vec![vec![]; clades.len()];
+        for (index, clade) in clades.iter().enumerate() {
+            if let Some(parent) = clade.parent {
+                children[parent].push(index);
+            }
+        }
+        let mut placed: Vec<Option<Placed>> = clades.iter().map(|_| None).collect();
+        let mut rows = 0;
+        // Walked without recursion, placing every child before its parent.
+        let mut stack: Vec<(usize, bool)> = (0..clades.len())
+            .rev()
+            .filter(|&index| clades[index].parent.is_none())
+            .map(|index| (index, false))
+            .collect();
+        while let Some((index, visited)) = stack.pop() {
+            let clade = &clades[index];
+            let collapsed = self.collapsed.contains(&clade.id) && !children[index].is_empty();
+            if !visited && !collapsed && !children[index].is_empty() {
+                stack.push((index, true));
+                stack.extend(children[index].iter().rev().map(|&child| (child, false)));
+                continue;
+            }
+            let spanned = match (children[index].first(), children[index].last()) {
+                (Some(&first), Some(&last)) if !collapsed => {
+                    let row = |child: usize| placed[child].as_ref().unwrap().y;
+                    Some((row(first), row(last)))
+                }
+                _ => None,
+            };
+            let y = match spanned {
+                Some((first, last)) => (first + last) / 2.0,
+                None => {
+                    rows += 1;
+                    (rows - 1) as f32
+                }
+            };
+            placed[index] = Some(Placed {
+                clade: index,
+                start: across(
+                    clade
+                        .parent
+                        .map_or(clade.born, |parent| clades[parent].born),
+                ),
+                x: across(clade.born),
+                y,
+                children: spanned,
+                collapsed,
+            });
+        }
+        self.placed = placed.into_iter().flatten().collect();
+        self.rows = rows;
+        self.cache.clear();
+    }
+
+    /// Where a point of the tree is on a canvas of a size, fitted to it and then zoomed about
+    /// its center and panned.
+    fn to_screen(&self, size: Size, x: f32, y: f32) -> Point {
+        let row = (size.height - 2.0 * PADDING) / self.rows.max(1) as f32;
+        let fitted = Point::new(
+            PADDING + x * (size.width - 2.0 * PADDING),
+            PADDING + (y + 0.5) * row,
+        );
+        let center = Point::new(size.width / 2.0, size.height / 2.0);
+        Point::new(
+            center.x + (fitted.x - center.x) * self.scaling + self.translation.x,
+            center.y + (fitted.y - center.y) * self.scaling + self.translation.y,
+        )
+    }
+
+    /// The clade drawn nearest a position, if any is close enough to pick.
+    fn pick(&self, size: Size, position: Point) -> Option<&Placed> {
+        self.placed
+            .iter()
+            .map(|placed| {
+                let at = self.to_screen(size, placed.x, placed.y);
+                (placed, (at.x - position.x).hypot(at.y - position.y))
+            })
+            .filter(|&(_, distance)| distance <= PICK_RADIUS)
+            .min_by_key(|&(_, distance)| FloatOrd(distance))
+            .map(|(placed, _)| placed)
+    }
+}
+
+impl canvas::Program<Message> for Tree {
+    fn update(&mut self, event: Event, bounds: Rectangle, cursor: Cursor) -> Option<Message> {
+        if let Event::Mouse(mouse::Event::ButtonReleased(_)) = event {
+            self.panning = None;
+        }
+        let position = cursor.position_in(&bounds)?;
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let placed = self.pick(bounds.size(), position)?;
+                if placed.children.is_none() && !placed.collapsed {
+                    return None;
+                }
+                Some(Message::Toggle(self.phylogeny.clades[placed.clade].id))
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                self.panning = Some((self.translation, position));
+                None
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let Some((translation, start)) = self.panning {
+                    self.translation = translation + (position - start);
+                    self.cache.clear();
+                }
+                None
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } | mouse::ScrollDelta::Pixels { y, .. } => y,
+                };
+                let old = self.scaling;
+                self.scaling = (old * (1.0 + y / 30.0)).max(1.0).min(MAX_SCALING);
+                // The point under the cursor stays put.
+                let center = Vector::new(bounds.width / 2.0, bounds.height / 2.0);
+                let from_center = Vector::new(position.x, position.y) - center;
+                self.translation =
+                    from_center - (from_center - self.translation) * (self.scaling / old);
+                self.cache.clear();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+        let tree = self.cache.draw(bounds.size(), |frame| {
+            let size = frame.size();
+            let most = self
+                .phylogeny
+                .clades
+                .iter()
+                .map(|clade| clade.population)
+                .max()
+                .unwrap_or(1)
+                .max(1) as f32;
+            for placed in &self.placed {
+                let clade = &self.phylogeny.clades[placed.clade];
+                let width = (MAX_BRANCH * clade.population as f32 / most).max(1.0);
+                // Leaves and collapsed clades run on to the present.
+                let end = if placed.children.is_some() {
+                    placed.x
+                } else {
+                    1.0
+                };
+                let start = self.to_screen(size, placed.start, placed.y);
+                let end = self.to_screen(size, end, placed.y);
+                frame.fill_rectangle(
+                    Point::new(start.x, start.y - width / 2.0),
+                    Size::new((end.x - start.x).max(1.0), width),
+                    clade.color,
+                );
+                if let Some((first, last)) = placed.children {
+                    let top = self.to_screen(size, placed.x, first);
+                    let bottom = self.to_screen(size, placed.x, last);
+                    frame.fill_rectangle(
+                        Point::new(top.x - 0.5, top.y),
+                        Size::new(1.0, bottom.y - top.y),
+                        CONNECTOR_COLOR,
+                    );
+                }
+                if placed.collapsed {
+                    // A square marks a clade to click open again.
+                    let at = self.to_screen(size, placed.x, placed.y);
+                    frame.fill_rectangle(
+                        Point::new(at.x - 3.0, at.y - 3.0),
+                        Size::new(6.0, 6.0),
+                        Color::WHITE,
+                    );
+                }
+            }
+        });
+
+        let mut overlay = Frame::new(bounds.size());
+        let hovered = cursor
+            .position_in(&bounds)
+            .and_then(|position| self.pick(bounds.size(), position));
+        if let Some(placed) = hovered {
+            let clade = &self.phylogeny.clades[placed.clade];
+            overlay.fill_text(canvas::Text {
+                content: format!(
+                    "{}: {} living, born on tick {}",
+                    crate::describe_lineage(clade.lineage, clade.name.clone()),
+                    clade.population,
+                    clade.born
+                ),
+                position: Point::new(PADDING, PADDING),
+                color: Color::WHITE,
+                size: LABEL_SIZE,
+                ..canvas::Text::default()
+            });
+        }
+        vec![tree, overlay.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, bounds: Rectangle, cursor: Cursor) -> mouse::Interaction {
+        match self.panning {
+            Some(_) => mouse::Interaction::Grabbing,
+            None if cursor.is_over(&bounds) => mouse::Interaction::Pointer,
+            None => mouse::Interaction::default(),
+        }
+    }
+}
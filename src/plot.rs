@@ -1,12 +1,19 @@
+use crate::sim::{AgePyramid, Candle, Junk, LineagePrices, MoneyFlows, Ohlc, Params, TracePoint};
 use iced::image;
 use plotters::drawing::bitmap_pixel::BGRXPixel;
 use plotters::prelude::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
 
+/// The best bid and ask of each sample, shaded where the circuit breaker halted trading.
 pub fn graph_bids_asks(
     bids: &[i32],
     asks: &[i32],
+    halted: &[bool],
 ) -> Result<image::Handle, Box<dyn std::error::Error>> {
     assert_eq!(bids.len(), asks.len());
+    assert_eq!(bids.len(), halted.len());
     if bids.is_empty() {
         return Ok(image::Handle::from_pixels(0, 0, vec![]));
     }
@@ -34,6 +41,13 @@ pub fn graph_bids_asks(
         .disable_y_mesh()
         .draw()?;
 
+    chart.draw_series(
+        halted
+            .iter()
+            .enumerate()
+            .filter(|&(_, &halted)| halted)
+            .map(|(i, _)| Rectangle::new([(i, min), (i + 1, max + 1)], RED.mix(0.2).filled())),
+    )?;
     chart.draw_series(LineSeries::new(bids.iter().copied().enumerate(), &BLUE))?;
     chart.draw_secondary_series(LineSeries::new(asks.iter().copied().enumerate(), &RED))?;
 
@@ -184,3 +198,609 @@ pub fn graph_mean_max_age(
 
     Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
 }
+
+/// The living cells in each band of age as a bar centered on the axis, youngest at the bottom,
+/// so that a cohort born in a boom shows as a bulge moving up.
+pub fn graph_age_pyramid(
+    pyramid: &AgePyramid,
+) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    let widest = match pyramid.counts.iter().copied().max() {
+        Some(widest) if widest > 0 => widest as i64,
+        _ => return Ok(image::Handle::from_pixels(0, 0, vec![])),
+    };
+    let bands = pyramid.counts.len() as u32;
+
+    const WIDTH: u32 = 240;
+    const HEIGHT: u32 = 200;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 60)
+        .set_label_area_size(LabelAreaPosition::Bottom, 20)
+        .build_ranged(-widest..widest + 1, 0..bands * pyramid.width)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .x_label_formatter(&|count| count.abs().to_string())
+        .draw()?;
+
+    chart.draw_series(pyramid.counts.iter().enumerate().map(|(band, &count)| {
+        let (half, low) = (count as i64, band as u32 * pyramid.width);
+        Rectangle::new(
+            [(-half, low), (half, low + pyramid.width)],
+            BLUE.mix(0.6).filled(),
+        )
+    }))?;
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+pub fn graph_money_flows(
+    flows: &[MoneyFlows],
+) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    if flows.is_empty() {
+        return Ok(image::Handle::from_pixels(0, 0, vec![]));
+    }
+    let max = flows
+        .iter()
+        .map(|f| {
+            f.trades
+                + f.reserve_purchases
+                + f.reserve_sales
+                + f.escheatment
+                + f.demurrage
+                + f.inheritance_tax
+        })
+        .max()
+        .unwrap();
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..flows.len(), 0..max + 1)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    // Each tick is a bar with the flows stacked from the bottom up in this order.
+    let layers: [(fn(&MoneyFlows) -> u32, &RGBColor); 6] = [
+        (|f| f.trades, &BLUE),
+        (|f| f.reserve_purchases, &GREEN),
+        (|f| f.reserve_sales, &MAGENTA),
+        (|f| f.escheatment, &RED),
+        (|f| f.demurrage, &CYAN),
+        (|f| f.inheritance_tax, &YELLOW),
+    ];
+    let mut bases = vec![0; flows.len()];
+    for &(amount, color) in &layers {
+        chart.draw_series(
+            flows
+                .iter()
+                .zip(&mut bases)
+                .enumerate()
+                .map(|(x, (flow, base))| {
+                    let top = *base + amount(flow);
+                    let bar = Rectangle::new([(x, *base), (x + 1, top)], color.filled());
+                    *base = top;
+                    bar
+                }),
+        )?;
+    }
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+pub fn graph_params(params: &[Params]) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    if params.is_empty() {
+        return Ok(image::Handle::from_pixels(0, 0, vec![]));
+    }
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..params.len(), 0.0..1.0)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    // The parameters differ by orders of magnitude, so each is drawn relative to its own maximum.
+    let lines: [(fn(&Params) -> f64, &RGBColor); 5] = [
+        (|p| p.spawn_chance, &BLUE),
+        (|p| p.mutation_chance, &GREEN),
+        (|p| p.general_food_chance, &MAGENTA),
+        (|p| p.cornacopia_chance, &RED),
+        (|p| p.cornacopia_bounty as f64, &CYAN),
+    ];
+    for &(value, color) in &lines {
+        let max = params.iter().map(value).fold(0.0, f64::max);
+        let scale = if max > 0.0 { max.recip() } else { 0.0 };
+        chart.draw_series(LineSeries::new(
+            params.iter().map(|p| value(p) * scale).enumerate(),
+            color,
+        ))?;
+    }
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// The shares of codons which are unreachable in red and which did not run in blue, with the
+/// mean genome length in green relative to its longest, so that bloat shows as the shares rising
+/// along with the length.
+pub fn graph_junk(junk: &[Junk]) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    if junk.is_empty() {
+        return Ok(image::Handle::from_pixels(0, 0, vec![]));
+    }
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..junk.len(), 0.0..1.0)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    let longest = junk.iter().map(|j| j.mean_length).fold(0.0, f64::max);
+    let scale = if longest > 0.0 { longest.recip() } else { 0.0 };
+    chart.draw_series(LineSeries::new(
+        junk.iter().map(|j| j.mean_length * scale).enumerate(),
+        &GREEN,
+    ))?;
+    chart.draw_series(LineSeries::new(
+        junk.iter().map(|j| j.unreachable).enumerate(),
+        &RED,
+    ))?;
+    chart.draw_series(LineSeries::new(
+        junk.iter().map(|j| j.unexecuted).enumerate(),
+        &BLUE,
+    ))?;
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// The color of each kind of decision in the graph of decisions, by `Choice::index`.
+const CHOICE_COLORS: [RGBColor; 6] = [
+    RGBColor(60, 110, 220),
+    RGBColor(40, 170, 70),
+    RGBColor(220, 60, 50),
+    RGBColor(240, 170, 30),
+    RGBColor(150, 70, 190),
+    RGBColor(190, 190, 190),
+];
+/// The names of `CHOICE_COLORS`, for the legend.
+pub const CHOICE_COLOR_NAMES: [&str; 6] = ["blue", "green", "red", "orange", "purple", "grey"];
+
+/// The share of the living cells which made each kind of decision in each sample, stacked from
+/// moving at the bottom to doing nothing at the top, so that shifts in behavior show as bands
+/// widening and narrowing.
+pub fn graph_choices(samples: &[[u32; 6]]) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    if samples.is_empty() {
+        return Ok(image::Handle::from_pixels(0, 0, vec![]));
+    }
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..samples.len(), 0.0..1.0)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    chart.draw_series(samples.iter().enumerate().flat_map(|(x, counts)| {
+        let total = counts.iter().sum::<u32>();
+        let scale = if total > 0 { 1.0 / total as f64 } else { 0.0 };
+        counts
+            .iter()
+            .zip(CHOICE_COLORS.iter())
+            .scan(0.0, move |bottom, (&count, color)| {
+                let low = *bottom;
+                *bottom += count as f64 * scale;
+                Some(Rectangle::new([(x, low), (x + 1, *bottom)], color.filled()))
+            })
+    }))?;
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// Price levels are grouped into at most this many rows of the depth graph.
+const DEPTH_ROWS: i32 = 50;
+
+/// A heat map of the food resting in orders at each price level over the samples, from pale for
+/// thin levels to dark red for the thickest.
+pub fn graph_depth(
+    samples: &[Vec<(i32, f32)>],
+) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    let prices = || samples.iter().flatten().map(|&(price, _)| price);
+    let (min, max) = match (prices().min(), prices().max()) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return Ok(image::Handle::from_pixels(0, 0, vec![])),
+    };
+    // The prices in each row.
+    let bin = (max - min) / DEPTH_ROWS + 1;
+    let rows = ((max - min) / bin + 1) as usize;
+    let mut volumes = vec![vec![0.0f32; rows]; samples.len()];
+    for (column, sample) in volumes.iter_mut().zip(samples) {
+        for &(price, food) in sample {
+            column[((price - min) / bin) as usize] += food;
+        }
+    }
+    let peak = volumes.iter().flatten().copied().fold(0.0, f32::max);
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..samples.len(), min..min + rows as i32 * bin)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    chart.draw_series(volumes.iter().enumerate().flat_map(|(x, column)| {
+        column
+            .iter()
+            .enumerate()
+            .filter(|&(_, &volume)| volume > 0.0)
+            .map(move |(row, &volume)| {
+                // A log scale keeps thin levels visible next to the thickest.
+                let heat = ((1.0 + volume).ln() / (1.0 + peak).ln()) as f64;
+                let color = RGBColor(
+                    (255.0 - 115.0 * heat) as u8,
+                    (235.0 * (1.0 - heat)) as u8,
+                    (190.0 * (1.0 - heat)) as u8,
+                );
+                let low = min + row as i32 * bin;
+                Rectangle::new([(x, low), (x + 1, low + bin)], color.filled())
+            })
+    }))?;
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// A line through the closing prices of each of the lineages in the last sample, in its color,
+/// with a bar from the low to the high of every sample it traded in.
+pub fn graph_lineage_prices(
+    samples: &[Vec<LineagePrices>],
+) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    let latest = match samples.last() {
+        Some(latest) if !latest.is_empty() => latest,
+        _ => return Ok(image::Handle::from_pixels(0, 0, vec![])),
+    };
+    // The prices of each lineage in every sample it traded in.
+    let series: Vec<(RGBColor, Vec<(usize, Ohlc)>)> = latest
+        .iter()
+        .map(|lineage| {
+            let color = RGBColor(
+                (lineage.color.r * 255.0) as u8,
+                (lineage.color.g * 255.0) as u8,
+                (lineage.color.b * 255.0) as u8,
+            );
+            let prices = samples
+                .iter()
+                .enumerate()
+                .filter_map(|(x, sample)| {
+                    sample
+                        .iter()
+                        .find(|other| other.lineage == lineage.lineage)
+                        .and_then(|other| other.prices)
+                        .map(|prices| (x, prices))
+                })
+                .collect();
+            (color, prices)
+        })
+        .collect();
+    let all = || series.iter().flat_map(|(_, prices)| prices);
+    let (min, max) = match (
+        all().map(|(_, p)| p.low).min(),
+        all().map(|(_, p)| p.high).max(),
+    ) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return Ok(image::Handle::from_pixels(0, 0, vec![])),
+    };
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..samples.len(), min..max + 1)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    for (color, prices) in &series {
+        chart.draw_series(
+            prices
+                .iter()
+                .map(|&(x, p)| PathElement::new(vec![(x, p.low), (x, p.high)], color)),
+        )?;
+        chart.draw_series(LineSeries::new(
+            prices.iter().map(|&(x, p)| (x, p.close)),
+            color,
+        ))?;
+    }
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// A candle for each of the last `window` samples of the prices trades were executed at, green
+/// where the price closed at or above where it opened and red where it fell, with a gap wherever
+/// nothing traded.
+pub fn graph_price_candles(
+    candles: &[Option<Candle>],
+    window: usize,
+) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    let candles = &candles[candles.len().saturating_sub(window)..];
+    let traded = || candles.iter().flatten();
+    let (min, max) = match (
+        traded().map(|candle| candle.low).min(),
+        traded().map(|candle| candle.high).max(),
+    ) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return Ok(image::Handle::from_pixels(0, 0, vec![])),
+    };
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 150;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(0..candles.len() + 1, min..max + 1)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+
+    // Each body takes its share of the width less a pixel to keep neighbors apart.
+    let body = ((WIDTH - 40) / (candles.len() as u32 + 1))
+        .saturating_sub(1)
+        .max(1);
+    chart.draw_series(candles.iter().enumerate().filter_map(|(x, candle)| {
+        candle.map(|candle| {
+            CandleStick::new(
+                x + 1,
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                GREEN.filled(),
+                RED.filled(),
+                body,
+            )
+        })
+    }))?;
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// The food and money of the followed cell at each tick it was seen, with money on its own scale.
+pub fn graph_trace(points: &[TracePoint]) -> Result<image::Handle, Box<dyn std::error::Error>> {
+    if points.is_empty() {
+        return Ok(image::Handle::from_pixels(0, 0, vec![]));
+    }
+    let first = points.first().unwrap().tick;
+    let last = points.last().unwrap().tick;
+    let max_food = points.iter().map(|point| point.food).max().unwrap();
+    let max_money = points.iter().map(|point| point.money).max().unwrap();
+
+    const WIDTH: u32 = 240;
+    const HEIGHT: u32 = 200;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 4];
+    let root = BitMapBackend::<BGRXPixel>::with_buffer_and_format(&mut buffer, (WIDTH, HEIGHT))?
+        .into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .set_label_area_size(LabelAreaPosition::Top, 5)
+        .set_label_area_size(LabelAreaPosition::Left, 40)
+        .set_label_area_size(LabelAreaPosition::Right, 40)
+        .set_label_area_size(LabelAreaPosition::Bottom, 5)
+        .build_ranged(first..last + 1, 0..max_food + 1)?
+        .set_secondary_coord(first..last + 1, 0..max_money + 1);
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .draw()?;
+    chart.configure_secondary_axes().draw()?;
+
+    chart.draw_series(LineSeries::new(
+        points.iter().map(|point| (point.tick, point.food)),
+        &GREEN,
+    ))?;
+    chart.draw_secondary_series(LineSeries::new(
+        points.iter().map(|point| (point.tick, point.money)),
+        &BLUE,
+    ))?;
+
+    drop(chart);
+    drop(root);
+
+    Ok(image::Handle::from_pixels(WIDTH, HEIGHT, buffer))
+}
+
+/// The colors of the runs on a chart comparing them, in the order they were given, starting
+/// over after the last.
+const RUN_COLORS: [RGBColor; 6] = [
+    RGBColor(60, 110, 220),
+    RGBColor(220, 60, 50),
+    RGBColor(40, 170, 70),
+    RGBColor(240, 170, 30),
+    RGBColor(150, 70, 190),
+    RGBColor(40, 40, 40),
+];
+
+/// One stat of each of several runs over the same ticks, in a color for each run with a legend
+/// of their names, saved as a PNG. Every chart given the same last tick lines up with the others.
+pub fn save_comparison(
+    path: &Path,
+    caption: &str,
+    last_tick: u64,
+    runs: &[(String, Vec<(u64, f64)>)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (min, max) = runs
+        .iter()
+        .flat_map(|(_, series)| series.iter().map(|&(_, value)| value))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+    let (min, max) = if min <= max {
+        (min, max.max(min + 1.0))
+    } else {
+        (0.0, 1.0)
+    };
+
+    const WIDTH: u32 = 800;
+    const HEIGHT: u32 = 400;
+    let mut buffer = vec![0; WIDTH as usize * HEIGHT as usize * 3];
+    let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(caption, ("sans-serif", 20))
+        .margin(10)
+        .set_label_area_size(LabelAreaPosition::Left, 60)
+        .set_label_area_size(LabelAreaPosition::Bottom, 40)
+        .build_ranged(0..last_tick.max(1), min..max)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .x_desc("tick")
+        .draw()?;
+
+    for (i, (name, series)) in runs.iter().enumerate() {
+        let color = RUN_COLORS[i % RUN_COLORS.len()];
+        chart
+            .draw_series(LineSeries::new(series.iter().copied(), &color))?
+            .label(name.as_str())
+            .legend(move |(x, y)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(1))
+            });
+    }
+    chart
+        .configure_series_labels()
+        .background_style(&WHITE.mix(0.8))
+        .border_style(&BLACK)
+        .draw()?;
+
+    drop(chart);
+    drop(root);
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&buffer)?;
+    Ok(())
+}
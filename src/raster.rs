@@ -0,0 +1,208 @@
+//! Drawing views of the world into images off the screen, at any size regardless of how the grid
+//! is zoomed, for timelapses and for renders of part of the world.
+
+use crate::sim::{View, ViewColors};
+use iced::Color;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// The lines between tiles, as the grid draws them.
+const LINE_COLOR: [u8; 3] = [70, 74, 83];
+/// How the cells of the marked lineage are lightened, as the grid highlights them.
+const MARKED_ALPHA: f32 = 0.6;
+
+/// The offset within a tile and the shade of each ancestry marking of a cell of a generation,
+/// in fractions of a tile. The markings spiral out from the top left corner, one for each octal
+/// digit of the generation, darker for higher digits.
+pub fn markings(generation: usize) -> Vec<(f32, f32, f32)> {
+    let mut markings = vec![];
+    let mut marking: u32 = 0;
+    let (mut x_off, mut y_off) = (0.0, 0.0);
+    let mut consumed = 0;
+    // 0, F, FF, FFF, ...
+    while generation > consumed {
+        let digit = (generation & (7 * 8usize.pow(marking))) / 8usize.pow(marking);
+        markings.push((0.075 + x_off, 0.075 + y_off, (7.0 - digit as f32) / 7.0));
+
+        let band = marking / 11;
+        // 0123 right, 4567 down, 89AB left, CDEF up, 10;11;12;13 right, ...
+        let dir = if band == 0 {
+            marking / 3
+        } else if marking % 11 == 0 {
+            0
+        } else {
+            marking
+        } % 4;
+        let step = if marking % 3 == 1 || marking / 12 == 1 {
+            0.25
+        } else {
+            0.2
+        };
+        match dir {
+            0 => x_off += step,
+            1 => y_off += step,
+            2 => x_off -= step,
+            _ => y_off -= step,
+        }
+        consumed += 8 * 8usize.pow(marking);
+        marking += 1;
+    }
+    markings
+}
+
+/// What is drawn over the tiles of a render.
+#[derive(Clone, Debug, Default)]
+pub struct Overlays {
+    pub lines: bool,
+    /// Tiles highlighted as the grid highlights the cells of the selected lineage, as (column,
+    /// row).
+    pub marked: Vec<(usize, usize)>,
+}
+
+/// A rectangle of tiles, as its first column and row and how many of each it covers.
+#[derive(Copy, Clone, Debug)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// An RGB image being drawn.
+pub struct Raster {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Raster {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width * height * 3],
+        }
+    }
+
+    /// Fills a rectangle of pixels, clipped to the image.
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, rgb: [u8; 3]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let at = (row * self.width + column) * 3;
+                self.data[at..at + 3].copy_from_slice(&rgb);
+            }
+        }
+    }
+
+    /// The whole world, averaging each square of `downscale` tiles into a pixel. Squares cut off
+    /// by the edge of the world average the tiles they have.
+    pub fn downscaled(view: &View, downscale: usize) -> Self {
+        let (height, width) = view.colors.dim();
+        let mut raster = Self::new(
+            (width + downscale - 1) / downscale,
+            (height + downscale - 1) / downscale,
+        );
+        for out_y in 0..raster.height {
+            for out_x in 0..raster.width {
+                let mut sum = [0.0f32; 3];
+                let mut count = 0.0;
+                for y in out_y * downscale..((out_y + 1) * downscale).min(height) {
+                    for x in out_x * downscale..((out_x + 1) * downscale).min(width) {
+                        let color = view.colors.color(y, x);
+                        sum[0] += color.r;
+                        sum[1] += color.g;
+                        sum[2] += color.b;
+                        count += 1.0;
+                    }
+                }
+                let average =
+                    |channel: f32| (channel / count * 255.0).round().max(0.0).min(255.0) as u8;
+                raster.fill(
+                    out_x,
+                    out_y,
+                    1,
+                    1,
+                    [average(sum[0]), average(sum[1]), average(sum[2])],
+                );
+            }
+        }
+        raster
+    }
+
+    /// A rectangle of the world with each tile `pixels` wide, with the ancestry markings of a
+    /// full view and the overlays asked for.
+    pub fn region(view: &View, rect: Rect, pixels: usize, overlays: &Overlays) -> Self {
+        let (rows, columns) = view.colors.dim();
+        let rect = Rect {
+            x: rect.x.min(columns),
+            y: rect.y.min(rows),
+            width: rect.width.min(columns.saturating_sub(rect.x)),
+            height: rect.height.min(rows.saturating_sub(rect.y)),
+        };
+        let pixels = pixels.max(1);
+        let mut raster = Self::new(rect.width * pixels, rect.height * pixels);
+        let marking = (pixels as f32 * 0.1).round() as usize;
+        for y in 0..rect.height {
+            for x in 0..rect.width {
+                let (column, row) = (rect.x + x, rect.y + y);
+                let color = view.colors.color(row, column);
+                let color = if overlays.marked.contains(&(column, row)) {
+                    lighten(color, MARKED_ALPHA)
+                } else {
+                    color
+                };
+                let (left, top) = (x * pixels, y * pixels);
+                raster.fill(left, top, pixels, pixels, bytes(color));
+                // Markings too small to see are left out, as when the grid is zoomed out.
+                if let (ViewColors::Full(colors), true) = (&view.colors, marking > 0) {
+                    for (x_off, y_off, shade) in markings(colors[(row, column)].1) {
+                        let offset = |off: f32| (off * pixels as f32).round().max(0.0) as usize;
+                        let shaded =
+                            Color::from_rgb(color.r * shade, color.g * shade, color.b * shade);
+                        raster.fill(
+                            left + offset(x_off),
+                            top + offset(y_off),
+                            marking,
+                            marking,
+                            bytes(shaded),
+                        );
+                    }
+                }
+            }
+        }
+        if overlays.lines && pixels >= 4 {
+            for y in 0..rect.height {
+                raster.fill(0, y * pixels, raster.width, 1, LINE_COLOR);
+            }
+            for x in 0..rect.width {
+                raster.fill(x * pixels, 0, 1, raster.height, LINE_COLOR);
+            }
+        }
+        raster
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            self.width as u32,
+            self.height as u32,
+        );
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.data)?;
+        Ok(())
+    }
+}
+
+fn bytes(color: Color) -> [u8; 3] {
+    let byte = |channel: f32| (channel * 255.0).round().max(0.0).min(255.0) as u8;
+    [byte(color.r), byte(color.g), byte(color.b)]
+}
+
+/// A color with white laid over it at some opacity.
+fn lighten(color: Color, alpha: f32) -> Color {
+    let over = |channel: f32| channel * (1.0 - alpha) + alpha;
+    Color::from_rgb(over(color.r), over(color.g), over(color.b))
+}
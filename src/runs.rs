@@ -0,0 +1,150 @@
+//! A directory for each run of a sim, named by when it started, which everything the run writes
+//! goes into: saves, exports, the stats database, timelapse frames, a log of its events, a journal
+//! of manual interventions, postmortems of major lineages and a copy of the scenario it was started
+//! with.
+
+use crate::io::FileKind;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct RunDir {
+    path: PathBuf,
+}
+
+impl RunDir {
+    /// Creates a new directory for a run under `root`, creating `root` too if needed.
+    pub fn create(root: &Path) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(root)?;
+        let stamp = timestamp(SystemTime::now());
+        // Runs started within the same second are told apart by a suffix.
+        let mut path = root.join(format!("run-{}", stamp));
+        let mut attempt = 1;
+        loop {
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    attempt += 1;
+                    path = root.join(format!("run-{}-{}", stamp, attempt));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// A run directory made before, such as one to compare with others.
+    pub fn open(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where a file of some kind written at a tick goes, so that exports never overwrite
+    /// each other.
+    pub fn artifact(&self, kind: FileKind, tick: u64) -> PathBuf {
+        let name = Path::new(kind.default_name());
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = name.extension().unwrap_or_default().to_string_lossy();
+        self.path
+            .join(format!("{}-tick{}.{}", stem, tick, extension))
+    }
+
+    /// The database the stats of the whole run are recorded into.
+    pub fn database(&self) -> PathBuf {
+        self.path.join("stats.sqlite")
+    }
+
+    /// The world saved every so often while the run goes on and when the app exits, each save
+    /// replacing the last.
+    pub fn autosave(&self) -> PathBuf {
+        self.path.join("autosave.snap")
+    }
+
+    /// Where the postmortem of a lineage which went extinct at a tick goes.
+    pub fn postmortem(&self, lineage: u64, tick: u64) -> PathBuf {
+        self.path
+            .join(format!("postmortem-lineage{}-tick{}.json", lineage, tick))
+    }
+
+    pub fn timelapse(&self) -> PathBuf {
+        self.path.join("timelapse")
+    }
+
+    /// Keeps a copy of the scenario the run was started with, since the original may be edited
+    /// while the run goes on.
+    pub fn copy_scenario(&self, scenario: &Path) -> Result<(), Box<dyn Error>> {
+        let name = scenario
+            .file_name()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| "scenario.toml".into());
+        fs::copy(scenario, self.path.join(name))?;
+        Ok(())
+    }
+
+    /// The copy of the scenario the run was started with, if it was started with one.
+    pub fn scenario(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(false, |extension| extension == "toml")
+            {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Appends a line to the log of what happened in the run.
+    pub fn log(&self, tick: u64, event: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.join("events.log"))?;
+        writeln!(file, "tick {}: {}", tick, event)?;
+        Ok(())
+    }
+
+    /// Appends a line to the journal of manual interventions in the run, by the tick of the
+    /// world they were made on.
+    pub fn journal(&self, tick: u64, event: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.join("interventions.log"))?;
+        writeln!(file, "tick {}: {}", tick, event)?;
+        Ok(())
+    }
+}
+
+/// A time in UTC as `YYYYMMDD-HHMMSS`, which sorts runs by when they started.
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+    // The civil date of a count of days since 1970, by Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,122 @@
+//! Rules for combinations of settings known to stall a sim or to need more memory than a
+//! machine is likely to have, checked before a world is started so that one is not run by
+//! accident.
+
+use crate::sim::{self, Params};
+
+/// The bytes a new world may take before it is warned about.
+const MEMORY_BUDGET: usize = 4 << 30;
+/// A mutation chance each tick at which or above which brains change faster than selection can
+/// keep anything.
+const RUNAWAY_MUTATION_CHANCE: f64 = 0.5;
+/// What a runaway mutation chance is clamped to, the top of its slider.
+const CLAMPED_MUTATION_CHANCE: f64 = 0.02;
+
+/// The settings a new world is started with.
+#[derive(Copy, Clone, Debug)]
+pub struct Settings {
+    pub params: Params,
+    pub width: usize,
+    pub height: usize,
+    /// The chance each tile is a cornacopia.
+    pub cornacopias: f64,
+}
+
+/// A combination of settings to warn about, and how to bring it back within bounds.
+struct Rule {
+    /// Why the settings would stall the sim, if they would.
+    check: fn(&Settings) -> Option<String>,
+    clamp: fn(&mut Settings),
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        check: |settings| {
+            if settings.params.spawn_chance == 0.0 {
+                Some("No cells ever spawn, so a new world stays empty.".to_owned())
+            } else {
+                None
+            }
+        },
+        clamp: |settings| settings.params.spawn_chance = Params::default().spawn_chance,
+    },
+    Rule {
+        check: |settings| {
+            let params = &settings.params;
+            let cornacopias = settings.cornacopias > 0.0
+                && params.cornacopia_chance > 0.0
+                && params.cornacopia_bounty > 0;
+            if params.general_food_chance == 0.0 && !cornacopias {
+                Some(
+                    "No food grows anywhere, so every cell starves soon after it spawns."
+                        .to_owned(),
+                )
+            } else {
+                None
+            }
+        },
+        clamp: |settings| {
+            settings.params.general_food_chance = Params::default().general_food_chance
+        },
+    },
+    Rule {
+        check: |settings| {
+            let chance = settings.params.mutation_chance;
+            if chance >= RUNAWAY_MUTATION_CHANCE {
+                Some(format!(
+                    "A mutation chance of {} changes brains faster than selection can keep \
+                     anything.",
+                    chance
+                ))
+            } else {
+                None
+            }
+        },
+        clamp: |settings| {
+            let chance = &mut settings.params.mutation_chance;
+            *chance = chance.min(CLAMPED_MUTATION_CHANCE);
+        },
+    },
+    Rule {
+        check: |settings| {
+            let bytes = world_bytes(settings);
+            if bytes > MEMORY_BUDGET {
+                Some(format!(
+                    "A world of {} by {} takes about {:.1} GiB before anything lives in it.",
+                    settings.width,
+                    settings.height,
+                    bytes as f64 / (1u64 << 30) as f64
+                ))
+            } else {
+                None
+            }
+        },
+        clamp: |settings| {
+            // Both sides shrink alike, keeping the aspect ratio.
+            let scale = (MEMORY_BUDGET as f64 / world_bytes(settings) as f64).sqrt();
+            settings.width = (settings.width as f64 * scale) as usize;
+            settings.height = (settings.height as f64 * scale) as usize;
+        },
+    },
+];
+
+fn world_bytes(settings: &Settings) -> usize {
+    settings.width * settings.height * sim::tile_bytes()
+}
+
+/// Why the settings would stall the sim, one warning for each rule they break.
+pub fn warnings(settings: &Settings) -> Vec<String> {
+    RULES
+        .iter()
+        .filter_map(|rule| (rule.check)(settings))
+        .collect()
+}
+
+/// Brings the settings within every rule they break, leaving the rest of them alone.
+pub fn clamp(settings: &mut Settings) {
+    for rule in RULES {
+        if (rule.check)(settings).is_some() {
+            (rule.clamp)(settings);
+        }
+    }
+}
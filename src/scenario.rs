@@ -0,0 +1,378 @@
+use crate::sim::{
+    CircuitBreaker, Demurrage, Edges, Exhaustion, Freeze, Mutations, Noise, Phase, Proceeds,
+    Recovery, Region, Sanitization, Spoilage, Tiers, MAX_REGIONS,
+};
+use rand::Rng;
+use serde::Deserialize;
+use std::path::Path;
+
+/// The version of the units scenarios are written in, raised whenever a field keeps its name but
+/// changes what it means. Version 1 scaled `mutation_chance` up by 50, and `cornacopia_chance`
+/// and `cornacopia_count_probability` up by 10.
+pub const VERSION: u32 = 2;
+
+/// Parameters of an experiment, expressed in the same units as the GUI controls, so that the
+/// chances are the probabilities per tile or cell per tick which are in effect.
+///
+/// Every field is optional so that a scenario only overrides what it mentions.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    /// The version of the units the scenario is written in, which it must give if it sets a
+    /// chance whose units changed.
+    pub version: Option<u32>,
+    pub width: Option<usize>,
+    pub openness: Option<usize>,
+    /// Tiles added to the width of every passage of the maze.
+    pub corridor_width: Option<usize>,
+    /// Passes of wearing away the corners and ends of walls.
+    pub smoothing: Option<usize>,
+    /// Food growing or draining along the edges of the world.
+    pub edges: Option<Edges>,
+    pub cornacopia_count_probability: Option<f64>,
+    /// How common each tier of cornacopia is in new worlds and what it yields.
+    pub tiers: Option<Tiers>,
+    pub speed: Option<usize>,
+    pub frames_per_second: Option<usize>,
+    pub spawn_rate: Option<f64>,
+    pub mutation_chance: Option<f64>,
+    pub general_food_chance: Option<f64>,
+    pub cornacopia_chance: Option<f64>,
+    pub cornacopia_bounty: Option<u32>,
+    pub direct_exchange: Option<bool>,
+    /// The order of the phases of a tick, which must name each phase once.
+    pub phases: Option<Vec<Phase>>,
+    pub sanitization: Option<Sanitization>,
+    /// What becomes of the money of a cell which starves right after trading.
+    pub proceeds: Option<Proceeds>,
+    /// The part of the world held still while the rest goes on.
+    pub freeze: Option<Freeze>,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    pub sense_life: Option<bool>,
+    pub sense_fills: Option<bool>,
+    pub starvation_grace: Option<u32>,
+    pub demurrage: Option<Demurrage>,
+    /// The percentage of the money passed to offspring which goes to the reserve.
+    pub inheritance_tax: Option<f64>,
+    /// What the reserve does when it has too little money for the food offered to it.
+    pub exhaustion: Option<Exhaustion>,
+    /// Food a cell pays each tick its signal is not zero.
+    pub signal_cost: Option<f64>,
+    /// Food a cell pays for each codon its brain runs to decide.
+    pub metabolism: Option<f64>,
+    /// How much food cells can keep before the rest spoils.
+    pub spoilage: Option<Spoilage>,
+    /// The percentage of the cells with brains stepped each tick, below 100 for an approximate
+    /// run.
+    pub stepped_percent: Option<f64>,
+    /// Parts of the world where some parameters differ, replacing any the world had. A tile in
+    /// several regions takes the parameters of the last.
+    pub regions: Option<Vec<Region>>,
+    /// What a world is started over with once every cell died.
+    pub recovery: Option<Recovery>,
+    /// Noise in what brains sense and remember, to test how robust evolved programs are.
+    pub noise: Option<Noise>,
+    /// The chance of each kind of change when a genome mutates.
+    pub mutations: Option<Mutations>,
+    pub jitter: Jitters,
+}
+
+/// Parameters which drift on their own while the sim runs.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Jitters {
+    pub spawn_rate: Option<Jitter>,
+    pub mutation_chance: Option<Jitter>,
+    pub general_food_chance: Option<Jitter>,
+    pub cornacopia_chance: Option<Jitter>,
+    pub cornacopia_bounty: Option<Jitter>,
+}
+
+impl Jitters {
+    pub fn is_empty(&self) -> bool {
+        self.spawn_rate.is_none()
+            && self.mutation_chance.is_none()
+            && self.general_food_chance.is_none()
+            && self.cornacopia_chance.is_none()
+            && self.cornacopia_bounty.is_none()
+    }
+}
+
+/// A random walk of a parameter between bounds.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Jitter {
+    pub min: f64,
+    pub max: f64,
+    /// The largest change in a single frame.
+    pub step: f64,
+}
+
+impl Jitter {
+    pub fn walk(&self, rng: &mut impl Rng, value: f64) -> f64 {
+        let value = if self.step > 0.0 {
+            value + rng.gen_range(-self.step, self.step)
+        } else {
+            value
+        };
+        value.max(self.min).min(self.max)
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Reads a scenario from TOML, rejecting chances which are not probabilities.
+    pub fn parse(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut scenario: Self = toml::from_str(text)?;
+        match scenario.version {
+            // Without a version there is no telling which units such a chance is in.
+            None if scenario.rescaled() => {
+                return Err(format!(
+                    "a scenario which sets mutation_chance, cornacopia_chance or \
+                     cornacopia_count_probability must give its version: `version = {}` for \
+                     the probabilities in effect, or `version = 1` for the scaled units of \
+                     older scenarios",
+                    VERSION
+                )
+                .into())
+            }
+            None | Some(VERSION) => {}
+            Some(1) => scenario.convert_version_1(),
+            Some(version) => {
+                return Err(format!(
+                    "scenario version {} is not one this build reads, which are 1 to {}",
+                    version, VERSION
+                )
+                .into())
+            }
+        }
+        let mut chances = vec![
+            (
+                "cornacopia_count_probability",
+                scenario.cornacopia_count_probability,
+            ),
+            ("spawn_rate", scenario.spawn_rate),
+            ("mutation_chance", scenario.mutation_chance),
+            ("general_food_chance", scenario.general_food_chance),
+            ("cornacopia_chance", scenario.cornacopia_chance),
+        ];
+        let jitters = &scenario.jitter;
+        for &(name, jitter) in &[
+            ("jitter.spawn_rate", jitters.spawn_rate),
+            ("jitter.mutation_chance", jitters.mutation_chance),
+            ("jitter.general_food_chance", jitters.general_food_chance),
+            ("jitter.cornacopia_chance", jitters.cornacopia_chance),
+        ] {
+            if let Some(jitter) = jitter {
+                chances.push((name, Some(jitter.min)));
+                chances.push((name, Some(jitter.max)));
+            }
+        }
+        let regions = scenario.regions.as_deref().unwrap_or_default();
+        if regions.len() > MAX_REGIONS {
+            return Err(format!("there can be at most {} regions", MAX_REGIONS).into());
+        }
+        for region in regions {
+            chances.extend_from_slice(&region.chances());
+        }
+        if let Some(demurrage) = scenario.demurrage {
+            if !(0.0..=100.0).contains(&demurrage.percent) {
+                return Err(format!(
+                    "demurrage.percent must be from 0 to 100, not {}",
+                    demurrage.percent
+                )
+                .into());
+            }
+        }
+        if let Some(tax) = scenario.inheritance_tax {
+            if !(0.0..=100.0).contains(&tax) {
+                return Err(format!("inheritance_tax must be from 0 to 100, not {}", tax).into());
+            }
+        }
+        if let Some(cost) = scenario.signal_cost {
+            if !(0.0..=2.0).contains(&cost) {
+                return Err(format!("signal_cost must be from 0 to 2, not {}", cost).into());
+            }
+        }
+        if let Some(cost) = scenario.metabolism {
+            if !(0.0..=0.1).contains(&cost) {
+                return Err(format!("metabolism must be from 0 to 0.1, not {}", cost).into());
+            }
+        }
+        if let Some(spoilage) = scenario.spoilage {
+            if !(0.0..=100.0).contains(&spoilage.percent) {
+                return Err(format!(
+                    "spoilage.percent must be from 0 to 100, not {}",
+                    spoilage.percent
+                )
+                .into());
+            }
+        }
+        if let Some(percent) = scenario.stepped_percent {
+            if !(1.0..=100.0).contains(&percent) {
+                return Err(
+                    format!("stepped_percent must be from 1 to 100, not {}", percent).into(),
+                );
+            }
+        }
+        if let Some(tiers) = scenario.tiers {
+            tiers.check()?;
+        }
+        if let Some(edges) = scenario.edges {
+            chances.push(("edges.chance", Some(edges.chance)));
+        }
+        if let Some(noise) = scenario.noise {
+            if noise.input < 0.0 || !noise.input.is_finite() {
+                return Err(format!("noise.input must be 0 or more, not {}", noise.input).into());
+            }
+            chances.push(("noise.forgetting", Some(noise.forgetting)));
+        }
+        if let Some(mutations) = scenario.mutations {
+            for &(name, chance) in &mutations.chances() {
+                chances.push((name, Some(chance)));
+            }
+        }
+        for (name, value) in chances {
+            match value {
+                Some(value) if !(0.0..=1.0).contains(&value) => {
+                    return Err(format!("{} must be from 0 to 1, not {}", name, value).into())
+                }
+                _ => {}
+            }
+        }
+        Ok(scenario)
+    }
+
+    /// Whether the scenario sets a chance whose units changed since version 1.
+    fn rescaled(&self) -> bool {
+        self.mutation_chance.is_some()
+            || self.cornacopia_chance.is_some()
+            || self.cornacopia_count_probability.is_some()
+            || self.jitter.mutation_chance.is_some()
+            || self.jitter.cornacopia_chance.is_some()
+    }
+
+    /// Turns the scaled units of version 1 into the probabilities in effect.
+    fn convert_version_1(&mut self) {
+        let scale = |value: &mut Option<f64>, by: f64| {
+            if let Some(value) = value {
+                *value /= by;
+            }
+        };
+        let scale_jitter = |jitter: &mut Option<Jitter>, by: f64| {
+            if let Some(jitter) = jitter {
+                jitter.min /= by;
+                jitter.max /= by;
+                jitter.step /= by;
+            }
+        };
+        scale(&mut self.mutation_chance, 50.0);
+        scale(&mut self.cornacopia_chance, 10.0);
+        scale(&mut self.cornacopia_count_probability, 10.0);
+        scale_jitter(&mut self.jitter.mutation_chance, 50.0);
+        scale_jitter(&mut self.jitter.cornacopia_chance, 10.0);
+        self.version = Some(VERSION);
+    }
+
+    /// The names of the parameters this scenario sets, for logging.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = vec![];
+        let mut check = |set: bool, name| {
+            if set {
+                names.push(name);
+            }
+        };
+        check(self.width.is_some(), "width");
+        check(self.openness.is_some(), "openness");
+        check(self.corridor_width.is_some(), "corridor_width");
+        check(self.smoothing.is_some(), "smoothing");
+        check(self.edges.is_some(), "edges");
+        check(
+            self.cornacopia_count_probability.is_some(),
+            "cornacopia_count_probability",
+        );
+        check(self.tiers.is_some(), "tiers");
+        check(self.speed.is_some(), "speed");
+        check(self.frames_per_second.is_some(), "frames_per_second");
+        check(self.spawn_rate.is_some(), "spawn_rate");
+        check(self.mutation_chance.is_some(), "mutation_chance");
+        check(self.general_food_chance.is_some(), "general_food_chance");
+        check(self.cornacopia_chance.is_some(), "cornacopia_chance");
+        check(self.cornacopia_bounty.is_some(), "cornacopia_bounty");
+        check(self.direct_exchange.is_some(), "direct_exchange");
+        check(self.phases.is_some(), "phases");
+        check(self.sanitization.is_some(), "sanitization");
+        check(self.proceeds.is_some(), "proceeds");
+        check(self.freeze.is_some(), "freeze");
+        check(self.circuit_breaker.is_some(), "circuit_breaker");
+        check(self.sense_life.is_some(), "sense_life");
+        check(self.sense_fills.is_some(), "sense_fills");
+        check(self.starvation_grace.is_some(), "starvation_grace");
+        check(self.demurrage.is_some(), "demurrage");
+        check(self.inheritance_tax.is_some(), "inheritance_tax");
+        check(self.exhaustion.is_some(), "exhaustion");
+        check(self.signal_cost.is_some(), "signal_cost");
+        check(self.metabolism.is_some(), "metabolism");
+        check(self.spoilage.is_some(), "spoilage");
+        check(self.stepped_percent.is_some(), "stepped_percent");
+        check(self.regions.is_some(), "regions");
+        check(self.recovery.is_some(), "recovery");
+        check(self.noise.is_some(), "noise");
+        check(self.mutations.is_some(), "mutations");
+        check(!self.jitter.is_empty(), "jitter");
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_rescaled_chances_without_a_version() {
+        assert!(Scenario::parse("mutation_chance = 0.01").is_err());
+        assert!(
+            Scenario::parse("[jitter.cornacopia_chance]\nmin = 0\nmax = 0.1\nstep = 0.01").is_err()
+        );
+        // Chances whose units never changed need no version.
+        let scenario = Scenario::parse("general_food_chance = 0.1\nwidth = 64").unwrap();
+        assert_eq!(scenario.general_food_chance, Some(0.1));
+    }
+
+    #[test]
+    fn reads_the_current_version_as_it_is() {
+        let scenario = Scenario::parse("version = 2\nmutation_chance = 0.0002").unwrap();
+        assert_eq!(scenario.mutation_chance, Some(0.0002));
+    }
+
+    #[test]
+    fn converts_version_1() {
+        let scenario = Scenario::parse(
+            "version = 1\n\
+             mutation_chance = 0.01\n\
+             cornacopia_chance = 0.1\n\
+             cornacopia_count_probability = 0.005\n\
+             [jitter.mutation_chance]\n\
+             min = 0.0\n\
+             max = 0.5\n\
+             step = 0.05",
+        )
+        .unwrap();
+        assert_eq!(scenario.version, Some(VERSION));
+        assert_eq!(scenario.mutation_chance, Some(0.01 / 50.0));
+        assert_eq!(scenario.cornacopia_chance, Some(0.1 / 10.0));
+        assert_eq!(scenario.cornacopia_count_probability, Some(0.005 / 10.0));
+        let jitter = scenario.jitter.mutation_chance.unwrap();
+        assert_eq!(jitter.max, 0.5 / 50.0);
+        assert_eq!(jitter.step, 0.05 / 50.0);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert!(Scenario::parse("version = 3").is_err());
+        assert!(Scenario::parse("version = 0").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! How the window was left when it was last used, kept apart from saves of the world so that the
+//! app and each world reopen the way they were left: the size of the window, which panels were
+//! shown and where the grid of each size of world was panned and zoomed to.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Worlds of this many sizes have their viewports remembered, forgetting the least recent.
+const MAX_VIEWPORTS: usize = 16;
+
+/// Where the grid of a world of some size was panned and zoomed to.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub width: usize,
+    pub height: usize,
+    pub x: f32,
+    pub y: f32,
+    pub scaling: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// The inner size of the window, if it was ever resized.
+    pub window: Option<(u32, u32)>,
+    pub show_stats: bool,
+    pub grid_lines: bool,
+    /// Whether the rooms and regions of the world are outlined over the grid.
+    pub floorplan: bool,
+    /// The most recently used last.
+    pub viewports: Vec<Viewport>,
+    /// The genome files in the library, in the order they were added.
+    pub genomes: Vec<PathBuf>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            window: None,
+            show_stats: true,
+            grid_lines: false,
+            floorplan: false,
+            viewports: vec![],
+            genomes: vec![],
+        }
+    }
+}
+
+impl Session {
+    /// Where the session is kept unless another file is given, in the configuration directory
+    /// of the user.
+    pub fn default_path() -> Option<PathBuf> {
+        let config = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => match env::var_os("APPDATA") {
+                Some(dir) => PathBuf::from(dir),
+                None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+            },
+        };
+        Some(config.join("evonomics").join("session.json"))
+    }
+
+    /// Reads a session, giving the default one if it was never written.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Where the grid of a world of this size was last left.
+    pub fn viewport(&self, width: usize, height: usize) -> Option<Viewport> {
+        self.viewports
+            .iter()
+            .rev()
+            .find(|viewport| viewport.width == width && viewport.height == height)
+            .copied()
+    }
+
+    /// Keeps the viewport of a world, replacing the one kept for a world of its size.
+    pub fn remember(&mut self, viewport: Viewport) {
+        self.viewports
+            .retain(|kept| (kept.width, kept.height) != (viewport.width, viewport.height));
+        self.viewports.push(viewport);
+        if self.viewports.len() > MAX_VIEWPORTS {
+            self.viewports.remove(0);
+        }
+    }
+}
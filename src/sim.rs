@@ -1,46 +1,188 @@
+use crate::raster::{Overlays, Raster, Rect};
 use crate::rng;
+use crate::tape::Tape;
+use crate::timelapse::Timelapse;
 use arrayvec::ArrayVec;
-use brain::{Brain, Decision};
+use brain::{Brain, Life};
+use config::{SimConfig, Tally};
+use journal::Journal;
 use futures::{
-    channel::mpsc::{self, Receiver, Sender},
+    channel::{
+        mpsc::{self, Receiver, Sender},
+        oneshot,
+    },
+    future::{self, Either},
     prelude::*,
     Future,
 };
-use gridsim::{moore::*, Neighborhood, SquareGrid};
+use gridsim::{moore::*, Direction, Neighborhood, SquareGrid};
 use iced::Color;
-use min_max_heap::MinMaxHeap;
 use ndarray::Array2;
 use rand::{distributions::Bernoulli, seq::SliceRandom, Rng};
+use rand_chacha::ChaCha8Rng;
 use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use control::{Report, Request};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering::{Greater, Less};
 use std::iter::once;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::task::block_in_place;
 
 type LifeContainer = SquareGrid<'static, Evonomics>;
 
+mod ancestry;
+mod audit;
 mod brain;
+mod config;
+mod control;
+mod edges;
+mod features;
+mod filter;
+mod footprint;
+mod journal;
+mod layout;
+mod lineage;
+mod market;
+mod masks;
+mod noise;
+mod portal;
+mod postmortem;
+mod recovery;
+mod regions;
+mod sampler;
+mod selection;
+mod snapshot;
+mod spoilage;
+mod streams;
+mod tiers;
+mod trace;
+
+use ancestry::Ancestry;
+pub use ancestry::{AncestryFormat, Clade, Phylogeny};
+pub use audit::Audit;
+pub use brain::{
+    diff, BrainTrace, Choice, Decision, DiffRow, EntryTrace, Edit, Expression, GeneExpression,
+    Genome, Junk, Locus, Mutations, Sanitization, Sanitized, TraceStep,
+};
+pub use control::ControlLink;
+use edges::EdgeMap;
+pub use edges::{EdgePolicy, Edges};
+pub use features::FeatureSet;
+pub use filter::{Comparison, Filter};
+pub use footprint::{tile_bytes, Footprint};
+pub use journal::{Intervention, JournalUpdate};
+pub use layout::{Floorplan, Layout, LayoutStats, Maze};
+pub use lineage::{LineageEvent, LineageSummary};
+use lineage::Lineages;
+pub use market::{
+    Candle, CircuitBreaker, Demurrage, Exhaustion, Halt, Limit, Market, MoneyFlows, OrderCounts,
+    Print, Proceeds, Shortfall, MAX_EXPIRY,
+};
+pub use masks::{Bitset, Flips, Masks};
+pub use noise::Noise;
+pub use portal::{link_worlds, PortalLink};
+pub use postmortem::{Cause, LineageTick, MarketTick, Postmortem, WealthPoint};
+pub use recovery::{Recovered, Recovery, Seeds};
+pub use regions::{Area, Region, MAX_REGIONS};
+use regions::{Overrides, RegionMap};
+pub use tiers::{Tier, TierYield, Tiers};
+pub use trace::TracePoint;
+use trace::Follow;
+use portal::{Migrant, Traffic};
+pub use sampler::{AgePyramid, LineagePrices, Ohlc, Sampling};
+pub use selection::{Selection, FITNESS, TRAITS};
+pub use spoilage::Spoilage;
+use selection::Deaths;
+use streams::{Draw, Streams};
+use sampler::Sampler;
 
 const FOOD_COLOR_MULTIPLIER: f32 = 0.05;
 const MONEY_COLOR_MULTIPLIER: f32 = 0.1;
+/// The tint added to an empty cornacopia of each tier, from the smallest: blue, orange and white.
 
 // starting food for cell
 const SPAWN_FOOD: u32 = 16;
 const MOVE_PENALTY: u32 = 32;
-
-static mut CORNACOPIA_FOOD_SPAWN: u32 = 0;
-static mut CELL_SPAWN_DISTRIBUTION: Option<Bernoulli> = None;
-static mut MUTATE_DISTRIBUTION: Option<Bernoulli> = None;
-static mut CORNACOPIA_FOOD_DISTRIBUTION: Option<Bernoulli> = None;
-static mut NORMAL_FOOD_DISTRIBUTION: Option<Bernoulli> = None;
+/// Food eaten every tick by a cell which neither moves nor divides.
+const UPKEEP: u32 = 1;
+/// Cells which can last this many ticks or more are shown as safe from starvation.
+const STARVATION_HORIZON: u32 = 64;
+/// Food or money a brush drops on each tile under it every time it paints.
+const BRUSH_AMOUNT: u32 = 4;
 
 const RESERVE_MULTIPLIER: u32 = 0;
 const TILE_MONEY_SPAWN: u32 = 64;
 
-const REPO: bool = false;
+/// The most recent trades of a burst of ticks sent for the ticker.
+pub const TICKER_PRINTS: usize = 24;
 
-#[derive(Clone, Debug)]
+/// Worlds with more tiles than this get compact views unless told otherwise.
+const COMPACT_VIEW_AREA: usize = 1 << 20;
+
+/// Ticks between passes which merge identical genomes into one allocation.
+const GENOME_INTERN_INTERVAL: u64 = 256;
+
+/// Tiles in each chunk of the phases which touch every tile, each drawing from a stream of its
+/// own. Fixed, rather than split by the threads there are, so that a seed replays the same way on
+/// any machine.
+const PHASE_CHUNK: usize = 4096;
+
+/// How often the memory footprint of the world is measured and sent while it runs.
+const FOOTPRINT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait on an external controller before letting the brain decide instead.
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Trade {
     pub rate: i32,
     pub food: i32,
+    pub limit: Limit,
+}
+
+impl Trade {
+    /// Whether the cell can still settle the whole order, which a resting order may no longer
+    /// be able to once its cell has eaten or spent since placing it.
+    fn settleable(&self, cell: &Cell) -> bool {
+        self.food <= cell.food as i32 && -self.rate * self.food <= cell.money as i32
+    }
+
+    /// Whether filling the whole order would sell the last of the food of a cell with no grace
+    /// left, so that it starves before it can act again.
+    fn fatal(&self, cell: &Cell, starvation_grace: u32) -> bool {
+        self.food > 0
+            && self.food as u32 >= cell.food
+            && cell
+                .brain
+                .as_ref()
+                .map_or(true, |brain| brain.life.hungry >= starvation_grace)
+    }
+}
+
+/// Food and money a cell offered to one neighbor, standing for a single tick.
+///
+/// Positive amounts are given by the offering cell and are held out of its own food and money
+/// until the offer is accepted or expires. Negative amounts are asked of the neighbor.
+#[derive(Copy, Clone, Debug)]
+pub struct Offer {
+    pub dir: MooreDirection,
+    pub food: i32,
+    pub money: i32,
+}
+
+impl Offer {
+    /// The food and money held back from the offering cell.
+    fn escrow(&self) -> (u32, u32) {
+        (self.food.max(0) as u32, self.money.max(0) as u32)
+    }
+
+    /// The food and money the accepting cell must give.
+    fn price(&self) -> (u32, u32) {
+        ((-self.food).max(0) as u32, (-self.money).max(0) as u32)
+    }
 }
 
 struct Evonomics {}
@@ -60,241 +202,594 @@ impl<'a> gridsim::Sim<'a> for Evonomics {
     type MoveNeighbors = MooreNeighbors<Move>;
 
     fn step(cell: &Cell, neighbors: Self::Neighbors) -> (Diff, Self::MoveNeighbors) {
-        if cell.brain.is_none() || cell.food == 0 {
-            return (
-                Diff {
-                    consume: 0,
-                    spend: 0,
-                    moved: true,
-                    trade: None,
-                },
-                MooreNeighbors::new(|_| Move {
-                    food: 0,
-                    money: 0,
-                    brain: None,
-                }),
-            );
+        config::with(|config, tally, streams| step_cell(config, tally, streams, cell, neighbors))
+    }
+
+    fn update(cell: &mut Cell, diff: Diff, moves: Self::MoveNeighbors) {
+        config::with(|config, tally, streams| {
+            update_cell(config, tally, streams, cell, diff, moves)
+        })
+    }
+}
+
+/// Steps a cell under the config of its world, deciding what it does and what it sends to each
+/// neighbor.
+fn step_cell(
+    config: &SimConfig,
+    tally: &Tally,
+    streams: Streams,
+    cell: &Cell,
+    neighbors: MooreNeighbors<&Cell>,
+) -> (Diff, MooreNeighbors<Move>) {
+    if cell.brain.is_none() || starves(config, cell) {
+        return (
+            Diff {
+                consume: 0,
+                spend: 0,
+                moved: true,
+                trade: None,
+                ..Diff::default()
+            },
+            MooreNeighbors::new(|_| Move::default()),
+        );
+    }
+    let rng = &mut streams.rng(Draw::Step, cell.tile);
+    // Under approximation a brain left out of a tick neither decides nor eats, while one which
+    // is stepped eats for the ticks it waits through on average. A possessed brain is always
+    // stepped, since its controller is waiting on it.
+    let stepped = config.stepped;
+    let upkeep = match cell.brain {
+        Some(ref brain) if stepped < 1.0 && !brain.possessed => {
+            if !rng.gen_bool(stepped) {
+                return (
+                    Diff {
+                        consume: 0,
+                        spend: 0,
+                        moved: false,
+                        trade: None,
+                        lived: Some(Lived {
+                            skipped: true,
+                            ..Lived::default()
+                        }),
+                        ..Diff::default()
+                    },
+                    MooreNeighbors::new(|_| Move::default()),
+                );
+            }
+            let upkeep = UPKEEP as f64 / stepped;
+            upkeep.floor() as u32 + rng.gen_bool(upkeep.fract()) as u32
         }
-        // Closure for just existing (consuming food and nothing happening).
-        let just_exist = |trade| {
-            (
-                Diff {
-                    consume: 1,
-                    spend: 0,
-                    moved: false,
-                    trade,
-                },
-                MooreNeighbors::new(|_| Move {
-                    food: 0,
-                    money: 0,
-                    brain: None,
-                }),
+        _ => UPKEEP,
+    };
+    // Closure for just existing (consuming food and nothing happening).
+    let just_exist = |trade| {
+        (
+            Diff {
+                consume: upkeep,
+                spend: 0,
+                moved: false,
+                trade,
+                ..Diff::default()
+            },
+            MooreNeighbors::new(|_| Move::default()),
+        )
+    };
+    let mut lived = Lived {
+        // A cell at zero food lives on the grace, when all it can do is buy food.
+        hungry: match cell.brain {
+            Some(ref brain) if cell.food == 0 => brain.life.hungry + 1,
+            _ => 0,
+        },
+        ..Lived::default()
+    };
+    let decision = cell
+        .brain
+        .as_ref()
+        .map(|brain| {
+            // An external controller has already decided for a possessed cell.
+            if let Some(decision) = brain.forced {
+                lived.forced = Some(decision.into());
+                return decision;
+            }
+            let mut inputs = inputs(config, cell, neighbors.iter());
+            // This handles rotation of inputs in respect to cell.
+            inputs[0..NEIGHBOR_INPUTS * 4].rotate_left(NEIGHBOR_INPUTS * brain.rotation());
+            let offers = NEIGHBOR_INPUTS * 4 + SELF_INPUTS..OFFERS_END;
+            if inputs.len() >= OFFERS_END {
+                inputs[offers].rotate_left(OFFER_INPUTS * brain.rotation());
+            }
+            noise::perturb(&mut *rng, &config.noise, &mut inputs);
+            // A promise is made here not to look at the brain of any other cell elsewhere.
+            let brain = unsafe { &mut *(brain as *const Brain as *mut Brain) };
+            brain.decide(
+                &mut *rng,
+                &inputs,
+                config.sanitization,
+                config.noise.forgetting,
+                tally,
             )
-        };
-        let decision = cell
-            .brain
-            .as_ref()
-            .map(|brain| {
-                const NEIGHBOR_INPUTS: usize = 5;
-                const SELF_INPUTS: usize = 2;
-                const INPUTS: usize = NEIGHBOR_INPUTS * 4 + SELF_INPUTS;
-                let boolnum = |n| if n { 1.0 } else { 0.0 };
-                let mut inputs: ArrayVec<[f64; INPUTS]> = neighbors
-                    .iter()
-                    .flat_map(|n| {
-                        once(boolnum(n.brain.is_some()))
-                            .chain(once(boolnum(n.ty == CellType::Wall)))
-                            .chain(once(n.food as f64))
-                            .chain(once(n.signal))
-                            .chain(once(n.money as f64))
-                    })
-                    .chain(once(cell.food as f64))
-                    .chain(once(cell.money as f64))
-                    .collect();
-                // This handles rotation of inputs in respect to cell.
-                inputs[0..NEIGHBOR_INPUTS * 4].rotate_left(NEIGHBOR_INPUTS * brain.rotation());
-                // A promise is made here not to look at the brain of any other cell elsewhere.
-                let brain = unsafe { &mut *(brain as *const Brain as *mut Brain) };
-                brain.decide(unsafe { rng() }, &inputs)
-            })
-            .unwrap_or(Decision::Nothing);
-
-        match decision {
-            Decision::Move(dir) => {
-                let is_wall = neighbors[dir].ty == CellType::Wall;
-                let moved_money = if is_wall { 0 } else { cell.money };
-                if cell.food > MOVE_PENALTY {
-                    (
-                        Diff {
-                            consume: cell.food,
-                            spend: moved_money,
-                            moved: true,
-                            trade: None,
-                        },
-                        MooreNeighbors::new(|nd| {
-                            if nd == dir {
-                                Move {
-                                    food: cell.food - 1 - MOVE_PENALTY,
-                                    money: moved_money,
-                                    brain: cell.brain.clone(),
-                                }
-                            } else {
-                                Move {
-                                    food: 0,
-                                    money: 0,
-                                    brain: None,
-                                }
-                            }
-                        }),
-                    )
-                } else {
-                    just_exist(None)
-                }
-            }
-            Decision::Divide(dir) => {
-                let is_wall = neighbors[dir].ty == CellType::Wall;
-                let moved_money = if is_wall { 0 } else { cell.money };
-                if cell.food >= 2 + MOVE_PENALTY {
-                    (
-                        Diff {
-                            consume: cell.food / 2 + 1 + MOVE_PENALTY / 2,
-                            spend: moved_money / 2,
-                            moved: false,
-                            trade: None,
-                        },
-                        MooreNeighbors::new(|nd| {
-                            if nd == dir {
-                                Move {
-                                    food: cell.food / 2 - MOVE_PENALTY / 2,
-                                    money: moved_money / 2,
-                                    brain: {
-                                        if let Some(mut t) = cell.brain.clone() {
-                                            t.generation += 1;
-                                            Some(t)
-                                        } else {
-                                            None
-                                        }
-                                    },
-                                }
-                            } else {
-                                Move {
-                                    food: 0,
-                                    money: 0,
-                                    brain: None,
-                                }
+        })
+        .unwrap_or(Decision::Nothing);
+
+    let (mut diff, moves) = match decision {
+        Decision::Move(dir) => {
+            let is_wall = neighbors[dir].ty == CellType::Wall;
+            let moved_money = if is_wall { 0 } else { cell.money };
+            if cell.food > MOVE_PENALTY {
+                if is_wall {
+                    Tally::add(&tally.died_in_walls, 1);
+                }
+                (
+                    Diff {
+                        consume: cell.food,
+                        spend: moved_money,
+                        moved: true,
+                        trade: None,
+                        ..Diff::default()
+                    },
+                    MooreNeighbors::new(|nd| {
+                        if nd == dir {
+                            Move {
+                                food: cell.food - 1 - MOVE_PENALTY,
+                                money: moved_money,
+                                // The brain takes what this tick changed in its life along.
+                                brain: cell.brain.clone().map(|mut brain| {
+                                    lived.apply(&mut brain.life);
+                                    brain
+                                }),
+                                ..Move::default()
                             }
-                        }),
-                    )
-                } else {
-                    just_exist(None)
+                        } else {
+                            Move::default()
+                        }
+                    }),
+                )
+            } else {
+                just_exist(None)
+            }
+        }
+        Decision::Divide(dir) => {
+            let is_wall = neighbors[dir].ty == CellType::Wall;
+            let moved_money = if is_wall { 0 } else { cell.money };
+            if cell.food >= 2 + MOVE_PENALTY {
+                if is_wall {
+                    Tally::add(&tally.died_in_walls, 1);
                 }
+                let tax = inheritance_tax(&mut *rng, config, moved_money / 2);
+                Tally::add(&tally.inheritance_taxed, tax as usize);
+                // Offspring divided into a wall is never placed.
+                lived.divided = !is_wall;
+                (
+                    Diff {
+                        consume: cell.food / 2 + 1 + MOVE_PENALTY / 2,
+                        spend: moved_money / 2,
+                        moved: false,
+                        trade: None,
+                        ..Diff::default()
+                    },
+                    MooreNeighbors::new(|nd| {
+                        if nd == dir {
+                            Move {
+                                food: cell.food / 2 - MOVE_PENALTY / 2,
+                                money: moved_money / 2 - tax,
+                                brain: {
+                                    if let Some(mut t) = cell.brain.clone() {
+                                        t.parents = vec![t.id];
+                                        t.id = brain::next_id();
+                                        t.generation += 1;
+                                        t.life = Default::default();
+                                        // Offspring are never possessed by the controller.
+                                        t.possessed = false;
+                                        t.forced = None;
+                                        t.expression = None;
+                                        t.execution = None;
+                                        Some(t)
+                                    } else {
+                                        None
+                                    }
+                                },
+                                ..Move::default()
+                            }
+                        } else {
+                            Move::default()
+                        }
+                    }),
+                )
+            } else {
+                just_exist(None)
             }
-            Decision::Trade(rate, food) => {
-                // Only trade if we can actually make the trade.
-                let cost = -rate * food;
-                if food < cell.food as i32 && cost <= cell.money as i32 {
-                    just_exist(Some(Trade { rate, food }))
-                } else {
-                    just_exist(None)
+        }
+        Decision::Trade(rate, food, limit) => {
+            // Only trade if we can actually make the trade.
+            let cost = -rate * food;
+            if food < cell.food as i32 && cost <= cell.money as i32 {
+                just_exist(Some(Trade { rate, food, limit }))
+            } else {
+                just_exist(None)
+            }
+        }
+        Decision::Offer(dir, food, money) => {
+            let offer = Offer { dir, food, money };
+            let (food, money) = offer.escrow();
+            let is_wall = neighbors[dir].ty == CellType::Wall;
+            // Only offer what can be held back while still eating this tick, which is more than
+            // one food for a brain stepped only every so many ticks.
+            if config.direct_exchange
+                && !is_wall
+                && food + upkeep <= cell.food
+                && money <= cell.money
+                && (offer.food != 0 || offer.money != 0)
+            {
+                let (mut diff, moves) = just_exist(None);
+                diff.offer = Some(offer);
+                (diff, moves)
+            } else {
+                just_exist(None)
+            }
+        }
+        Decision::Accept(dir) => {
+            // The neighbor must have made its offer to this cell.
+            let offer = neighbors[dir]
+                .offer
+                .filter(|offer| offer.dir == opposite(dir));
+            match offer {
+                Some(offer) if config.direct_exchange => {
+                    let (price_food, price_money) = offer.price();
+                    if price_food + upkeep <= cell.food && price_money <= cell.money {
+                        let (escrow_food, escrow_money) = offer.escrow();
+                        (
+                            Diff {
+                                consume: upkeep + price_food,
+                                spend: price_money,
+                                moved: false,
+                                trade: None,
+                                offer: None,
+                                receive_food: escrow_food,
+                                receive_money: escrow_money,
+                                lived: None,
+                            },
+                            MooreNeighbors::new(|nd| {
+                                if nd == dir {
+                                    Move {
+                                        food: price_food,
+                                        money: price_money,
+                                        brain: None,
+                                        claim: true,
+                                    }
+                                } else {
+                                    Move::default()
+                                }
+                            }),
+                        )
+                    } else {
+                        just_exist(None)
+                    }
                 }
+                _ => just_exist(None),
             }
-            Decision::Nothing => just_exist(None),
         }
-    }
+        Decision::Nothing => just_exist(None),
+    };
+    diff.lived = Some(lived);
+    (diff, moves)
+}
 
-    fn update(cell: &mut Cell, diff: Diff, moves: Self::MoveNeighbors) {
-        // Handle money movement (even if wall so that it can be reclaimed by reserve).
-        cell.money += moves.clone().iter().map(|m| m.money).sum::<u32>();
-        if cell.ty != CellType::Wall {
-            let rng = unsafe { rng() };
-            // Handle food reduction from diff.
-            cell.food = cell.food.saturating_sub(diff.consume);
-            // Handle money reduction from diff.
-            cell.money = cell.money.saturating_sub(diff.spend);
+/// Updates a cell under the config of its world with its own diff and what its neighbors sent it.
+fn update_cell(
+    config: &SimConfig,
+    tally: &Tally,
+    streams: Streams,
+    cell: &mut Cell,
+    diff: Diff,
+    moves: MooreNeighbors<Move>,
+) {
+    let occupied = cell.brain.is_some();
+    let arrivals = Arrivals::gather(moves);
+    // Handle money movement (even if wall so that it can be reclaimed by reserve).
+    cell.money += arrivals.money;
+    if cell.ty != CellType::Wall {
+        // Handle food reduction from diff.
+        cell.food = cell.food.saturating_sub(diff.consume);
+        // Handle money reduction from diff.
+        cell.money = cell.money.saturating_sub(diff.spend);
 
-            // Handle taking the brain.
-            if diff.moved {
-                cell.brain.take();
-            }
+        // Handle taking the brain.
+        if diff.moved {
+            cell.brain.take();
+        } else if let (Some(lived), Some(brain)) = (diff.lived, &mut cell.brain) {
+            lived.apply(&mut brain.life);
+        }
 
-            // Create trade.
+        // A new order replaces the one resting on the tile, which is cancelled if its brain
+        // left or died.
+        if diff.trade.is_some() || diff.moved {
             cell.trade = diff.trade;
+        }
 
-            // Handle brain movement.
-            let mut brain_moves = moves.clone().iter().flat_map(|m| m.brain);
-            if brain_moves.clone().count() + cell.brain.is_some() as usize > 1 {
-                // Brains that enter the same space are combined together.
-                cell.brain = Some(brain::combine(
-                    &mut *rng,
-                    cell.brain.clone().into_iter().chain(brain_moves),
-                ));
-            } else if brain_moves.clone().count() == 1 {
-                let m = brain_moves.next().unwrap();
-                cell.brain = Some(m);
-            }
-
-            // Handle food movement.
-            cell.food += moves.clone().iter().map(|m| m.food).sum::<u32>();
-
-            // Handle mutation.
-            if let Some(ref mut brain) = cell.brain {
-                if rng.sample(unsafe {
-                    match MUTATE_DISTRIBUTION {
-                        Some(v) => v,
-                        None => Bernoulli::new(0.0001).unwrap(),
-                    }
-                }) {
-                    brain.mutate(&mut *rng);
-                }
+        // Settle the offer made last tick, returning what was held back if nobody accepted it.
+        if let Some(offer) = cell.offer.take() {
+            if !arrivals.claim {
+                let (food, money) = offer.escrow();
+                cell.food += food;
+                cell.money += money;
             }
-
-            // Handle spawning.
-            if cell.brain.is_none()
-                && unsafe {
-                    rng.sample(match CELL_SPAWN_DISTRIBUTION {
-                        Some(dist) => dist,
-                        None => Bernoulli::new(0.00003).unwrap(),
-                    })
-                }
+        }
+        // Take what was held back in an offer this cell accepted.
+        cell.food += diff.receive_food;
+        cell.money += diff.receive_money;
+        // Hold back what this cell is offering now. An offer which no longer fits what is left
+        // after eating and spending is never made.
+        if let Some(offer) = diff.offer {
+            let (food, money) = offer.escrow();
+            if let (Some(food), Some(money)) =
+                (cell.food.checked_sub(food), cell.money.checked_sub(money))
             {
-                cell.brain = Some(rng.gen());
-                cell.food += SPAWN_FOOD;
+                cell.food = food;
+                cell.money = money;
+                cell.offer = Some(offer);
             }
-            if cell.ty == CellType::Source {
-                if rng.sample(unsafe {
-                    match CORNACOPIA_FOOD_DISTRIBUTION {
-                        Some(val) => val,
-                        None => Bernoulli::new(0.0).unwrap(),
-                    }
-                }) {
-                    cell.food += unsafe { CORNACOPIA_FOOD_SPAWN };
-                }
-            } else {
-                if rng.sample(unsafe {
-                    match NORMAL_FOOD_DISTRIBUTION {
-                        Some(val) => val,
-                        None => Bernoulli::new(0.01).unwrap(),
-                    }
-                }) {
-                    cell.food += 1;
+        }
+
+        // Handle brain movement.
+        let brains = arrivals.brains.len() + cell.brain.is_some() as usize;
+        if brains > 1 {
+            Tally::add(&tally.died_combining, brains - 1);
+            // Brains that enter the same space are combined together.
+            cell.brain = Some(brain::combine(
+                &mut streams.rng(Draw::Combining, cell.tile),
+                cell.brain.take().into_iter().chain(arrivals.brains),
+            ));
+        } else if let Some(brain) = arrivals.brains.into_iter().next() {
+            cell.brain = Some(brain);
+        }
+
+        // Handle food movement.
+        cell.food += arrivals.food;
+
+        // Handle signal.
+        if let Some(ref mut brain) = cell.brain {
+            // Running the brain costs food for every codon of its last decision, paid once
+            // wherever it went. A fraction of food is paid whole by chance, as signals are.
+            let cost = config.metabolism * brain.life.executed as f64;
+            if cost > 0.0 {
+                let rng = &mut streams.rng(Draw::Metabolism, cell.tile);
+                let cost = cost.floor() as u32 + rng.gen_bool(cost.fract()) as u32;
+                let paid = cost.min(cell.food);
+                cell.food -= paid;
+                Tally::add(&tally.metabolized, paid as usize);
+            }
+            brain.life.executed = 0;
+            cell.signal = brain.signal();
+            // Holding a signal costs food every tick, whichever decision was made. A cost
+            // of a fraction of food is paid whole as often as that fraction of ticks.
+            let cost = config.signal_cost;
+            if cell.signal != 0.0 && cost > 0.0 {
+                let rng = &mut streams.rng(Draw::Signaling, cell.tile);
+                let cost = cost.floor() as u32 + rng.gen_bool(cost.fract()) as u32;
+                let paid = cost.min(cell.food);
+                cell.food -= paid;
+                Tally::add(&tally.signaling, paid as usize);
+            }
+        } else {
+            cell.signal = 0.0;
+        }
+    }
+    if cell.brain.is_some() != occupied {
+        tally.occupancy.flip(cell.tile);
+    }
+}
+
+/// Whether a cell has gone without food for longer than the starvation grace, so that its brain
+/// dies as the world cycles.
+fn starves(config: &SimConfig, cell: &Cell) -> bool {
+    cell.food == 0
+        && cell
+            .brain
+            .as_ref()
+            .map_or(false, |brain| brain.life.hungry >= config.starvation_grace)
+}
+
+/// A distribution which is true with a probability, clamped into 0 to 1 with NaN as never, so
+/// that a parameter out of range cannot panic.
+pub(crate) fn chance(p: f64) -> Bernoulli {
+    let p = if p.is_nan() { 0.0 } else { p.max(0.0).min(1.0) };
+    Bernoulli::new(p).unwrap()
+}
+
+/// Applies a phase to every tile in parallel under the config of the world, along with what its
+/// region overrides, marking the tiles where a brain spawned or went. Each chunk of tiles draws
+/// from a stream of its own, in order, so that the phase comes out the same however the chunks
+/// are spread over threads.
+fn each_tile(
+    grid: &mut LifeContainer,
+    regions: &RegionMap,
+    config: &SimConfig,
+    tally: &Tally,
+    streams: Streams,
+    draw: Draw,
+    phase: fn(&SimConfig, &mut ChaCha8Rng, &mut Cell, Option<&Overrides>),
+) {
+    grid.get_cells_mut()
+        .par_chunks_mut(PHASE_CHUNK)
+        .enumerate()
+        .for_each(|(chunk, cells)| {
+            let rng = &mut streams.rng(draw, chunk);
+            for (offset, cell) in cells.iter_mut().enumerate() {
+                let occupied = cell.brain.is_some();
+                phase(config, rng, cell, regions.at(chunk * PHASE_CHUNK + offset));
+                if cell.brain.is_some() != occupied {
+                    tally.occupancy.flip(cell.tile);
                 }
             }
+        });
+}
 
-            // Handle signal.
-            if let Some(ref mut brain) = cell.brain {
-                cell.signal = brain.signal();
+/// Mutates the brain of a cell by chance, which its region may override.
+fn mutate(config: &SimConfig, rng: &mut ChaCha8Rng, cell: &mut Cell, region: Option<&Overrides>) {
+    if let Some(ref mut brain) = cell.brain {
+        if rng.sample(
+            region
+                .and_then(|region| region.mutate)
+                .unwrap_or(config.mutate),
+        ) {
+            brain.mutate(&mut *rng, &config.mutations, config.codons());
+        }
+    }
+}
+
+/// Spawns a random brain on an empty tile by chance, which its region may override.
+fn spawn(config: &SimConfig, rng: &mut ChaCha8Rng, cell: &mut Cell, region: Option<&Overrides>) {
+    if cell.ty != CellType::Wall
+        && cell.brain.is_none()
+        && rng.sample(
+            region
+                .and_then(|region| region.spawn)
+                .unwrap_or(config.spawn),
+        )
+    {
+        cell.brain = Some(rng.sample(config.codons()));
+        cell.food += SPAWN_FOOD;
+    }
+}
+
+/// Grows food on a tile by chance, which is more plentiful on sources and more so on those of
+/// higher tiers, and which its region may override. Sources of a tier with a schedule yield in
+/// harvests instead.
+fn feed(config: &SimConfig, rng: &mut ChaCha8Rng, cell: &mut Cell, region: Option<&Overrides>) {
+    if let CellType::Source(tier) = cell.ty {
+        let tiers = &config.tiers;
+        let base = region
+            .and_then(|region| region.cornacopia)
+            .unwrap_or(config.cornacopia_chance);
+        let bounty = region
+            .and_then(|region| region.bounty)
+            .unwrap_or(config.cornacopia_bounty);
+        let every = tiers.get(tier).every;
+        if every > 0 {
+            // The tile counts down to its next harvest, and a schedule made shorter takes effect
+            // at once.
+            let left = cell.harvest.min(every - 1);
+            if left == 0 {
+                cell.food += tiers.harvest(tier, base, bounty);
+                cell.harvest = every - 1;
             } else {
-                cell.signal = 0.0;
+                cell.harvest = left - 1;
             }
+        } else if rng.sample(chance(base * tiers.get(tier).chance)) {
+            cell.food += tiers.bounty(tier, bounty);
+        }
+    } else if cell.ty != CellType::Wall {
+        if rng.sample(
+            region
+                .and_then(|region| region.food)
+                .unwrap_or(config.general_food),
+        ) {
+            cell.food += 1;
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// The share of money passed to offspring which goes to the reserve under the config given.
+fn inheritance_tax(rng: &mut impl Rng, config: &SimConfig, passed: u32) -> u32 {
+    let rate = config.inheritance_tax.max(0.0).min(100.0) / 100.0;
+    if passed == 0 || rate == 0.0 {
+        return 0;
+    }
+    // Rounded up by chance, so that even small amounts are taxed at the rate on average.
+    let tax = passed as f64 * rate;
+    (tax.floor() as u32 + rng.gen_bool(tax.fract()) as u32).min(passed)
+}
+
+const NEIGHBOR_INPUTS: usize = 5;
+const SELF_INPUTS: usize = 2;
+/// The food and money each neighbor offers, only sensed while direct exchange is enabled.
+const OFFER_INPUTS: usize = 2;
+const OFFERS_END: usize = (NEIGHBOR_INPUTS + OFFER_INPUTS) * 4 + SELF_INPUTS;
+/// The age and generation of the cell itself, only sensed while life inputs are enabled.
+const LIFE_INPUTS: usize = 2;
+/// How much of its last order the market filled and at what price, only sensed while fill inputs
+/// are enabled.
+const FILL_INPUTS: usize = 2;
+const INPUTS: usize = OFFERS_END + LIFE_INPUTS + FILL_INPUTS;
+/// The age in ticks and the generation at which each life input reaches one half.
+const AGE_SCALE: f64 = 256.0;
+const GENERATION_SCALE: f64 = 16.0;
+
+/// The inputs to the brain of a cell, unrotated, with neighbors in the order gridsim iterates them.
+fn inputs<'a>(
+    config: &SimConfig,
+    cell: &Cell,
+    neighbors: impl Iterator<Item = &'a Cell>,
+) -> ArrayVec<[f64; INPUTS]> {
+    let boolnum = |n| if n { 1.0 } else { 0.0 };
+    let neighbors: ArrayVec<[&Cell; 4]> = neighbors.collect();
+    let mut inputs: ArrayVec<[f64; INPUTS]> = neighbors
+        .iter()
+        .flat_map(|n| {
+            once(boolnum(n.brain.is_some()))
+                .chain(once(boolnum(n.ty == CellType::Wall)))
+                .chain(once(n.food as f64))
+                .chain(once(n.signal))
+                .chain(once(n.money as f64))
+        })
+        .chain(once(cell.food as f64))
+        .chain(once(cell.money as f64))
+        .collect();
+    if config.direct_exchange {
+        let directions = [
+            MooreDirection::Right,
+            MooreDirection::Up,
+            MooreDirection::Left,
+            MooreDirection::Down,
+        ];
+        // Only offers made to this cell are sensed.
+        inputs.extend(neighbors.iter().zip(&directions).flat_map(|(n, &dir)| {
+            let offer = n.offer.filter(|offer| offer.dir == opposite(dir));
+            once(offer.map(|o| o.food as f64).unwrap_or(0.0))
+                .chain(once(offer.map(|o| o.money as f64).unwrap_or(0.0)))
+        }));
+    }
+    if config.sense_life {
+        // Both rise from zero toward one, so they stay on the scale of the other inputs.
+        let squash = |n: f64, scale: f64| n / (n + scale);
+        let (age, generation) = cell.brain.as_ref().map_or((0.0, 0.0), |brain| {
+            (brain.life.ticks as f64, brain.generation as f64)
+        });
+        inputs.push(squash(age, AGE_SCALE));
+        inputs.push(squash(generation, GENERATION_SCALE));
+    }
+    if config.sense_fills {
+        let (filled, price) = cell.brain.as_ref().map_or((0.0, 0.0), |brain| {
+            (brain.life.filled, brain.life.fill_price)
+        });
+        inputs.push(filled);
+        inputs.push(price);
+    }
+    inputs
+}
+
+fn opposite(dir: MooreDirection) -> MooreDirection {
+    dir.turn_counterclockwise().turn_counterclockwise()
+}
+
+/// The indices of the neighbors of a cell in the order gridsim iterates them (right, up, left, down).
+fn neighbor_indices(index: usize, width: usize, height: usize) -> impl Iterator<Item = usize> {
+    use crate::gridgen;
+    let pos = (index / width, index % width);
+    [(0, 1), (-1, 0), (0, -1), (1, 0)]
+        .iter()
+        .map(move |&delta| gridgen::dir(pos, (height, width), delta))
+        .map(move |(y, x)| y * width + x)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CellType {
     Wall,
-    Source,
+    /// A cornacopia, where food grows by the cornacopia chance and bounty as scaled by its tier.
+    Source(Tier),
     Empty,
+    /// Sends whatever is on it to the linked world, if there is one.
+    Portal,
 }
 
 #[derive(Clone, Debug)]
@@ -305,6 +800,17 @@ pub struct Cell {
     pub signal: f64,
     pub brain: Option<Brain>,
     pub trade: Option<Trade>,
+    pub offer: Option<Offer>,
+    /// An order placed from this tile was filled when the market last cleared.
+    pub traded: bool,
+    /// Money the orders placed from this tile brought in when the market last cleared, which is
+    /// not saved.
+    proceeds: u32,
+    /// Ticks left until the cornacopia on this tile next yields, while its tier yields on a
+    /// schedule, which is not saved.
+    harvest: u32,
+    /// Where the tile lies in the grid in row-major order, which picks its random streams.
+    tile: usize,
 }
 
 impl Default for Cell {
@@ -316,10 +822,25 @@ impl Default for Cell {
             signal: 0.0,
             brain: None,
             trade: None,
+            offer: None,
+            traded: false,
+            proceeds: 0,
+            harvest: 0,
+            tile: 0,
         }
     }
 }
 
+impl Cell {
+    /// Takes the money the last clearing brought in which the cell still holds, such as when it
+    /// starves before it can act on it.
+    fn forfeit(&mut self) -> u32 {
+        let proceeds = std::mem::take(&mut self.proceeds).min(self.money);
+        self.money -= proceeds;
+        proceeds
+    }
+}
+
 fn cap_color(n: f32, cap: f32) -> f32 {
     if n > cap {
         cap
@@ -328,24 +849,97 @@ fn cap_color(n: f32, cap: f32) -> f32 {
     }
 }
 
+/// Scales a color from a fifth of its brightness at the bottom percentile to all of it at the top.
+fn brighten(color: Color, percentile: f32) -> Color {
+    let scale = 0.2 + 0.8 * percentile;
+    Color::from_rgb(color.r * scale, color.g * scale, color.b * scale)
+}
+
+/// Red for cells about to starve through yellow to green for those past the horizon.
+fn starvation_color(ticks: u32) -> Color {
+    let safety = ticks.min(STARVATION_HORIZON) as f32 / STARVATION_HORIZON as f32;
+    Color::from_rgb(
+        (2.0 * (1.0 - safety)).min(1.0),
+        (2.0 * safety).min(1.0),
+        0.0,
+    )
+}
+
+/// The fraction of the sorted values which are less than this one.
+fn percentile(sorted: &[u64], value: u64) -> f32 {
+    let below = match sorted.binary_search_by(|&v| if v < value { Less } else { Greater }) {
+        Ok(index) | Err(index) => index,
+    };
+    below as f32 / (sorted.len() - 1).max(1) as f32
+}
+
+/// The closest tile to `from` which is open and has no brain, searching outward across walls and
+/// around the edges of the world, and marking the tiles it visits with `search`.
+fn nearest_free(
+    cells: &[Cell],
+    width: usize,
+    height: usize,
+    from: usize,
+    visited: &mut [u32],
+    search: u32,
+) -> Option<usize> {
+    let mut queue = std::collections::VecDeque::new();
+    visited[from] = search;
+    queue.push_back(from);
+    while let Some(index) = queue.pop_front() {
+        let cell = &cells[index];
+        if cell.ty != CellType::Wall && cell.brain.is_none() {
+            return Some(index);
+        }
+        let (x, y) = (index % width, index / width);
+        let neighbors = [
+            y * width + (x + 1) % width,
+            y * width + (x + width - 1) % width,
+            (y + 1) % height * width + x,
+            (y + height - 1) % height * width + x,
+        ];
+        for &neighbor in &neighbors {
+            if visited[neighbor] != search {
+                visited[neighbor] = search;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    None
+}
+
+/// Darkens a color so that the cells a filter matches stand out.
+fn dim(color: Color) -> Color {
+    Color::from_rgb(color.r * 0.2, color.g * 0.2, color.b * 0.2)
+}
+
 impl Cell {
-    fn color(&self) -> Color {
+    fn color(&self, terrain: &TerrainColors) -> Color {
         match self.ty {
-            CellType::Wall => Color::from_rgb(0.4, 0.0, 0.0),
-            CellType::Empty | CellType::Source => {
+            CellType::Wall => terrain.wall,
+            CellType::Portal if self.brain.is_none() => terrain.portal,
+            CellType::Empty | CellType::Source(_) | CellType::Portal => {
                 if self.brain.is_some() {
                     self.brain.as_ref().unwrap().color()
                 } else {
                     let food_color = cap_color(FOOD_COLOR_MULTIPLIER * self.food as f32, 0.3);
                     let money_color = cap_color(MONEY_COLOR_MULTIPLIER * self.money as f32, 1.0);
+                    let (r, g, b) = match self.ty {
+                        CellType::Source(tier) => {
+                            let tint = terrain.sources[tier.index()];
+                            (tint.r, tint.g, tint.b)
+                        }
+                        _ => (0.0, 0.0, 0.0),
+                    };
                     Color::from_rgb(
-                        money_color,
-                        if food_color > money_color {
+                        (money_color + r).min(1.0),
+                        (if food_color > money_color {
                             food_color
                         } else {
                             money_color
-                        },
-                        money_color,
+                        } + g)
+                            .min(1.0),
+                        (money_color + b).min(1.0),
                     )
                 }
             }
@@ -353,423 +947,2492 @@ impl Cell {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Move {
     food: u32,
     money: u32,
     brain: Option<Brain>,
+    /// The offer the receiving cell made to this one was accepted.
+    claim: bool,
 }
 
-#[derive(Clone, Debug)]
-pub struct Diff {
-    consume: u32,
-    spend: u32,
-    moved: bool,
-    trade: Option<Trade>,
+impl Move {
+    /// A brain moving onto a tile with nothing else, as the benchmarks make them.
+    pub fn carrying(brain: Brain) -> Self {
+        Self {
+            brain: Some(brain),
+            ..Default::default()
+        }
+    }
 }
 
-/// The entrypoint for the grid.
-pub fn run_sim(
-    inbound: usize,
-    outbound: usize,
-    width: usize,
-    height: usize,
-    openness: usize,
-    cornacopia_count_probability: f64,
-) -> (Sender<ToSim>, Receiver<FromSim>, impl Future<Output = ()>) {
-    let (oncoming_tx, mut oncoming) = mpsc::channel(inbound);
-    let (mut outgoing, outgoing_rx) = mpsc::channel(outbound);
+/// Everything the neighbors of a tile moved onto it over a tick.
+#[derive(Debug, Default)]
+pub struct Arrivals {
+    food: u32,
+    money: u32,
+    brains: Vec<Brain>,
+    claim: bool,
+}
 
-    let mut sim = Sim::new(width, height, openness, cornacopia_count_probability);
-    let task = async move {
-        while let Some(oncoming) = oncoming.next().await {
-            match oncoming {
-                ToSim::Tick(times) => {
-                    for _ in 0..times {
-                        sim = block_in_place(move || sim.tick());
-                        outgoing.send(sim.stats()).await.ok();
-                    }
-                    let view = block_in_place(|| sim.view(times));
-                    outgoing.send(FromSim::View(view)).await.ok();
-                }
-                ToSim::SetSpawnChance(new_spawn_chance) => unsafe {
-                    CELL_SPAWN_DISTRIBUTION = Some(Bernoulli::new(new_spawn_chance).unwrap());
-                },
-                ToSim::SetCornacopiaChance(val) => unsafe {
-                    CORNACOPIA_FOOD_DISTRIBUTION = Some(Bernoulli::new(val).unwrap());
-                },
-                ToSim::SetCornacopiaBounty(val) => unsafe {
-                    CORNACOPIA_FOOD_SPAWN = val;
-                },
-                ToSim::SetMutationChance(val) => unsafe {
-                    MUTATE_DISTRIBUTION = Some(Bernoulli::new(val).unwrap());
-                },
-                ToSim::SetGeneralFoodChance(val) => unsafe {
-                    NORMAL_FOOD_DISTRIBUTION = Some(Bernoulli::new(val).unwrap());
-                },
-            }
+impl Arrivals {
+    /// Gathers the moves in one pass which consumes them, since cloning them would clone every
+    /// brain among them.
+    pub fn gather(moves: MooreNeighbors<Move>) -> Self {
+        let mut arrivals = Self::default();
+        for m in moves.iter() {
+            arrivals.food += m.food;
+            arrivals.money += m.money;
+            arrivals.brains.extend(m.brain);
+            arrivals.claim |= m.claim;
         }
-    };
+        arrivals
+    }
 
-    (oncoming_tx, outgoing_rx, task)
+    /// Gathers the moves by cloning them for each thing gathered, as they once were, which is
+    /// only kept to benchmark against.
+    pub fn gather_by_cloning(moves: MooreNeighbors<Move>) -> Self {
+        Self {
+            food: moves.clone().iter().map(|m| m.food).sum(),
+            money: moves.clone().iter().map(|m| m.money).sum(),
+            brains: moves.clone().iter().flat_map(|m| m.brain).collect(),
+            claim: moves.iter().any(|m| m.claim),
+        }
+    }
 }
 
-/// Messages sent to the grid.
-#[derive(Debug)]
-pub enum ToSim {
-    // Populate(evo::CellState),
-    // Unpopulate(evo::CellState),
-    Tick(usize),
-    SetSpawnChance(f64),
-    SetMutationChance(f64),
-    SetGeneralFoodChance(f64),
-    SetCornacopiaBounty(u32),
-    SetCornacopiaChance(f64),
+#[derive(Clone, Debug, Default)]
+pub struct Diff {
+    consume: u32,
+    spend: u32,
+    moved: bool,
+    trade: Option<Trade>,
+    offer: Option<Offer>,
+    receive_food: u32,
+    receive_money: u32,
+    /// What the tick changed in the life of the brain which stayed, if it was stepped.
+    lived: Option<Lived>,
 }
 
-/// Messages sent from the grid.
-#[derive(Debug)]
-pub enum FromSim {
-    View(View),
-    Stats {
-        bid: Option<i32>,
-        ask: Option<i32>,
-        reserve: u32,
-        buy_volume: u32,
-        sell_volume: u32,
-        mean_age: u64,
-        max_age: u64,
-    },
+/// What a tick changes in the life of a brain, which is only written to it once its cell is
+/// updated, since stepping sees every cell as it was.
+#[derive(Copy, Clone, Debug, Default)]
+struct Lived {
+    /// The brain was left out of the tick under approximation.
+    skipped: bool,
+    /// Ticks in a row the brain has now spent at zero food.
+    hungry: u32,
+    /// The decision an external controller made for it.
+    forced: Option<Choice>,
+    /// It divided and its offspring was placed.
+    divided: bool,
 }
 
-/// Contains the data to display the simulation.
-#[derive(Default, Debug)]
-pub struct View {
-    pub colors: Array2<(Color, usize)>,
-    pub cells: usize,
-    pub ticks: usize,
+impl Lived {
+    fn apply(self, life: &mut Life) {
+        if self.skipped {
+            life.last = None;
+            return;
+        }
+        life.hungry = self.hungry;
+        if let Some(choice) = self.forced {
+            life.last = Some(choice);
+        }
+        if self.divided {
+            life.offspring += 1;
+        }
+    }
 }
 
-pub struct Sim {
-    grid: LifeContainer,
-    reserve: u32,
-    last_bid: Option<i32>,
-    last_ask: Option<i32>,
-    buy_volume: u32,
-    sell_volume: u32,
-    mean_age: u64,
-    max_age: u64,
+/// Finish the tape, save the world to a file if one is given, then answer with anything which
+/// failed and end the task.
+pub struct Shutdown {
+    pub autosave: Option<PathBuf>,
+    pub done: std::sync::mpsc::Sender<Vec<String>>,
 }
 
-impl Sim {
-    fn new(
-        width: usize,
-        height: usize,
-        openness: usize,
-        cornacopia_count_probability: f64,
-    ) -> Self {
-        use crate::gridgen;
-        let mut grid = SquareGrid::<Evonomics>::new(width, height);
-        let rng = unsafe { rng() };
-        let open_scale = openness + 1;
-        let (open_width, open_height) = (width / open_scale, height / open_scale);
-        let os = (open_height, open_width);
-        let walls = gridgen::generate_walls(rng, os);
-        let cornacopia_spawn_dist = Bernoulli::new(cornacopia_count_probability).unwrap();
-        for (ix, cell) in grid.get_cells_mut().iter_mut().enumerate() {
-            if rng.sample(cornacopia_spawn_dist) {
-                cell.ty = CellType::Source;
-            }
-            let x = ix % width;
-            let y = ix / width;
-            let ox = x / open_scale;
-            let oy = y / open_scale;
-            if ox >= open_width || oy >= open_height {
-                continue;
-            }
-            let op = (oy, ox);
+/// Shuts a sim down, even while it waits for room to send to a GUI which is itself waiting for
+/// the sim to end. Dropping it ends the sim without saving.
+pub type Stop = oneshot::Sender<Shutdown>;
 
-            let dir = |dy, dx| walls[gridgen::dir(op, os, (dy, dx))];
+/// The channel to the GUI, whose sends give way once the sim is asked to stop.
+struct Outgoing {
+    tx: Sender<FromSim>,
+    stop: oneshot::Receiver<Shutdown>,
+    stopped: bool,
+    /// How to shut down, once asked to.
+    shutdown: Option<Shutdown>,
+}
 
-            if dir(0, 0) {
-                cell.ty = CellType::Wall;
-            } else {
-                cell.money = TILE_MONEY_SPAWN;
-            }
-        }
-        Self {
-            grid: grid,
-            reserve: width as u32 * height as u32 * RESERVE_MULTIPLIER,
+impl Outgoing {
+    /// Takes the stop if it came, or if the GUI dropped it.
+    fn stopping(&mut self, stop: Result<Shutdown, oneshot::Canceled>) {
+        self.stopped = true;
+        self.shutdown = stop.ok();
+    }
 
-            last_bid: None,
-            last_ask: None,
-            buy_volume: 0,
-            sell_volume: 0,
-            mean_age: 0,
-            max_age: 0,
+    /// Sends a message to the GUI, failing if the GUI is gone or the sim is asked to stop first.
+    async fn send(&mut self, message: FromSim) -> Result<(), ()> {
+        if self.stopped {
+            return Err(());
+        }
+        match future::select(&mut self.stop, self.tx.send(message)).await {
+            Either::Left((stop, _)) => {
+                self.stopping(stop);
+                Err(())
+            }
+            Either::Right((sent, _)) => sent.map_err(|_| ()),
         }
     }
 
-    pub fn tick(mut self) -> Self {
-        use std::cmp::Ordering;
-
-        #[derive(PartialEq, Eq)]
-        struct Order {
-            index: usize,
-            rate: i32,
-            food: i32,
+    /// Waits for the next message from the GUI, or nothing once the GUI is gone or the sim is
+    /// asked to stop.
+    async fn recv(&mut self, oncoming: &mut Receiver<ToSim>) -> Option<ToSim> {
+        if self.stopped {
+            return None;
         }
-
-        #[derive(Debug, PartialEq, Eq)]
-        enum Intent {
-            Bid,
-            Ask,
-            Nothing,
+        // The stop comes first, so that it is never missed for the GUI closing its channel
+        // right after.
+        match future::select(&mut self.stop, oncoming.next()).await {
+            Either::Left((stop, _)) => {
+                self.stopping(stop);
+                None
+            }
+            Either::Right((Some(oncoming), _)) => Some(oncoming),
+            Either::Right((None, _)) => {
+                if let Ok(Some(shutdown)) = self.stop.try_recv() {
+                    self.shutdown = Some(shutdown);
+                }
+                self.stopped = true;
+                None
+            }
         }
+    }
+}
+
+/// The entrypoint for the grid.
+pub fn run_sim(
+    inbound: usize,
+    outbound: usize,
+    mut sim: Sim,
+) -> (Sender<ToSim>, Receiver<FromSim>, Stop, impl Future<Output = ()>) {
+    let (oncoming_tx, mut oncoming) = mpsc::channel(inbound);
+    let (outgoing_tx, outgoing_rx) = mpsc::channel(outbound);
+    let (stop, stop_rx) = oneshot::channel();
+    let mut outgoing = Outgoing {
+        tx: outgoing_tx,
+        stop: stop_rx,
+        stopped: false,
+        shutdown: None,
+    };
+
+    // Every burst sends the GUI the lineage events since the last one.
+    sim.lineages.collecting = true;
+    let task = async move {
+        let mut send_stats = true;
+        let mut sampling = Sampling::default();
+        let mut sampler = Sampler::default();
+        let mut settings = ViewSettings::default();
+        let mut timelapse: Option<Timelapse> = None;
+        let mut tape: Option<Tape> = None;
+        // Whether the trades of each tick are sent for the ticker.
+        let mut ticker = false;
+        // The last cell inspected, as long as it lives.
+        let mut following: Option<Follow> = None;
+        // Whether how the followed cell decides is traced codon by codon.
+        let mut tracing_brain = false;
+        let mut last_footprint: Option<Instant> = None;
+        // The frame the window has, which later frames are sent as changes to.
+        let mut shown = View::default();
+        let mut audit: Option<Audit> = None;
+        let mut journal = Journal::default();
+        while let Some(oncoming) = outgoing.recv(&mut oncoming).await {
+            match oncoming {
+                ToSim::Tick(times) => {
+                    // Everything from the burst is sent once it is over, so that the sim never
+                    // waits on the GUI between ticks.
+                    let mut samples = vec![];
+                    let mut halts = vec![];
+                    let mut shortfalls = vec![];
+                    let mut recoveries = vec![];
+                    let mut audited = None;
+                    let mut trace = vec![];
+                    let mut brain_traces = vec![];
+                    let mut lost = false;
+                    let mut tape_stopped = None;
+                    let mut prints = vec![];
+                    for _ in 0..times {
+                        let taped = tape
+                            .as_ref()
+                            .map_or(false, |recording| recording.records(sim.tick + 1));
+                        // The ticker is fed from the same trades the tape records.
+                        sim.market.taping = taped || ticker;
+                        sim = block_in_place(move || sim.tick());
+                        if ticker {
+                            prints.extend_from_slice(&sim.market.tape);
+                            if prints.len() > TICKER_PRINTS {
+                                prints.drain(..prints.len() - TICKER_PRINTS);
+                            }
+                        }
+                        if let (Some(recording), true) = (&mut tape, taped) {
+                            let written = block_in_place(|| {
+                                recording.write(sim.tick, &sim.market.tape)
+                            });
+                            if let Err(e) = written {
+                                tape_stopped = Some(FromSim::TapeStopped {
+                                    path: recording.path().to_owned(),
+                                    trades: recording.trades(),
+                                    error: Some(e.to_string()),
+                                });
+                                tape = None;
+                            }
+                        }
+                        if let Some(halt) = sim.market.halt {
+                            halts.push(halt);
+                        }
+                        if let Some(shortfall) = sim.market.shortfall {
+                            shortfalls.push(shortfall);
+                        }
+                        if let Some(recovered) = sim.recovered {
+                            recoveries.push(recovered);
+                        }
+                        if let Some(ref mut auditing) = audit {
+                            if auditing.add(&sim.market.arrivals) {
+                                audited = audit.take();
+                                sim.market.auditing = false;
+                            }
+                        }
+                        if let Some(ref mut follow) = following {
+                            match follow.find(&sim) {
+                                Some(point) => {
+                                    trace.push(point);
+                                    if tracing_brain {
+                                        brain_traces.extend(follow.take_execution(&mut sim));
+                                    }
+                                }
+                                None => {
+                                    following = None;
+                                    lost = true;
+                                }
+                            }
+                        }
+                        if send_stats {
+                            sampler.add(&sim);
+                            match sampling {
+                                Sampling::Ticks(ticks) if sampler.ticks() >= ticks => {
+                                    samples.push(sampler.take(&sim));
+                                }
+                                _ => {}
+                            }
+                        }
+                        if let Some(ref mut recording) = timelapse {
+                            let saved = block_in_place(|| recording.tick(&sim, &settings))
+                                .map_err(|e| e.to_string());
+                            if let Err(error) = saved {
+                                outgoing
+                                    .send(FromSim::TimelapseStopped {
+                                        frames: recording.frames(),
+                                        error: Some(error),
+                                    })
+                                    .await
+                                    .ok();
+                                timelapse = None;
+                            }
+                        }
+                    }
+                    if let Some(stopped) = tape_stopped {
+                        outgoing.send(stopped).await.ok();
+                    }
+                    if !prints.is_empty() {
+                        outgoing.send(FromSim::Ticker(prints)).await.ok();
+                    }
+                    if sampling == Sampling::Frame && sampler.ticks() > 0 {
+                        samples.push(sampler.take(&sim));
+                    }
+                    for halt in halts {
+                        outgoing.send(FromSim::Halted(halt)).await.ok();
+                    }
+                    for shortfall in shortfalls {
+                        outgoing.send(FromSim::ReserveShort(shortfall)).await.ok();
+                    }
+                    for recovered in recoveries {
+                        outgoing.send(FromSim::Recovered(recovered)).await.ok();
+                    }
+                    if let Some(audited) = audited {
+                        outgoing.send(FromSim::Audited(audited)).await.ok();
+                    }
+                    if !samples.is_empty() {
+                        outgoing.send(FromSim::Stats(samples)).await.ok();
+                    }
+                    if !trace.is_empty() || lost {
+                        outgoing
+                            .send(FromSim::Trace {
+                                points: trace,
+                                lost,
+                            })
+                            .await
+                            .ok();
+                    }
+                    if let Some(expression) = following.and_then(|follow| follow.expression(&sim)) {
+                        outgoing.send(FromSim::Expression(expression)).await.ok();
+                    }
+                    if !brain_traces.is_empty() {
+                        outgoing.send(FromSim::BrainTrace(brain_traces)).await.ok();
+                    }
+                    let frame = block_in_place(|| next_frame(&sim, times, &settings, &mut shown));
+                    if last_footprint.map_or(true, |last| last.elapsed() >= FOOTPRINT_INTERVAL) {
+                        last_footprint = Some(Instant::now());
+                        let footprint = block_in_place(|| sim.footprint(&shown));
+                        outgoing.send(FromSim::Footprint(footprint)).await.ok();
+                    }
+                    outgoing.send(frame).await.ok();
+                    outgoing
+                        .send(FromSim::Lineages {
+                            events: sim.lineage_events(),
+                            legend: sim.lineages.legend(),
+                        })
+                        .await
+                        .ok();
+                }
+                ToSim::SetSpawnChance(new_spawn_chance) => {
+                    sim.params.spawn_chance = new_spawn_chance;
+                    sim.reconfigure();
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetCornacopiaChance(val) => {
+                    sim.params.cornacopia_chance = val;
+                    sim.reconfigure();
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetCornacopiaBounty(val) => {
+                    sim.params.cornacopia_bounty = val;
+                    sim.reconfigure();
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetMutationChance(val) => {
+                    sim.params.mutation_chance = val;
+                    sim.reconfigure();
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetGeneralFoodChance(val) => {
+                    sim.params.general_food_chance = val;
+                    sim.reconfigure();
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetRules(rules) => {
+                    sim.set_rules(rules);
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetMetabolismCost(metabolism) => {
+                    sim.set_rules(Rules {
+                        metabolism,
+                        ..sim.rules
+                    });
+                    outgoing.send(sim.applied()).await.ok();
+                }
+                ToSim::SetRegions(regions) => {
+                    sim.set_regions(regions);
+                    let floorplan = block_in_place(|| sim.floorplan());
+                    outgoing.send(FromSim::Floorplan(floorplan)).await.ok();
+                }
+                ToSim::SetRecovery(recovery) => sim.set_recovery(recovery),
+                ToSim::Reseed(recovery) => {
+                    let rng = &mut sim.hand_rng();
+                    let recovered = sim.recover(rng, recovery);
+                    // Show the new cells right away even while paused.
+                    let frame = block_in_place(|| next_frame(&sim, 0, &settings, &mut shown));
+                    outgoing.send(frame).await.ok();
+                    outgoing.send(FromSim::Reseeded(recovered)).await.ok();
+                }
+                ToSim::Plant {
+                    genome,
+                    population,
+                    pos,
+                } => {
+                    let population = match pos {
+                        Some((x, y)) => sim.plant_at(&genome, x, y) as usize,
+                        None => sim.plant(&genome, population),
+                    };
+                    let frame = block_in_place(|| next_frame(&sim, 0, &settings, &mut shown));
+                    outgoing.send(frame).await.ok();
+                    outgoing.send(FromSim::Planted { population }).await.ok();
+                }
+                ToSim::SetNoise(noise) => sim.set_noise(noise),
+                ToSim::SetMutations(mutations) => sim.set_mutations(mutations),
+                ToSim::Brush {
+                    x,
+                    y,
+                    radius,
+                    brush,
+                    stroke,
+                } => {
+                    if let Some(update) = journal.brush(&mut sim, x, y, radius, brush, stroke) {
+                        outgoing.send(FromSim::Journal(update)).await.ok();
+                    }
+                }
+                ToSim::RegenerateTerrain {
+                    maze,
+                    cornacopia_count_probability,
+                    seed,
+                } => {
+                    let (regenerated, update) = block_in_place(|| {
+                        journal.regenerate(&mut sim, maze, cornacopia_count_probability, seed)
+                    });
+                    // Show the new terrain right away even while paused.
+                    let frame = block_in_place(|| next_frame(&sim, 0, &settings, &mut shown));
+                    outgoing.send(frame).await.ok();
+                    outgoing
+                        .send(FromSim::Regenerated(regenerated))
+                        .await
+                        .ok();
+                    let floorplan = block_in_place(|| sim.floorplan());
+                    outgoing.send(FromSim::Floorplan(floorplan)).await.ok();
+                    outgoing.send(FromSim::Journal(update)).await.ok();
+                }
+                ToSim::Undo | ToSim::Redo => {
+                    let undo = matches!(oncoming, ToSim::Undo);
+                    let update = block_in_place(|| {
+                        if undo {
+                            journal.undo(&mut sim)
+                        } else {
+                            journal.redo(&mut sim)
+                        }
+                    });
+                    if let Some(update) = update {
+                        // Show what was reversed right away even while paused.
+                        let frame =
+                            block_in_place(|| next_frame(&sim, 0, &settings, &mut shown));
+                        outgoing.send(frame).await.ok();
+                        // What was reversed may have been the terrain.
+                        let floorplan = block_in_place(|| sim.floorplan());
+                        outgoing.send(FromSim::Floorplan(floorplan)).await.ok();
+                        outgoing.send(FromSim::Journal(update)).await.ok();
+                    }
+                }
+                ToSim::SubscribeStats(subscribe) => send_stats = subscribe,
+                ToSim::SetSampling(new_sampling) => {
+                    sampling = new_sampling;
+                    sampler = Sampler::default();
+                }
+                ToSim::SetView(new_settings) => {
+                    settings = new_settings;
+                    // Show the change right away even while paused.
+                    let frame = block_in_place(|| next_frame(&sim, 0, &settings, &mut shown));
+                    outgoing.send(frame).await.ok();
+                }
+                ToSim::RequestFullView => {
+                    shown = block_in_place(|| sim.view(0, &settings));
+                    outgoing
+                        .send(FromSim::FullView {
+                            view: shown.clone(),
+                            params: sim.params,
+                            rules: sim.rules,
+                        })
+                        .await
+                        .ok();
+                }
+                ToSim::SetTimelapse(new_timelapse) => {
+                    if let Some(recording) = std::mem::replace(&mut timelapse, new_timelapse) {
+                        outgoing
+                            .send(FromSim::TimelapseStopped {
+                                frames: recording.frames(),
+                                error: None,
+                            })
+                            .await
+                            .ok();
+                    }
+                }
+                ToSim::SetTicker(enabled) => ticker = enabled,
+                ToSim::SetTape(new_tape) => {
+                    sim.market.taping = false;
+                    if let Some(recording) = std::mem::replace(&mut tape, new_tape) {
+                        let path = recording.path().to_owned();
+                        let trades = recording.trades();
+                        let finished = block_in_place(|| recording.finish());
+                        outgoing
+                            .send(FromSim::TapeStopped {
+                                path,
+                                trades,
+                                error: finished.err().map(|e| e.to_string()),
+                            })
+                            .await
+                            .ok();
+                    }
+                }
+                ToSim::NameLineage(lineage, name) => {
+                    sim.lineages.rename(lineage, name);
+                    // Show the new name right away even while paused.
+                    outgoing
+                        .send(FromSim::Lineages {
+                            events: vec![],
+                            legend: sim.lineages.legend(),
+                        })
+                        .await
+                        .ok();
+                }
+                ToSim::Hover(x, y) if x < sim.width() && y < sim.height() => {
+                    let summary = sim.summary(y * sim.width() + x);
+                    outgoing
+                        .send(FromSim::CellSummary { x, y, summary })
+                        .await
+                        .ok();
+                }
+                ToSim::Hover(..) => {}
+                ToSim::Inspect(x, y) if x < sim.width() && y < sim.height() => {
+                    let index = y * sim.width() + x;
+                    let detail = sim.detail(index);
+                    outgoing
+                        .send(FromSim::CellDetail { x, y, detail })
+                        .await
+                        .ok();
+                    // Clicking an empty tile keeps following the last cell.
+                    if let Some(mut follow) = Follow::new(&sim, index) {
+                        if let Some(old) = following {
+                            old.trace_expression(&mut sim, false);
+                            old.trace_execution(&mut sim, false);
+                        }
+                        follow.trace_expression(&mut sim, true);
+                        follow.trace_execution(&mut sim, tracing_brain);
+                        let start = follow.find(&sim);
+                        following = Some(follow);
+                        outgoing
+                            .send(FromSim::Trace {
+                                points: start.into_iter().collect(),
+                                lost: false,
+                            })
+                            .await
+                            .ok();
+                    }
+                }
+                ToSim::Inspect(..) => {}
+                ToSim::StopFollowing => {
+                    if let Some(old) = following.take() {
+                        old.trace_expression(&mut sim, false);
+                        old.trace_execution(&mut sim, false);
+                    }
+                }
+                ToSim::TraceBrain(tracing) => {
+                    tracing_brain = tracing;
+                    if let Some(follow) = following {
+                        follow.trace_execution(&mut sim, tracing);
+                    }
+                }
+                ToSim::Audit(ticks) => {
+                    audit = Some(Audit::new(ticks));
+                    sim.market.auditing = true;
+                }
+                ToSim::TrackAncestry(tracking) => sim.track_ancestry(tracking),
+                ToSim::AskPhylogeny => {
+                    if let Some(phylogeny) = block_in_place(|| sim.phylogeny()) {
+                        outgoing.send(FromSim::Phylogeny(phylogeny)).await.ok();
+                    }
+                }
+                ToSim::ExportAncestry { path, format } => {
+                    let result =
+                        block_in_place(|| sim.export_ancestry(&path, format)).map_err(|e| e.to_string());
+                    outgoing
+                        .send(FromSim::AncestryExported { path, result })
+                        .await
+                        .ok();
+                }
+                ToSim::Save(path) => {
+                    let error = block_in_place(|| sim.save(&path))
+                        .err()
+                        .map(|e| e.to_string());
+                    outgoing.send(FromSim::Saved { path, error }).await.ok();
+                }
+                ToSim::RenderRegion {
+                    path,
+                    rect,
+                    pixels,
+                    overlays,
+                } => {
+                    // A full view for the markings, whatever the grid is shown with.
+                    let full = ViewSettings {
+                        memory: ViewMemory::Full,
+                        ..settings.clone()
+                    };
+                    let error = block_in_place(|| {
+                        Raster::region(&sim.view(0, &full), rect, pixels, &overlays).save(&path)
+                    })
+                    .err()
+                    .map(|e| e.to_string());
+                    outgoing.send(FromSim::Rendered { path, error }).await.ok();
+                }
+            }
+        }
+        // The tape is finished even with nobody left to tell, so that it can be read.
+        let mut errors = vec![];
+        if let Some(recording) = tape {
+            let path = recording.path().to_owned();
+            if let Err(e) = block_in_place(|| recording.finish()) {
+                errors.push(format!("failed to finish {}: {}", path.display(), e));
+            }
+        }
+        if let Some(Shutdown { autosave, done }) = outgoing.shutdown.take() {
+            if let Some(path) = autosave {
+                if let Err(e) = block_in_place(|| sim.save(&path)) {
+                    errors.push(format!("failed to save to {}: {}", path.display(), e));
+                }
+            }
+            // The GUI may have stopped waiting, which is no reason not to end.
+            done.send(errors).ok();
+        }
+    };
+
+    (oncoming_tx, outgoing_rx, stop, task)
+}
+
+/// Messages sent to the grid.
+#[derive(Debug)]
+pub enum ToSim {
+    // Populate(evo::CellState),
+    // Unpopulate(evo::CellState),
+    Tick(usize),
+    SetSpawnChance(f64),
+    SetMutationChance(f64),
+    SetGeneralFoodChance(f64),
+    SetCornacopiaBounty(u32),
+    SetCornacopiaChance(f64),
+    /// Write a snapshot of the world to this file.
+    Save(PathBuf),
+    /// Ask for the genome of the cell at this position, and follow it if it has a brain.
+    Inspect(usize, usize),
+    /// Ask for a glance at what is on the tile at this position, without following anything.
+    Hover(usize, usize),
+    /// Stop recording the trace of the followed cell.
+    StopFollowing,
+    /// Start or stop recording how the followed cell, and any followed after it, decides codon
+    /// by codon.
+    TraceBrain(bool),
+    /// Start or stop tracking the family tree of the brains, which starts over when tracking
+    /// starts again.
+    TrackAncestry(bool),
+    /// Send the largest clades of the family tree, while it is tracked.
+    AskPhylogeny,
+    /// Write the family tree of the brains to this file, while it is tracked.
+    ExportAncestry {
+        path: PathBuf,
+        format: AncestryFormat,
+    },
+    /// Record how fairly orders are matched over this many ticks, replacing any audit underway.
+    Audit(usize),
+    SetRules(Rules),
+    /// Change the food paid for each codon a brain runs, leaving the other rules as they are.
+    SetMetabolismCost(f64),
+    /// Override parameters in parts of the world, replacing any regions it had.
+    SetRegions(Vec<Region>),
+    /// Start the world over whenever every cell died, or stop doing so with `None`.
+    SetRecovery(Option<Recovery>),
+    /// Spawn cells on open tiles right away as the world would recover, such as into a world
+    /// which is empty.
+    Reseed(Recovery),
+    /// Spawn copies of a genome as a lineage of their own, on random open tiles, or as a single
+    /// cell on the tile at a position if it is open.
+    Plant {
+        genome: Genome,
+        population: usize,
+        pos: Option<(usize, usize)>,
+    },
+    /// Blur what brains sense and remember, for the whole world.
+    SetNoise(Noise),
+    /// Change how genomes mutate, for the whole world.
+    SetMutations(Mutations),
+    /// Name a lineage by the id of its founder, or clear its name with `None`.
+    NameLineage(u64, Option<String>),
+    /// Whether stats are sent, which is on until turned off.
+    SubscribeStats(bool),
+    /// How many ticks each sample of stats covers.
+    SetSampling(Sampling),
+    /// Change how the world is drawn.
+    SetView(ViewSettings),
+    /// Ask for a fresh frame along with the parameters and rules in effect, to resync a GUI
+    /// which may have fallen behind the sim.
+    RequestFullView,
+    /// Start saving frames of the world, or stop with `None`.
+    SetTimelapse(Option<Timelapse>),
+    /// Record the trades of the market onto a tape, or stop recording them.
+    SetTape(Option<Tape>),
+    /// Send the most recent trades of every burst of ticks for the ticker, or stop sending them.
+    SetTicker(bool),
+    /// Draw a rectangle of the world into a PNG with each tile this many pixels wide.
+    RenderRegion {
+        path: PathBuf,
+        rect: Rect,
+        pixels: usize,
+        overlays: Overlays,
+    },
+    /// Generate new walls from a seed, and new cornacopias at this density if one is given.
+    RegenerateTerrain {
+        maze: Maze,
+        cornacopia_count_probability: Option<f64>,
+        seed: u64,
+    },
+    /// Drop food or money on the tiles within the radius of a position, as part of the last
+    /// stroke of the brush unless this starts a new one.
+    Brush {
+        x: usize,
+        y: usize,
+        radius: usize,
+        brush: Brush,
+        stroke: bool,
+    },
+    /// Reverse the last manual intervention still in effect.
+    Undo,
+    /// Make the last undone intervention again.
+    Redo,
+}
+
+/// Stats of the world averaged over the ticks of a sample.
+#[derive(Debug)]
+pub struct Stats {
+    /// The tick of the world the sample ends on.
+    pub tick: u64,
+    pub bid: Option<i32>,
+    pub ask: Option<i32>,
+    pub reserve: u32,
+    pub buy_volume: u32,
+    pub sell_volume: u32,
+    pub money_flows: MoneyFlows,
+    pub mean_age: u64,
+    pub max_age: u64,
+    pub params: Params,
+    pub population: usize,
+    pub unique_genomes: usize,
+    /// Genomes merged into an identical one by the last interning pass.
+    pub interned_genomes: usize,
+    /// The mean number of separator codons in the genomes of living cells.
+    pub separators: f64,
+    pub junk: Junk,
+    /// Living cells which made each kind of decision each tick, by `Choice::index`.
+    pub choices: [u32; 6],
+    pub sanitized: Sanitized,
+    pub deaths: DeathCauses,
+    pub selection: Selection,
+    /// Living cells by the ticks they have lived, at the end of the sample.
+    pub ages: AgePyramid,
+    /// The prices the largest lineages traded at.
+    pub lineage_prices: Vec<LineagePrices>,
+    /// The prices every trade of the sample was executed at, `None` if nothing traded.
+    pub candle: Option<Candle>,
+    /// The mean food resting in orders at each price, lowest price first.
+    pub depth: Vec<(i32, f32)>,
+    /// Trading was halted by the circuit breaker for at least one tick of the sample.
+    pub halted: bool,
+    pub orders: OrderCounts,
+    /// Tiles food grows on by the general food chance, which are neither walls nor sources.
+    pub fertile: usize,
+    /// Tiles food grows on by the cornacopia chance, of each tier from the smallest.
+    pub sources: [usize; 3],
+    /// Living cells in each region whose parameters differ, in the order they were given.
+    pub region_populations: Vec<(Area, usize)>,
+    /// Ticks the world has run with only some of the brains stepped, including before it was
+    /// saved, so that a run which was ever approximate is known to be.
+    pub approximated: u64,
+    /// Food paid for holding signals each tick.
+    pub signaling: u32,
+    /// Food which spoiled in the stockpiles of cells each tick.
+    pub spoiled: u32,
+    /// Food paid for the codons brains ran to decide each tick.
+    pub metabolized: u32,
+}
+
+/// Messages sent from the grid.
+#[derive(Debug)]
+pub enum FromSim {
+    View(View),
+    /// The samples taken over a burst of ticks, oldest first.
+    Stats(Vec<Stats>),
+    /// The followed cell at the end of each tick of a burst, or where it was when inspected.
+    Trace {
+        points: Vec<TracePoint>,
+        /// It died or combined with another, so nothing more will be sent.
+        lost: bool,
+    },
+    /// Which genes of the followed cell ran, sent after each burst while it is followed.
+    Expression(Expression),
+    /// How the followed cell came to each decision of a burst, while its brain is traced.
+    BrainTrace(Vec<BrainTrace>),
+    /// The parameters and rules in effect once a change to either was applied, so that the GUI
+    /// can tell when its controls show something else.
+    ParamApplied {
+        params: Params,
+        rules: Rules,
+    },
+    /// What changed since the last frame sent, which the frames after it build on.
+    ViewDelta(ViewDelta),
+    /// A fresh frame and what is in effect, in answer to `ToSim::RequestFullView`.
+    FullView {
+        view: View,
+        params: Params,
+        rules: Rules,
+    },
+    /// What a clicked cell was when it was inspected, if it has a brain.
+    CellDetail {
+        x: usize,
+        y: usize,
+        detail: Option<CellDetail>,
+    },
+    /// What was on a hovered tile, in answer to `ToSim::Hover`.
+    CellSummary {
+        x: usize,
+        y: usize,
+        summary: CellSummary,
+    },
+    /// Lineages which originated or went extinct since the last frame, and the legend.
+    Lineages {
+        events: Vec<LineageEvent>,
+        legend: Vec<LineageSummary>,
+    },
+    /// The circuit breaker halted trading.
+    Halted(Halt),
+    /// The reserve ran short of money for the food offered to it.
+    ReserveShort(Shortfall),
+    /// Every cell died and the world was started over.
+    Recovered(Recovered),
+    /// Cells were spawned as asked, fewer than asked for if there were not enough open tiles.
+    Reseeded(Recovered),
+    Planted { population: usize },
+    /// The window of an audit of the matching of orders is over.
+    Audited(Audit),
+    /// A snapshot was written, or failed to be.
+    Saved {
+        path: PathBuf,
+        error: Option<String>,
+    },
+    /// The largest clades of the family tree, in answer to `ToSim::AskPhylogeny`.
+    Phylogeny(Phylogeny),
+    /// The family tree was written with this many brains in it, or failed to be.
+    AncestryExported {
+        path: PathBuf,
+        result: Result<usize, String>,
+    },
+    /// The timelapse stopped, either when asked to or because a frame failed to save.
+    TimelapseStopped {
+        frames: usize,
+        error: Option<String>,
+    },
+    /// The tape of trades stopped, either when asked to or because writing to it failed.
+    TapeStopped {
+        path: PathBuf,
+        trades: usize,
+        error: Option<String>,
+    },
+    /// The most recent trades of a burst of ticks, oldest first.
+    Ticker(Vec<Print>),
+    /// A render of a region was written, or failed to be.
+    Rendered {
+        path: PathBuf,
+        error: Option<String>,
+    },
+    /// The terrain was regenerated, in answer to `ToSim::RegenerateTerrain`.
+    Regenerated(Regenerated),
+    /// The rooms or regions changed, with the terrain or the regions.
+    Floorplan(Floorplan),
+    /// The journal of manual interventions changed.
+    Journal(JournalUpdate),
+    /// What the world holds and the memory it takes, sent every few seconds while it runs.
+    Footprint(Footprint),
+}
+
+/// The parameters in effect in the sim, as probabilities per tile per tick.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Params {
+    pub spawn_chance: f64,
+    pub mutation_chance: f64,
+    pub general_food_chance: f64,
+    pub cornacopia_chance: f64,
+    pub cornacopia_bounty: u32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        // These match the distributions used before any are set.
+        Self {
+            spawn_chance: 0.00003,
+            mutation_chance: 0.0001,
+            general_food_chance: 0.01,
+            cornacopia_chance: 0.0,
+            cornacopia_bounty: 0,
+        }
+    }
+}
+
+/// A cell with a brain as it was when it was inspected.
+#[derive(Clone, Debug, Serialize)]
+pub struct CellDetail {
+    pub genome: Genome,
+    /// Every codon of the genome in order, marking where execution starts.
+    pub codons: Vec<Locus>,
+    /// The numbers the brain remembers, of which the first is its signal.
+    pub memory: Vec<f64>,
+    pub generation: usize,
+    pub lineage: u64,
+    /// Ticks the brain decided in since it was spawned, divided off or combined.
+    pub ticks: u32,
+    pub food: u32,
+    pub money: u32,
+    pub signal: f64,
+}
+
+/// What is on a tile at a glance, cheap enough to ask for as the cursor moves.
+#[derive(Copy, Clone, Debug)]
+pub struct CellSummary {
+    pub food: u32,
+    pub money: u32,
+    /// The lineage of the brain on the tile, if there is one.
+    pub lineage: Option<u64>,
+}
+
+/// What regenerating the terrain did to the cells living on it.
+#[derive(Copy, Clone, Debug)]
+pub struct Regenerated {
+    /// The seed the new terrain was generated from.
+    pub seed: u64,
+    /// Cells moved off tiles which became walls.
+    pub relocated: usize,
+    /// Cells which died for want of an open tile to move to.
+    pub lost: usize,
+}
+
+/// How brains died over a tick, and how many are living on the starvation grace.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DeathCauses {
+    /// Brains which ran out of food and grace.
+    pub starved: u32,
+    /// Brains which moved or divided into a wall.
+    pub walls: u32,
+    /// Brains lost by combining with others on the same tile, one less than those combined.
+    pub combined: u32,
+    /// Brains at zero food which are still alive on the grace.
+    pub hungry: u32,
+}
+
+/// Switches which change how the world works rather than how often things happen in it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    /// Cells may offer food and money to a single one of their four orthogonal neighbors, which
+    /// may accept it next tick.
+    pub direct_exchange: bool,
+    pub pipeline: Pipeline,
+    pub sanitization: Sanitization,
+    /// Halts trading when the clearing price swings too far, if set.
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Cells sense their own age and generation.
+    pub sense_life: bool,
+    /// Cells sense how much of their last order was filled and at what price.
+    pub sense_fills: bool,
+    /// Ticks a cell may live at zero food before it starves, buying food if it can.
+    pub starvation_grace: u32,
+    /// Money decays back to the reserve every tick, if set.
+    pub demurrage: Option<Demurrage>,
+    /// The share of the money a cell passes to its offspring when it divides which goes to the
+    /// reserve instead, as a percentage.
+    pub inheritance_tax: f64,
+    /// How common each tier of cornacopia is when sources are generated, and what it yields.
+    pub tiers: Tiers,
+    /// The share of the cells with brains stepped each tick, chosen at random, if not every
+    /// cell is. Those stepped pay upkeep for the ticks the others skip, so the world runs faster
+    /// but only approximately.
+    pub approximation: Option<f64>,
+    /// What becomes of what a cell got from a clearing when it starves before it can act again.
+    pub proceeds: Proceeds,
+    /// What the reserve does when it has too little money for the food offered to it.
+    pub exhaustion: Exhaustion,
+    /// Food a cell pays every tick its signal is not zero, so that signals are not free.
+    pub signal_cost: f64,
+    /// Food a cell pays for each codon its brain runs to decide, so that thinking longer is not
+    /// free and lean genomes are favored.
+    pub metabolism: f64,
+    /// The part of the world held still while the rest goes on.
+    pub freeze: Freeze,
+    /// How much food cells can keep before the rest spoils.
+    pub spoilage: Spoilage,
+}
+
+/// A part of the world which can be held still, so that what the other part does to the
+/// population can be told apart from it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freeze {
+    /// Everything goes on.
+    Nothing,
+    /// Orders are never matched, so trading comes to nothing, while cells still move, eat,
+    /// divide, spawn and mutate.
+    Market,
+    /// Cells neither decide nor move, food neither grows nor is eaten, and nothing spawns or
+    /// mutates, while the orders resting in the book keep clearing until they expire.
+    Ecology,
+}
+
+impl Default for Freeze {
+    fn default() -> Self {
+        Freeze::Nothing
+    }
+}
+
+impl Freeze {
+    /// Whether a phase of a tick is held still.
+    fn holds(self, phase: Phase) -> bool {
+        match self {
+            Freeze::Nothing => false,
+            Freeze::Market => phase == Phase::Market,
+            Freeze::Ecology => phase != Phase::Market,
+        }
+    }
+}
+
+/// A part of a tick which is applied to the whole world at once.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Brains decide, then cells move, divide, place orders, and eat.
+    Movement,
+    /// Food grows on tiles.
+    Feeding,
+    /// Orders placed by cells are matched.
+    Market,
+    /// Random brains appear on empty tiles.
+    Spawning,
+    /// Brains mutate.
+    Mutation,
+}
+
+/// The order in which the phases of a tick happen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pipeline(pub [Phase; 5]);
+
+impl Pipeline {
+    /// Orders are matched after cells have eaten, so food bought is only eaten next tick.
+    pub const MARKET_AFTER_CONSUMPTION: Pipeline = Pipeline([
+        Phase::Movement,
+        Phase::Mutation,
+        Phase::Spawning,
+        Phase::Feeding,
+        Phase::Market,
+    ]);
+    /// Orders from last tick are matched before cells eat, so food bought is eaten right away.
+    pub const MARKET_BEFORE_CONSUMPTION: Pipeline = Pipeline([
+        Phase::Market,
+        Phase::Movement,
+        Phase::Mutation,
+        Phase::Spawning,
+        Phase::Feeding,
+    ]);
+
+    /// Makes a pipeline from phases given in order, which must contain each phase exactly once.
+    pub fn new(phases: &[Phase]) -> Option<Self> {
+        let mut pipeline = Self::default();
+        if phases.len() != pipeline.0.len() {
+            return None;
+        }
+        for (slot, &phase) in pipeline.0.iter_mut().zip(phases) {
+            if phases.iter().filter(|&&p| p == phase).count() != 1 {
+                return None;
+            }
+            *slot = phase;
+        }
+        Some(pipeline)
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::MARKET_AFTER_CONSUMPTION
+    }
+}
+
+
+/// What a brush drops onto the tiles under it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Brush {
+    Food,
+    /// Money is paid out of the reserve, so nothing is dropped once it runs dry.
+    Money,
+}
+
+/// What living cells are colored by.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ViewMode {
+    /// The hue of their lineage.
+    Species,
+    /// The hue of their lineage, brighter the higher the percentile of their generation.
+    Age,
+    /// The hue of their lineage, brighter the higher the percentile of their food and money.
+    Wealth,
+    /// From red to green by how many ticks their food lasts at upkeep alone.
+    Starvation,
+}
+
+impl Default for ViewMode {
+    fn default() -> Self {
+        ViewMode::Species
+    }
+}
+
+/// How much of each tile a view holds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ViewMemory {
+    /// Compact for worlds larger than `COMPACT_VIEW_AREA` tiles, and full otherwise.
+    Auto,
+    Full,
+    Compact,
+}
+
+impl Default for ViewMemory {
+    fn default() -> Self {
+        ViewMemory::Auto
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ViewSettings {
+    pub mode: ViewMode,
+    pub memory: ViewMemory,
+    /// Dims every cell the filter does not match.
+    pub filter: Option<Filter>,
+    pub terrain: TerrainColors,
+}
+
+/// The colors tiles are drawn in by what they are, which a theme can change.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TerrainColors {
+    pub wall: Color,
+    /// Portals with no cell on them.
+    pub portal: Color,
+    /// Added to the colors of the food and money on the cornacopias of each tier, from the
+    /// smallest.
+    pub sources: [Color; 3],
+}
+
+impl Default for TerrainColors {
+    fn default() -> Self {
+        Self {
+            wall: Color::from_rgb(0.4, 0.0, 0.0),
+            portal: Color::from_rgb(0.5, 0.0, 0.8),
+            sources: [
+                Color::from_rgb(0.0, 0.05, 0.3),
+                Color::from_rgb(0.35, 0.15, 0.0),
+                Color::from_rgb(0.4, 0.4, 0.4),
+            ],
+        }
+    }
+}
+
+/// The colors of every tile, by row then column.
+#[derive(Clone, Debug)]
+pub enum ViewColors {
+    /// Each color along with the generation of the cell, for the ancestry markings.
+    Full(Array2<(Color, usize)>),
+    /// Each color alone as red, green and blue bytes, an eighth of the size, for large worlds.
+    Compact(Array2<[u8; 3]>),
+}
+
+impl Default for ViewColors {
+    fn default() -> Self {
+        ViewColors::Compact(Array2::default((0, 0)))
+    }
+}
+
+impl ViewColors {
+    /// The rows and columns.
+    pub fn dim(&self) -> (usize, usize) {
+        match self {
+            ViewColors::Full(colors) => colors.dim(),
+            ViewColors::Compact(colors) => colors.dim(),
+        }
+    }
+
+    pub fn color(&self, y: usize, x: usize) -> Color {
+        match self {
+            ViewColors::Full(colors) => colors[(y, x)].0,
+            ViewColors::Compact(colors) => {
+                let [r, g, b] = colors[(y, x)];
+                Color::from_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+            }
+        }
+    }
+}
+
+/// Contains the data to display the simulation.
+#[derive(Clone, Default, Debug)]
+pub struct View {
+    pub colors: ViewColors,
+    pub cells: usize,
+    /// Cells matched by the filter, if one is set.
+    pub matches: Option<usize>,
+    pub ticks: usize,
+}
+
+impl View {
+    /// Brings the view up to the frame after it.
+    pub fn apply(&mut self, delta: ViewDelta) {
+        match (&mut self.colors, delta.changes) {
+            (ViewColors::Full(colors), ViewChanges::Full(changes)) => {
+                let colors = colors.as_slice_mut().unwrap();
+                for (index, color) in changes {
+                    if let Some(old) = colors.get_mut(index) {
+                        *old = color;
+                    }
+                }
+            }
+            (ViewColors::Compact(colors), ViewChanges::Compact(changes)) => {
+                let colors = colors.as_slice_mut().unwrap();
+                for (index, color) in changes {
+                    if let Some(old) = colors.get_mut(index) {
+                        *old = color;
+                    }
+                }
+            }
+            // A delta only ever follows a frame of the same world kept the same way, so one which
+            // does not fit is left out rather than drawn over the wrong tiles.
+            _ => {}
+        }
+        self.cells = delta.cells;
+        self.matches = delta.matches;
+        self.ticks = delta.ticks;
+    }
+}
+
+/// The tiles of a view which changed, by their index in row-major order, with their new colors
+/// as the view keeps them.
+#[derive(Debug)]
+pub enum ViewChanges {
+    Full(Vec<(usize, (Color, usize))>),
+    Compact(Vec<(usize, [u8; 3])>),
+}
+
+impl ViewChanges {
+    fn len(&self) -> usize {
+        match self {
+            ViewChanges::Full(changes) => changes.len(),
+            ViewChanges::Compact(changes) => changes.len(),
+        }
+    }
+}
+
+/// A frame sent as what changed since the frame before it, so that a large world in which few
+/// tiles changed costs little to send and to take in.
+#[derive(Debug)]
+pub struct ViewDelta {
+    pub changes: ViewChanges,
+    pub cells: usize,
+    /// Cells matched by the filter, if one is set.
+    pub matches: Option<usize>,
+    pub ticks: usize,
+}
+
+impl ViewDelta {
+    /// Folds the delta of the frame after this one in, so that applying this does both.
+    pub fn then(&mut self, later: ViewDelta) {
+        match (&mut self.changes, later.changes) {
+            (ViewChanges::Full(changes), ViewChanges::Full(later)) => changes.extend(later),
+            (ViewChanges::Compact(changes), ViewChanges::Compact(later)) => {
+                changes.extend(later)
+            }
+            _ => {}
+        }
+        self.cells = later.cells;
+        self.matches = later.matches;
+        self.ticks += later.ticks;
+    }
+}
+
+pub struct Sim {
+    grid: LifeContainer,
+    masks: Masks,
+    lineages: Lineages,
+    /// The family tree of the brains, while it is tracked, which is not saved.
+    ancestry: Option<Ancestry>,
+    /// The indices of the portal tiles.
+    portals: Vec<usize>,
+    portal: Option<PortalLink>,
+    market: Market,
+    mean_age: u64,
+    max_age: u64,
+    population: usize,
+    unique_genomes: usize,
+    interned_genomes: usize,
+    /// Numbers replaced by sanitization or trade clamping last tick.
+    sanitized: Sanitized,
+    /// What the cells counted while they were stepped and updated this tick, until it is taken.
+    tally: Arc<Tally>,
+    /// Codons of the sampled decisions of last tick.
+    executed: brain::Executed,
+    /// Living cells which made each kind of decision last tick, by `Choice::index`.
+    choices: [u32; 6],
+    /// How brains died last tick.
+    death_causes: DeathCauses,
+    /// Food paid for holding signals last tick.
+    signaling: u32,
+    /// Food which spoiled last tick.
+    spoiled: u32,
+    /// Food paid for running brains last tick.
+    metabolized: u32,
+    deaths: Deaths,
+    tick: u64,
+    /// What the random streams of every tick are drawn from.
+    seed: u64,
+    /// Times cells were spawned by hand, so that each time draws numbers of its own, which is
+    /// not saved.
+    hand_spawns: usize,
+    /// Times numbers were drawn for a controller, counted the same way.
+    controls: usize,
+    /// Ticks run with only some of the brains stepped, which marks the run as approximate.
+    approximated: u64,
+    params: Params,
+    rules: Rules,
+    /// Noise in what brains sense and remember, which is not saved.
+    noise: Noise,
+    /// The chance of each kind of mutation, which is not saved either.
+    mutations: Mutations,
+    /// What cells are stepped under, made from the parameters, rules, noise and mutations.
+    config: Arc<SimConfig>,
+    /// Where the parameters differ from `params`.
+    regions: RegionMap,
+    /// Food growing or draining along the edges, which is not saved.
+    edges: Option<EdgeMap>,
+    /// What the world is started over with once every cell died, which is not saved.
+    recovery: Option<Recovery>,
+    /// The world was started over this tick.
+    recovered: Option<Recovered>,
+    control: Option<Arc<ControlLink>>,
+    /// Whether a cell was possessed last tick, so that its death can be reported.
+    possessing: bool,
+}
+
+impl Sim {
+    /// A world on a new layout whose cornacopias are all of the middle tier.
+    pub fn new(
+        width: usize,
+        height: usize,
+        maze: Maze,
+        cornacopia_count_probability: f64,
+        control: Option<Arc<ControlLink>>,
+    ) -> Self {
+        let seed = unsafe { rng() }.gen();
+        Self::from_layout(
+            &Layout::generate(
+                width,
+                height,
+                maze,
+                cornacopia_count_probability,
+                &Tiers::default(),
+                seed,
+            ),
+            control,
+        )
+    }
+
+    /// Starts a world on a layout, with money on every open tile of the maze.
+    pub fn from_layout(layout: &Layout, control: Option<Arc<ControlLink>>) -> Self {
+        let (width, height) = (layout.width(), layout.height());
+        let mut grid = SquareGrid::<Evonomics>::new(width, height);
+        for (ix, (cell, &ty)) in grid
+            .get_cells_mut()
+            .iter_mut()
+            .zip(layout.tiles())
+            .enumerate()
+        {
+            cell.ty = ty;
+            cell.tile = ix;
+            if ty != CellType::Wall && layout.in_maze(ix) {
+                cell.money = TILE_MONEY_SPAWN;
+            }
+        }
+        Self {
+            masks: Masks::new(grid.get_cells()),
+            lineages: Lineages::default(),
+            ancestry: None,
+            portals: vec![],
+            portal: None,
+            grid: grid,
+            market: Market::new(width as u32 * height as u32 * RESERVE_MULTIPLIER),
+            mean_age: 0,
+            max_age: 0,
+            population: 0,
+            unique_genomes: 0,
+            interned_genomes: 0,
+            sanitized: Sanitized::default(),
+            tally: Arc::new(Tally::new(width * height)),
+            executed: Default::default(),
+            choices: [0; 6],
+            death_causes: DeathCauses::default(),
+            signaling: 0,
+            spoiled: 0,
+            metabolized: 0,
+            deaths: Deaths::default(),
+            tick: 0,
+            seed: unsafe { rng() }.gen(),
+            hand_spawns: 0,
+            controls: 0,
+            approximated: 0,
+            params: Params::default(),
+            rules: Rules::default(),
+            noise: Noise::default(),
+            mutations: Mutations::default(),
+            config: Arc::new(SimConfig::default()),
+            regions: RegionMap::default(),
+            edges: None,
+            recovery: None,
+            recovered: None,
+            control,
+            possessing: false,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.get_width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.get_height()
+    }
 
-        impl Order {
-            fn intent(&self) -> Intent {
-                if self.food < 0 {
-                    Intent::Bid
-                } else if self.food > 0 {
-                    Intent::Ask
-                } else {
-                    Intent::Nothing
-                }
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// The optional mechanics the world runs with by its rules.
+    pub fn features(&self) -> FeatureSet {
+        FeatureSet::of(&self.rules)
+    }
+
+    /// The lineage events since they were last taken, with the features in effect recorded in
+    /// the postmortems among them.
+    fn lineage_events(&mut self) -> Vec<LineageEvent> {
+        let features = self.features();
+        let mut events = self.lineages.take_events();
+        for event in &mut events {
+            if let LineageEvent::Extinct {
+                postmortem: Some(ref mut postmortem),
+                ..
+            } = event
+            {
+                postmortem.features = features;
             }
         }
+        events
+    }
+
+    /// What the random streams of every tick are drawn from, so that the same world on the same
+    /// seed runs the same way.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Makes what cells are stepped under anew once the parameters, rules, noise or mutations
+    /// changed, which takes effect from the next tick.
+    fn reconfigure(&mut self) {
+        self.config = Arc::new(SimConfig::new(
+            &self.params,
+            &self.rules,
+            &self.noise,
+            self.mutations,
+        ));
+    }
+
+    /// The echo of the parameters and rules now in effect.
+    fn applied(&self) -> FromSim {
+        FromSim::ParamApplied {
+            params: self.params,
+            rules: self.rules,
+        }
+    }
+
+    pub fn masks(&self) -> &Masks {
+        &self.masks
+    }
+
+    /// The market as it was left by the last tick.
+    pub fn market(&self) -> &Market {
+        &self.market
+    }
+
+    /// Living cells at the end of the last tick.
+    pub fn population(&self) -> usize {
+        self.population
+    }
 
-        impl PartialOrd for Order {
-            fn partial_cmp(&self, other: &Order) -> Option<Ordering> {
-                Some(self.cmp(other))
+    /// Drops food or money on every open tile within the radius of a position, returning how
+    /// much landed on each tile.
+    pub fn brush(&mut self, x: usize, y: usize, radius: usize, brush: Brush) -> Vec<(usize, u32)> {
+        let width = self.grid.get_width();
+        let height = self.grid.get_height();
+        let cells = self.grid.get_cells_mut();
+        let (x, y, r) = (x as isize, y as isize, radius as isize);
+        let mut dropped = vec![];
+        for ty in (y - r).max(0)..=(y + r).min(height as isize - 1) {
+            for tx in (x - r).max(0)..=(x + r).min(width as isize - 1) {
+                if (tx - x).pow(2) + (ty - y).pow(2) > r * r {
+                    continue;
+                }
+                let index = ty as usize * width + tx as usize;
+                let cell = &mut cells[index];
+                if cell.ty == CellType::Wall {
+                    continue;
+                }
+                let amount = match brush {
+                    Brush::Food => BRUSH_AMOUNT,
+                    Brush::Money => BRUSH_AMOUNT.min(self.market.reserve),
+                };
+                match brush {
+                    Brush::Food => cell.food += amount,
+                    Brush::Money => {
+                        self.market.reserve -= amount;
+                        cell.money += amount;
+                    }
+                }
+                if amount > 0 {
+                    dropped.push((index, amount));
+                }
             }
         }
+        dropped
+    }
 
-        impl Ord for Order {
-            fn cmp(&self, other: &Order) -> Ordering {
-                self.rate.cmp(&other.rate)
+    /// Drops food or money onto tiles again after it was taken back, skipping tiles which became
+    /// walls since, and returns how much landed on each.
+    pub fn drop_on(&mut self, brush: Brush, amounts: &FxHashMap<usize, u32>) -> FxHashMap<usize, u32> {
+        let cells = self.grid.get_cells_mut();
+        let mut dropped = FxHashMap::default();
+        for (&index, &amount) in amounts {
+            let cell = &mut cells[index];
+            if cell.ty == CellType::Wall {
+                continue;
             }
+            let amount = match brush {
+                Brush::Food => {
+                    cell.food += amount;
+                    amount
+                }
+                Brush::Money => {
+                    let amount = amount.min(self.market.reserve);
+                    self.market.reserve -= amount;
+                    cell.money += amount;
+                    amount
+                }
+            };
+            dropped.insert(index, amount);
+        }
+        dropped
+    }
+
+    /// Takes food or money back off tiles, as much of each amount as is still there, returning
+    /// money to the reserve, and returns how much was taken from each.
+    pub fn take_back(&mut self, brush: Brush, amounts: &FxHashMap<usize, u32>) -> FxHashMap<usize, u32> {
+        let cells = self.grid.get_cells_mut();
+        let mut taken = FxHashMap::default();
+        for (&index, &amount) in amounts {
+            let cell = &mut cells[index];
+            let amount = match brush {
+                Brush::Food => {
+                    let amount = amount.min(cell.food);
+                    cell.food -= amount;
+                    amount
+                }
+                Brush::Money => {
+                    let amount = amount.min(cell.money);
+                    cell.money -= amount;
+                    self.market.reserve += amount;
+                    amount
+                }
+            };
+            taken.insert(index, amount);
+        }
+        taken
+    }
+
+    /// The type of every tile in row-major order.
+    pub fn tiles(&self) -> Vec<CellType> {
+        self.grid.get_cells().iter().map(|cell| cell.ty).collect()
+    }
+
+    /// The rooms the walls divide the world into and the regions it has, as they are now.
+    pub fn floorplan(&self) -> Floorplan {
+        let (width, height) = (self.width(), self.height());
+        let tiles = self.tiles();
+        let (rooms, _) = layout::label_rooms(&tiles, width, height);
+        Floorplan {
+            width,
+            height,
+            tiles,
+            rooms,
+            regions: self.regions.tiles().to_vec(),
+            region_names: self
+                .regions
+                .regions()
+                .iter()
+                .enumerate()
+                .map(|(index, region)| format!("{} {}", index + 1, region.area))
+                .collect(),
         }
+    }
 
-        // Cycle the grid.
-        self.grid.cycle();
-        // Extract all trades.
-        let mut orders: Vec<Order> = self
+    /// Replaces the walls with those of a new layout, and the cornacopias too if a density is
+    /// given. Portals stay where they are. Cells left on a new wall move as `retile` moves them.
+    pub fn regenerate(
+        &mut self,
+        maze: Maze,
+        cornacopia_count_probability: Option<f64>,
+        seed: u64,
+    ) -> Regenerated {
+        let (width, height) = (self.grid.get_width(), self.grid.get_height());
+        let layout = Layout::generate(
+            width,
+            height,
+            maze,
+            cornacopia_count_probability.unwrap_or(0.0),
+            &self.rules.tiers,
+            seed,
+        );
+        let tiles: Vec<CellType> = self
             .grid
-            .get_cells_mut()
-            .iter_mut()
-            .enumerate()
-            .filter_map(|(ix, cell)| cell.trade.take().map(|trade| (ix, trade)))
-            .map(|(index, Trade { rate, food })| Order { index, rate, food })
+            .get_cells()
+            .iter()
+            .zip(layout.tiles())
+            .map(|(cell, &ty)| match (cell.ty, ty) {
+                (CellType::Portal, _) => CellType::Portal,
+                (_, CellType::Wall) => CellType::Wall,
+                (CellType::Source(tier), _) if cornacopia_count_probability.is_none() => {
+                    CellType::Source(tier)
+                }
+                (_, _) if cornacopia_count_probability.is_none() => CellType::Empty,
+                (_, ty) => ty,
+            })
             .collect();
-        // Put the trades into a random order.
-        orders.shuffle(unsafe { rng() });
-
-        // Reset buy and sell volume.
-        self.buy_volume = 0;
-        self.sell_volume = 0;
-        let mut bids: MinMaxHeap<Order> = MinMaxHeap::new();
-        let mut asks: MinMaxHeap<Order> = MinMaxHeap::new();
-        let fulfill = |sim: &mut Self, new: &mut Order, existing: &mut Order| {
-            let rate = existing.rate;
-            let num = std::cmp::min(new.food.abs(), existing.food.abs());
-            {
-                let new_cell = &mut sim.grid.get_cells_mut()[new.index];
-                new_cell.money = (new_cell.money as i32 + rate * num * new.food.signum()) as u32;
-                new_cell.food = (new_cell.food as i32 - num * new.food.signum()) as u32;
-                new.food -= new.food.signum() * num;
-            }
-            {
-                let existing_cell = &mut sim.grid.get_cells_mut()[existing.index];
-                existing_cell.money =
-                    (existing_cell.money as i32 + rate * num * existing.food.signum()) as u32;
-                existing_cell.food =
-                    (existing_cell.food as i32 - num * existing.food.signum()) as u32;
-                existing.food -= existing.food.signum() * num;
-            }
-            sim.buy_volume += num as u32;
-            sim.sell_volume += num as u32;
-        };
-        // Allows an ask order to be fulfilled by the reserve at a rate of one money per food.
-        let fulfill_reserve = |sim: &mut Self, order: &mut Order| {
-            let num = std::cmp::min(order.food, sim.reserve as i32);
-            {
-                let cell = &mut sim.grid.get_cells_mut()[order.index];
-                cell.money = (cell.money as i32 + num * order.food.signum()) as u32;
-                cell.food = (cell.food as i32 - num * order.food.signum()) as u32;
-                order.food -= order.food.signum() * num;
+        let (relocated, lost) = self.retile(&tiles);
+        Regenerated {
+            seed,
+            relocated,
+            lost,
+        }
+    }
+
+    /// Gives every tile a new type, in row-major order. Cells left on a new wall move with their
+    /// food, money and order to the nearest open tile nobody lives on, and die only if there is
+    /// none, while whatever lay on empty tiles that became walls is lost. Returns how many cells
+    /// were moved and how many died.
+    pub fn retile(&mut self, tiles: &[CellType]) -> (usize, usize) {
+        let (width, height) = (self.grid.get_width(), self.grid.get_height());
+        let cells = self.grid.get_cells_mut();
+        let mut displaced = vec![];
+        for (index, (cell, &ty)) in cells.iter_mut().zip(tiles).enumerate() {
+            if ty == CellType::Wall && cell.ty != CellType::Wall && cell.brain.is_some() {
+                // Nobody is left to accept an offer from a wall, so it is taken back.
+                if let Some(offer) = cell.offer.take() {
+                    let (food, money) = offer.escrow();
+                    cell.food += food;
+                    cell.money += money;
+                }
+                displaced.push(index);
             }
-            sim.reserve -= num as u32;
-            sim.sell_volume += num as u32;
-        };
-        // Allows a bid order to buy food from the reserve at one money per food.
-        let food_reserve = |sim: &mut Self, order: &mut Order| {
-            // We will take as much as there is in the order.
-            let num = -order.food;
-            {
-                let cell = &mut sim.grid.get_cells_mut()[order.index];
-                cell.money = (cell.money as i32 + num * order.food.signum()) as u32;
-                cell.food = (cell.food as i32 - num * order.food.signum()) as u32;
-                order.food -= order.food.signum() * num;
+            cell.ty = ty;
+        }
+
+        let (mut relocated, mut lost) = (0, 0);
+        // The search which last visited each tile, so that it need not be cleared between them.
+        let mut visited = vec![0; cells.len()];
+        for (search, &from) in (1..).zip(&displaced) {
+            match nearest_free(cells, width, height, from, &mut visited, search) {
+                Some(to) => {
+                    let (food, money) = (cells[from].food, cells[from].money);
+                    cells[from].food = 0;
+                    cells[from].money = 0;
+                    cells[to].food += food;
+                    cells[to].money += money;
+                    cells[to].brain = cells[from].brain.take();
+                    cells[to].trade = cells[from].trade.take();
+                    relocated += 1;
+                }
+                None => {
+                    cells[from].brain = None;
+                    cells[from].trade = None;
+                    lost += 1;
+                }
             }
-            sim.reserve += num as u32;
-            sim.buy_volume += num as u32;
-        };
-        for mut order in orders {
-            let intent = order.intent();
-
-            match intent {
-                Intent::Bid => {
-                    // Keep resolving the bid with asks until the order runs out or the asks are too high.
-                    loop {
-                        if let Some(mut ask) = asks.pop_min() {
-                            if ask.rate > order.rate {
-                                // The best asking price was higher than our bid, so just push the bid to the bids.
-                                if order.food != 0 {
-                                    bids.push(order);
-                                }
-                                break;
-                            } else {
-                                // Fulfill as much as possible on both ends.
-                                fulfill(&mut self, &mut order, &mut ask);
-
-                                // If the ask is not complete, we must return it to the asks.
-                                if ask.food != 0 {
-                                    asks.push(ask);
-                                }
+        }
+        self.masks = Masks::new(self.grid.get_cells());
+        self.population = self.masks.occupied.count();
+        // Nothing was traded in moving cells off the new walls.
+        self.lineages
+            .update(self.grid.get_cells(), &self.masks.occupied, self.tick, &[]);
+        (relocated, lost)
+    }
 
-                                // If the order is complete, we can break from this loop.
-                                if order.food == 0 {
-                                    break;
-                                }
-                            }
-                        } else {
-                            if REPO {
-                                // Only repo the money if there are no other ask offers out there.
-                                if order.rate >= 1 {
-                                    food_reserve(&mut self, &mut order);
+    /// Turns random open tiles into portals to the world at the other end of the link.
+    pub fn open_portals(&mut self, link: PortalLink, count: usize) {
+        let rng = unsafe { rng() };
+        let cells = self.grid.get_cells_mut();
+        let open: Vec<usize> = (0..cells.len())
+            .filter(|&ix| cells[ix].ty == CellType::Empty)
+            .collect();
+        for &index in open.choose_multiple(rng, count) {
+            cells[index].ty = CellType::Portal;
+            self.portals.push(index);
+        }
+        self.portal = Some(link);
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+        self.reconfigure();
+    }
+
+    pub fn set_params(&mut self, params: Params) {
+        self.params = params;
+        self.reconfigure();
+    }
+
+    /// Overrides parameters in parts of the world, replacing any regions it had.
+    pub fn set_regions(&mut self, regions: Vec<Region>) {
+        self.regions = RegionMap::new(regions, self.width(), self.height());
+    }
+
+    /// Makes food grow or drain along the edges, replacing whatever the edges did before.
+    pub fn set_edges(&mut self, edges: Edges) {
+        self.edges = EdgeMap::new(edges, self.width(), self.height());
+    }
+
+    /// Starts the world over whenever every cell died, or leaves it empty with `None`.
+    pub fn set_recovery(&mut self, recovery: Option<Recovery>) {
+        self.recovery = recovery;
+    }
+
+    /// Adds noise to what brains sense and makes them forget, which is not saved with the world.
+    pub fn set_noise(&mut self, noise: Noise) {
+        self.noise = noise;
+        self.reconfigure();
+    }
+
+    /// Sets the chance of each kind of mutation, which is not saved with the world either.
+    pub fn set_mutations(&mut self, mutations: Mutations) {
+        self.mutations = mutations;
+        self.reconfigure();
+    }
+
+    pub fn tick(mut self) -> Self {
+        // Let an external controller decide for its cell.
+        self.control();
+        // Money left by cells which starved right after trading, owed to the reserve.
+        let mut forfeited = 0;
+        let streams = Streams::new(self.seed, self.tick);
+        for &phase in &self.rules.pipeline.0 {
+            if self.rules.freeze.holds(phase) {
+                // A held market still starts its tick over, so that stale prices are not
+                // reported as new ones.
+                if phase == Phase::Market {
+                    self.market.pause(self.grid.get_cells_mut());
+                }
+                continue;
+            }
+            match phase {
+                Phase::Movement => {
+                    // Brains without food or grace starve as the world cycles.
+                    let forfeit = self.rules.proceeds == Proceeds::Reserve;
+                    let cells = self.grid.get_cells_mut();
+                    self.death_causes.starved = 0;
+                    for index in self.masks.occupied.ones() {
+                        match cells[index].brain {
+                            Some(ref brain) if starves(&self.config, &cells[index]) => {
+                                self.deaths.record(brain);
+                                self.lineages.starved(brain.lineage);
+                                self.death_causes.starved += 1;
+                                // The last clearing is the only thing the cell did since it
+                                // last decided, so only what it brought in is owed.
+                                if forfeit {
+                                    forfeited += cells[index].forfeit();
                                 }
                             }
-                            // There were no asks, so push our bid.
-                            if order.food != 0 {
-                                bids.push(order);
-                            }
-                            break;
+                            _ => {}
                         }
                     }
+                    // Cells are stepped under this world's config, which holds off any other
+                    // world from stepping until they are done.
+                    let _installed = config::install(&self.config, &self.tally, streams);
+                    self.grid.cycle()
                 }
-                Intent::Ask => {
-                    // Keep resolving the ask with bids until the order runs out or the bids are too low.
-                    loop {
-                        if let Some(mut bid) = bids.pop_max() {
-                            if bid.rate < order.rate {
-                                // The best bid price was lower than our ask, so just push the ask to the asks.
-                                // Try to sell to the reserve.
-                                if order.rate <= 1 {
-                                    fulfill_reserve(&mut self, &mut order);
-                                }
-                                // There were no bids, so push our ask.
-                                if order.food != 0 {
-                                    asks.push(order);
-                                }
-                                break;
-                            } else {
-                                // If the reserve provides a better deal, then use the reserve.
-                                if bid.rate < 1 {
-                                    fulfill_reserve(&mut self, &mut order);
-                                }
-                                // Fulfill as much as possible on both ends.
-                                fulfill(&mut self, &mut order, &mut bid);
+                Phase::Feeding => {
+                    each_tile(
+                        &mut self.grid,
+                        &self.regions,
+                        &self.config,
+                        &self.tally,
+                        streams,
+                        Draw::Feeding,
+                        feed,
+                    );
+                    if let Some(ref edges) = self.edges {
+                        edges.apply(
+                            &mut streams.rng(Draw::Edges, 0),
+                            self.grid.get_cells_mut(),
+                        );
+                    }
+                }
+                // Match all trades placed by cells.
+                Phase::Market => self.market.clear(
+                    &mut streams.rng(Draw::Market, 0),
+                    self.grid.get_cells_mut(),
+                    self.rules.circuit_breaker,
+                    self.rules.proceeds,
+                    self.rules.exhaustion,
+                    self.config.starvation_grace,
+                ),
+                Phase::Spawning => each_tile(
+                    &mut self.grid,
+                    &self.regions,
+                    &self.config,
+                    &self.tally,
+                    streams,
+                    Draw::Spawning,
+                    spawn,
+                ),
+                Phase::Mutation => each_tile(
+                    &mut self.grid,
+                    &self.regions,
+                    &self.config,
+                    &self.tally,
+                    streams,
+                    Draw::Mutation,
+                    mutate,
+                ),
+            }
+        }
+        // Cells stay where they are while the ecology is held still.
+        if self.rules.freeze != Freeze::Ecology {
+            self.travel(&mut streams.rng(Draw::Travel, 0));
+        }
+        self.sanitized = self.tally.take_sanitized();
+        self.executed = self.tally.take_executed();
+        self.death_causes.walls = Tally::take(&self.tally.died_in_walls) as u32;
+        self.death_causes.combined = Tally::take(&self.tally.died_combining) as u32;
+        self.signaling = Tally::take(&self.tally.signaling) as u32;
+        self.metabolized = Tally::take(&self.tally.metabolized) as u32;
+        self.tick += 1;
+        if self.rules.approximation.is_some() {
+            self.approximated += 1;
+        }
+        self.masks.flip_occupied(&self.tally.occupancy);
+        let before = self.population;
+        self.population = self.masks.occupied.count();
+        // Only a world which had life can go extinct, rather than one which has yet to spawn any.
+        self.recovered = match self.recovery {
+            Some(recovery) if before > 0 && self.population == 0 => {
+                Some(self.recover(&mut streams.rng(Draw::Recovery, 0), recovery))
+            }
+            _ => None,
+        };
+        self.lineages.record_market(self.tick, &self.market);
+        self.lineages.update(
+            self.grid.get_cells(),
+            &self.masks.occupied,
+            self.tick,
+            &self.market.fills,
+        );
+        if let Some(ref mut ancestry) = self.ancestry {
+            ancestry.update(self.grid.get_cells_mut(), &self.masks.occupied, self.tick);
+        }
+        let cells = self.grid.get_cells();
+        let brains = self
+            .masks
+            .occupied
+            .ones()
+            .map(|index| cells[index].brain.as_ref().unwrap());
+        self.death_causes.hungry = brains.clone().filter(|b| b.life.hungry > 0).count() as u32;
+        self.choices = brain::tally_choices(brains.clone());
+        self.mean_age = match self.population {
+            0 => 0,
+            n => brains.clone().map(|b| b.generation as u64).sum::<u64>() / n as u64,
+        };
+        self.max_age = brains
+            .clone()
+            .map(|b| b.generation as u64)
+            .max()
+            .unwrap_or(0);
+        self.unique_genomes = brain::count_genomes(brains);
+        // Periodically merge genomes which mutation and crossover made identical.
+        if self.tick % GENOME_INTERN_INTERVAL == 0 {
+            let before = self.unique_genomes;
+            let after = brain::intern(
+                self.grid
+                    .get_cells_mut()
+                    .iter_mut()
+                    .filter_map(|c| c.brain.as_mut()),
+            );
+            self.interned_genomes = before.saturating_sub(after);
+            self.unique_genomes = after;
+        }
+        // Return all the money on walls to the reserve
+        self.market
+            .escheat(self.grid.get_cells_mut(), &self.masks.walls);
+        if let Some(demurrage) = self.rules.demurrage {
+            let rng = &mut streams.rng(Draw::Demurrage, 0);
+            self.market.demur(rng, self.grid.get_cells_mut(), demurrage);
+        }
+        // Stockpiles keep while the ecology is held still, as the food on tiles does.
+        self.spoiled = if !self.rules.spoilage.is_active() || self.rules.freeze.holds(Phase::Feeding)
+        {
+            0
+        } else {
+            let rng = &mut streams.rng(Draw::Spoilage, 0);
+            self.rules.spoilage.spoil(rng, self.grid.get_cells_mut())
+        };
+        self.market
+            .tax_inheritance(Tally::take(&self.tally.inheritance_taxed) as u32);
+        self.market.forfeit(forfeited);
 
-                                // If the bid is not complete, we must return it to the bids.
-                                if bid.food != 0 {
-                                    bids.push(bid);
-                                }
+        self
+    }
 
-                                // If the order is complete, we can break from this loop.
-                                if order.food == 0 {
-                                    break;
-                                }
-                            }
-                        } else {
-                            // Try to sell to the reserve.
-                            if order.rate <= 1 {
-                                fulfill_reserve(&mut self, &mut order);
-                            }
-                            // There were no bids, so push our ask.
-                            if order.food != 0 {
-                                asks.push(order);
-                            }
-                            break;
-                        }
+    /// Sends everything on the portal tiles to the linked world, then lands whatever it sent.
+    fn travel(&mut self, rng: &mut ChaCha8Rng) {
+        let link = match self.portal {
+            Some(ref mut link) => link,
+            None => return,
+        };
+        let (width, height) = (self.grid.get_width(), self.grid.get_height());
+        let cells = self.grid.get_cells_mut();
+
+        let mut departures = Traffic::default();
+        for &index in &self.portals {
+            let cell = &mut cells[index];
+            // Goods held back by an offer leave with the cell.
+            let (escrow_food, escrow_money) = cell
+                .offer
+                .take()
+                .map(|offer| offer.escrow())
+                .unwrap_or((0, 0));
+            let food = std::mem::take(&mut cell.food) + escrow_food;
+            let money = std::mem::take(&mut cell.money) + escrow_money;
+            cell.trade = None;
+            match cell.brain.take() {
+                Some(mut brain) => {
+                    self.tally.occupancy.flip(index);
+                    brain.possessed = false;
+                    brain.forced = None;
+                    departures.migrants.push(Migrant { brain, food, money });
+                }
+                None => {
+                    departures.food += food;
+                    departures.money += money;
+                }
+            }
+        }
+        link.outbox.unbounded_send(departures).ok();
+
+        // Arrivals land next to a portal rather than on one, so they are not sent straight back.
+        let landing = |cells: &[Cell], portal: usize| {
+            neighbor_indices(portal, width, height).find(|&ix| {
+                let cell = &cells[ix];
+                cell.brain.is_none() && cell.ty != CellType::Wall && cell.ty != CellType::Portal
+            })
+        };
+        while let Ok(Some(arrivals)) = link.inbox.try_next() {
+            for migrant in arrivals.migrants {
+                let landed = self
+                    .portals
+                    .choose_multiple(rng, self.portals.len())
+                    .find_map(|&portal| landing(cells, portal));
+                match landed {
+                    Some(index) => {
+                        self.tally.occupancy.flip(index);
+                        let cell = &mut cells[index];
+                        cell.brain = Some(migrant.brain);
+                        cell.food += migrant.food;
+                        cell.money += migrant.money;
                     }
+                    // With nowhere to land the migrant dies and its money goes to the reserve.
+                    None => self.market.reserve += migrant.money,
+                }
+            }
+            match self.portals.choose(rng) {
+                Some(&portal) => {
+                    let index = landing(cells, portal).unwrap_or(portal);
+                    cells[index].food += arrivals.food;
+                    cells[index].money += arrivals.money;
                 }
-                Intent::Nothing => {}
+                None => self.market.reserve += arrivals.money,
             }
         }
-        self.last_bid = bids.pop_max().map(|order| order.rate);
-        self.last_ask = asks.pop_min().map(|order| order.rate);
-        let ages = self
-            .grid
+    }
+
+    /// Exchanges messages with the external controller and applies its decision for this tick.
+    fn control(&mut self) {
+        let link = match self.control.clone() {
+            Some(link) => link,
+            None => return,
+        };
+        while let Some(request) = link.try_recv() {
+            self.handle_control(&link, request);
+        }
+
+        let width = self.grid.get_width();
+        let index = match self.possessed() {
+            Some(index) => index,
+            None => {
+                if self.possessing {
+                    self.possessing = false;
+                    link.send(&Report::Released { reason: "died" });
+                }
+                return;
+            }
+        };
+
+        let cell = self.cell(index);
+        link.send(&Report::Observation {
+            tick: self.tick,
+            x: index % width,
+            y: index / width,
+            food: cell.food,
+            money: cell.money,
+            generation: cell.brain.as_ref().unwrap().generation,
+            inputs: self.observe(index),
+        });
+
+        let decision = match link.recv_timeout(CONTROL_TIMEOUT) {
+            Some(request) => match request.decision() {
+                Some(decision) => Some(decision),
+                None => {
+                    self.handle_control(&link, request);
+                    None
+                }
+            },
+            // The controller took too long, so the brain decides this tick.
+            None => None,
+        };
+        self.force(index, decision);
+    }
+
+    fn handle_control(&mut self, link: &ControlLink, request: Request) {
+        let (width, height) = (self.grid.get_width(), self.grid.get_height());
+        match request {
+            Request::Possess { x, y } => {
+                if x >= width || y >= height {
+                    self.release();
+                    link.send(&Report::Error {
+                        message: format!("({}, {}) is outside the world", x, y),
+                    });
+                } else if self.possess(y * width + x) {
+                    link.send(&Report::Possessed { x, y });
+                } else {
+                    link.send(&Report::Error {
+                        message: format!("there is no cell at ({}, {})", x, y),
+                    });
+                }
+            }
+            Request::PossessAny => match self.possess_any() {
+                Some(index) => link.send(&Report::Possessed {
+                    x: index % width,
+                    y: index / width,
+                }),
+                None => link.send(&Report::Error {
+                    message: "there is no open tile to spawn a cell on".to_owned(),
+                }),
+            },
+            Request::Release => {
+                if self.possessing {
+                    self.release();
+                    link.send(&Report::Released {
+                        reason: "requested",
+                    });
+                }
+            }
+            _ => link.send(&Report::Error {
+                message: "no decision is pending".to_owned(),
+            }),
+        }
+    }
+
+    /// The index of the possessed cell, if there is one.
+    pub fn possessed(&self) -> Option<usize> {
+        self.grid
             .get_cells()
             .iter()
-            .filter_map(|c| c.brain.as_ref().map(|b| b.generation as u64));
-        self.mean_age = match ages.clone().count() {
-            0 => 0,
-            n => ages.clone().sum::<u64>() / n as u64,
-        };
-        self.max_age = ages.max().unwrap_or(0);
-        // Return all the money on walls to the reserve
-        for cell in self.grid.get_cells_mut() {
-            if cell.ty == CellType::Wall {
-                self.reserve += cell.money;
-                cell.money = 0;
+            .position(|c| c.brain.as_ref().map(|b| b.possessed).unwrap_or(false))
+    }
+
+    /// Possesses the cell at an index, returning false if it has no brain.
+    pub fn possess(&mut self, index: usize) -> bool {
+        self.release();
+        match self.grid.get_cells_mut()[index].brain.as_mut() {
+            Some(brain) => {
+                brain.possessed = true;
+                self.possessing = true;
+                true
             }
+            None => false,
         }
+    }
 
-        self
+    /// The numbers drawn for a controller, from the stream of the tick to come but apart from
+    /// what the tick draws, and apart from every other time.
+    pub fn control_rng(&mut self) -> ChaCha8Rng {
+        self.controls += 1;
+        Streams::new(self.seed, self.tick).rng(Draw::Control, self.controls)
+    }
+
+    /// Possesses a random living cell, or spawns one on a random open tile if there are none.
+    pub fn possess_any(&mut self) -> Option<usize> {
+        let rng = &mut self.control_rng();
+        let cells = self.grid.get_cells();
+        let living: Vec<usize> = (0..cells.len())
+            .filter(|&ix| cells[ix].brain.is_some())
+            .collect();
+        let index = match living.choose(rng) {
+            Some(&index) => index,
+            None => {
+                let open: Vec<usize> = (0..cells.len())
+                    .filter(|&ix| cells[ix].ty != CellType::Wall)
+                    .collect();
+                let index = *open.choose(rng)?;
+                let cell = &mut self.grid.get_cells_mut()[index];
+                cell.brain = Some(rng.sample(self.config.codons()));
+                cell.food += SPAWN_FOOD;
+                self.masks.occupied.insert(index);
+                self.population += 1;
+                index
+            }
+        };
+        self.possess(index);
+        Some(index)
     }
 
-    pub fn stats(&self) -> FromSim {
-        FromSim::Stats {
-            ask: self.last_ask,
-            bid: self.last_bid,
-            reserve: self.reserve,
-            buy_volume: self.buy_volume,
-            sell_volume: self.sell_volume,
-            mean_age: self.mean_age,
-            max_age: self.max_age,
+    /// The unrotated brain inputs of the cell at an index.
+    pub fn observe(&self, index: usize) -> Vec<f64> {
+        let (width, height) = (self.grid.get_width(), self.grid.get_height());
+        let cells = self.grid.get_cells();
+        inputs(
+            &self.config,
+            &cells[index],
+            neighbor_indices(index, width, height).map(|n| &cells[n]),
+        )
+        .to_vec()
+    }
+
+    /// Overrides the next decision of the brain at an index, or lets it decide with `None`.
+    pub fn force(&mut self, index: usize, decision: Option<Decision>) {
+        if let Some(brain) = self.grid.get_cells_mut()[index].brain.as_mut() {
+            brain.forced = decision;
         }
     }
 
-    pub fn view(&self, times: usize) -> View {
-        View {
-            colors: Array2::from_shape_vec(
-                (self.grid.get_height(), self.grid.get_width()),
-                self.grid
-                    .get_cells()
-                    .par_iter()
-                    .map(|c| {
-                        (
-                            c.color(),
-                            match &c.brain {
-                                Some(brain) => brain.generation,
-                                None => 0,
-                            },
-                        )
-                    })
-                    .collect::<Vec<(Color, usize)>>(),
+    pub fn cell(&self, index: usize) -> &Cell {
+        &self.grid.get_cells()[index]
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        self.grid.get_cells()
+    }
+
+    /// What the cell at an index is, if it has a brain.
+    pub fn detail(&self, index: usize) -> Option<CellDetail> {
+        let cell = self.cell(index);
+        let brain = cell.brain.as_ref()?;
+        Some(CellDetail {
+            genome: brain.genome(),
+            codons: brain.codons(),
+            memory: brain.memory.to_vec(),
+            generation: brain.generation,
+            lineage: brain.lineage,
+            ticks: brain.life.ticks,
+            food: cell.food,
+            money: cell.money,
+            signal: cell.signal,
+        })
+    }
+
+    /// What is on the tile at an index, whether or not it has a brain.
+    pub fn summary(&self, index: usize) -> CellSummary {
+        let cell = self.cell(index);
+        CellSummary {
+            food: cell.food,
+            money: cell.money,
+            lineage: cell.brain.as_ref().map(|brain| brain.lineage),
+        }
+    }
+
+    /// Gives every possessed cell back to its brain.
+    fn release(&mut self) {
+        self.possessing = false;
+        for brain in self
+            .grid
+            .get_cells_mut()
+            .iter_mut()
+            .filter_map(|c| c.brain.as_mut())
+        {
+            brain.possessed = false;
+            brain.forced = None;
+        }
+    }
+
+    /// The color of each tile in a view with some settings.
+    fn tile_colors<'a>(&'a self, settings: &'a ViewSettings) -> impl Fn(&Cell) -> Color + Sync + 'a {
+        let filter = settings.filter.as_ref();
+        let terrain = &settings.terrain;
+        let cells = self.grid.get_cells();
+        let value = move |cell: &Cell| match settings.mode {
+            ViewMode::Species | ViewMode::Starvation => 0,
+            ViewMode::Age => cell.brain.as_ref().map_or(0, |b| b.generation as u64),
+            ViewMode::Wealth => cell.food as u64 + cell.money as u64,
+        };
+        // The values of every living cell in order, to find the percentile of each.
+        let mut ranked: Vec<u64> = match settings.mode {
+            ViewMode::Species | ViewMode::Starvation => vec![],
+            ViewMode::Age | ViewMode::Wealth => self
+                .masks
+                .occupied
+                .ones()
+                .map(|index| value(&cells[index]))
+                .collect(),
+        };
+        ranked.par_sort_unstable();
+        move |c: &Cell| {
+            let color = match c.brain {
+                Some(_) if settings.mode == ViewMode::Starvation => {
+                    starvation_color(c.food / UPKEEP)
+                }
+                Some(_) if !ranked.is_empty() => {
+                    brighten(c.color(terrain), percentile(&ranked, value(c)))
+                }
+                _ => c.color(terrain),
+            };
+            match filter {
+                Some(filter) if !filter.matches(c) => dim(color),
+                _ => color,
+            }
+        }
+    }
+
+    /// Whether a view with some settings keeps its colors compact.
+    fn compact_view(&self, settings: &ViewSettings) -> bool {
+        match settings.memory {
+            ViewMemory::Auto => self.grid.get_cells().len() > COMPACT_VIEW_AREA,
+            ViewMemory::Full => false,
+            ViewMemory::Compact => true,
+        }
+    }
+
+    /// The cells matched by the filter of a view, if it has one.
+    fn view_matches(&self, settings: &ViewSettings) -> Option<usize> {
+        let cells = self.grid.get_cells();
+        // Only tiles with a brain can match, so the rest are skipped.
+        settings.filter.as_ref().map(|filter| {
+            self.masks
+                .occupied
+                .par_count_where(|index| filter.matches(&cells[index]))
+        })
+    }
+
+    pub fn view(&self, times: usize, settings: &ViewSettings) -> View {
+        let cells = self.grid.get_cells();
+        let color = self.tile_colors(settings);
+        let shape = (self.grid.get_height(), self.grid.get_width());
+        let colors = if self.compact_view(settings) {
+            ViewColors::Compact(
+                Array2::from_shape_vec(
+                    shape,
+                    cells.par_iter().map(|c| compact_color(color(c))).collect(),
+                )
+                .unwrap(),
             )
-            .unwrap(),
-            cells: self.grid.get_cells().iter().fold(0, |acc, cell| {
-                acc + if cell.brain.is_some() { 1 } else { 0 }
-            }),
+        } else {
+            ViewColors::Full(
+                Array2::from_shape_vec(
+                    shape,
+                    cells.par_iter().map(|c| full_color(color(c), c)).collect(),
+                )
+                .unwrap(),
+            )
+        };
+        View {
+            colors,
+            cells: self.population,
+            matches: self.view_matches(settings),
             ticks: times,
         }
     }
+
+    /// Brings `shown`, the frame the window last got, up to date with the world, returning only
+    /// the tiles which changed. `None` means the frame is better sent whole: when `shown` was of
+    /// another size or kept its colors otherwise, in which case it is made anew, or when most of
+    /// its tiles changed, such as after the view mode did.
+    pub fn view_delta(
+        &self,
+        times: usize,
+        settings: &ViewSettings,
+        shown: &mut View,
+    ) -> Option<ViewDelta> {
+        let cells = self.grid.get_cells();
+        let compact = self.compact_view(settings);
+        let same_layout = match shown.colors {
+            ViewColors::Full(_) => !compact,
+            ViewColors::Compact(_) => compact,
+        };
+        if !same_layout || shown.colors.dim() != (self.grid.get_height(), self.grid.get_width()) {
+            *shown = self.view(times, settings);
+            return None;
+        }
+        let color = self.tile_colors(settings);
+        // Every tile is compared and updated in place, so that no frame is allocated whole.
+        let changes = match shown.colors {
+            ViewColors::Full(ref mut colors) => ViewChanges::Full(changed(
+                colors.as_slice_mut().unwrap(),
+                cells,
+                |c| full_color(color(c), c),
+            )),
+            ViewColors::Compact(ref mut colors) => ViewChanges::Compact(changed(
+                colors.as_slice_mut().unwrap(),
+                cells,
+                |c| compact_color(color(c)),
+            )),
+        };
+        shown.cells = self.population;
+        shown.matches = self.view_matches(settings);
+        shown.ticks = times;
+        if changes.len() > cells.len() / 2 {
+            return None;
+        }
+        Some(ViewDelta {
+            changes,
+            cells: shown.cells,
+            matches: shown.matches,
+            ticks: times,
+        })
+    }
+}
+
+/// A color as a compact view keeps it.
+fn compact_color(color: Color) -> [u8; 3] {
+    let byte = |channel: f32| (channel * 255.0).round().max(0.0).min(255.0) as u8;
+    [byte(color.r), byte(color.g), byte(color.b)]
+}
+
+/// A color as a full view keeps it, with the generation of the cell for the ancestry markings.
+fn full_color(color: Color, cell: &Cell) -> (Color, usize) {
+    (color, cell.brain.as_ref().map_or(0, |brain| brain.generation))
+}
+
+/// Updates the colors of the tiles in parallel, returning those which changed by their index.
+fn changed<T: Copy + PartialEq + Send>(
+    colors: &mut [T],
+    cells: &[Cell],
+    color: impl Fn(&Cell) -> T + Sync,
+) -> Vec<(usize, T)> {
+    colors
+        .par_iter_mut()
+        .zip(cells.par_iter())
+        .enumerate()
+        .filter_map(|(index, (old, cell))| {
+            let new = color(cell);
+            if *old == new {
+                None
+            } else {
+                *old = new;
+                Some((index, new))
+            }
+        })
+        .collect()
+}
+
+/// The message which brings the frame the window has up to date with the world, whole or as
+/// what changed.
+fn next_frame(sim: &Sim, times: usize, settings: &ViewSettings, shown: &mut View) -> FromSim {
+    match sim.view_delta(times, settings, shown) {
+        Some(delta) => FromSim::ViewDelta(delta),
+        None => FromSim::View(shown.clone()),
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum TickError {
     JoinFailed,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// A cell holding food with a brain which offers some of it to its right neighbor.
+    fn offering(food: u32, offered: u32, tile: usize) -> Cell {
+        let mut brain: Brain = ChaCha8Rng::seed_from_u64(0).sample(SimConfig::default().codons());
+        brain.forced = Some(Decision::Offer(MooreDirection::Right, offered as i32, 0));
+        Cell {
+            food,
+            brain: Some(brain),
+            tile,
+            ..Cell::default()
+        }
+    }
+
+    /// Steps and updates cells on many tiles, returning how much food was left on those stepped
+    /// and whether they held back an offer.
+    fn step_offers(config: &SimConfig, food: u32, offered: u32) -> Vec<(u32, bool)> {
+        let tally = Tally::new(64);
+        let streams = Streams::new(0, 0);
+        let empty = Cell::default();
+        let mut stepped = vec![];
+        for tile in 0..64 {
+            let mut cell = offering(food, offered, tile);
+            let (diff, moves) = step_cell(
+                config,
+                &tally,
+                streams,
+                &cell,
+                MooreNeighbors::new(|_| &empty),
+            );
+            if diff.lived.map_or(false, |lived| lived.skipped) {
+                continue;
+            }
+            update_cell(config, &tally, streams, &mut cell, diff, moves);
+            stepped.push((cell.food, cell.offer.is_some()));
+        }
+        stepped
+    }
+
+    #[test]
+    fn a_brain_stepped_every_other_tick_cannot_offer_what_it_eats() {
+        let config = SimConfig {
+            direct_exchange: true,
+            stepped: 0.5,
+            ..SimConfig::default()
+        };
+        // Stepped half the time, a brain eats two food when it is.
+        let stepped = step_offers(&config, 10, 9);
+        assert!(!stepped.is_empty());
+        assert!(stepped.iter().all(|&stepped| stepped == (8, false)));
+        let stepped = step_offers(&config, 10, 8);
+        assert!(!stepped.is_empty());
+        assert!(stepped.iter().all(|&stepped| stepped == (0, true)));
+    }
+
+    #[test]
+    fn an_offer_which_no_longer_fits_is_not_made() {
+        let config = SimConfig::default();
+        let mut cell = offering(10, 8, 0);
+        let diff = Diff {
+            consume: 5,
+            offer: Some(Offer {
+                dir: MooreDirection::Right,
+                food: 8,
+                money: 0,
+            }),
+            ..Diff::default()
+        };
+        update_cell(
+            &config,
+            &Tally::new(1),
+            Streams::new(0, 0),
+            &mut cell,
+            diff,
+            MooreNeighbors::new(|_| Move::default()),
+        );
+        assert_eq!(cell.food, 5);
+        assert!(cell.offer.is_none());
+    }
+}
@@ -0,0 +1,360 @@
+//! The family tree of the brains in the world, while it is tracked, so that how lineages split and
+//! merged can be studied with the tools of phylogenetics.
+//!
+//! Every brain which lives through a tick is recorded with the brains it was born of, dividing
+//! from one or combining from several. A brain whose parents are not in the tree, such as one
+//! which spawned, arrived through a portal or was alive when tracking started, becomes a root.
+//!
+//! Only the ancestry of the living is kept. A brain which died without offspring in the tree is
+//! dropped, and one which died with a single child in it is spliced out, so the tree holds at most
+//! about twice as many brains as are alive, however long it is tracked.
+
+use super::lineage::Lineages;
+use super::{Bitset, Cell, Sim};
+use iced::Color;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cmp::Reverse;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// How the tree is written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AncestryFormat {
+    /// A Newick tree whose branch lengths are in ticks, following each brain from the parent
+    /// whose lineage it kept.
+    Newick,
+    /// A GraphViz digraph, which also shows the other parents of combined brains.
+    Graphviz,
+}
+
+impl fmt::Display for AncestryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AncestryFormat::Newick => "Newick",
+            AncestryFormat::Graphviz => "GraphViz",
+        })
+    }
+}
+
+/// Clades in a phylogeny at most, the largest, so that the tree of a large world stays quick to
+/// send and to draw.
+const PHYLOGENY_CLADES: usize = 256;
+
+/// A brain of the family tree standing for itself and every brain descended from it.
+#[derive(Clone, Debug)]
+pub struct Clade {
+    pub id: u64,
+    /// Where its parent is among the clades of the phylogeny, or `None` for a root.
+    pub parent: Option<usize>,
+    pub lineage: u64,
+    pub name: Option<String>,
+    pub color: Color,
+    pub born: u64,
+    /// The living brains it stands for, itself included while it is alive.
+    pub population: usize,
+}
+
+/// The largest clades of the family tree and every ancestor of theirs, each after its parent and
+/// siblings oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct Phylogeny {
+    pub clades: Vec<Clade>,
+    /// The tick it was taken on.
+    pub tick: u64,
+}
+
+struct Node {
+    /// The parent whose lineage the brain kept, if it is in the tree.
+    parent: Option<u64>,
+    /// The other brains it combined from, which only the graph shows.
+    others: Vec<u64>,
+    children: Vec<u64>,
+    lineage: u64,
+    generation: usize,
+    born: u64,
+    died: Option<u64>,
+}
+
+#[derive(Default)]
+pub(super) struct Ancestry {
+    nodes: FxHashMap<u64, Node>,
+    /// The ids of the brains alive at the end of the last tick.
+    living: FxHashSet<u64>,
+}
+
+impl Ancestry {
+    /// Records the brains born since the last tick and those which died, given the living cells.
+    pub(super) fn update(&mut self, cells: &mut [Cell], occupied: &Bitset, tick: u64) {
+        let mut living = FxHashSet::default();
+        living.reserve(self.living.len());
+        for index in occupied.ones() {
+            let brain = cells[index].brain.as_mut().unwrap();
+            living.insert(brain.id);
+            if !self.nodes.contains_key(&brain.id) {
+                let parents = std::mem::take(&mut brain.parents);
+                self.born(brain.id, &parents, brain.lineage, brain.generation, tick);
+            }
+        }
+        let died: Vec<u64> = self
+            .living
+            .iter()
+            .filter(|id| !living.contains(id))
+            .copied()
+            .collect();
+        for id in died {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                node.died = Some(tick);
+            }
+            self.prune(id);
+        }
+        self.living = living;
+    }
+
+    fn born(&mut self, id: u64, parents: &[u64], lineage: u64, generation: usize, tick: u64) {
+        let mut known = parents
+            .iter()
+            .copied()
+            .filter(|p| self.nodes.contains_key(p));
+        let parent = known.next();
+        let others = known.collect();
+        if let Some(parent) = parent {
+            self.nodes.get_mut(&parent).unwrap().children.push(id);
+        }
+        self.nodes.insert(
+            id,
+            Node {
+                parent,
+                others,
+                children: vec![],
+                lineage,
+                generation,
+                born: tick,
+                died: None,
+            },
+        );
+    }
+
+    /// Drops a dead brain which has no children in the tree, along with any of its ancestors
+    /// which are left without descendants, or splices it out if it has a single child.
+    fn prune(&mut self, mut id: u64) {
+        loop {
+            let node = match self.nodes.get(&id) {
+                Some(node) if node.died.is_some() && node.children.len() < 2 => node,
+                _ => return,
+            };
+            let parent = node.parent;
+            let child = node.children.first().copied();
+            self.nodes.remove(&id);
+            if let Some(child) = child {
+                // The child keeps when it was born, so its branch spans the one spliced out.
+                self.nodes.get_mut(&child).unwrap().parent = parent;
+            }
+            let parent = match parent {
+                Some(parent) => parent,
+                None => return,
+            };
+            let siblings = &mut self.nodes.get_mut(&parent).unwrap().children;
+            match child {
+                Some(child) => {
+                    let at = siblings.iter().position(|&s| s == id).unwrap();
+                    siblings[at] = child;
+                    return;
+                }
+                None => siblings.retain(|&s| s != id),
+            }
+            id = parent;
+        }
+    }
+
+    /// The brains with no parent in the tree, oldest first.
+    fn roots(&self) -> Vec<u64> {
+        let mut roots: Vec<(u64, u64)> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.parent.is_none())
+            .map(|(&id, node)| (node.born, id))
+            .collect();
+        roots.sort_unstable();
+        roots.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// The living brains descended from each brain in the tree, itself included while it is
+    /// alive.
+    fn populations(&self) -> FxHashMap<u64, usize> {
+        let mut populations = FxHashMap::default();
+        populations.reserve(self.nodes.len());
+        // Walked without recursion, counting every child before its parent.
+        let mut stack: Vec<(u64, bool)> = self.roots().into_iter().map(|id| (id, false)).collect();
+        while let Some((id, counted)) = stack.pop() {
+            let node = &self.nodes[&id];
+            if counted {
+                let descended: usize = node.children.iter().map(|child| populations[child]).sum();
+                populations.insert(id, node.died.is_none() as usize + descended);
+            } else {
+                stack.push((id, true));
+                stack.extend(node.children.iter().map(|&child| (child, false)));
+            }
+        }
+        populations
+    }
+
+    /// The largest clades and their ancestors, named and colored after their lineages.
+    fn phylogeny(&self, lineages: &Lineages, tick: u64) -> Phylogeny {
+        let populations = self.populations();
+        let mut ranked: Vec<(Reverse<usize>, u64, u64)> = populations
+            .iter()
+            .map(|(&id, &population)| (Reverse(population), self.nodes[&id].born, id))
+            .collect();
+        ranked.sort_unstable();
+        let mut shown = FxHashSet::default();
+        for &(_, _, id) in ranked.iter().take(PHYLOGENY_CLADES) {
+            // An ancestor is at least as large, but may have lost its place on a tie.
+            let mut next = Some(id);
+            while let Some(id) = next {
+                if !shown.insert(id) {
+                    break;
+                }
+                next = self.nodes[&id].parent;
+            }
+        }
+        let mut clades = vec![];
+        let mut stack: Vec<(u64, Option<usize>)> = self
+            .roots()
+            .into_iter()
+            .rev()
+            .filter(|id| shown.contains(id))
+            .map(|id| (id, None))
+            .collect();
+        while let Some((id, parent)) = stack.pop() {
+            let node = &self.nodes[&id];
+            let (name, color) = lineages.describe(node.lineage);
+            clades.push(Clade {
+                id,
+                parent,
+                lineage: node.lineage,
+                name,
+                color,
+                born: node.born,
+                population: populations[&id],
+            });
+            let at = Some(clades.len() - 1);
+            let mut children: Vec<u64> = node
+                .children
+                .iter()
+                .copied()
+                .filter(|child| shown.contains(child))
+                .collect();
+            // Youngest pushed first, so that the oldest comes out first.
+            children.sort_unstable_by_key(|child| Reverse(self.nodes[child].born));
+            stack.extend(children.into_iter().map(|child| (child, at)));
+        }
+        Phylogeny { clades, tick }
+    }
+
+    /// Writes the tree to a file in a format.
+    pub(super) fn write(&self, path: &Path, format: AncestryFormat) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format {
+            AncestryFormat::Newick => self.write_newick(&mut writer)?,
+            AncestryFormat::Graphviz => self.write_graphviz(&mut writer)?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes every root as a subtree of one unnamed root, labeling each brain by its id.
+    fn write_newick(&self, w: &mut impl Write) -> std::io::Result<()> {
+        write!(w, "(")?;
+        for (n, root) in self.roots().into_iter().enumerate() {
+            if n > 0 {
+                write!(w, ",")?;
+            }
+            // Walked without recursion, since a tree tracked for long can be very deep.
+            let mut stack = vec![(root, 0)];
+            while let Some((id, next)) = stack.pop() {
+                let node = &self.nodes[&id];
+                if next < node.children.len() {
+                    write!(w, "{}", if next == 0 { "(" } else { "," })?;
+                    stack.push((id, next + 1));
+                    stack.push((node.children[next], 0));
+                    continue;
+                }
+                if !node.children.is_empty() {
+                    write!(w, ")")?;
+                }
+                write!(w, "{}", id)?;
+                if let Some(parent) = node.parent {
+                    write!(w, ":{}", node.born - self.nodes[&parent].born)?;
+                }
+            }
+        }
+        writeln!(w, ");")
+    }
+
+    /// Writes a node for every brain, with solid edges from the parent whose lineage it kept and
+    /// dashed edges from the others it combined from. The dead are grey.
+    fn write_graphviz(&self, w: &mut impl Write) -> std::io::Result<()> {
+        writeln!(w, "digraph ancestry {{")?;
+        let mut ids: Vec<u64> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+        for &id in &ids {
+            let node = &self.nodes[&id];
+            write!(
+                w,
+                "  b{} [label=\"{}\\nlineage {:x}\\ngeneration {}\\nborn {}",
+                id, id, node.lineage, node.generation, node.born
+            )?;
+            match node.died {
+                Some(died) => writeln!(w, "\\ndied {}\", color=grey];", died)?,
+                None => writeln!(w, "\"];")?,
+            }
+        }
+        for &id in &ids {
+            let node = &self.nodes[&id];
+            if let Some(parent) = node.parent {
+                writeln!(w, "  b{} -> b{};", parent, id)?;
+            }
+            // Other parents may have been dropped since, having left no line of their own.
+            for other in node.others.iter().filter(|o| self.nodes.contains_key(o)) {
+                writeln!(w, "  b{} -> b{} [style=dashed];", other, id)?;
+            }
+        }
+        writeln!(w, "}}")
+    }
+}
+
+impl Sim {
+    /// Starts tracking the family tree from the brains alive now, or stops and drops it.
+    pub(super) fn track_ancestry(&mut self, tracking: bool) {
+        if tracking != self.ancestry.is_some() {
+            self.ancestry = if tracking {
+                Some(Ancestry::default())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// The largest clades of the family tree, if it is tracked.
+    pub(super) fn phylogeny(&self) -> Option<Phylogeny> {
+        let ancestry = self.ancestry.as_ref()?;
+        Some(ancestry.phylogeny(&self.lineages, self.tick))
+    }
+
+    /// Writes the family tree to a file, returning how many brains are in it.
+    pub(super) fn export_ancestry(
+        &self,
+        path: &Path,
+        format: AncestryFormat,
+    ) -> Result<usize, Box<dyn Error>> {
+        let ancestry = self
+            .ancestry
+            .as_ref()
+            .ok_or("the ancestry is not being tracked")?;
+        ancestry.write(path, format)?;
+        Ok(ancestry.nodes.len())
+    }
+}
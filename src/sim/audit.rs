@@ -0,0 +1,205 @@
+//! Checking that the market does not favor some cells over others, by how much of each order is
+//! filled depending on where it arrived after the shuffle, its price and the wealth of its cell.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Arrival positions and wealth are split into tenths of the orders of each clearing.
+const DECILES: usize = 10;
+/// How many standard errors the orders of a tenth may be from all of them before they count as
+/// favored or disfavored.
+const FAVORED_Z: f64 = 3.0;
+
+/// An order taking part in a clearing, recorded while auditing.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Arrival {
+    pub(super) bid: bool,
+    pub(super) rate: i32,
+    /// The money of its cell when the clearing began.
+    pub(super) wealth: u32,
+    /// The food of the order when the clearing began.
+    pub(super) placed: u32,
+    pub(super) filled: u32,
+}
+
+/// Orders of one side of the book which fell into a bucket.
+#[derive(Copy, Clone, Debug, Default)]
+struct Tally {
+    orders: u64,
+    placed: u64,
+    filled: u64,
+    /// The sum of the share of each order filled, and of its square.
+    shares: f64,
+    squares: f64,
+}
+
+impl Tally {
+    fn add(&mut self, arrival: &Arrival) {
+        let share = arrival.filled as f64 / arrival.placed as f64;
+        self.orders += 1;
+        self.placed += arrival.placed as u64;
+        self.filled += arrival.filled as u64;
+        self.shares += share;
+        self.squares += share * share;
+    }
+
+    fn mean(&self) -> f64 {
+        self.shares / self.orders as f64
+    }
+
+    /// How many standard errors the mean share filled in this bucket is from that of `all`.
+    fn z(&self, all: &Tally) -> f64 {
+        if self.orders == 0 || all.orders < 2 {
+            return 0.0;
+        }
+        let mean = all.mean();
+        let variance = (all.squares / all.orders as f64 - mean * mean).max(0.0);
+        if variance == 0.0 {
+            return 0.0;
+        }
+        (self.mean() - mean) / (variance / self.orders as f64).sqrt()
+    }
+}
+
+/// Fill rates of the orders of every clearing over a window of ticks, bids apart from asks.
+#[derive(Clone, Debug)]
+pub struct Audit {
+    ticks_left: usize,
+    ticks: usize,
+    all: [Tally; 2],
+    by_position: [[Tally; 2]; DECILES],
+    by_wealth: [[Tally; 2]; DECILES],
+    by_price: BTreeMap<i32, [Tally; 2]>,
+}
+
+impl Audit {
+    pub(super) fn new(ticks: usize) -> Self {
+        Self {
+            ticks_left: ticks,
+            ticks: 0,
+            all: Default::default(),
+            by_position: Default::default(),
+            by_wealth: Default::default(),
+            by_price: BTreeMap::new(),
+        }
+    }
+
+    /// Tallies the orders of a clearing, in the order they arrived in, giving whether the window
+    /// is over.
+    pub(super) fn add(&mut self, arrivals: &[Arrival]) -> bool {
+        let mut wealths: Vec<u32> = arrivals.iter().map(|arrival| arrival.wealth).collect();
+        wealths.sort_unstable();
+        let decile = |rank: usize| rank * DECILES / arrivals.len();
+        for (position, arrival) in arrivals.iter().enumerate() {
+            if arrival.placed == 0 {
+                continue;
+            }
+            // Cells equally wealthy share the rank of the first of them.
+            let rank = wealths
+                .binary_search_by(|&wealth| {
+                    if wealth < arrival.wealth {
+                        Ordering::Less
+                    } else {
+                        Ordering::Greater
+                    }
+                })
+                .unwrap_err();
+            let side = arrival.bid as usize;
+            self.all[side].add(arrival);
+            self.by_position[decile(position)][side].add(arrival);
+            self.by_wealth[decile(rank)][side].add(arrival);
+            self.by_price.entry(arrival.rate).or_default()[side].add(arrival);
+        }
+        self.ticks += 1;
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        self.ticks_left == 0
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    /// Orders tallied, bids and asks together.
+    pub fn orders(&self) -> u64 {
+        self.all[0].orders + self.all[1].orders
+    }
+
+    /// Every bucket of every dimension, each a row of the dimension, the bucket, the side,
+    /// the orders, the food placed and filled, the share of food filled, the mean share of each
+    /// order filled and how many standard errors that is from every order of the side.
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        let mut buckets = vec![("all", String::new(), &self.all)];
+        for &(dimension, tallies) in &[("position", &self.by_position), ("wealth", &self.by_wealth)]
+        {
+            for (decile, tallies) in tallies.iter().enumerate() {
+                buckets.push((dimension, tenth(decile), tallies));
+            }
+        }
+        for (price, tallies) in &self.by_price {
+            buckets.push(("price", price.to_string(), tallies));
+        }
+        let mut rows = vec![];
+        for (dimension, bucket, tallies) in buckets {
+            for &(side, index) in &[("ask", 0), ("bid", 1)] {
+                let tally = &tallies[index];
+                rows.push(vec![
+                    dimension.to_owned(),
+                    bucket.clone(),
+                    side.to_owned(),
+                    tally.orders.to_string(),
+                    tally.placed.to_string(),
+                    tally.filled.to_string(),
+                    share(tally.filled as f64, tally.placed as f64),
+                    share(tally.shares, tally.orders as f64),
+                    format!("{:.2}", tally.z(&self.all[index])),
+                ]);
+            }
+        }
+        rows
+    }
+
+    /// The tenths of arrival position or wealth whose orders were filled more or less than the
+    /// rest of their side by more than chance allows. Price is left out, since a better price is
+    /// meant to be filled more.
+    pub fn favored(&self) -> Vec<String> {
+        let mut favored = vec![];
+        for &(dimension, tallies) in &[
+            ("arrival position", &self.by_position),
+            ("wealth", &self.by_wealth),
+        ] {
+            for (decile, tallies) in tallies.iter().enumerate() {
+                for &(side, index) in &[("asks", 0), ("bids", 1)] {
+                    let z = tallies[index].z(&self.all[index]);
+                    if z.abs() > FAVORED_Z {
+                        favored.push(format!(
+                            "{} of the {} by {} ({:+.1} standard errors)",
+                            side,
+                            tenth(decile),
+                            dimension,
+                            z
+                        ));
+                    }
+                }
+            }
+        }
+        favored
+    }
+}
+
+/// A tenth as the range of percentiles it covers.
+fn tenth(decile: usize) -> String {
+    format!(
+        "{}-{}%",
+        decile * 100 / DECILES,
+        (decile + 1) * 100 / DECILES
+    )
+}
+
+/// A ratio to four places, or nothing when there was nothing to divide.
+fn share(part: f64, whole: f64) -> String {
+    if whole == 0.0 {
+        String::new()
+    } else {
+        format!("{:.4}", part / whole)
+    }
+}
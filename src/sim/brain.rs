@@ -1,14 +1,22 @@
+use super::config::Tally;
+use super::{noise, Limit, MAX_EXPIRY};
 use arrayvec::ArrayVec;
 use gridsim::{moore::MooreDirection, Direction};
 use iced::Color;
 use itertools::Itertools;
 use nalgebra::{Complex, Unit, UnitComplex};
 use rand::{
-    distributions::{Bernoulli, Distribution, Standard},
+    distributions::{Bernoulli, Distribution},
     seq::SliceRandom,
     Rng,
 };
 use rand_distr::Exp1;
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::iter::once;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::Relaxed};
 use std::sync::Arc;
 
 const NUM_STATE: usize = 4;
@@ -17,9 +25,21 @@ const INITIAL_GENOME_SCALE: f64 = 256.0;
 const INITIAL_ENTRIES_SCALE: f64 = 64.0;
 
 const RECOLOR_AFTER_MUTATIONS: usize = 1;
+/// The chance a mutation switches between running the entries in order and shuffling them.
+const ORDER_MUTATION_CHANCE: f64 = 1.0 / 32.0;
+/// The largest magnitude `Sanitization::Clamp` lets into memory.
+const MEMORY_BOUND: f64 = 1_000_000.0;
+/// Genes are not duplicated into a genome which would grow longer than this.
+const MAX_DUPLICATED_LENGTH: usize = 4096;
+/// One brain in this many, by id, is sampled for how much of its genome is junk.
+const JUNK_SAMPLING: u64 = 64;
+
+/// The id given to the next brain which is spawned, divided off or combined.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
 
 lazy_static::lazy_static! {
     static ref HALF_CHANCE: Bernoulli = Bernoulli::new(0.5).unwrap();
+    static ref ORDER_MUTATION: Bernoulli = Bernoulli::new(ORDER_MUTATION_CHANCE).unwrap();
 }
 
 /// The hue in radians.
@@ -39,28 +59,229 @@ fn merge_colors(rng: &mut impl Rng, colors: impl Iterator<Item = f64>) -> f64 {
     }
 }
 
+/// What happens to the numbers brains write to memory, and so to their signal, which is the first
+/// slot of memory.
+///
+/// Trade amounts are always clamped to something which can be traded, whatever this is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sanitization {
+    /// NaN and infinity are written as they are.
+    Off,
+    /// NaN and infinity are written as zero.
+    Zero,
+    /// NaN is written as zero and everything else is clamped to `MEMORY_BOUND`.
+    Clamp,
+}
+
+impl Default for Sanitization {
+    fn default() -> Self {
+        Sanitization::Off
+    }
+}
+
+impl Sanitization {
+    fn apply(self, n: f64) -> f64 {
+        match self {
+            Sanitization::Off => n,
+            Sanitization::Zero | Sanitization::Clamp if n.is_nan() => 0.0,
+            Sanitization::Zero if n.is_infinite() => 0.0,
+            Sanitization::Zero => n,
+            Sanitization::Clamp => n.max(-MEMORY_BOUND).min(MEMORY_BOUND),
+        }
+    }
+}
+
+/// The chance of each kind of change every time a genome mutates, where several may happen at
+/// once. Entries are added or removed and the order of the entries switched as well, whatever
+/// these are.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Mutations {
+    /// Inserting a random codon or removing one, half the time each.
+    pub indel: f64,
+    /// Nudging the operand of a codon, such as the value of a literal, the direction of a move or
+    /// the rate of a simple trade, or replacing a codon which has none.
+    pub point: f64,
+    /// Copying a whole gene in right after itself.
+    pub duplication: f64,
+    /// Moving a whole gene to the start of another.
+    pub transposition: f64,
+}
+
+impl Mutations {
+    /// Only the insertions and removals genomes always mutated by.
+    const DEFAULT: Self = Self {
+        indel: 1.0,
+        point: 0.0,
+        duplication: 0.0,
+        transposition: 0.0,
+    };
+
+    /// The chances by name, for checking that they are probabilities.
+    pub fn chances(&self) -> [(&'static str, f64); 4] {
+        [
+            ("mutations.indel", self.indel),
+            ("mutations.point", self.point),
+            ("mutations.duplication", self.duplication),
+            ("mutations.transposition", self.transposition),
+        ]
+    }
+}
+
+impl Default for Mutations {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How many numbers were replaced by sanitization or trade clamping.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Sanitized {
+    pub memory: u32,
+    pub signals: u32,
+    pub trades: u32,
+}
+
+/// Codons of the decisions of sampled brains, and how many of them did not run.
+#[derive(Copy, Clone, Debug, Default)]
+pub(super) struct Executed {
+    pub(super) codons: usize,
+    pub(super) idle: usize,
+}
+
+/// How much of the genomes of the living cells is junk, which never affects what they do.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Junk {
+    /// The mean number of codons in a genome.
+    pub mean_length: f64,
+    /// The share of the codons of sampled genomes which no entry point can reach.
+    pub unreachable: f64,
+    /// The share of the codons of sampled genomes which did not run when they decided, averaged
+    /// over the ticks of a sample.
+    pub unexecuted: f64,
+}
+
+/// How much of the genomes of these brains is junk, given the codons of their sampled decisions.
+pub(super) fn junk<'a>(brains: impl Iterator<Item = &'a Brain>, executed: Executed) -> Junk {
+    let (mut genomes, mut codons) = (0, 0);
+    let (mut sampled, mut unreachable) = (0, 0);
+    for brain in brains {
+        genomes += 1;
+        codons += brain.genome_len();
+        if brain.id % JUNK_SAMPLING == 0 {
+            sampled += brain.genome_len();
+            unreachable += brain.genome_len() - brain.code.reachable();
+        }
+    }
+    let share = |part: usize, whole: usize| match whole {
+        0 => 0.0,
+        whole => part as f64 / whole as f64,
+    };
+    Junk {
+        mean_length: share(codons, genomes),
+        unreachable: share(unreachable, sampled),
+        unexecuted: share(executed.idle, executed.codons),
+    }
+}
+
+/// Sanitizes a number, counting it if it changed.
+fn sanitize(sanitization: Sanitization, n: f64, count: &AtomicUsize) -> f64 {
+    let sanitized = sanitization.apply(n);
+    if sanitized.to_bits() != n.to_bits() {
+        Tally::add(count, 1);
+    }
+    sanitized
+}
+
+/// A new id, which no other brain in the process has had.
+pub(super) fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Relaxed)
+}
+
 pub fn combine(rng: &mut impl Rng, brains: impl IntoIterator<Item = Brain>) -> Brain {
     let brains = brains.into_iter().collect_vec();
     let code = Arc::new(crossover(rng, brains.iter().map(|b| (*b.code).clone())));
     let memory = std::iter::repeat(0.0).collect();
+    // The lineage follows the oldest line among the parents.
+    let eldest = brains.iter().max_by_key(|brain| brain.generation).unwrap();
+    // A parent born this tick is not in the ancestry yet, so its own parents stand in for it.
+    let parents = once(eldest)
+        .chain(brains.iter().filter(|brain| brain.id != eldest.id))
+        .flat_map(|brain| {
+            if brain.parents.is_empty() {
+                vec![brain.id]
+            } else {
+                brain.parents.clone()
+            }
+        })
+        .collect();
     Brain {
+        id: next_id(),
         color: merge_colors(rng, brains.iter().map(|b| b.color)),
         rotation: rng.gen_range(0, 4),
         generation: brains.iter().map(|brain| brain.generation).max().unwrap(),
+        lineage: eldest.lineage,
         memory,
         code,
+        life: Life::default(),
+        possessed: brains.iter().any(|brain| brain.possessed),
+        forced: None,
+        expression: None,
+        execution: None,
+        parents,
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Brain {
+    /// Tells this individual apart as it moves, which is not saved. Moving keeps it, while
+    /// dividing and combining give the new brain a new one.
+    pub(super) id: u64,
     /// The hue in radians.
-    color: f64,
+    pub(super) color: f64,
     /// Rotation counter-clockwise (direction of iteration in gridsim)
-    rotation: usize,
+    pub(super) rotation: usize,
     pub generation: usize,
-    memory: ArrayVec<[f64; NUM_STATE]>,
-    code: Arc<Dna>,
+    /// Shared by every descendant of the same randomly spawned brain.
+    pub lineage: u64,
+    pub(super) memory: ArrayVec<[f64; NUM_STATE]>,
+    pub(super) code: Arc<Dna>,
+    /// What this brain did since it was spawned, divided off or combined, which is not saved.
+    pub(super) life: Life,
+    /// Whether an external controller makes the decisions for this brain.
+    pub possessed: bool,
+    /// The decision the external controller made this tick.
+    pub forced: Option<Decision>,
+    /// Which parts of the genome ran, while the brain is inspected, which is not saved.
+    pub(super) expression: Option<Box<ExpressionTrace>>,
+    /// How each decision came about, while the execution of the brain is traced, which is not
+    /// saved.
+    pub(super) execution: Option<Box<ExecutionTrace>>,
+    /// The ids of the brains this one was born of, the one whose lineage it kept first, until
+    /// the ancestry records its birth, which is not saved.
+    pub(super) parents: Vec<u64>,
+}
+
+/// Counts of what a brain did over its life.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub(super) struct Life {
+    /// Ticks the brain made a decision in.
+    pub(super) ticks: u32,
+    pub(super) moves: u32,
+    /// Decisions to trade on the market or with a neighbor.
+    pub(super) trades: u32,
+    pub(super) offspring: u32,
+    /// Ticks in a row the brain has spent at zero food.
+    pub(super) hungry: u32,
+    /// The kind of decision made in the last tick the brain decided in.
+    pub(super) last: Option<Choice>,
+    /// Codons run in the last decision, until the food for running them is paid.
+    pub(super) executed: u32,
+    /// The share of what was left of its last order which the last clearing it was in filled.
+    pub(super) filled: f64,
+    /// The price its last order was last filled at, zero until one is.
+    pub(super) fill_price: f64,
 }
 
 impl Brain {
@@ -71,6 +292,7 @@ impl Brain {
         Color::from_rgb(rgb.red as f32, rgb.green as f32, rgb.blue as f32)
     }
 
+    /// The first slot of memory, which is sanitized whenever the brain decides.
     pub fn signal(&self) -> f64 {
         self.memory[0]
     }
@@ -79,6 +301,16 @@ impl Brain {
         self.rotation
     }
 
+    /// The number of codons in the genome.
+    pub(super) fn genome_len(&self) -> usize {
+        self.code.sequence.len()
+    }
+
+    /// Whether the genome runs its entries in order rather than shuffled.
+    pub(super) fn ordered(&self) -> bool {
+        self.code.ordered
+    }
+
     pub fn rotate(&self, mut decision: Decision) -> Decision {
         let rot = |mut dir: MooreDirection| {
             for _ in 0..self.rotation {
@@ -89,31 +321,144 @@ impl Brain {
         match &mut decision {
             Decision::Divide(dir) => *dir = rot(*dir),
             Decision::Move(dir) => *dir = rot(*dir),
+            Decision::Offer(dir, ..) => *dir = rot(*dir),
+            Decision::Accept(dir) => *dir = rot(*dir),
             Decision::Nothing | Decision::Trade(..) => {}
         }
         decision
     }
 
-    pub fn decide(&mut self, rng: &mut impl Rng, inputs: &[f64]) -> Decision {
+    /// Runs the genome on the inputs, forgetting memory by the chance given if there is one and
+    /// sanitizing what is written to it, counting into the tally of the world.
+    pub(super) fn decide(
+        &mut self,
+        rng: &mut impl Rng,
+        inputs: &[f64],
+        sanitization: Sanitization,
+        forgetting: Option<Bernoulli>,
+        tally: &Tally,
+    ) -> Decision {
+        noise::forget(&mut *rng, forgetting, &mut self.memory);
+        // The signal may have been written under other rules, such as before they changed or
+        // the world was loaded.
+        self.memory[0] = sanitize(sanitization, self.memory[0], &tally.sanitized_signals);
         let mut decision = Decision::Nothing;
-        let mut entries = self.code.entries.clone();
-        entries.shuffle(rng);
-        for &entry in &entries {
-            match self.code.execute(inputs, &self.memory, entry) {
+        let mut shuffled;
+        let entries = if self.code.ordered {
+            &self.code.entries[..]
+        } else {
+            shuffled = self.code.entries.clone();
+            shuffled.shuffle(rng);
+            &shuffled[..]
+        };
+        // Only an inspected or sampled brain keeps track of what ran.
+        let sampled = self.id % JUNK_SAMPLING == 0;
+        let tracing = sampled || self.expression.is_some();
+        let mut ran = vec![false; if tracing { self.code.sequence.len() } else { 0 }];
+        let mut acted = vec![];
+        let mut decider = None;
+        let mut executed = 0;
+        let mut execution = self.execution.as_ref().map(|_| BrainTrace {
+            tick: 0,
+            inputs: inputs.to_vec(),
+            memory: self.memory.to_vec(),
+            rotation: self.rotation,
+            entries: vec![],
+            decision: String::new(),
+        });
+        for &entry in entries {
+            let mut steps = execution.as_ref().map(|_| vec![]);
+            let action = self.code.execute(
+                inputs,
+                &self.memory,
+                entry,
+                if tracing { Some(&mut ran) } else { None },
+                steps.as_mut(),
+                &mut executed,
+                tally,
+            );
+            if let (Some(execution), Some(steps)) = (&mut execution, steps) {
+                execution.entries.push(EntryTrace {
+                    entry,
+                    steps,
+                    action: format!("{:?}", action),
+                });
+            }
+            if tracing && !matches!(action, Action::Nothing) {
+                acted.push(entry);
+            }
+            match action {
                 Action::Write(pos, v) => {
                     let writepos = pos as usize % self.memory.len();
-                    self.memory[writepos] = v;
+                    self.memory[writepos] = sanitize(sanitization, v, &tally.sanitized_memory);
                 }
                 Action::RotateLeft => self.rotation = (self.rotation + 1) % 4,
                 Action::RotateRight => self.rotation = (self.rotation + 3) % 4,
-                action => decision = action.into(),
+                action => {
+                    // A later entry doing nothing undoes the decision of an earlier one.
+                    decider = match action {
+                        Action::Nothing => None,
+                        _ => Some(entry),
+                    };
+                    decision = action.into();
+                }
+            }
+        }
+        if let Some(ref mut expression) = self.expression {
+            expression.record(&self.code, &ran, &acted, decider);
+        }
+        if sampled {
+            Tally::add(&tally.sampled_codons, ran.len());
+            Tally::add(&tally.idle_codons, ran.iter().filter(|&&ran| !ran).count());
+        }
+        self.life.ticks += 1;
+        self.life.last = Some(decision.into());
+        self.life.executed = executed;
+        match decision {
+            Decision::Move(_) => self.life.moves += 1,
+            Decision::Trade(..) | Decision::Offer(..) | Decision::Accept(_) => {
+                self.life.trades += 1
             }
+            Decision::Divide(_) | Decision::Nothing => {}
+        }
+        let decision = self.rotate(decision);
+        if let (Some(mut execution), Some(traced)) = (execution, &mut self.execution) {
+            execution.decision = format!("{:?}", decision);
+            traced.decisions.push(execution);
         }
-        self.rotate(decision)
+        decision
+    }
+
+    /// Starts keeping track of which parts of the genome run, or stops with `false`.
+    pub(super) fn trace_expression(&mut self, tracing: bool) {
+        self.expression = if tracing {
+            Some(Box::new(ExpressionTrace::new(&self.code)))
+        } else {
+            None
+        };
+    }
+
+    /// Which parts of the genome ran since the brain was inspected or last mutated.
+    pub(super) fn expression(&self) -> Option<Expression> {
+        self.expression
+            .as_ref()
+            .map(|expression| expression.report(&self.code))
     }
 
-    pub fn mutate(&mut self, rng: &mut impl Rng) {
-        Arc::make_mut(&mut self.code).mutate(rng);
+    /// Starts recording how each decision comes about codon by codon, or stops with `false`.
+    pub(super) fn trace_execution(&mut self, tracing: bool) {
+        self.execution = if tracing { Some(Box::default()) } else { None };
+    }
+
+    /// The decisions traced since they were last taken, oldest first.
+    pub(super) fn take_execution(&mut self) -> Vec<BrainTrace> {
+        self.execution
+            .as_mut()
+            .map_or(vec![], |execution| std::mem::take(&mut execution.decisions))
+    }
+
+    pub(super) fn mutate(&mut self, rng: &mut impl Rng, mutations: &Mutations, codons: Codons) {
+        Arc::make_mut(&mut self.code).mutate(rng, mutations, codons);
         // Color is updated to differentiate species even so many mutations.
         if self.code.mutated % RECOLOR_AFTER_MUTATIONS == 0 {
             self.color = random_color(rng);
@@ -121,18 +466,26 @@ impl Brain {
     }
 }
 
-impl Distribution<Brain> for Standard {
+impl Distribution<Brain> for Codons {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Brain {
         let memory = std::iter::repeat(0.0).collect();
         let rotation = rng.gen_range(0, 4);
-        let code = Arc::new(rng.gen());
+        let code = Arc::new(rng.sample(self));
         let color = random_color(rng);
         Brain {
+            id: next_id(),
             color,
             rotation,
             generation: 0,
+            lineage: rng.gen(),
             memory,
             code,
+            life: Life::default(),
+            possessed: false,
+            forced: None,
+            expression: None,
+            execution: None,
+            parents: vec![],
         }
     }
 }
@@ -156,14 +509,33 @@ fn crossover(rng: &mut impl Rng, dnas: impl IntoIterator<Item = Dna>) -> Dna {
 
     // First shuffle the DNA to avoid bias.
     dnas.shuffle(rng);
+    // The order of the entries comes from a random parent.
+    let ordered = dnas.first().map(|dna| dna.ordered).unwrap_or(false);
 
     // Now we want to turn the DNA into "genes", for which there may be an unequal number on each DNA.
-    let mut genes: Vec<Vec<Vec<Codon>>> = dnas
+    // Each gene is kept along with whether it begins at an entry.
+    let mut genes: Vec<Vec<(bool, Vec<Codon>)>> = dnas
         .into_iter()
         .map(|dna| {
+            // Genes begin at the entries and at the separators, which start the gene after them.
+            let boundaries = dna
+                .entries
+                .iter()
+                .copied()
+                .merge(dna.separators())
+                .dedup()
+                .collect_vec();
             // Entries are always sorted. Extract all the sequence ranges in the DNA (genes).
-            split_points(&dna.entries, &dna.sequence)
-                .map(|s| s.to_vec())
+            let mut start = 0;
+            split_points(&boundaries, &dna.sequence)
+                .enumerate()
+                .map(|(i, s)| {
+                    // The leading part before the first entry was always given one.
+                    let entry = (i == 0 && !dna.entries.is_empty())
+                        || dna.entries.binary_search(&start).is_ok();
+                    start += s.len();
+                    (entry, s.to_vec())
+                })
                 .collect_vec()
         })
         .collect_vec();
@@ -182,39 +554,158 @@ fn crossover(rng: &mut impl Rng, dnas: impl IntoIterator<Item = Dna>) -> Dna {
         // Distribute empty genes randomly.
         for _ in 0..off_by {
             let position = rng.gen_range(0, genes.len() + 1);
-            genes.insert(position, vec![]);
+            genes.insert(position, (false, vec![]));
         }
     }
 
     // Now perform crossover by cycling beteween each DNA and taking a gene in order.
-    let mut dna = Dna::default();
+    let mut dna = Dna {
+        ordered,
+        ..Dna::default()
+    };
     for i in 0..highest_num_genes {
         let which = rng.gen_range(0, genes.len());
-        let gene = &genes[which][i][..];
+        let (entry, ref gene) = genes[which][i];
         if !gene.is_empty() {
             let position = dna.sequence.len();
             dna.sequence.extend_from_slice(gene);
-            dna.entries.push(position);
+            if entry {
+                dna.entries.push(position);
+            }
         }
     }
     dna
 }
 
-#[derive(Clone, Debug, Default)]
-struct Dna {
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(super) struct Dna {
+    sequence: Vec<Codon>,
+    entries: Vec<usize>,
+    mutated: usize,
+    /// The entries run in order every tick rather than in a new random order, so the behavior
+    /// of the brain is deterministic.
+    ordered: bool,
+}
+
+/// A genome as saved before it had an order, which always shuffled its entries.
+#[derive(Deserialize)]
+pub(super) struct DnaV10 {
     sequence: Vec<Codon>,
     entries: Vec<usize>,
     mutated: usize,
 }
 
 impl Dna {
-    fn mutate(&mut self, rng: &mut impl Rng) {
-        self.mutated += 1;
-        // Handle the creation and removal of codons.
+    /// Where the separator codons are, in order.
+    fn separators(&self) -> impl Iterator<Item = usize> + '_ {
+        self.sequence
+            .iter()
+            .positions(|&codon| codon == Codon::Separator)
+    }
+
+    /// The genes, which begin at the start and at every entry and separator, as crossover cuts
+    /// them.
+    fn genes(&self) -> Vec<Range<usize>> {
+        once(0)
+            .merge(self.entries.iter().copied())
+            .merge(self.separators())
+            .dedup()
+            .chain(once(self.sequence.len()))
+            .tuple_windows()
+            .map(|(start, end)| start..end)
+            .filter(|gene| !gene.is_empty())
+            .collect()
+    }
+
+    /// Each codon along with how many entries it has, so that codons can be moved with their
+    /// entries.
+    fn marked(&self) -> Vec<(Codon, usize)> {
+        let mut marked: Vec<(Codon, usize)> =
+            self.sequence.iter().map(|&codon| (codon, 0)).collect();
+        for &entry in &self.entries {
+            marked[entry].1 += 1;
+        }
+        marked
+    }
+
+    fn unmark(&mut self, marked: Vec<(Codon, usize)>) {
+        self.entries = marked
+            .iter()
+            .enumerate()
+            .flat_map(|(position, &(_, entries))| std::iter::repeat(position).take(entries))
+            .collect();
+        self.sequence = marked.into_iter().map(|(codon, _)| codon).collect();
+    }
+
+    /// Nudges the operand of a random codon, or replaces it if it has none.
+    fn point_mutate(&mut self, rng: &mut impl Rng, codons: Codons) {
+        if let Some(codon) = self.sequence.choose_mut(rng) {
+            *codon = codon.nudged(rng, codons);
+        }
+    }
+
+    /// Copies a random gene in right after itself, along with its entries.
+    fn duplicate(&mut self, rng: &mut impl Rng) {
+        let gene = match self.genes().choose(rng) {
+            Some(gene) if self.sequence.len() + gene.len() <= MAX_DUPLICATED_LENGTH => gene.clone(),
+            _ => return,
+        };
+        let mut marked = self.marked();
+        let copy = marked[gene.clone()].to_vec();
+        marked.splice(gene.end..gene.end, copy);
+        self.unmark(marked);
+    }
+
+    /// Moves a random gene, along with its entries, to the start of another or to the end.
+    fn transpose(&mut self, rng: &mut impl Rng) {
+        let genes = self.genes();
+        let gene = match genes.choose(rng) {
+            Some(gene) => gene.clone(),
+            None => return,
+        };
+        let mut marked = self.marked();
+        let moved: Vec<_> = marked.drain(gene.clone()).collect();
+        // Where the other genes start once it is taken out, except where it was.
+        let places: Vec<usize> = genes
+            .iter()
+            .map(|other| match other.start {
+                start if start > gene.start => start - gene.len(),
+                start => start,
+            })
+            .chain(once(marked.len()))
+            .filter(|&place| place != gene.start)
+            .collect();
+        let place = places.choose(rng).copied().unwrap_or(gene.start);
+        marked.splice(place..place, moved);
+        self.unmark(marked);
+    }
+
+    /// The memory taken by the genome, counting what it allocated as well.
+    pub(super) fn bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.sequence.capacity() * std::mem::size_of::<Codon>()
+            + self.entries.capacity() * std::mem::size_of::<usize>()
+    }
+}
+
+impl From<DnaV10> for Dna {
+    fn from(dna: DnaV10) -> Self {
+        Dna {
+            sequence: dna.sequence,
+            entries: dna.entries,
+            mutated: dna.mutated,
+            ordered: false,
+        }
+    }
+}
+
+impl Dna {
+    /// Inserts a random codon or removes one, half the time each.
+    fn indel(&mut self, rng: &mut impl Rng, codons: Codons) {
         if rng.sample(*HALF_CHANCE) {
             // Add a codon.
             let position = rng.gen_range(0, self.sequence.len() + 1);
-            self.sequence.insert(position, rng.gen::<Codon>());
+            self.sequence.insert(position, rng.sample(codons));
             // Move entries.
             for entry in &mut self.entries {
                 if *entry >= position {
@@ -234,6 +725,23 @@ impl Dna {
                 }
             }
         }
+    }
+
+    fn mutate(&mut self, rng: &mut impl Rng, mutations: &Mutations, codons: Codons) {
+        self.mutated += 1;
+        // Handle the creation and removal of codons.
+        if rng.gen_bool(mutations.indel) {
+            self.indel(rng, codons);
+        }
+        if rng.gen_bool(mutations.point) {
+            self.point_mutate(rng, codons);
+        }
+        if rng.gen_bool(mutations.duplication) {
+            self.duplicate(rng);
+        }
+        if rng.gen_bool(mutations.transposition) {
+            self.transpose(rng);
+        }
 
         // Handle the creation and removal of entry points.
         if !self.sequence.is_empty() && rng.sample(*HALF_CHANCE) {
@@ -250,11 +758,57 @@ impl Dna {
             let position = rng.gen_range(0, self.entries.len());
             self.entries.remove(position);
         }
+
+        if rng.sample(*ORDER_MUTATION) {
+            self.ordered = !self.ordered;
+        }
     }
 
-    fn execute(&self, inputs: &[f64], memory: &[f64], mut at: usize) -> Action {
+    /// How many codons can run from some entry, following the genome from each entry until a
+    /// codon which always ends the run or until the most codons a run may take.
+    fn reachable(&self) -> usize {
+        let len = self.sequence.len();
+        let mut reached = vec![false; len];
+        for &entry in &self.entries {
+            let mut at = entry;
+            for _ in 0..MAX_EXECUTE {
+                reached[at] = true;
+                if self.sequence[at].ends_run() {
+                    break;
+                }
+                at = (at + 1) % len;
+            }
+        }
+        reached.iter().filter(|&&reached| reached).count()
+    }
+
+    /// Runs the genome from an entry until it acts or gives up, marking each codon which ran in
+    /// `ran` and recording each with the stack it ran on in `steps` when they are given, and
+    /// counting the codons run in `executed`.
+    fn execute(
+        &self,
+        inputs: &[f64],
+        memory: &[f64],
+        mut at: usize,
+        mut ran: Option<&mut [bool]>,
+        mut steps: Option<&mut Vec<TraceStep>>,
+        executed: &mut u32,
+        tally: &Tally,
+    ) -> Action {
+        let clamp = |n| clamp(n, &tally.sanitized_trades);
         let mut stack = vec![];
         for _ in 0..MAX_EXECUTE {
+            *executed += 1;
+            if let Some(ref mut ran) = ran {
+                ran[at] = true;
+            }
+            if let Some(ref mut steps) = steps {
+                steps.push(TraceStep {
+                    at,
+                    codon: format!("{:?}", self.sequence[at]),
+                    stack: stack.clone(),
+                });
+            }
             match self.sequence[at] {
                 Codon::Add => {
                     if let Some(o) = stack.pop().and_then(|b| stack.pop().map(|a| a + b)) {
@@ -313,30 +867,37 @@ impl Dna {
                 }
                 Codon::Move(dir) => return Action::Move(dir),
                 Codon::Divide(dir) => return Action::Divide(dir),
-                Codon::Trade => {
-                    let clamp = |n: f64| {
-                        if n.is_finite() {
-                            if n > 10_000.0 {
-                                10_000.0
-                            } else if n < -10_000.0 {
-                                -10_000.0
-                            } else {
-                                n
-                            }
-                        } else {
-                            0.0
-                        }
-                    };
-                    match (stack.pop(), stack.pop()) {
-                        (Some(a), Some(b)) => {
-                            return Action::Trade(clamp(a) as i32, clamp(b) as i32)
-                        }
-                        _ => break,
+                Codon::Trade => match (stack.pop(), stack.pop()) {
+                    (Some(a), Some(b)) => {
+                        return Action::Trade(clamp(a) as i32, clamp(b) as i32, Limit::default())
                     }
-                }
-                Codon::SimpleTrade(a, b) => return Action::Trade(a, b),
+                    _ => break,
+                },
+                Codon::Offer(dir) => match (stack.pop(), stack.pop()) {
+                    (Some(food), Some(money)) => {
+                        return Action::Offer(dir, clamp(food) as i32, clamp(money) as i32)
+                    }
+                    _ => break,
+                },
+                Codon::Accept(dir) => return Action::Accept(dir),
+                Codon::SimpleTrade(a, b) => return Action::Trade(a, b, Limit::default()),
+                Codon::LimitTrade => match (stack.pop(), stack.pop(), stack.pop(), stack.pop()) {
+                    (Some(a), Some(b), Some(expiry), Some(all_or_nothing)) => {
+                        let expiry = clamp(expiry).max(0.0).min(MAX_EXPIRY as f64) as u32;
+                        return Action::Trade(
+                            clamp(a) as i32,
+                            clamp(b) as i32,
+                            Limit {
+                                expiry,
+                                all_or_nothing: all_or_nothing > 0.0,
+                            },
+                        );
+                    }
+                    _ => break,
+                },
                 Codon::RotateLeft => return Action::RotateLeft,
                 Codon::RotateRight => return Action::RotateRight,
+                Codon::Separator => {}
             }
             at = (at + 1) % self.sequence.len();
         }
@@ -344,10 +905,23 @@ impl Dna {
     }
 }
 
-impl Distribution<Dna> for Standard {
+/// Limits an amount a brain computed to something which can be traded, counting it if it changed.
+fn clamp(n: f64, count: &AtomicUsize) -> f64 {
+    if n.is_finite() && n.abs() <= 10_000.0 {
+        return n;
+    }
+    Tally::add(count, 1);
+    if n.is_finite() {
+        n.max(-10_000.0).min(10_000.0)
+    } else {
+        0.0
+    }
+}
+
+impl Distribution<Dna> for Codons {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Dna {
         let sequence_len = (rng.sample::<f64, _>(Exp1) * INITIAL_GENOME_SCALE) as usize;
-        let sequence = rng.sample_iter(Standard).take(sequence_len).collect();
+        let sequence = rng.sample_iter(*self).take(sequence_len).collect();
         let entries = {
             if sequence_len == 0 {
                 vec![]
@@ -365,11 +939,22 @@ impl Distribution<Dna> for Standard {
             sequence,
             entries,
             mutated: 0,
+            ordered: rng.sample(*HALF_CHANCE),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// Lets codons be saved even though the direction type is not ours.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MooreDirection")]
+enum MooreDirectionDef {
+    Right,
+    Up,
+    Left,
+    Down,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum Codon {
     Add,
     Sub,
@@ -381,17 +966,75 @@ enum Codon {
     Read(u32),
     Input(u32),
     Write(u32),
-    Move(MooreDirection),
-    Divide(MooreDirection),
+    Move(#[serde(with = "MooreDirectionDef")] MooreDirection),
+    Divide(#[serde(with = "MooreDirectionDef")] MooreDirection),
     Trade,
     SimpleTrade(i32, i32),
     RotateLeft,
     RotateRight,
+    /// Offers the neighbor the food and money popped from the stack, asking for them when negative.
+    Offer(#[serde(with = "MooreDirectionDef")] MooreDirection),
+    /// Accepts whatever the neighbor offered this cell last tick.
+    Accept(#[serde(with = "MooreDirectionDef")] MooreDirection),
+    /// Places an order like `Trade`, then pops how many ticks it rests in the book and whether it
+    /// is all-or-nothing, when positive.
+    LimitTrade,
+    /// Does nothing when run, but marks where crossover prefers to cut the genome.
+    Separator,
+}
+
+impl Codon {
+    /// Whether running the codon always ends the run, by acting or by giving up.
+    fn ends_run(self) -> bool {
+        !matches!(
+            self,
+            Codon::Add
+                | Codon::Sub
+                | Codon::Mul
+                | Codon::Div
+                | Codon::Literal(_)
+                | Codon::Less
+                | Codon::Copy(_)
+                | Codon::Read(_)
+                | Codon::Input(_)
+                | Codon::Separator
+        )
+    }
+}
+
+/// A random direction of the neighborhood, which holds only the four orthogonal neighbors, so
+/// that moving, dividing and exchanging never reach a diagonal one.
+fn random_direction<R: Rng + ?Sized>(rng: &mut R) -> MooreDirection {
+    match rng.gen_range(0, 4) {
+        0 => MooreDirection::Right,
+        1 => MooreDirection::Up,
+        2 => MooreDirection::Left,
+        3 => MooreDirection::Down,
+        _ => unreachable!(),
+    }
 }
 
-impl Distribution<Codon> for Standard {
+/// A random direction other than the one given.
+fn turned(rng: &mut impl Rng, dir: MooreDirection) -> MooreDirection {
+    loop {
+        let turned = random_direction(rng);
+        if turned != dir {
+            return turned;
+        }
+    }
+}
+
+/// Draws random codons, and the genomes and brains made of them.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Codons {
+    /// Whether the direct exchange codons are drawn, which they are only while it is enabled.
+    pub(super) direct_exchange: bool,
+}
+
+impl Distribution<Codon> for Codons {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Codon {
-        match rng.gen_range(0, 18) {
+        let kinds = if self.direct_exchange { 22 } else { 20 };
+        match rng.gen_range(0, kinds) {
             0 => Codon::Add,
             1 => Codon::Sub,
             2 => Codon::Mul,
@@ -402,51 +1045,565 @@ impl Distribution<Codon> for Standard {
             7 => Codon::Read(rng.gen::<u32>() % NUM_STATE as u32),
             8 => Codon::Input(rng.gen()),
             9 => Codon::Write(rng.gen::<u32>() % NUM_STATE as u32),
-            10 => Codon::Move(match rng.gen_range(0, 4) {
-                0 => MooreDirection::Right,
-                1 => MooreDirection::Up,
-                2 => MooreDirection::Left,
-                3 => MooreDirection::Down,
-                _ => unreachable!(),
-            }),
-            11 => Codon::Divide(match rng.gen_range(0, 4) {
-                0 => MooreDirection::Right,
-                1 => MooreDirection::Up,
-                2 => MooreDirection::Left,
-                3 => MooreDirection::Down,
-                _ => unreachable!(),
-            }),
+            10 => Codon::Move(random_direction(rng)),
+            11 => Codon::Divide(random_direction(rng)),
             12 => Codon::Trade,
             13 => Codon::RotateLeft,
             14 => Codon::RotateRight,
+            15 => Codon::LimitTrade,
+            16 => Codon::Separator,
+            20 => Codon::Offer(random_direction(rng)),
+            21 => Codon::Accept(random_direction(rng)),
             _ => Codon::SimpleTrade(rng.gen_range(1, 50), rng.gen_range(-10, 10)),
         }
     }
 }
 
+impl Codon {
+    /// This codon with its operand nudged, or a random codon if it has none.
+    fn nudged(self, rng: &mut impl Rng, codons: Codons) -> Codon {
+        match self {
+            Codon::Literal(n) => Codon::Literal(n + rng.gen_range(-0.5, 0.5)),
+            Codon::Copy(_) => Codon::Copy(rng.gen()),
+            Codon::Read(_) => Codon::Read(rng.gen::<u32>() % NUM_STATE as u32),
+            Codon::Input(_) => Codon::Input(rng.gen()),
+            Codon::Write(_) => Codon::Write(rng.gen::<u32>() % NUM_STATE as u32),
+            Codon::Move(dir) => Codon::Move(turned(rng, dir)),
+            Codon::Divide(dir) => Codon::Divide(turned(rng, dir)),
+            Codon::Offer(dir) => Codon::Offer(turned(rng, dir)),
+            Codon::Accept(dir) => Codon::Accept(turned(rng, dir)),
+            Codon::SimpleTrade(rate, food) => {
+                Codon::SimpleTrade((rate + rng.gen_range(-5, 6)).max(1), food)
+            }
+            _ => rng.sample(codons),
+        }
+    }
+
+    /// The kind of codon and its operand as raw bits, so that codons can be compared exactly.
+    fn bits(&self) -> (u8, u64) {
+        let dir = |dir: MooreDirection| match dir {
+            MooreDirection::Right => 0,
+            MooreDirection::Up => 1,
+            MooreDirection::Left => 2,
+            MooreDirection::Down => 3,
+        };
+        match *self {
+            Codon::Add => (0, 0),
+            Codon::Sub => (1, 0),
+            Codon::Mul => (2, 0),
+            Codon::Div => (3, 0),
+            Codon::Literal(n) => (4, n.to_bits()),
+            Codon::Less => (5, 0),
+            Codon::Copy(pos) => (6, pos as u64),
+            Codon::Read(pos) => (7, pos as u64),
+            Codon::Input(pos) => (8, pos as u64),
+            Codon::Write(pos) => (9, pos as u64),
+            Codon::Move(d) => (10, dir(d)),
+            Codon::Divide(d) => (11, dir(d)),
+            Codon::Trade => (12, 0),
+            Codon::SimpleTrade(a, b) => (13, (a as u32 as u64) << 32 | b as u32 as u64),
+            Codon::RotateLeft => (14, 0),
+            Codon::RotateRight => (15, 0),
+            Codon::Offer(d) => (16, dir(d)),
+            Codon::Accept(d) => (17, dir(d)),
+            Codon::LimitTrade => (18, 0),
+            Codon::Separator => (19, 0),
+        }
+    }
+}
+
+/// A genome which is equal to another only when every codon, entry, the order of the entries and
+/// the mutation count match bit for bit.
+struct GenomeKey(Arc<Dna>);
+
+impl PartialEq for GenomeKey {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+            || (self.0.mutated == other.0.mutated
+                && self.0.ordered == other.0.ordered
+                && self.0.entries == other.0.entries
+                && self.0.sequence.len() == other.0.sequence.len()
+                && self
+                    .0
+                    .sequence
+                    .iter()
+                    .zip(&other.0.sequence)
+                    .all(|(a, b)| a.bits() == b.bits()))
+    }
+}
+
+impl Eq for GenomeKey {}
+
+impl Hash for GenomeKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.mutated.hash(state);
+        self.0.ordered.hash(state);
+        self.0.entries.hash(state);
+        for codon in &self.0.sequence {
+            codon.bits().hash(state);
+        }
+    }
+}
+
+/// A genome taken from a brain, which stays available after the brain is gone.
+#[derive(Clone, Debug)]
+pub struct Genome(Arc<Dna>);
+
+impl Serialize for Genome {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+// A genome read back from a file is checked, since an entry past the end would panic on running.
+impl<'de> Deserialize<'de> for Genome {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let dna = Dna::deserialize(deserializer)?;
+        if let Some(&entry) = dna
+            .entries
+            .iter()
+            .find(|&&entry| entry >= dna.sequence.len())
+        {
+            return Err(serde::de::Error::custom(format!(
+                "entry {} is past the last of {} codons",
+                entry,
+                dna.sequence.len()
+            )));
+        }
+        Ok(Genome(Arc::new(dna)))
+    }
+}
+
+impl Genome {
+    /// How many codons the genome has.
+    pub fn codon_count(&self) -> usize {
+        self.0.sequence.len()
+    }
+}
+
+impl Brain {
+    pub fn genome(&self) -> Genome {
+        Genome(self.code.clone())
+    }
+
+    /// Every codon of the genome in order.
+    pub(super) fn codons(&self) -> Vec<Locus> {
+        (0..self.code.sequence.len())
+            .map(|index| locus(&self.code, index))
+            .collect()
+    }
+
+    /// A newly spawned brain with a genome kept from an earlier one, continuing its lineage.
+    pub(super) fn revive(rng: &mut impl Rng, genome: &Genome, lineage: u64) -> Brain {
+        Brain {
+            id: next_id(),
+            color: random_color(rng),
+            rotation: rng.gen_range(0, 4),
+            generation: 0,
+            lineage,
+            memory: std::iter::repeat(0.0).collect(),
+            code: genome.0.clone(),
+            life: Life::default(),
+            possessed: false,
+            forced: None,
+            expression: None,
+            execution: None,
+            parents: vec![],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    Same,
+    Substituted,
+    /// Only the second genome has the codon.
+    Inserted,
+    /// Only the first genome has the codon.
+    Deleted,
+}
+
+/// A codon at some position in a genome.
+#[derive(Clone, Debug, Serialize)]
+pub struct Locus {
+    pub index: usize,
+    pub codon: String,
+    /// Whether execution starts at this codon.
+    pub entry: bool,
+}
+
+/// One column of the alignment of two genomes.
+#[derive(Clone, Debug)]
+pub struct DiffRow {
+    pub edit: Edit,
+    pub a: Option<Locus>,
+    pub b: Option<Locus>,
+}
+
+impl DiffRow {
+    /// Whether the codons or entry points differ.
+    pub fn is_change(&self) -> bool {
+        self.edit != Edit::Same
+            || self.a.as_ref().map(|l| l.entry) != self.b.as_ref().map(|l| l.entry)
+    }
+}
+
+fn locus(dna: &Dna, index: usize) -> Locus {
+    Locus {
+        index,
+        codon: format!("{:?}", dna.sequence[index]),
+        entry: dna.entries.binary_search(&index).is_ok(),
+    }
+}
+
+/// Globally aligns the codon sequences of two genomes (Needleman-Wunsch) with unit costs.
+pub fn diff(a: &Genome, b: &Genome) -> Vec<DiffRow> {
+    let (a, b) = (&*a.0, &*b.0);
+    let (n, m) = (a.sequence.len(), b.sequence.len());
+    let same = |i: usize, j: usize| a.sequence[i].bits() == b.sequence[j].bits();
+
+    // The fewest edits which turn the first i codons of a into the first j codons of b.
+    let mut costs = vec![0usize; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in 0..=n {
+        costs[at(i, 0)] = i;
+    }
+    for j in 0..=m {
+        costs[at(0, j)] = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitute = costs[at(i - 1, j - 1)] + if same(i - 1, j - 1) { 0 } else { 1 };
+            let delete = costs[at(i - 1, j)] + 1;
+            let insert = costs[at(i, j - 1)] + 1;
+            costs[at(i, j)] = substitute.min(delete).min(insert);
+        }
+    }
+
+    let mut rows = vec![];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let cost = costs[at(i, j)];
+        if i > 0
+            && j > 0
+            && cost == costs[at(i - 1, j - 1)] + if same(i - 1, j - 1) { 0 } else { 1 }
+        {
+            i -= 1;
+            j -= 1;
+            rows.push(DiffRow {
+                edit: if same(i, j) {
+                    Edit::Same
+                } else {
+                    Edit::Substituted
+                },
+                a: Some(locus(a, i)),
+                b: Some(locus(b, j)),
+            });
+        } else if i > 0 && cost == costs[at(i - 1, j)] + 1 {
+            i -= 1;
+            rows.push(DiffRow {
+                edit: Edit::Deleted,
+                a: Some(locus(a, i)),
+                b: None,
+            });
+        } else {
+            j -= 1;
+            rows.push(DiffRow {
+                edit: Edit::Inserted,
+                a: None,
+                b: Some(locus(b, j)),
+            });
+        }
+    }
+    rows.reverse();
+    rows
+}
+
+/// What ran of the genome of an inspected brain, counted in ticks.
+#[derive(Clone, Debug)]
+pub(super) struct ExpressionTrace {
+    /// The mutation count of the genome it was started on, to start over when it mutates.
+    mutated: usize,
+    ticks: u32,
+    genes: Vec<Range<usize>>,
+    /// Ticks each codon ran in.
+    codons: Vec<u32>,
+    /// Ticks any codon of each gene ran in.
+    genes_ran: Vec<u32>,
+    /// Ticks the entry at each codon produced an action of any kind.
+    acted: Vec<u32>,
+    /// Ticks the entry at each codon produced the decision the brain acted on.
+    decided: Vec<u32>,
+}
+
+impl ExpressionTrace {
+    fn new(dna: &Dna) -> Self {
+        let genes = dna.genes();
+        let len = dna.sequence.len();
+        Self {
+            mutated: dna.mutated,
+            ticks: 0,
+            codons: vec![0; len],
+            genes_ran: vec![0; genes.len()],
+            acted: vec![0; len],
+            decided: vec![0; len],
+            genes,
+        }
+    }
+
+    fn record(&mut self, dna: &Dna, ran: &[bool], acted: &[usize], decider: Option<usize>) {
+        if dna.mutated != self.mutated || dna.sequence.len() != self.codons.len() {
+            *self = Self::new(dna);
+        }
+        self.ticks += 1;
+        for (count, _) in self.codons.iter_mut().zip(ran).filter(|&(_, &ran)| ran) {
+            *count += 1;
+        }
+        for (count, gene) in self.genes_ran.iter_mut().zip(&self.genes) {
+            if ran[gene.clone()].iter().any(|&ran| ran) {
+                *count += 1;
+            }
+        }
+        for &entry in acted {
+            self.acted[entry] += 1;
+        }
+        if let Some(entry) = decider {
+            self.decided[entry] += 1;
+        }
+    }
+
+    fn report(&self, dna: &Dna) -> Expression {
+        let genes = self
+            .genes
+            .iter()
+            .zip(&self.genes_ran)
+            .map(|(gene, &ran)| GeneExpression {
+                start: gene.start,
+                entry: dna.entries.binary_search(&gene.start).is_ok(),
+                codons: gene
+                    .clone()
+                    .map(|index| (format!("{:?}", dna.sequence[index]), self.codons[index]))
+                    .collect(),
+                ran,
+                acted: self.acted[gene.start],
+                decided: self.decided[gene.start],
+            })
+            .collect();
+        Expression {
+            ticks: self.ticks,
+            genes,
+        }
+    }
+}
+
+/// Which genes of an inspected brain ran and which made its decisions, over the ticks since it
+/// was inspected or last mutated.
+#[derive(Clone, Debug, Default)]
+pub struct Expression {
+    pub ticks: u32,
+    /// In the order of the genome, cut as crossover cuts it.
+    pub genes: Vec<GeneExpression>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneExpression {
+    /// Where the gene starts in the genome.
+    pub start: usize,
+    /// Whether execution starts at the start of the gene.
+    pub entry: bool,
+    /// Each codon, as the genome diff shows it, with the ticks it ran in.
+    pub codons: Vec<(String, u32)>,
+    /// Ticks any codon of the gene ran in, whether from its entry or run into from another gene.
+    pub ran: u32,
+    /// Ticks the entry of the gene produced an action, including writes and rotations.
+    pub acted: u32,
+    /// Ticks the entry of the gene produced the decision the brain acted on.
+    pub decided: u32,
+}
+
+impl GeneExpression {
+    /// Whether nothing of the gene ever ran, so it has no effect on behavior.
+    pub fn is_junk(&self) -> bool {
+        self.ran == 0
+    }
+}
+
+/// The decisions of a brain whose execution is traced, kept until they are taken.
+#[derive(Clone, Debug, Default)]
+pub(super) struct ExecutionTrace {
+    decisions: Vec<BrainTrace>,
+}
+
+/// How a traced brain came to its decision in one tick.
+#[derive(Clone, Debug, Serialize)]
+pub struct BrainTrace {
+    pub tick: u64,
+    /// What the brain sensed, turned to the way it faces and with any noise added.
+    pub inputs: Vec<f64>,
+    /// Memory before any entry ran, after anything forgotten.
+    pub memory: Vec<f64>,
+    /// The rotation before any entry ran.
+    pub rotation: usize,
+    /// Each entry in the order it ran, which is shuffled unless the genome is ordered.
+    pub entries: Vec<EntryTrace>,
+    /// The decision acted on, turned to the way the brain faces in the world.
+    pub decision: String,
+}
+
+/// What ran from one entry of a traced brain.
+#[derive(Clone, Debug, Serialize)]
+pub struct EntryTrace {
+    /// Where in the genome the entry starts.
+    pub entry: usize,
+    pub steps: Vec<TraceStep>,
+    /// What the entry produced, with directions as the brain faced when it ran.
+    pub action: String,
+}
+
+/// A codon which ran and the stack it ran on.
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceStep {
+    /// Where the codon is in the genome.
+    pub at: usize,
+    pub codon: String,
+    /// The stack before the codon ran, bottom first.
+    pub stack: Vec<f64>,
+}
+
+/// The number of distinct genome allocations shared by these brains.
+pub fn count_genomes<'a>(brains: impl Iterator<Item = &'a Brain>) -> usize {
+    brains
+        .map(|brain| &*brain.code as *const Dna)
+        .collect::<FxHashSet<_>>()
+        .len()
+}
+
+/// The mean number of separator codons in the genomes of these brains.
+pub fn mean_separators<'a>(brains: impl Iterator<Item = &'a Brain>) -> f64 {
+    let (genomes, separators) = brains.fold((0, 0), |(genomes, separators), brain| {
+        (genomes + 1, separators + brain.code.separators().count())
+    });
+    match genomes {
+        0 => 0.0,
+        genomes => separators as f64 / genomes as f64,
+    }
+}
+
+/// Makes brains with identical genomes share one allocation and returns how many genomes remain.
+pub fn intern<'a>(brains: impl Iterator<Item = &'a mut Brain>) -> usize {
+    let mut genomes: FxHashSet<GenomeKey> = FxHashSet::default();
+    for brain in brains {
+        let key = GenomeKey(brain.code.clone());
+        match genomes.get(&key) {
+            Some(existing) => brain.code = existing.0.clone(),
+            None => {
+                genomes.insert(key);
+            }
+        }
+    }
+    genomes.len()
+}
+
+#[derive(Debug)]
 pub enum Action {
     Write(u32, f64),
     Move(MooreDirection),
     Divide(MooreDirection),
-    Trade(i32, i32),
+    Trade(i32, i32, Limit),
+    Offer(MooreDirection, i32, i32),
+    Accept(MooreDirection),
     RotateLeft,
     RotateRight,
     Nothing,
 }
 
+#[derive(Copy, Clone, Debug)]
 pub enum Decision {
     Move(MooreDirection),
     Divide(MooreDirection),
-    Trade(i32, i32),
+    /// Place an order of food at a rate of money per food, buying when the food is negative.
+    Trade(i32, i32, Limit),
+    /// Offer a neighbor food and money, or ask for them with negative amounts.
+    Offer(MooreDirection, i32, i32),
+    /// Accept the offer a neighbor made to this cell.
+    Accept(MooreDirection),
     Nothing,
 }
 
+/// The kinds of decision the living cells are counted by in the stats.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Choice {
+    Move,
+    Divide,
+    /// An order on the market for food.
+    Buy,
+    /// An order on the market of food.
+    Sell,
+    /// An offer to a neighbor or the acceptance of one.
+    Exchange,
+    /// Nothing, including an order for no food.
+    Nothing,
+}
+
+impl Choice {
+    /// Every kind, in the order they are stacked in the graph.
+    pub const ALL: [Choice; 6] = [
+        Choice::Move,
+        Choice::Divide,
+        Choice::Buy,
+        Choice::Sell,
+        Choice::Exchange,
+        Choice::Nothing,
+    ];
+
+    /// Where the kind is in `ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Choice::Move => "move",
+            Choice::Divide => "divide",
+            Choice::Buy => "buy",
+            Choice::Sell => "sell",
+            Choice::Exchange => "exchange",
+            Choice::Nothing => "nothing",
+        }
+    }
+}
+
+impl From<Decision> for Choice {
+    fn from(decision: Decision) -> Choice {
+        match decision {
+            Decision::Move(_) => Choice::Move,
+            Decision::Divide(_) => Choice::Divide,
+            Decision::Trade(_, food, _) if food < 0 => Choice::Buy,
+            Decision::Trade(_, food, _) if food > 0 => Choice::Sell,
+            Decision::Offer(..) | Decision::Accept(_) => Choice::Exchange,
+            Decision::Trade(..) | Decision::Nothing => Choice::Nothing,
+        }
+    }
+}
+
+/// How many of the brains made each kind of decision in the last tick they decided in, by
+/// `Choice::index`. Brains which have yet to decide are left out.
+pub(super) fn tally_choices<'a>(brains: impl Iterator<Item = &'a Brain>) -> [u32; 6] {
+    let mut tally = [0; 6];
+    for choice in brains.filter_map(|brain| brain.life.last) {
+        tally[choice.index()] += 1;
+    }
+    tally
+}
+
 impl From<Action> for Decision {
     fn from(action: Action) -> Decision {
         match action {
             Action::Move(dir) => Decision::Move(dir),
             Action::Divide(dir) => Decision::Divide(dir),
-            Action::Trade(a, b) => Decision::Trade(a, b),
+            Action::Trade(a, b, limit) => Decision::Trade(a, b, limit),
+            Action::Offer(dir, food, money) => Decision::Offer(dir, food, money),
+            Action::Accept(dir) => Decision::Accept(dir),
             Action::Nothing => Decision::Nothing,
             _ => panic!("you shouldn't try to turn just any action into a decision"),
         }
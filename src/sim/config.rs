@@ -0,0 +1,210 @@
+//! The parameters, rules, noise and mutation chances a world is stepped under, owned by the world
+//! and put into effect for exactly the ticks it runs.
+//!
+//! gridsim steps and updates cells through functions which take no world, so the config of the
+//! world whose cells are being stepped is installed for the length of that step, along with the
+//! random streams of the tick and the tally its cells count what they did into. Installing it
+//! holds off every other world in the process until the step is over, so linked worlds each run
+//! under their own config. Each thread keeps its own handle on the installed config and only
+//! takes a new one once another was installed, so cells stepped in parallel never contend for it.
+//!
+//! Everything else a tick does is handed the config of its world directly.
+
+use super::brain::{Codons, Executed, Mutations, Sanitization, Sanitized};
+use super::masks::Flips;
+use super::noise::{Distributions, Noise};
+use super::streams::Streams;
+use super::{chance, Params, Rules, Tiers};
+use lazy_static::lazy_static;
+use rand::distributions::Bernoulli;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Everything a tick of a world depends on besides the world itself, as it is used.
+#[derive(Clone, Debug)]
+pub(super) struct SimConfig {
+    pub(super) spawn: Bernoulli,
+    pub(super) mutate: Bernoulli,
+    pub(super) general_food: Bernoulli,
+    pub(super) cornacopia_chance: f64,
+    pub(super) cornacopia_bounty: u32,
+    pub(super) direct_exchange: bool,
+    pub(super) sanitization: Sanitization,
+    pub(super) sense_life: bool,
+    pub(super) sense_fills: bool,
+    pub(super) starvation_grace: u32,
+    pub(super) tiers: Tiers,
+    /// The percentage of the money passed to offspring which is taxed.
+    pub(super) inheritance_tax: f64,
+    /// The share of the cells with brains stepped each tick.
+    pub(super) stepped: f64,
+    pub(super) signal_cost: f64,
+    /// Food per codon run.
+    pub(super) metabolism: f64,
+    pub(super) noise: Distributions,
+    pub(super) mutations: Mutations,
+}
+
+impl SimConfig {
+    pub(super) fn new(params: &Params, rules: &Rules, noise: &Noise, mutations: Mutations) -> Self {
+        Self {
+            spawn: chance(params.spawn_chance),
+            mutate: chance(params.mutation_chance),
+            general_food: chance(params.general_food_chance),
+            cornacopia_chance: params.cornacopia_chance,
+            cornacopia_bounty: params.cornacopia_bounty,
+            direct_exchange: rules.direct_exchange,
+            sanitization: rules.sanitization,
+            sense_life: rules.sense_life,
+            sense_fills: rules.sense_fills,
+            starvation_grace: rules.starvation_grace,
+            tiers: rules.tiers,
+            inheritance_tax: rules.inheritance_tax,
+            stepped: rules.approximation.unwrap_or(1.0),
+            signal_cost: rules.signal_cost,
+            metabolism: rules.metabolism,
+            noise: noise.distributions(),
+            mutations,
+        }
+    }
+
+    /// What the new codons of spawned and mutated brains are drawn from.
+    pub(super) fn codons(&self) -> Codons {
+        Codons {
+            direct_exchange: self.direct_exchange,
+        }
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self::new(
+            &Params::default(),
+            &Rules::default(),
+            &Noise::default(),
+            Mutations::default(),
+        )
+    }
+}
+
+/// What the cells of a world counted while they were stepped and updated, since it was last
+/// taken at the end of a tick.
+#[derive(Debug, Default)]
+pub(super) struct Tally {
+    /// Numbers replaced by sanitization or trade clamping.
+    pub(super) sanitized_memory: AtomicUsize,
+    pub(super) sanitized_signals: AtomicUsize,
+    pub(super) sanitized_trades: AtomicUsize,
+    /// Codons of the decisions of sampled brains, and how many of them did not run.
+    pub(super) sampled_codons: AtomicUsize,
+    pub(super) idle_codons: AtomicUsize,
+    /// Brains lost by moving or dividing into walls, and by being combined into another.
+    pub(super) died_in_walls: AtomicUsize,
+    pub(super) died_combining: AtomicUsize,
+    /// Money taxed from offspring, which is owed to the reserve.
+    pub(super) inheritance_taxed: AtomicUsize,
+    /// Food paid for holding signals.
+    pub(super) signaling: AtomicUsize,
+    /// Food paid for the codons brains ran.
+    pub(super) metabolized: AtomicUsize,
+    /// Tiles which gained or lost a brain, flipped in the occupied mask at the end of the tick.
+    pub(super) occupancy: Flips,
+}
+
+impl Tally {
+    pub(super) fn new(tiles: usize) -> Self {
+        Self {
+            occupancy: Flips::new(tiles),
+            ..Self::default()
+        }
+    }
+
+    pub(super) fn add(count: &AtomicUsize, n: usize) {
+        count.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(super) fn take(count: &AtomicUsize) -> usize {
+        count.swap(0, Ordering::Relaxed)
+    }
+
+    /// The numbers replaced since they were last taken.
+    pub(super) fn take_sanitized(&self) -> Sanitized {
+        Sanitized {
+            memory: Self::take(&self.sanitized_memory) as u32,
+            signals: Self::take(&self.sanitized_signals) as u32,
+            trades: Self::take(&self.sanitized_trades) as u32,
+        }
+    }
+
+    /// The codons of sampled decisions since they were last taken.
+    pub(super) fn take_executed(&self) -> Executed {
+        Executed {
+            codons: Self::take(&self.sampled_codons),
+            idle: Self::take(&self.idle_codons),
+        }
+    }
+}
+
+lazy_static! {
+    /// The config of the world whose cells are being stepped, or were last, its tally and the
+    /// streams of the tick.
+    static ref INSTALLED: Mutex<(Arc<SimConfig>, Arc<Tally>, Streams)> = Mutex::new((
+        Arc::new(SimConfig::default()),
+        Arc::new(Tally::default()),
+        Streams::new(0, 0)
+    ));
+    /// Held by the world whose cells are being stepped.
+    static ref STEPPING: Mutex<()> = Mutex::new(());
+}
+
+/// Counts the configs installed, so that a thread can tell its handle is out of date.
+static INSTALLS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// This thread's handle on the installed config, tally and streams, along with the install
+    /// they were taken from.
+    static LOCAL: RefCell<Option<(u64, Arc<SimConfig>, Arc<Tally>, Streams)>> = RefCell::new(None);
+}
+
+/// Puts the config of a world, its tally and the streams of its tick into effect for the cells
+/// stepped until the guard is dropped.
+pub(super) fn install(
+    config: &Arc<SimConfig>,
+    tally: &Arc<Tally>,
+    streams: Streams,
+) -> MutexGuard<'static, ()> {
+    // A panic while stepping leaves nothing half done which a later step would see.
+    let guard = STEPPING.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut installed = INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+    if !Arc::ptr_eq(&installed.0, config)
+        || !Arc::ptr_eq(&installed.1, tally)
+        || installed.2 != streams
+    {
+        *installed = (config.clone(), tally.clone(), streams);
+        INSTALLS.fetch_add(1, Ordering::Release);
+    }
+    guard
+}
+
+/// Runs `f` with the installed config, tally and streams, from whichever thread is stepping a
+/// cell.
+pub(super) fn with<T>(f: impl FnOnce(&SimConfig, &Tally, Streams) -> T) -> T {
+    LOCAL.with(|local| {
+        let installs = INSTALLS.load(Ordering::Acquire);
+        let stale = local
+            .borrow()
+            .as_ref()
+            .map_or(true, |&(taken, ..)| taken != installs);
+        if stale {
+            let (config, tally, streams) = INSTALLED
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            *local.borrow_mut() = Some((installs, config, tally, streams));
+        }
+        let local = local.borrow();
+        let (_, ref config, ref tally, streams) = *local.as_ref().unwrap();
+        f(config, tally, streams)
+    })
+}
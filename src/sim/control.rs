@@ -0,0 +1,209 @@
+use super::brain::Decision;
+use super::{Limit, MAX_EXPIRY};
+use gridsim::moore::MooreDirection;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// A direction as it is named in the control protocol.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Right,
+    Up,
+    Left,
+    Down,
+}
+
+impl From<Direction> for MooreDirection {
+    fn from(dir: Direction) -> MooreDirection {
+        match dir {
+            Direction::Right => MooreDirection::Right,
+            Direction::Up => MooreDirection::Up,
+            Direction::Left => MooreDirection::Left,
+            Direction::Down => MooreDirection::Down,
+        }
+    }
+}
+
+/// A line of JSON sent by the controller.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Take over the cell at this position.
+    Possess { x: usize, y: usize },
+    /// Take over a random cell, spawning one if the world is empty.
+    PossessAny,
+    /// Give the possessed cell back to its brain.
+    Release,
+    /// Decide to move the possessed cell.
+    Move { dir: Direction },
+    /// Decide to divide the possessed cell.
+    Divide { dir: Direction },
+    /// Decide to place an order for the possessed cell, resting in the book for `expiry` more
+    /// ticks and only filled in full if `all_or_nothing`, both of which may be left out.
+    Trade {
+        rate: i32,
+        food: i32,
+        #[serde(default)]
+        expiry: u32,
+        #[serde(default)]
+        all_or_nothing: bool,
+    },
+    /// Decide to offer a neighbor food and money, asking for them with negative amounts.
+    Offer {
+        dir: Direction,
+        food: i32,
+        money: i32,
+    },
+    /// Decide to accept the offer a neighbor made last tick.
+    Accept { dir: Direction },
+    /// Decide to do nothing with the possessed cell.
+    Nothing,
+}
+
+impl Request {
+    /// The decision this request makes for the possessed cell, if it is one.
+    pub fn decision(self) -> Option<Decision> {
+        match self {
+            Request::Move { dir } => Some(Decision::Move(dir.into())),
+            Request::Divide { dir } => Some(Decision::Divide(dir.into())),
+            Request::Trade {
+                rate,
+                food,
+                expiry,
+                all_or_nothing,
+            } => Some(Decision::Trade(
+                rate,
+                food,
+                Limit {
+                    expiry: expiry.min(MAX_EXPIRY),
+                    all_or_nothing,
+                },
+            )),
+            Request::Offer { dir, food, money } => Some(Decision::Offer(dir.into(), food, money)),
+            Request::Accept { dir } => Some(Decision::Accept(dir.into())),
+            Request::Nothing => Some(Decision::Nothing),
+            Request::Possess { .. } | Request::PossessAny | Request::Release => None,
+        }
+    }
+}
+
+/// A line of JSON sent to the controller.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Report {
+    /// The cell at this position is now controlled.
+    Possessed {
+        x: usize,
+        y: usize,
+    },
+    /// The possessed cell is waiting on a decision for this tick.
+    ///
+    /// The inputs are the same ones a brain sees, but never rotated: five for each neighbor
+    /// (right, up, left, down) being whether it has a brain, whether it is a wall, its food, its
+    /// signal, and its money, followed by the food and money of the cell itself. While direct
+    /// exchange is enabled, the food and money each neighbor offers the cell follow in the same
+    /// neighbor order. While life inputs are enabled, the age and generation of the cell follow,
+    /// each squashed between zero and one. While fill inputs are enabled, the share of its last
+    /// order the market filled and the price it was last filled at come last.
+    Observation {
+        tick: u64,
+        x: usize,
+        y: usize,
+        food: u32,
+        money: u32,
+        generation: usize,
+        inputs: Vec<f64>,
+    },
+    /// The cell is no longer controlled.
+    Released {
+        reason: &'static str,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// A TCP endpoint through which one controller at a time can possess a cell.
+pub struct ControlLink {
+    requests: Mutex<mpsc::Receiver<Request>>,
+    client: Arc<Mutex<Option<TcpStream>>>,
+}
+
+impl ControlLink {
+    /// Start accepting controllers on the given address.
+    pub fn listen(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let (tx, requests) = mpsc::channel();
+        let client = Arc::new(Mutex::new(None));
+        let accept_client = client.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                let reader = match stream.try_clone() {
+                    Ok(reader) => reader,
+                    Err(_) => continue,
+                };
+                let mut errors = match stream.try_clone() {
+                    Ok(errors) => errors,
+                    Err(_) => continue,
+                };
+                *accept_client.lock().unwrap() = Some(stream);
+                // Serve this controller until it disconnects.
+                for line in BufReader::new(reader).lines() {
+                    let line = match line {
+                        Ok(line) => line,
+                        Err(_) => break,
+                    };
+                    match serde_json::from_str(&line) {
+                        Ok(request) => {
+                            if tx.send(request).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            let report = Report::Error {
+                                message: e.to_string(),
+                            };
+                            write_report(&mut errors, &report).ok();
+                        }
+                    }
+                }
+                *accept_client.lock().unwrap() = None;
+                // Losing the controller gives the cell back to its brain.
+                if tx.send(Request::Release).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            requests: Mutex::new(requests),
+            client,
+        })
+    }
+
+    pub fn try_recv(&self) -> Option<Request> {
+        self.requests.lock().unwrap().try_recv().ok()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Request> {
+        self.requests.lock().unwrap().recv_timeout(timeout).ok()
+    }
+
+    /// Sends a report to the controller, if one is connected.
+    pub fn send(&self, report: &Report) {
+        let mut client = self.client.lock().unwrap();
+        if let Some(stream) = client.as_mut() {
+            if write_report(stream, report).is_err() {
+                client.take();
+            }
+        }
+    }
+}
+
+fn write_report(stream: &mut TcpStream, report: &Report) -> io::Result<()> {
+    serde_json::to_writer(&mut *stream, report)?;
+    stream.write_all(b"\n")
+}
@@ -0,0 +1,114 @@
+//! Food growing or draining along the edges of the map, so that there is a gradient from the
+//! edges to the middle of the world for cells to spread along.
+//!
+//! The world wraps around, so its edges are where the view of it is cut and cells cross them
+//! freely. The band along each edge is strongest at the edge and fades toward the middle.
+
+use super::{chance, Cell, CellType};
+use rand::{distributions::Bernoulli, Rng};
+use serde::Deserialize;
+use std::fmt;
+
+/// What happens to the food on the tiles along the edges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgePolicy {
+    /// The edges are no different from anywhere else.
+    Neutral,
+    /// Extra food grows along the edges.
+    River,
+    /// Food rots away along the edges.
+    Hostile,
+}
+
+impl fmt::Display for EdgePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            EdgePolicy::Neutral => "neutral",
+            EdgePolicy::River => "river",
+            EdgePolicy::Hostile => "hostile",
+        })
+    }
+}
+
+/// The effect along the edges of a world and how far it reaches.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Edges {
+    pub policy: EdgePolicy,
+    /// How many tiles in from each edge the band reaches.
+    pub width: usize,
+    /// The chance each tick that a tile right on an edge gains or loses a food, which falls off
+    /// evenly to nothing across the band.
+    pub chance: f64,
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self {
+            policy: EdgePolicy::Neutral,
+            width: 8,
+            chance: 0.05,
+        }
+    }
+}
+
+impl Edges {
+    pub fn is_neutral(&self) -> bool {
+        self.policy == EdgePolicy::Neutral || self.width == 0 || self.chance == 0.0
+    }
+}
+
+/// The tiles within the band along the edges of a world, generated along with it.
+#[derive(Clone, Debug)]
+pub(super) struct EdgeMap {
+    policy: EdgePolicy,
+    /// The chance at each depth into the band, from the edge inward.
+    chances: Vec<Bernoulli>,
+    /// Each tile in the band along with how deep into it the tile is.
+    tiles: Vec<(usize, usize)>,
+}
+
+impl EdgeMap {
+    /// The band of a world of this size, or `None` when the edges are neutral.
+    pub(super) fn new(edges: Edges, width: usize, height: usize) -> Option<Self> {
+        if edges.is_neutral() {
+            return None;
+        }
+        let band = edges.width;
+        let chances = (0..band)
+            .map(|depth| chance(edges.chance * (band - depth) as f64 / band as f64))
+            .collect();
+        let tiles = (0..width * height)
+            .filter_map(|index| {
+                let (x, y) = (index % width, index / width);
+                let depth = x.min(width - 1 - x).min(y).min(height - 1 - y);
+                if depth < band {
+                    Some((index, depth))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Some(Self {
+            policy: edges.policy,
+            chances,
+            tiles,
+        })
+    }
+
+    /// Grows or drains the food of every open tile in the band by the chance at its depth.
+    pub(super) fn apply(&self, rng: &mut impl Rng, cells: &mut [Cell]) {
+        for &(index, depth) in &self.tiles {
+            let cell = &mut cells[index];
+            if cell.ty == CellType::Wall || !rng.sample(self.chances[depth]) {
+                continue;
+            }
+            match self.policy {
+                EdgePolicy::River => cell.food += 1,
+                EdgePolicy::Hostile => cell.food = cell.food.saturating_sub(1),
+                EdgePolicy::Neutral => {}
+            }
+        }
+    }
+}
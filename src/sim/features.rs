@@ -0,0 +1,142 @@
+//! The optional mechanics a world runs with, so that saves and reports say which of them were in
+//! play without every rule having to be read, and a save which needs a mechanic this build does
+//! not have is refused instead of loaded without it.
+//!
+//! Each feature is recorded by its name, which matches the setting of a scenario that turns it
+//! on, so a build can name the features of a save it cannot run.
+
+use super::{Exhaustion, Freeze, Proceeds, Rules, Tier};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use std::fmt;
+
+/// A set of optional mechanics, a bit for each.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeatureSet(u32);
+
+impl FeatureSet {
+    pub const DIRECT_EXCHANGE: FeatureSet = FeatureSet(1 << 0);
+    pub const CIRCUIT_BREAKER: FeatureSet = FeatureSet(1 << 1);
+    pub const SENSE_LIFE: FeatureSet = FeatureSet(1 << 2);
+    pub const SENSE_FILLS: FeatureSet = FeatureSet(1 << 3);
+    pub const STARVATION_GRACE: FeatureSet = FeatureSet(1 << 4);
+    pub const DEMURRAGE: FeatureSet = FeatureSet(1 << 5);
+    pub const INHERITANCE_TAX: FeatureSet = FeatureSet(1 << 6);
+    pub const APPROXIMATION: FeatureSet = FeatureSet(1 << 7);
+    /// The proceeds of starving cells go anywhere but their tile.
+    pub const PROCEEDS: FeatureSet = FeatureSet(1 << 8);
+    pub const SIGNAL_COST: FeatureSet = FeatureSet(1 << 9);
+    pub const METABOLISM: FeatureSet = FeatureSet(1 << 10);
+    pub const FREEZE: FeatureSet = FeatureSet(1 << 11);
+    pub const SPOILAGE: FeatureSet = FeatureSet(1 << 12);
+    /// A tier of cornacopia yields in harvests every so many ticks.
+    pub const HARVESTS: FeatureSet = FeatureSet(1 << 13);
+    /// The reserve mints or rations money when it runs short, rather than only stopping.
+    pub const EXHAUSTION: FeatureSet = FeatureSet(1 << 14);
+
+    /// Every feature this build can run, by the name it is recorded under, which must never
+    /// change once a save records it.
+    const NAMES: [(FeatureSet, &'static str); 15] = [
+        (FeatureSet::DIRECT_EXCHANGE, "direct_exchange"),
+        (FeatureSet::CIRCUIT_BREAKER, "circuit_breaker"),
+        (FeatureSet::SENSE_LIFE, "sense_life"),
+        (FeatureSet::SENSE_FILLS, "sense_fills"),
+        (FeatureSet::STARVATION_GRACE, "starvation_grace"),
+        (FeatureSet::DEMURRAGE, "demurrage"),
+        (FeatureSet::INHERITANCE_TAX, "inheritance_tax"),
+        (FeatureSet::APPROXIMATION, "approximation"),
+        (FeatureSet::PROCEEDS, "proceeds"),
+        (FeatureSet::SIGNAL_COST, "signal_cost"),
+        (FeatureSet::METABOLISM, "metabolism"),
+        (FeatureSet::FREEZE, "freeze"),
+        (FeatureSet::SPOILAGE, "spoilage"),
+        (FeatureSet::HARVESTS, "harvests"),
+        (FeatureSet::EXHAUSTION, "exhaustion"),
+    ];
+
+    /// The features a world running by some rules uses.
+    pub fn of(rules: &Rules) -> Self {
+        let used = [
+            (FeatureSet::DIRECT_EXCHANGE, rules.direct_exchange),
+            (FeatureSet::CIRCUIT_BREAKER, rules.circuit_breaker.is_some()),
+            (FeatureSet::SENSE_LIFE, rules.sense_life),
+            (FeatureSet::SENSE_FILLS, rules.sense_fills),
+            (FeatureSet::STARVATION_GRACE, rules.starvation_grace > 0),
+            (FeatureSet::DEMURRAGE, rules.demurrage.is_some()),
+            (FeatureSet::INHERITANCE_TAX, rules.inheritance_tax > 0.0),
+            (FeatureSet::APPROXIMATION, rules.approximation.is_some()),
+            (FeatureSet::PROCEEDS, rules.proceeds != Proceeds::Tile),
+            (FeatureSet::SIGNAL_COST, rules.signal_cost > 0.0),
+            (FeatureSet::METABOLISM, rules.metabolism > 0.0),
+            (FeatureSet::FREEZE, rules.freeze != Freeze::Nothing),
+            (FeatureSet::SPOILAGE, rules.spoilage.is_active()),
+            (
+                FeatureSet::HARVESTS,
+                Tier::ALL
+                    .iter()
+                    .any(|&tier| rules.tiers.get(tier).every > 0),
+            ),
+            (FeatureSet::EXHAUSTION, rules.exhaustion != Exhaustion::Halt),
+        ];
+        used.iter()
+            .filter(|&&(_, used)| used)
+            .fold(FeatureSet::default(), |set, &(feature, _)| {
+                FeatureSet(set.0 | feature.0)
+            })
+    }
+
+    pub fn contains(self, feature: FeatureSet) -> bool {
+        self.0 & feature.0 == feature.0
+    }
+
+    /// The names of the features in the set, in the order they were added to the build.
+    pub fn names(self) -> impl Iterator<Item = &'static str> {
+        FeatureSet::NAMES
+            .iter()
+            .filter(move |&&(feature, _)| self.contains(feature))
+            .map(|&(_, name)| name)
+    }
+
+    /// The features recorded by some names, or an error naming every one this build does not
+    /// have.
+    pub fn from_names(names: &[String]) -> Result<Self, String> {
+        let mut set = FeatureSet::default();
+        let mut missing = vec![];
+        for name in names {
+            match FeatureSet::NAMES.iter().find(|&&(_, known)| known == name) {
+                Some(&(feature, _)) => set.0 |= feature.0,
+                None => missing.push(name.as_str()),
+            }
+        }
+        if missing.is_empty() {
+            Ok(set)
+        } else {
+            Err(format!(
+                "uses {}, which this build does not have",
+                missing.join(", ")
+            ))
+        }
+    }
+}
+
+impl fmt::Display for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.names().collect();
+        if names.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&names.join(", "))
+        }
+    }
+}
+
+// A set is written as the names of its features, so that it reads the same to any build.
+impl Serialize for FeatureSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names: Vec<&str> = self.names().collect();
+        let mut seq = serializer.serialize_seq(Some(names.len()))?;
+        for name in names {
+            seq.serialize_element(name)?;
+        }
+        seq.end()
+    }
+}
@@ -0,0 +1,127 @@
+use super::Cell;
+use std::fmt;
+use std::str::FromStr;
+
+/// A predicate over living cells which the view highlights by dimming every other tile.
+///
+/// It is written as `<field> <comparison> <value>`, such as `money > 50`, `generation >= 10` or
+/// `lineage == 1234`, or as just `traded` for cells whose order was filled last tick.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    Money(Comparison, u32),
+    Food(Comparison, u32),
+    Generation(Comparison, usize),
+    Lineage(u64),
+    Traded,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn test<T: PartialOrd>(self, a: T, b: T) -> bool {
+        match self {
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+            Comparison::GreaterOrEqual => a >= b,
+            Comparison::Greater => a > b,
+        }
+    }
+}
+
+impl Filter {
+    /// Only tiles with a brain can match.
+    pub fn matches(&self, cell: &Cell) -> bool {
+        let brain = match cell.brain {
+            Some(ref brain) => brain,
+            None => return false,
+        };
+        match *self {
+            Filter::Money(comparison, money) => comparison.test(cell.money, money),
+            Filter::Food(comparison, food) => comparison.test(cell.food, food),
+            Filter::Generation(comparison, generation) => {
+                comparison.test(brain.generation, generation)
+            }
+            Filter::Lineage(lineage) => brain.lineage == lineage,
+            Filter::Traded => cell.traded,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            "==" | "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            ">=" => Comparison::GreaterOrEqual,
+            ">" => Comparison::Greater,
+            _ => return Err(format!("unknown comparison {:?}", s)),
+        })
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let (field, comparison, value) = match words[..] {
+            ["traded"] => return Ok(Filter::Traded),
+            [field, comparison, value] => (field, comparison.parse()?, value),
+            _ => return Err("expected `<field> <comparison> <value>` or `traded`".to_owned()),
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{:?} is not a whole number", value))
+        };
+        match field {
+            "money" => Ok(Filter::Money(comparison, number(value)? as u32)),
+            "food" => Ok(Filter::Food(comparison, number(value)? as u32)),
+            "generation" => Ok(Filter::Generation(comparison, number(value)? as usize)),
+            "lineage" if comparison == Comparison::Equal => Ok(Filter::Lineage(number(value)?)),
+            "lineage" => Err("lineage can only be compared with ==".to_owned()),
+            _ => Err(format!("unknown field {:?}", field)),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        })
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Filter::Money(comparison, money) => write!(f, "money {} {}", comparison, money),
+            Filter::Food(comparison, food) => write!(f, "food {} {}", comparison, food),
+            Filter::Generation(comparison, generation) => {
+                write!(f, "generation {} {}", comparison, generation)
+            }
+            Filter::Lineage(lineage) => write!(f, "lineage == {}", lineage),
+            Filter::Traded => f.write_str("traded"),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! An estimate of the memory a world takes, so that users can judge how large a world their
+//! machine can run and spot leaks over a long run.
+
+use super::{Sim, View, ViewColors};
+use rustc_hash::FxHashSet;
+use std::mem::size_of;
+
+/// How many of each thing the sim holds and roughly how many bytes they take.
+///
+/// Only the largest allocations are counted, so the process as a whole uses somewhat more.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Footprint {
+    /// Tiles of the grid, each of which holds its brain inline, along with the masks over them.
+    pub cells: usize,
+    pub cell_bytes: usize,
+    pub brains: usize,
+    /// Distinct genomes, which brains with identical code share once they are interned.
+    pub genomes: usize,
+    pub genome_bytes: usize,
+    /// Lineages tracked, living or named.
+    pub lineages: usize,
+    pub lineage_bytes: usize,
+    /// The colors of the last frame sent to the GUI.
+    pub view_bytes: usize,
+}
+
+impl Footprint {
+    pub fn total_bytes(&self) -> usize {
+        self.cell_bytes + self.genome_bytes + self.lineage_bytes + self.view_bytes
+    }
+}
+
+impl Sim {
+    /// Counts what the world holds, which walks every brain, so it is only done now and then.
+    pub(super) fn footprint(&self, view: &View) -> Footprint {
+        let cells = self.grid.get_cells();
+        let masks = &self.masks;
+        let mut genomes = FxHashSet::default();
+        let mut genome_bytes = 0;
+        for index in masks.occupied.ones() {
+            let code = &cells[index].brain.as_ref().unwrap().code;
+            if genomes.insert(&**code as *const _) {
+                genome_bytes += code.bytes();
+            }
+        }
+        let (lineages, lineage_bytes) = self.lineages.footprint();
+        Footprint {
+            cells: cells.len(),
+            cell_bytes: cells.len() * size_of::<super::Cell>()
+                + masks.walls.bytes()
+                + masks.sources.bytes()
+                + masks.occupied.bytes(),
+            brains: self.population,
+            genomes: genomes.len(),
+            genome_bytes,
+            lineages,
+            lineage_bytes,
+            view_bytes: match view.colors {
+                ViewColors::Full(ref colors) => colors.len() * size_of::<(iced::Color, usize)>(),
+                ViewColors::Compact(ref colors) => colors.len() * size_of::<[u8; 3]>(),
+            },
+        }
+    }
+}
+
+/// Roughly the bytes each tile of a new world takes before anything lives on it, for the grid
+/// and the colors of its view, so that a world can be judged before it is made.
+pub fn tile_bytes() -> usize {
+    size_of::<super::Cell>() + size_of::<(iced::Color, usize)>()
+}
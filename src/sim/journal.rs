@@ -0,0 +1,255 @@
+//! The manual interventions made in a world, each kept along with what it changed, so that it can
+//! be undone exactly and then redone.
+//!
+//! Undoing a stroke of a brush takes back from each tile as much of what was dropped there as is
+//! still on it, since cells may have eaten, spent or carried off some of it since. Undoing new
+//! terrain puts back every tile as it was, moving cells off tiles which become walls again the
+//! same way regenerating does.
+
+use super::{Brush, CellType, Maze, Regenerated, Sim};
+use rustc_hash::FxHashMap;
+use std::fmt;
+
+/// Interventions kept for undoing, beyond which the oldest are forgotten, since each change of
+/// terrain keeps every tile of the world.
+const MAX_ENTRIES: usize = 64;
+
+/// A manual change to the world.
+#[derive(Copy, Clone, Debug)]
+pub enum Intervention {
+    /// A stroke of a brush, from where it was first pressed.
+    Brush {
+        brush: Brush,
+        radius: usize,
+        x: usize,
+        y: usize,
+    },
+    /// New walls, and new cornacopias at this density if one was given.
+    Terrain {
+        maze: Maze,
+        cornacopia_count_probability: Option<f64>,
+        seed: u64,
+    },
+}
+
+impl fmt::Display for Intervention {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Intervention::Brush {
+                brush,
+                radius,
+                x,
+                y,
+            } => write!(
+                f,
+                "painted {} with radius {} from ({}, {})",
+                match brush {
+                    Brush::Food => "food",
+                    Brush::Money => "money",
+                },
+                radius,
+                x,
+                y
+            ),
+            Intervention::Terrain {
+                cornacopia_count_probability,
+                seed,
+                ..
+            } => write!(
+                f,
+                "regenerated the terrain{} from seed {}",
+                if cornacopia_count_probability.is_some() {
+                    " and cornacopias"
+                } else {
+                    ""
+                },
+                seed
+            ),
+        }
+    }
+}
+
+/// What an intervention changed, as needed to reverse it.
+enum Change {
+    /// Food or money on each tile it was dropped on or taken back from.
+    Dropped(FxHashMap<usize, u32>),
+    /// Every tile as it was before the terrain last changed either way.
+    Tiles(Vec<CellType>),
+}
+
+struct Entry {
+    intervention: Intervention,
+    change: Change,
+}
+
+/// What the GUI is told whenever the journal changes.
+#[derive(Clone, Debug)]
+pub struct JournalUpdate {
+    /// The tick of the world it changed on.
+    pub tick: u64,
+    /// What was just done, undone or redone.
+    pub event: String,
+    /// Whether the event undid or redid an intervention rather than making a new one.
+    pub reversed: bool,
+    /// The interventions which can be undone, oldest first.
+    pub done: Vec<String>,
+    /// The interventions which can be redone, the next to be redone last.
+    pub undone: Vec<String>,
+}
+
+/// The interventions in effect and those undone since the last new one.
+#[derive(Default)]
+pub(super) struct Journal {
+    done: Vec<Entry>,
+    undone: Vec<Entry>,
+}
+
+impl Journal {
+    /// Paints with a brush, adding to the last stroke unless this starts a new one or the brush
+    /// changed. The journal only changes visibly when a stroke starts.
+    pub(super) fn brush(
+        &mut self,
+        sim: &mut Sim,
+        x: usize,
+        y: usize,
+        radius: usize,
+        brush: Brush,
+        stroke: bool,
+    ) -> Option<JournalUpdate> {
+        let dropped = sim.brush(x, y, radius, brush);
+        if let Some(Entry {
+            intervention:
+                Intervention::Brush {
+                    brush: last,
+                    radius: last_radius,
+                    ..
+                },
+            change: Change::Dropped(ref mut total),
+        }) = self.done.last_mut()
+        {
+            if !stroke && *last == brush && *last_radius == radius {
+                for (index, amount) in dropped {
+                    *total.entry(index).or_insert(0) += amount;
+                }
+                return None;
+            }
+        }
+        let mut total = FxHashMap::default();
+        for (index, amount) in dropped {
+            *total.entry(index).or_insert(0) += amount;
+        }
+        Some(self.push(
+            sim.tick,
+            Intervention::Brush {
+                brush,
+                radius,
+                x,
+                y,
+            },
+            Change::Dropped(total),
+        ))
+    }
+
+    /// Regenerates the terrain, keeping the tiles it replaced.
+    pub(super) fn regenerate(
+        &mut self,
+        sim: &mut Sim,
+        maze: Maze,
+        cornacopia_count_probability: Option<f64>,
+        seed: u64,
+    ) -> (Regenerated, JournalUpdate) {
+        let tiles = sim.tiles();
+        let regenerated = sim.regenerate(maze, cornacopia_count_probability, seed);
+        let update = self.push(
+            sim.tick,
+            Intervention::Terrain {
+                maze,
+                cornacopia_count_probability,
+                seed,
+            },
+            Change::Tiles(tiles),
+        );
+        (regenerated, update)
+    }
+
+    fn push(&mut self, tick: u64, intervention: Intervention, change: Change) -> JournalUpdate {
+        // A new intervention branches off, so what was undone can no longer be redone.
+        self.undone.clear();
+        self.done.push(Entry {
+            intervention,
+            change,
+        });
+        if self.done.len() > MAX_ENTRIES {
+            self.done.remove(0);
+        }
+        self.update(tick, intervention.to_string(), false)
+    }
+
+    /// Reverses the last intervention still in effect, if there is one.
+    pub(super) fn undo(&mut self, sim: &mut Sim) -> Option<JournalUpdate> {
+        let mut entry = self.done.pop()?;
+        let reversal = reverse(sim, &mut entry, false);
+        let event = format!("undid \"{}\", {}", entry.intervention, reversal);
+        self.undone.push(entry);
+        Some(self.update(sim.tick, event, true))
+    }
+
+    /// Makes the last intervention undone again, if nothing new was done since.
+    pub(super) fn redo(&mut self, sim: &mut Sim) -> Option<JournalUpdate> {
+        let mut entry = self.undone.pop()?;
+        let reversal = reverse(sim, &mut entry, true);
+        let event = format!("redid \"{}\", {}", entry.intervention, reversal);
+        self.done.push(entry);
+        Some(self.update(sim.tick, event, true))
+    }
+
+    fn update(&self, tick: u64, event: String, reversed: bool) -> JournalUpdate {
+        let describe = |entries: &[Entry]| {
+            entries
+                .iter()
+                .map(|entry| entry.intervention.to_string())
+                .collect()
+        };
+        JournalUpdate {
+            tick,
+            event,
+            reversed,
+            done: describe(&self.done),
+            undone: describe(&self.undone),
+        }
+    }
+}
+
+/// Applies the change of an entry the other way, dropping again what was taken back when
+/// `drop` is set and taking back what was dropped otherwise, then keeps what it did in the entry
+/// so that the next reversal undoes exactly that. Returns what happened, for the log.
+fn reverse(sim: &mut Sim, entry: &mut Entry, drop: bool) -> String {
+    match (&entry.intervention, &mut entry.change) {
+        (&Intervention::Brush { brush, .. }, Change::Dropped(amounts)) => {
+            let asked: u32 = amounts.values().sum();
+            *amounts = if drop {
+                sim.drop_on(brush, amounts)
+            } else {
+                sim.take_back(brush, amounts)
+            };
+            let moved: u32 = amounts.values().sum();
+            format!(
+                "{} {} of {}",
+                if drop { "dropping" } else { "taking back" },
+                moved,
+                asked
+            )
+        }
+        (_, Change::Tiles(tiles)) => {
+            let before = sim.tiles();
+            let (relocated, lost) = sim.retile(tiles);
+            *tiles = before;
+            format!(
+                "moving {} cells off walls, of which {} found no open tile and died",
+                relocated, lost
+            )
+        }
+        // Every entry is made with the change of its intervention.
+        _ => unreachable!(),
+    }
+}
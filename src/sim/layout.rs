@@ -0,0 +1,247 @@
+//! The shape of a new world, its maze of walls and its cornacopia sources, generated from a seed
+//! so that it can be previewed before a sim is started on it.
+
+use super::{CellType, Tiers};
+use crate::gridgen;
+use ndarray::Array2;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The BGRA pixel of a square holding a source of each tier, from the smallest.
+/// Blue, orange and white, as the grid tints them.
+const THUMBNAIL_SOURCES: [[u8; 4]; 3] =
+    [[200, 120, 0, 255], [0, 200, 255, 255], [255, 255, 255, 255]];
+
+/// The walls and sources of a new world, before anything lives in it.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    width: usize,
+    height: usize,
+    /// Tiles per side of each square of the maze.
+    open_scale: usize,
+    /// In row-major order.
+    tiles: Vec<CellType>,
+}
+
+/// How the maze of walls of a new world is drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Maze {
+    /// Tiles per side of each square of the maze, less one, which is how thick the walls are.
+    pub openness: usize,
+    /// Tiles added to the width of every passage, carved out of the walls beside it.
+    pub corridor_width: usize,
+    /// Passes of wearing away walls which are mostly surrounded by open tiles.
+    pub smoothing: usize,
+}
+
+impl Maze {
+    /// A maze with passages as wide as its walls are thick, left unsmoothed.
+    pub fn new(openness: usize) -> Self {
+        Self {
+            openness,
+            corridor_width: 0,
+            smoothing: 0,
+        }
+    }
+}
+
+/// The rooms and regions of a world, which stay put while it runs, for outlining them over the
+/// grid.
+#[derive(Clone, Debug, Default)]
+pub struct Floorplan {
+    pub width: usize,
+    pub height: usize,
+    /// What each tile is in row-major order, to draw the terrain up close.
+    pub tiles: Vec<CellType>,
+    /// The room of each tile in row-major order, counting from one, or zero for a wall.
+    pub rooms: Vec<u32>,
+    /// The region of each tile in row-major order, counting from one, or zero for none. Empty
+    /// when the world has no regions.
+    pub regions: Vec<u8>,
+    /// What each region is called, by its index.
+    pub region_names: Vec<String>,
+}
+
+/// Counts of what a layout is made of.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LayoutStats {
+    /// Tiles which are not walls, sources included.
+    pub open: usize,
+    /// Separate regions of open tiles which cells cannot move between.
+    pub rooms: usize,
+    /// The sources of each tier, from the smallest.
+    pub sources: [usize; 3],
+}
+
+impl Layout {
+    /// The same seed and arguments always give the same layout.
+    pub fn generate(
+        width: usize,
+        height: usize,
+        maze: Maze,
+        cornacopia_count_probability: f64,
+        tiers: &Tiers,
+        seed: u64,
+    ) -> Self {
+        let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        // Tiers come from a stream of their own, so that a seed places the walls and sources
+        // where it always did whatever the proportions of the tiers.
+        let tier_rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        tier_rng.set_stream(1);
+        // Squares grow by half the width added and walls are carved back by the other half, so
+        // that walls keep the thickness of the openness, or one more for an odd width.
+        let carved = maze.corridor_width / 2;
+        let open_scale = maze.openness + 1 + maze.corridor_width - carved;
+        let (open_width, open_height) = (width / open_scale, height / open_scale);
+        let os = (open_height, open_width);
+        let walls = gridgen::generate_walls(rng, os);
+        // Each wall gives up half of what is carved to the passage on either side of it.
+        let before = carved / 2;
+        let after = carved - before;
+        let mut maze_walls = Array2::from_shape_fn(
+            (open_height * open_scale, open_width * open_scale),
+            |(y, x)| {
+                let (oy, ox) = (y / open_scale, x / open_scale);
+                let (dy, dx) = (y % open_scale, x % open_scale);
+                let open = |delta| !walls[gridgen::dir((oy, ox), os, delta)];
+                let widened = dx < after && open((0, -1))
+                    || dx >= open_scale - before && open((0, 1))
+                    || dy < after && open((-1, 0))
+                    || dy >= open_scale - before && open((1, 0));
+                walls[(oy, ox)] && !widened
+            },
+        );
+        gridgen::smooth(&mut maze_walls, maze.smoothing);
+        let cornacopia_spawn_dist = super::chance(cornacopia_count_probability);
+        let tiles = (0..width * height)
+            .map(|ix| {
+                let source = rng.sample(cornacopia_spawn_dist);
+                let x = ix % width;
+                let y = ix / width;
+                if maze_walls.get((y, x)).copied().unwrap_or(false) {
+                    CellType::Wall
+                } else if source {
+                    CellType::Source(tiers.pick(tier_rng))
+                } else {
+                    CellType::Empty
+                }
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            open_scale,
+            tiles,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub(super) fn tiles(&self) -> &[CellType] {
+        &self.tiles
+    }
+
+    /// Whether a tile lies within the maze rather than in the strips along the far edges which
+    /// are left over when the world is not a whole number of squares across.
+    pub(super) fn in_maze(&self, ix: usize) -> bool {
+        let (x, y) = (ix % self.width, ix / self.width);
+        x / self.open_scale < self.width / self.open_scale
+            && y / self.open_scale < self.height / self.open_scale
+    }
+
+    pub fn stats(&self) -> LayoutStats {
+        let open = self
+            .tiles
+            .iter()
+            .filter(|&&ty| ty != CellType::Wall)
+            .count();
+        let mut sources = [0; 3];
+        for &ty in &self.tiles {
+            if let CellType::Source(tier) = ty {
+                sources[tier.index()] += 1;
+            }
+        }
+        let (_, rooms) = label_rooms(&self.tiles, self.width, self.height);
+        LayoutStats {
+            open,
+            rooms,
+            sources,
+        }
+    }
+
+    /// A picture of the layout in BGRA at most `max_width` pixels wide, with each pixel covering a
+    /// square of tiles. Walls are shaded by how much of the square they cover, and any source in
+    /// the square lights it up in the color of the highest tier there.
+    pub fn thumbnail(&self, max_width: usize) -> (u32, u32, Vec<u8>) {
+        let scale = ((self.width + max_width - 1) / max_width).max(1);
+        let (out_width, out_height) = (
+            (self.width + scale - 1) / scale,
+            (self.height + scale - 1) / scale,
+        );
+        let mut pixels = Vec::with_capacity(out_width * out_height * 4);
+        for out_y in 0..out_height {
+            for out_x in 0..out_width {
+                let (mut walls, mut count, mut source) = (0, 0, None);
+                for y in out_y * scale..((out_y + 1) * scale).min(self.height) {
+                    for x in out_x * scale..((out_x + 1) * scale).min(self.width) {
+                        match self.tiles[y * self.width + x] {
+                            CellType::Wall => walls += 1,
+                            CellType::Source(tier) => {
+                                source = source.max(Some(tier.index()));
+                            }
+                            CellType::Empty | CellType::Portal => {}
+                        }
+                        count += 1;
+                    }
+                }
+                if let Some(tier) = source {
+                    pixels.extend_from_slice(&THUMBNAIL_SOURCES[tier]);
+                } else {
+                    // The same red as walls are shown in the grid.
+                    let red = (102 * walls / count) as u8;
+                    pixels.extend_from_slice(&[0, 0, red, 255]);
+                }
+            }
+        }
+        (out_width as u32, out_height as u32, pixels)
+    }
+}
+
+/// Labels each tile with the room it is in, counting from one, or zero for a wall, along with how
+/// many rooms there are.
+pub(super) fn label_rooms(tiles: &[CellType], width: usize, height: usize) -> (Vec<u32>, usize) {
+    // Flood fill each region, wrapping around the edges as cells do.
+    let mut labels = vec![0; tiles.len()];
+    let mut rooms = 0;
+    let mut stack = vec![];
+    for start in 0..tiles.len() {
+        if labels[start] != 0 || tiles[start] == CellType::Wall {
+            continue;
+        }
+        rooms += 1;
+        labels[start] = rooms as u32;
+        stack.push(start);
+        while let Some(ix) = stack.pop() {
+            let (x, y) = (ix % width, ix / width);
+            let neighbors = [
+                y * width + (x + 1) % width,
+                y * width + (x + width - 1) % width,
+                (y + 1) % height * width + x,
+                (y + height - 1) % height * width + x,
+            ];
+            for &n in &neighbors {
+                if labels[n] == 0 && tiles[n] != CellType::Wall {
+                    labels[n] = rooms as u32;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+    (labels, rooms)
+}
@@ -0,0 +1,389 @@
+//! A registry of the lineages in the world, keyed by the id their founder drew when it spawned.
+
+use super::market::Fill;
+use super::postmortem::{
+    self, Cause, LineageTick, MarketTick, Postmortem, WealthPoint, LAST_TICKS, MAJOR_SIZE,
+};
+use super::{Bitset, Cell, FeatureSet, Genome, Market};
+use iced::Color;
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+/// Living cells a lineage needs before its origination is reported, so that the many cells which
+/// spawn and die alone do not flood the event log.
+const NOTABLE_SIZE: usize = 16;
+/// Lineages listed in the legend.
+const LEGEND_SIZE: usize = 8;
+/// Lineages whose genomes are kept in the hall of fame.
+const HALL_SIZE: usize = 16;
+
+#[derive(Clone, Debug)]
+struct Entry {
+    living: usize,
+    /// Living cells whose genome runs its entries in order.
+    ordered: usize,
+    /// The color of one of its living cells.
+    color: Color,
+    /// It has grown to `NOTABLE_SIZE` cells, so its extinction is reported too.
+    notable: bool,
+    /// The most living cells it has had at once, and when it first had them.
+    peak: usize,
+    peak_tick: u64,
+    name: Option<String>,
+    /// When it was first counted, with a genome of one of its cells then.
+    founder: Option<(u64, Genome)>,
+    /// Its living cells and wealth since it became notable.
+    wealth: Vec<WealthPoint>,
+    /// Ticks between points of wealth.
+    stride: u64,
+    /// How it fared in each of its last ticks since it became notable, oldest first.
+    last_ticks: VecDeque<LineageTick>,
+}
+
+impl Entry {
+    fn new(color: Color, name: Option<String>) -> Self {
+        Self {
+            living: 0,
+            ordered: 0,
+            color,
+            notable: false,
+            peak: 0,
+            peak_tick: 0,
+            name,
+            founder: None,
+            wealth: vec![],
+            stride: 1,
+            last_ticks: VecDeque::new(),
+        }
+    }
+
+    fn keep_tick(&mut self, tick: LineageTick) {
+        self.last_ticks.push_back(tick);
+        if self.last_ticks.len() > LAST_TICKS {
+            self.last_ticks.pop_front();
+        }
+    }
+}
+
+/// A lineage's cells as counted in a tick.
+struct Count {
+    living: usize,
+    ordered: usize,
+    hungry: usize,
+    food: u64,
+    money: u64,
+    color: Color,
+    /// One of its cells.
+    index: usize,
+}
+
+/// A lineage in the hall of fame, with the genome of one of its cells when it was largest.
+#[derive(Clone, Debug)]
+struct Famed {
+    lineage: u64,
+    peak: usize,
+    genome: Genome,
+}
+
+#[derive(Clone, Debug)]
+pub enum LineageEvent {
+    Originated {
+        lineage: u64,
+        name: Option<String>,
+    },
+    Extinct {
+        lineage: u64,
+        name: Option<String>,
+        /// What is known of it, if it was a major lineage.
+        postmortem: Option<Box<Postmortem>>,
+    },
+}
+
+/// A lineage as listed in the legend.
+#[derive(Clone, Debug)]
+pub struct LineageSummary {
+    pub lineage: u64,
+    pub name: Option<String>,
+    pub living: usize,
+    /// Living cells whose genome runs its entries in order rather than shuffled.
+    pub ordered: usize,
+    pub color: Color,
+}
+
+/// Named lineages stay in the registry after they die out, so their names are never lost.
+#[derive(Clone, Debug, Default)]
+pub struct Lineages {
+    entries: FxHashMap<u64, Entry>,
+    events: Vec<LineageEvent>,
+    /// Whether events are kept until they are taken, which only something taking them turns
+    /// on, so that a world run without a window does not pile them up.
+    pub(super) collecting: bool,
+    /// The notable lineages which grew the largest, largest first, kept after they die out.
+    /// It is not saved.
+    hall: Vec<Famed>,
+    /// Cells of each lineage which starved this tick.
+    starved: FxHashMap<u64, u32>,
+    /// The market in each of the last ticks, oldest first, which is not saved either.
+    market: VecDeque<MarketTick>,
+}
+
+impl Lineages {
+    /// Keeps the market of a tick for the postmortems.
+    pub(super) fn record_market(&mut self, tick: u64, market: &Market) {
+        self.market.push_back(MarketTick::new(tick, market));
+        if self.market.len() > LAST_TICKS {
+            self.market.pop_front();
+        }
+    }
+
+    /// Counts a cell of a lineage which starved this tick.
+    pub(super) fn starved(&mut self, lineage: u64) {
+        *self.starved.entry(lineage).or_insert(0) += 1;
+    }
+
+    /// Recounts the living cells of every lineage, recording which ones originated or went
+    /// extinct since the last count, along with a postmortem of each major one which did.
+    /// `fills` are the trades made since the last count.
+    pub(super) fn update(&mut self, cells: &[Cell], occupied: &Bitset, tick: u64, fills: &[Fill]) {
+        let mut counts: FxHashMap<u64, Count> = FxHashMap::default();
+        for index in occupied.ones() {
+            let cell = &cells[index];
+            let brain = cell.brain.as_ref().unwrap();
+            let count = counts.entry(brain.lineage).or_insert_with(|| Count {
+                living: 0,
+                ordered: 0,
+                hungry: 0,
+                food: 0,
+                money: 0,
+                color: brain.color(),
+                index,
+            });
+            count.living += 1;
+            count.ordered += brain.ordered() as usize;
+            count.hungry += (brain.life.hungry > 0) as usize;
+            count.food += cell.food as u64;
+            count.money += cell.money as u64;
+        }
+        // Only notable lineages keep how they traded.
+        let mut traded: FxHashMap<u64, (u32, u32)> = FxHashMap::default();
+        for fill in fills {
+            if self
+                .entries
+                .get(&fill.lineage)
+                .map_or(false, |entry| entry.notable)
+            {
+                let sides = traded.entry(fill.lineage).or_insert((0, 0));
+                if fill.buy {
+                    sides.0 += fill.food;
+                } else {
+                    sides.1 += fill.food;
+                }
+            }
+        }
+        let starved = std::mem::take(&mut self.starved);
+        let lineage_tick = |lineage: u64, before: usize, living: usize, hungry: usize| {
+            let starved = starved.get(&lineage).copied().unwrap_or(0);
+            let (bought, sold) = traded.get(&lineage).copied().unwrap_or((0, 0));
+            LineageTick {
+                tick,
+                living,
+                starved,
+                lost: before.saturating_sub(living + starved as usize) as u32,
+                hungry,
+                bought,
+                sold,
+            }
+        };
+
+        let collecting = self.collecting;
+        let events = &mut self.events;
+        let last_market = &self.market;
+        self.entries.retain(|&lineage, entry| {
+            if counts.contains_key(&lineage) || entry.living == 0 {
+                return true;
+            }
+            if entry.notable {
+                entry.keep_tick(lineage_tick(lineage, entry.living, 0, 0));
+            }
+            entry.living = 0;
+            entry.ordered = 0;
+            if entry.notable || entry.name.is_some() {
+                let postmortem = if entry.peak >= MAJOR_SIZE {
+                    let last_ticks: Vec<LineageTick> = entry.last_ticks.drain(..).collect();
+                    let starved = last_ticks.iter().map(|tick| tick.starved).sum();
+                    let lost = last_ticks.iter().map(|tick| tick.lost).sum();
+                    Some(Box::new(Postmortem {
+                        lineage,
+                        name: entry.name.clone(),
+                        founded: entry.founder.as_ref().map_or(0, |&(tick, _)| tick),
+                        extinct: tick,
+                        founder: entry.founder.as_ref().map(|(_, genome)| genome.clone()),
+                        peak: entry.peak,
+                        peak_tick: entry.peak_tick,
+                        starved,
+                        lost,
+                        dominant_cause: Cause::dominant(starved, lost),
+                        wealth: std::mem::take(&mut entry.wealth),
+                        last_ticks,
+                        market: last_market.iter().copied().collect(),
+                        // Filled in by the sim, which knows its rules.
+                        features: FeatureSet::default(),
+                    }))
+                } else {
+                    None
+                };
+                if collecting {
+                    events.push(LineageEvent::Extinct {
+                        lineage,
+                        name: entry.name.clone(),
+                        postmortem,
+                    });
+                }
+            }
+            entry.name.is_some()
+        });
+
+        for (lineage, count) in counts {
+            let entry = self
+                .entries
+                .entry(lineage)
+                .or_insert_with(|| Entry::new(count.color, None));
+            let genome = || cells[count.index].brain.as_ref().unwrap().genome();
+            if entry.founder.is_none() {
+                entry.founder = Some((tick, genome()));
+            }
+            let before = entry.living;
+            entry.living = count.living;
+            entry.ordered = count.ordered;
+            entry.color = count.color;
+            if !entry.notable && count.living >= NOTABLE_SIZE {
+                entry.notable = true;
+                if self.collecting {
+                    self.events.push(LineageEvent::Originated {
+                        lineage,
+                        name: entry.name.clone(),
+                    });
+                }
+            }
+            if entry.notable {
+                entry.keep_tick(lineage_tick(lineage, before, count.living, count.hungry));
+                postmortem::keep_wealth(
+                    &mut entry.wealth,
+                    &mut entry.stride,
+                    WealthPoint {
+                        tick,
+                        living: count.living,
+                        food: count.food,
+                        money: count.money,
+                    },
+                );
+            }
+            if entry.notable && count.living > entry.peak {
+                entry.peak = count.living;
+                entry.peak_tick = tick;
+                enshrine(&mut self.hall, lineage, count.living, genome());
+            }
+        }
+    }
+
+    /// The genomes of the lineages which grew the largest, largest first, along with their
+    /// lineage.
+    pub(super) fn hall_of_fame(&self) -> impl Iterator<Item = (u64, &Genome)> {
+        self.hall.iter().map(|famed| (famed.lineage, &famed.genome))
+    }
+
+    /// The events recorded since they were last taken.
+    pub(super) fn take_events(&mut self) -> Vec<LineageEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Names a lineage, or clears its name with `None`.
+    pub(super) fn rename(&mut self, lineage: u64, name: Option<String>) {
+        match self.entries.get_mut(&lineage) {
+            Some(entry) => entry.name = name,
+            // A lineage which has not been counted yet may still be named, such as one from a
+            // cell selected while paused.
+            None if name.is_some() => {
+                self.entries.insert(lineage, Entry::new(Color::BLACK, name));
+            }
+            None => {}
+        }
+    }
+
+    /// How many lineages are tracked and roughly the bytes they take, names and histories
+    /// included.
+    pub(super) fn footprint(&self) -> (usize, usize) {
+        let names: usize = self
+            .entries
+            .values()
+            .filter_map(|entry| entry.name.as_ref())
+            .map(String::capacity)
+            .sum();
+        let histories: usize = self
+            .entries
+            .values()
+            .map(|entry| {
+                entry.wealth.capacity() * std::mem::size_of::<WealthPoint>()
+                    + entry.last_ticks.capacity() * std::mem::size_of::<LineageTick>()
+            })
+            .sum();
+        let bytes =
+            self.entries.capacity() * std::mem::size_of::<(u64, Entry)>() + names + histories;
+        (self.entries.len(), bytes)
+    }
+
+    /// The name and color of a lineage, as of the last tick it was counted.
+    pub(super) fn describe(&self, lineage: u64) -> (Option<String>, Color) {
+        match self.entries.get(&lineage) {
+            Some(entry) => (entry.name.clone(), entry.color),
+            None => (None, Color::WHITE),
+        }
+    }
+
+    /// Every named lineage, living or not.
+    pub(super) fn names(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|(&lineage, entry)| Some((lineage, entry.name.as_deref()?)))
+    }
+
+    /// The largest living lineages, largest first, followed by any other named lineages.
+    pub(super) fn legend(&self) -> Vec<LineageSummary> {
+        let mut entries: Vec<(&u64, &Entry)> = self.entries.iter().collect();
+        entries.sort_unstable_by_key(|&(&lineage, entry)| (Reverse(entry.living), lineage));
+        entries
+            .into_iter()
+            .enumerate()
+            .filter(|&(rank, (_, entry))| {
+                (rank < LEGEND_SIZE && entry.living > 0) || entry.name.is_some()
+            })
+            .map(|(_, (&lineage, entry))| LineageSummary {
+                lineage,
+                name: entry.name.clone(),
+                living: entry.living,
+                ordered: entry.ordered,
+                color: entry.color,
+            })
+            .collect()
+    }
+}
+
+/// Keeps a genome of a lineage at a new peak if it is among the largest, replacing any it had.
+fn enshrine(hall: &mut Vec<Famed>, lineage: u64, peak: usize, genome: Genome) {
+    match hall.iter_mut().find(|famed| famed.lineage == lineage) {
+        // A lineage started over from the hall counts up from nothing again.
+        Some(famed) if famed.peak >= peak => return,
+        Some(famed) => {
+            famed.peak = peak;
+            famed.genome = genome;
+        }
+        None => hall.push(Famed {
+            lineage,
+            peak,
+            genome,
+        }),
+    }
+    hall.sort_unstable_by_key(|famed| Reverse(famed.peak));
+    hall.truncate(HALL_SIZE);
+}
@@ -0,0 +1,1122 @@
+use super::audit::Arrival;
+use super::{Bitset, Cell, Trade};
+use rand::{seq::SliceRandom, Rng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+const REPO: bool = false;
+/// The most ticks an order may rest in the book after the one it was placed on.
+pub const MAX_EXPIRY: u32 = 64;
+
+/// Money moved through each channel of the monetary circuit during a tick.
+///
+/// The channels added after the first are skipped here, so that the market keeps the layout
+/// older saves have, and saved after the rest of the world instead.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MoneyFlows {
+    /// Money exchanged between cells through matched orders.
+    pub trades: u32,
+    /// Money paid out of the reserve to cells selling it food (a source).
+    pub reserve_purchases: u32,
+    /// Money paid into the reserve by cells buying its food (a sink).
+    pub reserve_sales: u32,
+    /// Money left on walls and reclaimed by the reserve (a sink).
+    pub escheatment: u32,
+    /// Money which decayed back to the reserve under demurrage (a sink).
+    #[serde(skip)]
+    pub demurrage: u32,
+    /// Money taxed from offspring when their parent divided (a sink).
+    #[serde(skip)]
+    pub inheritance_tax: u32,
+    /// Money the reserve minted to pay for food when it had too little, which is counted among
+    /// its purchases as well.
+    #[serde(skip)]
+    pub minted: u32,
+}
+
+impl MoneyFlows {
+    /// Net money added to circulation (negative when the reserve absorbs more than it pays).
+    pub fn net(&self) -> i64 {
+        self.reserve_purchases as i64
+            - self.reserve_sales as i64
+            - self.escheatment as i64
+            - self.demurrage as i64
+            - self.inheritance_tax as i64
+    }
+}
+
+/// How orders which were not filled in full fared during a tick.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct OrderCounts {
+    /// Orders which left the book without being filled in full, either at their expiry or
+    /// because their cell could no longer settle them.
+    pub expired: u32,
+    /// Orders which were filled in part, leaving the rest unfilled.
+    pub partial: u32,
+}
+
+/// Food bought or sold by a cell with a brain at one price.
+#[derive(Copy, Clone, Debug)]
+pub struct Fill {
+    pub lineage: u64,
+    pub buy: bool,
+    /// Money per food.
+    pub price: i32,
+    pub food: u32,
+}
+
+/// A trade as it was executed, between two cells or a cell and the reserve.
+#[derive(Copy, Clone, Debug)]
+pub struct Print {
+    /// Money per food.
+    pub price: i32,
+    pub food: u32,
+    /// The lineages of the brains on either side, or `None` for the reserve or a tile whose
+    /// brain is gone.
+    pub buyer: Option<u64>,
+    pub seller: Option<u64>,
+    /// Whether the buyer was the aggressor, whose order arrived and took the order resting
+    /// against it or the reserve, rather than the seller.
+    pub taker_buys: bool,
+}
+
+/// The first, highest, lowest and last prices trades were executed at over some ticks, along with
+/// the food traded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Candle {
+    pub open: i32,
+    pub high: i32,
+    pub low: i32,
+    pub close: i32,
+    pub food: u64,
+}
+
+impl Candle {
+    fn new(price: i32, food: u64) -> Self {
+        Self {
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            food,
+        }
+    }
+
+    /// The candle covering this one and one after it.
+    pub(super) fn then(self, later: Candle) -> Candle {
+        Candle {
+            open: self.open,
+            high: self.high.max(later.high),
+            low: self.low.min(later.low),
+            close: later.close,
+            food: self.food + later.food,
+        }
+    }
+}
+
+/// Halts trading for a while whenever the clearing price swings too far within a window.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitBreaker {
+    /// How far the highest clearing price in the window may be above the lowest, as a percentage
+    /// of the lowest.
+    pub move_percent: f64,
+    /// Ticks of clearing prices compared.
+    pub window: usize,
+    /// Ticks trading stays halted once tripped, during which every order is rejected.
+    pub halt: usize,
+}
+
+/// A halt of trading, reported on the tick the breaker tripped.
+#[derive(Copy, Clone, Debug)]
+pub struct Halt {
+    /// The lowest and highest clearing prices in the window.
+    pub low: i32,
+    pub high: i32,
+    pub ticks: usize,
+}
+
+/// What the reserve does when the food offered to it is worth more money than it has left.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Exhaustion {
+    /// It buys what its money pays for, whichever asks come first, and buys nothing more once
+    /// it has none until money flows back to it.
+    Halt,
+    /// It mints the money it lacks and buys everything offered, so that the money in the world
+    /// is no longer bounded.
+    Mint,
+    /// It shares its money among the asks offered to it in proportion to their food, rather
+    /// than paying whichever come first.
+    Ration,
+}
+
+impl Default for Exhaustion {
+    fn default() -> Self {
+        Exhaustion::Halt
+    }
+}
+
+/// The reserve running short of money, reported on the first clearing it did so after it last
+/// had enough.
+#[derive(Copy, Clone, Debug)]
+pub struct Shortfall {
+    pub exhaustion: Exhaustion,
+    /// The food offered to the reserve which it left unbought, or bought with money it minted.
+    pub food: u32,
+}
+
+/// Money decaying back to the reserve every tick, so that holding on to it costs something.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Demurrage {
+    /// The share of the money which decays each tick, as a percentage.
+    pub percent: f64,
+    /// Money carried by cells decays too, rather than only money lying on tiles without one.
+    #[serde(default)]
+    pub carried: bool,
+}
+
+/// What becomes of what a cell got from a clearing when it starves before it can act again, such
+/// as after selling the last of its food.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Proceeds {
+    /// The money stays on its tile, for whatever moves or spawns there next.
+    Tile,
+    /// The money the last clearing brought it goes to the reserve, and the rest stays on its tile.
+    Reserve,
+    /// Orders which would leave a cell without food or grace are cancelled before matching, so
+    /// that no cell trades itself to death.
+    Cancel,
+}
+
+impl Default for Proceeds {
+    fn default() -> Self {
+        Proceeds::Tile
+    }
+}
+
+/// How long an order rests in the book, and whether it may be filled in part.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limit {
+    /// Ticks whatever is left of the order rests in the book after the one it was placed on,
+    /// where zero drops it once the clearing it was placed for is over.
+    pub expiry: u32,
+    /// The order only trades if it is filled in full within one clearing.
+    pub all_or_nothing: bool,
+}
+
+#[derive(PartialEq, Eq)]
+struct Order {
+    index: usize,
+    rate: i32,
+    food: i32,
+    /// The food of the order before this clearing filled any of it.
+    placed: i32,
+    limit: Limit,
+    /// Where the order came in the shuffled order of this clearing.
+    arrival: usize,
+    /// The food of an ask the reserve may still buy this clearing, which only rationing holds
+    /// below what is left of it.
+    quota: i32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Intent {
+    Bid,
+    Ask,
+    Nothing,
+}
+
+impl Order {
+    /// Whether this order is all-or-nothing and larger than what `other` has left to fill, so
+    /// that they must not trade.
+    fn too_big_for(&self, other: &Order) -> bool {
+        self.limit.all_or_nothing && self.food.abs() > other.food.abs()
+    }
+
+    fn intent(&self) -> Intent {
+        if self.food < 0 {
+            Intent::Bid
+        } else if self.food > 0 {
+            Intent::Ask
+        } else {
+            Intent::Nothing
+        }
+    }
+}
+
+/// The orders resting at one price, oldest first.
+#[derive(Default)]
+struct Level {
+    orders: VecDeque<Order>,
+    /// The food left in the orders here which may be filled in part.
+    divisible: i64,
+}
+
+/// One side of the book, where orders are matched best price first and, at the same price, in
+/// the order they arrived in.
+struct Side {
+    levels: BTreeMap<i32, Level>,
+    bids: bool,
+}
+
+impl Side {
+    fn new(bids: bool) -> Self {
+        Self {
+            levels: BTreeMap::new(),
+            bids,
+        }
+    }
+
+    /// Rests an order behind those already at its price.
+    fn push(&mut self, order: Order) {
+        let level = self.levels.entry(order.rate).or_default();
+        if !order.limit.all_or_nothing {
+            level.divisible += order.food.abs() as i64;
+        }
+        level.orders.push_back(order);
+    }
+
+    /// The prices on this side an order at a rate on the other side would trade at, best first.
+    fn crossing(&self, rate: i32) -> Vec<i32> {
+        if self.bids {
+            self.levels
+                .range(rate..)
+                .rev()
+                .map(|(&price, _)| price)
+                .collect()
+        } else {
+            self.levels
+                .range(..=rate)
+                .map(|(&price, _)| price)
+                .collect()
+        }
+    }
+
+    /// The food which may be filled in part at the prices an order at a rate would trade at.
+    fn divisible(&self, rate: i32) -> i64 {
+        let levels: Box<dyn Iterator<Item = &Level>> = if self.bids {
+            Box::new(self.levels.range(rate..).map(|(_, level)| level))
+        } else {
+            Box::new(self.levels.range(..=rate).map(|(_, level)| level))
+        };
+        levels.map(|level| level.divisible).sum()
+    }
+
+    /// The best price resting on this side.
+    fn best(&self) -> Option<i32> {
+        if self.bids {
+            self.levels.keys().next_back().copied()
+        } else {
+            self.levels.keys().next().copied()
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Every order resting on this side.
+    fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.levels.values().flat_map(|level| level.orders.iter())
+    }
+
+    fn into_orders(self) -> impl Iterator<Item = Order> {
+        self.levels
+            .into_iter()
+            .flat_map(|(_, level)| level.orders.into_iter())
+    }
+}
+
+/// The order book clearing and the reserve which backs it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Market {
+    pub reserve: u32,
+    pub last_bid: Option<i32>,
+    pub last_ask: Option<i32>,
+    pub buy_volume: u32,
+    pub sell_volume: u32,
+    pub flows: MoneyFlows,
+    /// Every fill of the last clearing.
+    #[serde(skip)]
+    pub fills: Vec<Fill>,
+    /// The prices of the trades of the last clearing, if there were any.
+    #[serde(skip)]
+    pub candle: Option<Candle>,
+    /// The food left resting in orders at each price once the last clearing matched what it
+    /// could, lowest price first. Every bid is below every ask, so each price holds one side.
+    #[serde(skip)]
+    pub depth: Vec<(i32, u32)>,
+    /// Trading was halted by the circuit breaker during the last clearing.
+    #[serde(skip)]
+    pub halted: bool,
+    /// How the orders not filled in full fared in the last clearing.
+    #[serde(skip)]
+    pub orders: OrderCounts,
+    /// Set on the tick the circuit breaker tripped.
+    #[serde(skip)]
+    pub halt: Option<Halt>,
+    /// Ticks of the current halt still to come.
+    #[serde(skip)]
+    halt_left: usize,
+    /// Set on the clearing the reserve ran short of money after it last had enough.
+    #[serde(skip)]
+    pub shortfall: Option<Shortfall>,
+    /// The reserve ran short of money during the last clearing it matched orders in.
+    #[serde(skip)]
+    short: bool,
+    /// Food offered to the reserve during the last clearing which it left unbought.
+    #[serde(skip)]
+    unpaid: u32,
+    /// The clearing price of each tick in the window of the circuit breaker, if there were trades.
+    #[serde(skip)]
+    prices: VecDeque<Option<i32>>,
+    /// Money and food exchanged between cells during the last clearing.
+    #[serde(skip)]
+    matched: (i64, i64),
+    /// Whether the orders of each clearing are recorded into `arrivals`.
+    #[serde(skip)]
+    pub(super) auditing: bool,
+    /// The orders of the last clearing in the order they arrived in, while auditing.
+    #[serde(skip)]
+    pub(super) arrivals: Vec<Arrival>,
+    /// Whether the trades of each clearing are recorded into `tape`.
+    #[serde(skip)]
+    pub(super) taping: bool,
+    /// Every trade of the last clearing in the order it was executed, while taping.
+    #[serde(skip)]
+    pub(super) tape: Vec<Print>,
+}
+
+impl Market {
+    pub fn new(reserve: u32) -> Self {
+        Self {
+            reserve,
+            last_bid: None,
+            last_ask: None,
+            buy_volume: 0,
+            sell_volume: 0,
+            flows: MoneyFlows::default(),
+            fills: vec![],
+            candle: None,
+            depth: vec![],
+            halted: false,
+            orders: OrderCounts::default(),
+            halt: None,
+            halt_left: 0,
+            shortfall: None,
+            short: false,
+            unpaid: 0,
+            prices: VecDeque::new(),
+            matched: (0, 0),
+            auditing: false,
+            arrivals: vec![],
+            taping: false,
+            tape: vec![],
+        }
+    }
+
+    /// Resets buy and sell volume along with the money flows and everything else reported of a
+    /// clearing, given the orders dropped before matching.
+    fn reset(&mut self, expired: u32) {
+        self.buy_volume = 0;
+        self.sell_volume = 0;
+        self.flows = MoneyFlows::default();
+        self.fills.clear();
+        self.candle = None;
+        self.tape.clear();
+        self.matched = (0, 0);
+        self.depth.clear();
+        self.orders = OrderCounts {
+            expired,
+            partial: 0,
+        };
+        self.halt = None;
+        self.shortfall = None;
+        self.unpaid = 0;
+    }
+
+    /// Skips a clearing while the market is held still, leaving every order on its cell
+    /// untouched and reporting a tick without trades.
+    pub fn pause(&mut self, cells: &mut [Cell]) {
+        for cell in cells.iter_mut() {
+            cell.traded = false;
+            cell.proceeds = 0;
+        }
+        self.reset(0);
+        self.arrivals.clear();
+        self.halted = false;
+        self.last_bid = None;
+        self.last_ask = None;
+    }
+
+    /// Matches all the trades placed by cells this tick along with those still resting from
+    /// earlier ticks, unless the circuit breaker halted trading, in which case every order is
+    /// rejected. Whatever is left of an order goes back to its cell until it expires.
+    pub fn clear(
+        &mut self,
+        rng: &mut impl Rng,
+        cells: &mut [Cell],
+        breaker: Option<CircuitBreaker>,
+        proceeds: Proceeds,
+        exhaustion: Exhaustion,
+        starvation_grace: u32,
+    ) {
+        // Extract all trades in parallel, dropping those a cell can no longer settle since it
+        // placed them, and those which would starve it if the policy cancels them.
+        let extracted: Vec<Option<Order>> = cells
+            .par_iter_mut()
+            .enumerate()
+            .filter_map(|(ix, cell)| {
+                cell.traded = false;
+                cell.proceeds = 0;
+                let trade = cell.trade.take()?;
+                // Orders dropped or rejected below count as not filled at all.
+                if let Some(ref mut brain) = cell.brain {
+                    brain.life.filled = 0.0;
+                }
+                if !trade.settleable(cell)
+                    || (proceeds == Proceeds::Cancel && trade.fatal(cell, starvation_grace))
+                {
+                    return Some(None);
+                }
+                Some(Some(Order {
+                    index: ix,
+                    rate: trade.rate,
+                    food: trade.food,
+                    placed: trade.food,
+                    limit: trade.limit,
+                    arrival: 0,
+                    quota: trade.food,
+                }))
+            })
+            .collect();
+        let unsettleable = extracted.iter().filter(|order| order.is_none()).count() as u32;
+        let mut orders: Vec<Order> = extracted.into_iter().flatten().collect();
+        // Put the trades into a random order.
+        orders.shuffle(rng);
+        self.arrivals.clear();
+        for (arrival, order) in orders.iter_mut().enumerate() {
+            order.arrival = arrival;
+            if self.auditing {
+                // Counted as filled in full until found resting in the book at the end.
+                self.arrivals.push(Arrival {
+                    bid: order.food < 0,
+                    rate: order.rate,
+                    wealth: cells[order.index].money,
+                    placed: order.food.abs() as u32,
+                    filled: order.food.abs() as u32,
+                });
+            }
+        }
+
+        self.reset(unsettleable);
+        if breaker.is_none() {
+            self.halt_left = 0;
+            self.prices.clear();
+        }
+        self.halted = self.halt_left > 0;
+        if self.halted {
+            // Rejected orders say nothing about how fairly orders are matched.
+            self.arrivals.clear();
+            self.halt_left -= 1;
+            self.last_bid = None;
+            self.last_ask = None;
+            return;
+        }
+        if exhaustion == Exhaustion::Ration {
+            self.ration(&mut orders);
+        }
+        let mut bids = Side::new(true);
+        let mut asks = Side::new(false);
+        for mut order in orders {
+            match order.intent() {
+                Intent::Bid => {
+                    // An all-or-nothing bid rests untouched unless it can be filled in full now.
+                    if order.limit.all_or_nothing && !self.can_fill(&order, &asks, exhaustion) {
+                        bids.push(order);
+                        continue;
+                    }
+                    self.cross(cells, &mut order, &mut asks, exhaustion);
+                    // Only repo the money if there are no other ask offers out there.
+                    if REPO && order.food != 0 && asks.is_empty() && order.rate >= 1 {
+                        self.food_reserve(cells, &mut order);
+                    }
+                    if order.food != 0 {
+                        bids.push(order);
+                    }
+                }
+                Intent::Ask => {
+                    // An all-or-nothing ask rests untouched unless it can be filled in full now.
+                    if order.limit.all_or_nothing && !self.can_fill(&order, &bids, exhaustion) {
+                        asks.push(order);
+                        continue;
+                    }
+                    self.cross(cells, &mut order, &mut bids, exhaustion);
+                    // Whatever no bid took is offered to the reserve, and whatever it leaves
+                    // it had no money for.
+                    if order.food != 0 && order.rate <= 1 {
+                        self.fulfill_reserve(cells, &mut order, exhaustion);
+                        self.unpaid += order.food as u32;
+                    }
+                    if order.food != 0 {
+                        asks.push(order);
+                    }
+                }
+                Intent::Nothing => {}
+            }
+        }
+        let mut depth: BTreeMap<i32, u32> = BTreeMap::new();
+        for order in bids.orders().chain(asks.orders()) {
+            *depth.entry(order.rate).or_insert(0) += order.food.abs() as u32;
+        }
+        self.depth = depth.into_iter().collect();
+        self.last_bid = bids.best();
+        self.last_ask = asks.best();
+        for order in bids.into_orders().chain(asks.into_orders()) {
+            if let Some(arrival) = self.arrivals.get_mut(order.arrival) {
+                arrival.filled -= order.food.abs() as u32;
+            }
+            if order.food != order.placed {
+                self.orders.partial += 1;
+            }
+            if order.limit.expiry == 0 {
+                self.orders.expired += 1;
+                continue;
+            }
+            cells[order.index].trade = Some(Trade {
+                rate: order.rate,
+                food: order.food,
+                limit: Limit {
+                    expiry: order.limit.expiry - 1,
+                    ..order.limit
+                },
+            });
+        }
+        let short = self.unpaid > 0 || self.flows.minted > 0;
+        if short && !self.short {
+            self.shortfall = Some(Shortfall {
+                exhaustion,
+                food: self.unpaid + self.flows.minted,
+            });
+        }
+        self.short = short;
+        if let Some(breaker) = breaker {
+            self.check_breaker(breaker);
+        }
+    }
+
+    /// Holds the food the reserve buys of each ask which may sell to it to the share of its
+    /// money that the ask's share of all the food offered to it comes to, if it has too little
+    /// to buy everything. Orders too small for a whole food of it get none.
+    fn ration(&mut self, orders: &mut [Order]) {
+        let offered = |order: &Order| order.intent() == Intent::Ask && order.rate <= 1;
+        let food: i64 = orders
+            .iter()
+            .filter(|order| offered(order))
+            .map(|order| order.food as i64)
+            .sum();
+        if food <= self.reserve as i64 {
+            return;
+        }
+        for order in orders.iter_mut().filter(|order| offered(order)) {
+            order.quota = (order.food as i64 * self.reserve as i64 / food) as i32;
+        }
+    }
+
+    /// Whether an all-or-nothing order could be filled in full against the other side of the
+    /// book, counting only the orders there which may be filled in part, along with what the
+    /// reserve would buy of an ask.
+    fn can_fill(&self, order: &Order, book: &Side, exhaustion: Exhaustion) -> bool {
+        let mut available = book.divisible(order.rate);
+        if order.intent() == Intent::Ask && order.rate <= 1 {
+            available += self.reserve_buys(order, exhaustion) as i64;
+        }
+        available >= order.food.abs() as i64
+    }
+
+    /// Trips the circuit breaker if the clearing price moved too far within its window.
+    fn check_breaker(&mut self, breaker: CircuitBreaker) {
+        let (money, food) = self.matched;
+        self.prices.push_back(if food == 0 {
+            None
+        } else {
+            Some((money / food) as i32)
+        });
+        while self.prices.len() > breaker.window {
+            self.prices.pop_front();
+        }
+        let prices = self.prices.iter().flatten().copied();
+        let (low, high) = match (prices.clone().min(), prices.max()) {
+            (Some(low), Some(high)) => (low, high),
+            _ => return,
+        };
+        // Prices at or below zero are measured against a price of one.
+        if (high - low) as f64 > low.max(1) as f64 * breaker.move_percent / 100.0 {
+            self.halt_left = breaker.halt;
+            self.prices.clear();
+            self.halt = Some(Halt {
+                low,
+                high,
+                ticks: breaker.halt,
+            });
+        }
+    }
+
+    /// Return all the money on walls to the reserve.
+    pub fn escheat(&mut self, cells: &mut [Cell], walls: &Bitset) {
+        for index in walls.ones() {
+            let cell = &mut cells[index];
+            self.reserve += cell.money;
+            self.flows.escheatment += cell.money;
+            cell.money = 0;
+        }
+    }
+
+    /// Pays the money left by cells which starved right after trading into the reserve, counted
+    /// as escheated.
+    pub fn forfeit(&mut self, money: u32) {
+        self.reserve += money;
+        self.flows.escheatment += money;
+    }
+
+    /// Pays the money taxed from offspring into the reserve.
+    pub fn tax_inheritance(&mut self, taxed: u32) {
+        self.reserve += taxed;
+        self.flows.inheritance_tax += taxed;
+    }
+
+    /// Returns a share of the money lying on tiles, or of all money, to the reserve.
+    pub fn demur(&mut self, rng: &mut impl Rng, cells: &mut [Cell], demurrage: Demurrage) {
+        let rate = demurrage.percent.max(0.0).min(100.0) / 100.0;
+        for cell in cells {
+            if cell.money == 0 || (cell.brain.is_some() && !demurrage.carried) {
+                continue;
+            }
+            // Rounded up by chance, so that even small amounts decay at the rate on average.
+            let decay = cell.money as f64 * rate;
+            let decay = (decay.floor() as u32 + rng.gen_bool(decay.fract()) as u32).min(cell.money);
+            self.reserve += decay;
+            self.flows.demurrage += decay;
+            cell.money -= decay;
+        }
+    }
+
+    /// Fills an order against the orders resting on the other side of the book at prices it
+    /// crosses, best price first and oldest first at each price, until it is filled or none
+    /// are left. All-or-nothing orders too big for what is left of it are passed over, keeping
+    /// their place. An ask takes what the reserve buys before trading with any bid below its
+    /// price of one.
+    fn cross(
+        &mut self,
+        cells: &mut [Cell],
+        order: &mut Order,
+        book: &mut Side,
+        exhaustion: Exhaustion,
+    ) {
+        for price in book.crossing(order.rate) {
+            let level = book.levels.get_mut(&price).unwrap();
+            let mut at = 0;
+            while at < level.orders.len() && order.food != 0 {
+                let resting = &mut level.orders[at];
+                if resting.too_big_for(order) {
+                    at += 1;
+                    continue;
+                }
+                // If the reserve provides a better deal, then use the reserve.
+                if book.bids && price < 1 {
+                    self.fulfill_reserve(cells, order, exhaustion);
+                    if order.food == 0 {
+                        break;
+                    }
+                }
+                let before = resting.food.abs();
+                self.fulfill(cells, order, resting);
+                if !resting.limit.all_or_nothing {
+                    level.divisible -= (before - resting.food.abs()) as i64;
+                }
+                if resting.food == 0 {
+                    level.orders.remove(at);
+                } else {
+                    at += 1;
+                }
+            }
+            if level.orders.is_empty() {
+                book.levels.remove(&price);
+            }
+            if order.food == 0 {
+                return;
+            }
+        }
+    }
+
+    fn fulfill(&mut self, cells: &mut [Cell], new: &mut Order, existing: &mut Order) {
+        let rate = existing.rate;
+        let num = std::cmp::min(new.food.abs(), existing.food.abs());
+        let new_buys = new.intent() == Intent::Bid;
+        {
+            let new_cell = &mut cells[new.index];
+            new_cell.traded = true;
+            new_cell.proceeds += (rate * num * new.food.signum()).max(0) as u32;
+            new_cell.money = (new_cell.money as i32 + rate * num * new.food.signum()) as u32;
+            new_cell.food = (new_cell.food as i32 - num * new.food.signum()) as u32;
+            new.food -= new.food.signum() * num;
+        }
+        {
+            let existing_cell = &mut cells[existing.index];
+            existing_cell.traded = true;
+            existing_cell.proceeds += (rate * num * existing.food.signum()).max(0) as u32;
+            existing_cell.money =
+                (existing_cell.money as i32 + rate * num * existing.food.signum()) as u32;
+            existing_cell.food = (existing_cell.food as i32 - num * existing.food.signum()) as u32;
+            existing.food -= existing.food.signum() * num;
+        }
+        self.buy_volume += num as u32;
+        self.sell_volume += num as u32;
+        self.flows.trades += (rate * num).abs() as u32;
+        self.matched.0 += rate as i64 * num as i64;
+        self.matched.1 += num as i64;
+        self.record(cells, new.index, new_buys, rate, num);
+        self.record(cells, existing.index, !new_buys, rate, num);
+        report(cells, new, rate, num);
+        report(cells, existing, rate, num);
+        let (buyer, seller) = if new_buys {
+            (Some(new.index), Some(existing.index))
+        } else {
+            (Some(existing.index), Some(new.index))
+        };
+        self.print(cells, buyer, seller, rate, num, new_buys);
+    }
+
+    /// Puts a trade into the candle of the clearing, and on the tape while taping, with a missing
+    /// index standing for the reserve.
+    fn print(
+        &mut self,
+        cells: &[Cell],
+        buyer: Option<usize>,
+        seller: Option<usize>,
+        price: i32,
+        num: i32,
+        taker_buys: bool,
+    ) {
+        if num == 0 {
+            return;
+        }
+        let candle = Candle::new(price, num as u64);
+        self.candle = Some(match self.candle {
+            Some(earlier) => earlier.then(candle),
+            None => candle,
+        });
+        if !self.taping {
+            return;
+        }
+        let lineage = |index: Option<usize>| Some(cells[index?].brain.as_ref()?.lineage);
+        self.tape.push(Print {
+            price,
+            food: num as u32,
+            buyer: lineage(buyer),
+            seller: lineage(seller),
+            taker_buys,
+        });
+    }
+
+    /// Records food bought or sold by the cell at an index.
+    fn record(&mut self, cells: &[Cell], index: usize, buy: bool, price: i32, num: i32) {
+        if num == 0 {
+            return;
+        }
+        if let Some(ref brain) = cells[index].brain {
+            self.fills.push(Fill {
+                lineage: brain.lineage,
+                buy,
+                price,
+                food: num as u32,
+            });
+        }
+    }
+
+    /// The food the reserve would buy of an ask, as much as its quota and the money of the
+    /// reserve allow, unless the reserve mints what it lacks.
+    fn reserve_buys(&self, order: &Order, exhaustion: Exhaustion) -> i32 {
+        let offered = std::cmp::min(order.food, order.quota);
+        match exhaustion {
+            Exhaustion::Mint => offered,
+            Exhaustion::Halt | Exhaustion::Ration => std::cmp::min(offered, self.reserve as i32),
+        }
+    }
+
+    /// Allows an ask order to be fulfilled by the reserve at a rate of one money per food.
+    fn fulfill_reserve(&mut self, cells: &mut [Cell], order: &mut Order, exhaustion: Exhaustion) {
+        let num = self.reserve_buys(order, exhaustion);
+        let minted = (num as u32).saturating_sub(self.reserve);
+        self.reserve += minted;
+        self.flows.minted += minted;
+        order.quota -= num;
+        {
+            let cell = &mut cells[order.index];
+            cell.traded |= num != 0;
+            cell.proceeds += (num * order.food.signum()).max(0) as u32;
+            cell.money = (cell.money as i32 + num * order.food.signum()) as u32;
+            cell.food = (cell.food as i32 - num * order.food.signum()) as u32;
+            order.food -= order.food.signum() * num;
+        }
+        self.reserve -= num as u32;
+        self.sell_volume += num as u32;
+        self.flows.reserve_purchases += num as u32;
+        self.record(cells, order.index, false, 1, num);
+        self.print(cells, None, Some(order.index), 1, num, false);
+        report(cells, order, 1, num);
+    }
+
+    /// Allows a bid order to buy food from the reserve at one money per food.
+    fn food_reserve(&mut self, cells: &mut [Cell], order: &mut Order) {
+        // We will take as much as there is in the order.
+        let num = -order.food;
+        {
+            let cell = &mut cells[order.index];
+            cell.traded = true;
+            cell.money = (cell.money as i32 + num * order.food.signum()) as u32;
+            cell.food = (cell.food as i32 - num * order.food.signum()) as u32;
+            order.food -= order.food.signum() * num;
+        }
+        self.reserve += num as u32;
+        self.buy_volume += num as u32;
+        self.flows.reserve_sales += num as u32;
+        self.record(cells, order.index, true, 1, num);
+        self.print(cells, Some(order.index), None, 1, num, true);
+        report(cells, order, 1, num);
+    }
+}
+
+/// Tells the brain which placed an order how much of what the order had left this clearing is
+/// filled so far, and the price it was last filled at.
+fn report(cells: &mut [Cell], order: &Order, price: i32, num: i32) {
+    if num == 0 {
+        return;
+    }
+    if let Some(ref mut brain) = cells[order.index].brain {
+        brain.life.filled = 1.0 - order.food as f64 / order.placed as f64;
+        brain.life.fill_price = price as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const SELLER: usize = 0;
+    const BUYER: usize = 1;
+
+    /// A cell holding food and money with an order to trade food at a rate, which lasts only
+    /// the clearing it is placed for.
+    fn trader(food: u32, money: u32, rate: i32, trade: i32) -> Cell {
+        Cell {
+            food,
+            money,
+            trade: Some(Trade {
+                rate,
+                food: trade,
+                limit: Limit::default(),
+            }),
+            ..Cell::default()
+        }
+    }
+
+    /// Clears the orders of some cells once, without a circuit breaker.
+    fn clear(market: &mut Market, cells: &mut [Cell], proceeds: Proceeds, exhaustion: Exhaustion) {
+        market.clear(
+            &mut ChaCha8Rng::seed_from_u64(0),
+            cells,
+            None,
+            proceeds,
+            exhaustion,
+            0,
+        );
+    }
+
+    /// A seller offering the last of its food to a buyer with money to spare, cleared once.
+    fn sell_last_food(market: &mut Market, proceeds: Proceeds) -> Vec<Cell> {
+        let mut cells = vec![trader(5, 10, 2, 5), trader(50, 100, 2, -5)];
+        clear(market, &mut cells, proceeds, Exhaustion::Halt);
+        cells
+    }
+
+    #[test]
+    fn proceeds_stay_on_the_tile() {
+        let mut market = Market::new(0);
+        let mut cells = sell_last_food(&mut market, Proceeds::Tile);
+        assert_eq!(cells[SELLER].food, 0);
+        assert_eq!(cells[SELLER].money, 20);
+        assert_eq!(cells[BUYER].money, 90);
+        assert_eq!(market.flows.escheatment, 0);
+        // The policy leaves the proceeds alone, but they are still told apart.
+        assert_eq!(cells[SELLER].proceeds, 10);
+        assert_eq!(cells[BUYER].forfeit(), 0);
+    }
+
+    #[test]
+    fn reserve_takes_only_the_proceeds() {
+        let mut market = Market::new(0);
+        let mut cells = sell_last_food(&mut market, Proceeds::Reserve);
+        let forfeited = cells[SELLER].forfeit();
+        market.forfeit(forfeited);
+        assert_eq!(forfeited, 10);
+        // The money it held before the clearing stays on its tile.
+        assert_eq!(cells[SELLER].money, 10);
+        assert_eq!(market.reserve, 10);
+        assert_eq!(market.flows.escheatment, 10);
+        // Nothing is left to forfeit twice.
+        assert_eq!(cells[SELLER].forfeit(), 0);
+    }
+
+    #[test]
+    fn reserve_takes_only_the_proceeds_of_selling_to_it() {
+        let mut market = Market::new(100);
+        let mut cells = vec![trader(5, 10, 1, 5)];
+        clear(&mut market, &mut cells, Proceeds::Reserve, Exhaustion::Halt);
+        assert_eq!(cells[0].food, 0);
+        assert_eq!(cells[0].money, 15);
+        assert_eq!(cells[0].forfeit(), 5);
+        assert_eq!(cells[0].money, 10);
+    }
+
+    #[test]
+    fn cancel_drops_the_fatal_order() {
+        let mut market = Market::new(0);
+        let cells = sell_last_food(&mut market, Proceeds::Cancel);
+        assert_eq!(cells[SELLER].food, 5);
+        assert_eq!(cells[SELLER].money, 10);
+        assert_eq!(cells[SELLER].proceeds, 0);
+        assert!(!cells[SELLER].traded);
+        assert_eq!(cells[BUYER].money, 100);
+        // The cancelled ask and the bid left without a seller both count as expired.
+        assert_eq!(market.orders.expired, 2);
+    }
+
+    #[test]
+    fn proceeds_are_cleared_with_the_next_clearing() {
+        let mut market = Market::new(0);
+        let mut cells = sell_last_food(&mut market, Proceeds::Reserve);
+        clear(&mut market, &mut cells, Proceeds::Reserve, Exhaustion::Halt);
+        assert_eq!(cells[SELLER].forfeit(), 0);
+        assert_eq!(cells[SELLER].money, 20);
+    }
+
+    #[test]
+    fn halt_buys_nothing_with_an_empty_reserve() {
+        let mut market = Market::new(0);
+        let mut cells = vec![trader(5, 10, 1, 5)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Halt);
+        assert_eq!(cells[0].food, 5);
+        assert_eq!(cells[0].money, 10);
+        assert_eq!(market.reserve, 0);
+        assert_eq!(market.flows.reserve_purchases, 0);
+        assert_eq!(market.flows.minted, 0);
+        let shortfall = market.shortfall.unwrap();
+        assert_eq!(shortfall.exhaustion, Exhaustion::Halt);
+        assert_eq!(shortfall.food, 5);
+    }
+
+    #[test]
+    fn halt_buys_what_the_reserve_can_pay_for() {
+        let mut market = Market::new(3);
+        let mut cells = vec![trader(5, 10, 1, 5)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Halt);
+        assert_eq!(cells[0].food, 2);
+        assert_eq!(cells[0].money, 13);
+        assert_eq!(market.reserve, 0);
+        assert_eq!(market.shortfall.unwrap().food, 2);
+    }
+
+    #[test]
+    fn mint_mints_exactly_the_shortfall() {
+        let mut market = Market::new(3);
+        let mut cells = vec![trader(8, 0, 1, 8)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Mint);
+        assert_eq!(cells[0].food, 0);
+        assert_eq!(cells[0].money, 8);
+        assert_eq!(market.reserve, 0);
+        assert_eq!(market.flows.minted, 5);
+        assert_eq!(market.flows.reserve_purchases, 8);
+        let shortfall = market.shortfall.unwrap();
+        assert_eq!(shortfall.exhaustion, Exhaustion::Mint);
+        assert_eq!(shortfall.food, 5);
+    }
+
+    #[test]
+    fn mint_mints_nothing_while_the_reserve_has_enough() {
+        let mut market = Market::new(10);
+        let mut cells = vec![trader(8, 0, 1, 8)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Mint);
+        assert_eq!(market.reserve, 2);
+        assert_eq!(market.flows.minted, 0);
+        assert!(market.shortfall.is_none());
+    }
+
+    #[test]
+    fn ration_rounds_small_quotas_to_zero() {
+        let mut market = Market::new(4);
+        let mut cells = vec![
+            trader(10, 0, 1, 10),
+            trader(10, 0, 1, 10),
+            trader(1, 0, 1, 1),
+        ];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Ration);
+        // Each large ask comes to 10 / 21 of the reserve's 4 money, and the small one to less
+        // than a whole food.
+        assert_eq!(cells[0].food, 9);
+        assert_eq!(cells[1].food, 9);
+        assert_eq!(cells[2].food, 1);
+        assert_eq!(cells[2].money, 0);
+        assert_eq!(market.reserve, 2);
+        assert_eq!(market.shortfall.unwrap().food, 19);
+    }
+
+    #[test]
+    fn all_or_nothing_ask_rests_against_an_exhausted_reserve() {
+        let all_or_nothing = |food| {
+            let mut cell = trader(food, 0, 1, food as i32);
+            cell.trade.as_mut().unwrap().limit.all_or_nothing = true;
+            cell
+        };
+        let mut market = Market::new(3);
+        let mut cells = vec![all_or_nothing(5)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Halt);
+        assert_eq!(cells[0].food, 5);
+        assert_eq!(market.reserve, 3);
+        assert_eq!(market.orders.expired, 1);
+
+        let mut cells = vec![all_or_nothing(3)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Halt);
+        assert_eq!(cells[0].food, 0);
+        assert_eq!(market.reserve, 0);
+
+        // Minting, the reserve buys it whatever it has.
+        let mut cells = vec![all_or_nothing(5)];
+        clear(&mut market, &mut cells, Proceeds::Tile, Exhaustion::Mint);
+        assert_eq!(cells[0].food, 0);
+        assert_eq!(market.flows.minted, 5);
+    }
+
+    #[test]
+    fn shortfall_is_reported_on_the_first_short_clearing() {
+        let mut market = Market::new(0);
+        let short = |market: &mut Market| {
+            let mut cells = vec![trader(5, 0, 1, 5)];
+            clear(market, &mut cells, Proceeds::Tile, Exhaustion::Halt);
+            market.shortfall.map(|shortfall| shortfall.food)
+        };
+        assert_eq!(short(&mut market), Some(5));
+        assert_eq!(short(&mut market), None);
+        // Once the reserve has enough again, the next time it runs short is reported.
+        market.forfeit(5);
+        assert_eq!(short(&mut market), None);
+        assert_eq!(market.reserve, 0);
+        assert_eq!(short(&mut market), Some(5));
+    }
+}
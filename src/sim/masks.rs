@@ -0,0 +1,179 @@
+//! Packed bitsets over the tiles of the world, so that passes which only care about some tiles
+//! can skip the rest a word at a time.
+
+use super::{Cell, CellType};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const WORD_BITS: usize = 64;
+
+/// One bit per tile in row-major order.
+#[derive(Clone, Debug, Default)]
+pub struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    fn from_cells(cells: &[Cell], predicate: impl Fn(&Cell) -> bool + Sync) -> Self {
+        let mut bitset = Self {
+            words: vec![0; (cells.len() + WORD_BITS - 1) / WORD_BITS],
+        };
+        bitset.refresh(cells, predicate);
+        bitset
+    }
+
+    /// Recomputes every word from the cells in parallel.
+    fn refresh(&mut self, cells: &[Cell], predicate: impl Fn(&Cell) -> bool + Sync) {
+        self.words
+            .par_iter_mut()
+            .zip(cells.par_chunks(WORD_BITS))
+            .for_each(|(word, chunk)| {
+                *word = chunk.iter().enumerate().fold(0, |word, (bit, cell)| {
+                    word | (predicate(cell) as u64) << bit
+                });
+            });
+    }
+
+    /// Sets the bit of a tile.
+    pub(super) fn insert(&mut self, index: usize) {
+        self.words[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+    }
+
+    /// Flips every bit marked to flip, clearing the marks.
+    fn apply(&mut self, flips: &Flips) {
+        self.words
+            .par_iter_mut()
+            .zip(flips.words.par_iter())
+            .for_each(|(word, flipped)| *word ^= flipped.swap(0, Ordering::Relaxed));
+    }
+
+    /// The number of set bits.
+    pub fn count(&self) -> usize {
+        self.words
+            .par_iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The memory taken by the words.
+    pub(super) fn bytes(&self) -> usize {
+        self.words.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// The indices of the set bits in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + Clone + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .filter(|&(_, &word)| word != 0)
+            .flat_map(|(index, &word)| bits(word).map(move |bit| index * WORD_BITS + bit))
+    }
+
+    /// Counts the set bits whose index satisfies the predicate, in parallel.
+    pub fn par_count_where(&self, predicate: impl Fn(usize) -> bool + Sync) -> usize {
+        self.words
+            .par_iter()
+            .enumerate()
+            .map(|(index, &word)| {
+                bits(word)
+                    .filter(|bit| predicate(index * WORD_BITS + bit))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+/// Bits to flip in a bitset of as many tiles, which can be marked from every thread updating
+/// cells at once and are then flipped together.
+#[derive(Debug, Default)]
+pub struct Flips {
+    words: Vec<AtomicU64>,
+}
+
+impl Flips {
+    pub fn new(tiles: usize) -> Self {
+        Self {
+            words: (0..(tiles + WORD_BITS - 1) / WORD_BITS)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+        }
+    }
+
+    /// Marks the bit of a tile to flip, or unmarks it if it was already marked.
+    pub fn flip(&self, index: usize) {
+        self.words[index / WORD_BITS].fetch_xor(1 << (index % WORD_BITS), Ordering::Relaxed);
+    }
+}
+
+/// The positions of the set bits in a word.
+fn bits(mut word: u64) -> impl Iterator<Item = usize> + Clone {
+    std::iter::from_fn(move || {
+        if word == 0 {
+            None
+        } else {
+            let bit = word.trailing_zeros() as usize;
+            word &= word - 1;
+            Some(bit)
+        }
+    })
+}
+
+/// Which tiles are walls and which hold a brain.
+#[derive(Clone, Debug, Default)]
+pub struct Masks {
+    /// Walls only change when the terrain is regenerated.
+    pub walls: Bitset,
+    /// Sources only change with the walls.
+    pub sources: Bitset,
+    /// Flipped where brains moved, died or spawned at the end of every tick, and set at once
+    /// where they are planted between ticks.
+    pub occupied: Bitset,
+}
+
+impl Masks {
+    pub(super) fn new(cells: &[Cell]) -> Self {
+        Self {
+            walls: Bitset::from_cells(cells, |cell| cell.ty == CellType::Wall),
+            sources: Bitset::from_cells(cells, |cell| matches!(cell.ty, CellType::Source(_))),
+            occupied: Bitset::from_cells(cells, |cell| cell.brain.is_some()),
+        }
+    }
+
+    /// Flips the tiles which gained or lost a brain since they were last flipped.
+    pub fn flip_occupied(&mut self, flips: &Flips) {
+        self.occupied.apply(flips);
+    }
+
+    /// Recomputes the occupied tiles from every cell, which flipping only those which changed
+    /// is measured against.
+    pub fn rescan_occupied(&mut self, cells: &[Cell]) {
+        self.occupied.refresh(cells, |cell| cell.brain.is_some());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flips_only_the_marked_bits() {
+        let cells: Vec<Cell> = (0..130).map(|_| Cell::default()).collect();
+        let mut masks = Masks::new(&cells);
+        masks.occupied.insert(3);
+        masks.occupied.insert(129);
+        let flips = Flips::new(cells.len());
+        // A brain moving from 3 to 64, and one marked twice, which cancels out.
+        flips.flip(3);
+        flips.flip(64);
+        flips.flip(100);
+        flips.flip(100);
+        masks.flip_occupied(&flips);
+        assert_eq!(masks.occupied.ones().collect::<Vec<_>>(), vec![64, 129]);
+        // The marks are cleared once flipped.
+        masks.flip_occupied(&flips);
+        assert_eq!(masks.occupied.count(), 2);
+        // Rescanning goes by the cells, none of which has a brain.
+        masks.rescan_occupied(&cells);
+        assert_eq!(masks.occupied.count(), 0);
+    }
+}
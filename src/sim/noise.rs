@@ -0,0 +1,57 @@
+//! Noise in what brains sense and remember, to test how robust evolved programs are.
+
+use super::chance;
+use rand::{distributions::Bernoulli, Rng};
+use rand_distr::Normal;
+use serde::Deserialize;
+
+/// How noisy brains are, which is none at all by default.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Noise {
+    /// The standard deviation of the normal noise added to every input a brain senses.
+    pub input: f64,
+    /// The chance each tick that each slot of the memory of a brain is forgotten, becoming zero.
+    pub forgetting: f64,
+}
+
+impl Noise {
+    /// The distributions this noise is drawn from while it is in effect.
+    pub(super) fn distributions(&self) -> Distributions {
+        Distributions {
+            input: Normal::new(0.0, self.input)
+                .ok()
+                .filter(|_| self.input > 0.0),
+            forgetting: Some(self.forgetting)
+                .filter(|&forgetting| forgetting > 0.0)
+                .map(chance),
+        }
+    }
+}
+
+/// What the noise of a world is drawn from, with nothing for noise which is off.
+#[derive(Copy, Clone, Debug, Default)]
+pub(super) struct Distributions {
+    input: Option<Normal<f64>>,
+    pub(super) forgetting: Option<Bernoulli>,
+}
+
+/// Adds input noise to the inputs of a brain.
+pub(super) fn perturb(rng: &mut impl Rng, noise: &Distributions, inputs: &mut [f64]) {
+    if let Some(normal) = noise.input {
+        for input in inputs {
+            *input += rng.sample(normal);
+        }
+    }
+}
+
+/// Forgets each slot of the memory of a brain by a chance, if there is one.
+pub(super) fn forget(rng: &mut impl Rng, forgetting: Option<Bernoulli>, memory: &mut [f64]) {
+    if let Some(forgetting) = forgetting {
+        for slot in memory {
+            if rng.sample(forgetting) {
+                *slot = 0.0;
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Portals linking two worlds, which carry the cells and goods on their tiles over to the other
+//! world every tick.
+
+use super::brain::Brain;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    future, Future, StreamExt,
+};
+
+/// A cell on its way to the other world, carrying everything it owned.
+pub(super) struct Migrant {
+    pub(super) brain: Brain,
+    pub(super) food: u32,
+    pub(super) money: u32,
+}
+
+/// Everything that left the portal tiles of a world in one tick.
+#[derive(Default)]
+pub struct Traffic {
+    pub(super) migrants: Vec<Migrant>,
+    /// Goods left lying on the portal tiles.
+    pub(super) food: u32,
+    pub(super) money: u32,
+}
+
+/// One end of the link between two worlds, owned by the sim of one of them.
+pub struct PortalLink {
+    pub(super) outbox: UnboundedSender<Traffic>,
+    pub(super) inbox: UnboundedReceiver<Traffic>,
+}
+
+/// Links two worlds, returning the end for each along with the coordinator which must be run to
+/// pass the traffic of each world to the other.
+///
+/// The channels are unbounded so a tick never waits on the other world, which may run at its own
+/// pace; traffic simply arrives whenever the other world next ticks.
+pub fn link_worlds() -> (PortalLink, PortalLink, impl Future<Output = ()>) {
+    let (a_outbox, a_departures) = unbounded();
+    let (b_outbox, b_departures) = unbounded();
+    let (a_arrivals, a_inbox) = unbounded();
+    let (b_arrivals, b_inbox) = unbounded();
+
+    let coordinator = async move {
+        let a_to_b = a_departures.map(Ok).forward(b_arrivals);
+        let b_to_a = b_departures.map(Ok).forward(a_arrivals);
+        // Either world shutting down closes its end, which ends the forwarding both ways.
+        future::select(a_to_b, b_to_a).await;
+    };
+
+    (
+        PortalLink {
+            outbox: a_outbox,
+            inbox: a_inbox,
+        },
+        PortalLink {
+            outbox: b_outbox,
+            inbox: b_inbox,
+        },
+        coordinator,
+    )
+}
@@ -0,0 +1,137 @@
+//! Postmortems of the major lineages which went extinct, gathered while they lived, so that a
+//! run keeps an account of how each of them grew and died out.
+//!
+//! Deaths by starvation are counted per lineage where they happen. Cells which moved into walls
+//! or combined with others die deep inside a parallel step of the world, so a lineage only counts
+//! them as the cells it lost beyond those which starved, net of any born in the same tick.
+
+use super::{FeatureSet, Genome, Market};
+use serde::Serialize;
+
+/// The living cells a lineage must have had at once to get a postmortem.
+pub(super) const MAJOR_SIZE: usize = 64;
+/// Points of wealth kept for a lineage, beyond which every other point is dropped and points are
+/// kept half as often from then on.
+pub(super) const MAX_WEALTH_POINTS: usize = 512;
+/// Ticks before its extinction whose deaths and market go into a postmortem.
+pub(super) const LAST_TICKS: usize = 100;
+
+/// A lineage's living cells and what they held at a tick.
+#[derive(Copy, Clone, Debug, Serialize)]
+pub struct WealthPoint {
+    pub tick: u64,
+    pub living: usize,
+    pub food: u64,
+    pub money: u64,
+}
+
+/// How a lineage fared in one tick.
+#[derive(Copy, Clone, Debug, Serialize)]
+pub struct LineageTick {
+    pub tick: u64,
+    pub living: usize,
+    pub starved: u32,
+    /// Cells lost other than by starving, net of those born.
+    pub lost: u32,
+    /// Cells at zero food living on their grace.
+    pub hungry: usize,
+    /// Food bought and sold on the market.
+    pub bought: u32,
+    pub sold: u32,
+}
+
+/// The market of the whole world in one tick.
+#[derive(Copy, Clone, Debug, Serialize)]
+pub struct MarketTick {
+    pub tick: u64,
+    pub bid: Option<i32>,
+    pub ask: Option<i32>,
+    pub buy_volume: u32,
+    pub sell_volume: u32,
+    pub trades: u32,
+    pub reserve: u32,
+    pub halted: bool,
+}
+
+impl MarketTick {
+    pub(super) fn new(tick: u64, market: &Market) -> Self {
+        Self {
+            tick,
+            bid: market.last_bid,
+            ask: market.last_ask,
+            buy_volume: market.buy_volume,
+            sell_volume: market.sell_volume,
+            trades: market.flows.trades,
+            reserve: market.reserve,
+            halted: market.halted,
+        }
+    }
+}
+
+/// Why a lineage died out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cause {
+    Starvation,
+    /// Moving or dividing into walls, or combining with other cells.
+    WallsOrCombining,
+}
+
+impl Cause {
+    /// The cause of most deaths, starvation when they are even.
+    pub(super) fn dominant(starved: u32, lost: u32) -> Self {
+        if starved >= lost {
+            Cause::Starvation
+        } else {
+            Cause::WallsOrCombining
+        }
+    }
+}
+
+/// What is known of a major lineage once it went extinct.
+#[derive(Clone, Debug, Serialize)]
+pub struct Postmortem {
+    pub lineage: u64,
+    pub name: Option<String>,
+    /// The tick it was first counted on, or the world was loaded on if it was alive then.
+    pub founded: u64,
+    /// The tick it was first counted without a living cell.
+    pub extinct: u64,
+    /// The genome of one of its first cells counted, which is its founder's unless the founder
+    /// mutated or divided before it was counted.
+    pub founder: Option<Genome>,
+    pub peak: usize,
+    pub peak_tick: u64,
+    /// Deaths over its last ticks by cause.
+    pub starved: u32,
+    pub lost: u32,
+    /// The cause of most of those deaths.
+    pub dominant_cause: Cause,
+    /// Its living cells and wealth from when it became notable.
+    pub wealth: Vec<WealthPoint>,
+    /// How it fared in each of its last ticks, oldest first.
+    pub last_ticks: Vec<LineageTick>,
+    /// The market in each of its last ticks, oldest first.
+    pub market: Vec<MarketTick>,
+    /// The optional mechanics the world ran with when it died out.
+    pub features: FeatureSet,
+}
+
+/// Keeps a point of wealth if one is due, halving the points once there are too many so that
+/// they always span the life of the lineage since it became notable.
+pub(super) fn keep_wealth(points: &mut Vec<WealthPoint>, stride: &mut u64, point: WealthPoint) {
+    if let Some(last) = points.last() {
+        if point.tick < last.tick + *stride {
+            return;
+        }
+    }
+    points.push(point);
+    if points.len() > MAX_WEALTH_POINTS {
+        let mut index = 0;
+        points.retain(|_| {
+            index += 1;
+            index % 2 == 1
+        });
+        *stride *= 2;
+    }
+}
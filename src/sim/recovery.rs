@@ -0,0 +1,147 @@
+//! Starting a world over once every cell in it has died, so that a run left unattended keeps
+//! going instead of idling on an empty map, and spawning cells into a world by hand.
+
+use super::brain::Brain;
+use super::streams::{Draw, Streams};
+use super::{CellType, Genome, Sim, SPAWN_FOOD};
+use rand::{seq::SliceRandom, Rng};
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use std::fmt;
+
+/// Where the brains which start a world over come from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seeds {
+    Random,
+    /// The genomes of the lineages which grew the largest, in turn.
+    HallOfFame,
+}
+
+impl fmt::Display for Seeds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Seeds::Random => "random genomes",
+            Seeds::HallOfFame => "the hall of fame",
+        })
+    }
+}
+
+/// What a world which went extinct is started over with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Recovery {
+    pub population: usize,
+    #[serde(default = "random")]
+    pub seeds: Seeds,
+}
+
+fn random() -> Seeds {
+    Seeds::Random
+}
+
+/// A world started over, reported on the tick it happened.
+#[derive(Copy, Clone, Debug)]
+pub struct Recovered {
+    /// Cells spawned, fewer than asked for if there were not enough open tiles.
+    pub population: usize,
+    /// Random when the hall of fame was asked for but was still empty.
+    pub seeds: Seeds,
+}
+
+impl Sim {
+    /// Spawns the population of the recovery on random open tiles.
+    pub(super) fn recover(&mut self, rng: &mut ChaCha8Rng, recovery: Recovery) -> Recovered {
+        let hall: Vec<(u64, Genome)> = match recovery.seeds {
+            Seeds::HallOfFame => self
+                .lineages
+                .hall_of_fame()
+                .map(|(lineage, genome)| (lineage, genome.clone()))
+                .collect(),
+            Seeds::Random => vec![],
+        };
+        let codons = self.config.codons();
+        let population = self.spawn_open(rng, recovery.population, |rng, n| {
+            if hall.is_empty() {
+                rng.sample(codons)
+            } else {
+                // Every genome in the hall gets an equal share of the world.
+                let (lineage, ref genome) = hall[n % hall.len()];
+                Brain::revive(rng, genome, lineage)
+            }
+        });
+        Recovered {
+            population,
+            seeds: if hall.is_empty() {
+                Seeds::Random
+            } else {
+                Seeds::HallOfFame
+            },
+        }
+    }
+
+    /// Spawns copies of a genome on random open tiles as a lineage of their own, returning how
+    /// many were spawned.
+    pub(super) fn plant(&mut self, genome: &Genome, population: usize) -> usize {
+        let rng = &mut self.hand_rng();
+        let lineage = rng.gen();
+        self.spawn_open(rng, population, |rng, _| {
+            Brain::revive(rng, genome, lineage)
+        })
+    }
+
+    /// Spawns a copy of a genome as a lineage of its own on the tile at a position, returning
+    /// whether it was open to spawn on.
+    pub(super) fn plant_at(&mut self, genome: &Genome, x: usize, y: usize) -> bool {
+        let rng = &mut self.hand_rng();
+        let width = self.grid.get_width();
+        let cells = self.grid.get_cells_mut();
+        let cell = match cells.get_mut(y * width + x) {
+            Some(cell) if x < width => cell,
+            _ => return false,
+        };
+        if cell.brain.is_some() || !matches!(cell.ty, CellType::Empty | CellType::Source(_)) {
+            return false;
+        }
+        let lineage = rng.gen();
+        cell.brain = Some(Brain::revive(rng, genome, lineage));
+        cell.food += SPAWN_FOOD;
+        self.masks.occupied.insert(y * width + x);
+        self.population += 1;
+        true
+    }
+
+    /// The numbers drawn for spawning cells by hand between ticks, from the stream of the tick
+    /// to come but apart from what the tick draws, and apart from every other time.
+    pub(super) fn hand_rng(&mut self) -> ChaCha8Rng {
+        self.hand_spawns += 1;
+        Streams::new(self.seed, self.tick).rng(Draw::Recovery, self.hand_spawns)
+    }
+
+    /// Spawns the brains made for each of up to a population of random open tiles, returning
+    /// how many were spawned, fewer than asked for if there were not enough open tiles.
+    fn spawn_open(
+        &mut self,
+        rng: &mut ChaCha8Rng,
+        population: usize,
+        mut brain: impl FnMut(&mut ChaCha8Rng, usize) -> Brain,
+    ) -> usize {
+        let cells = self.grid.get_cells_mut();
+        let open: Vec<usize> = (0..cells.len())
+            .filter(|&index| {
+                let cell = &cells[index];
+                cell.brain.is_none() && matches!(cell.ty, CellType::Empty | CellType::Source(_))
+            })
+            .collect();
+        let mut spawned = 0;
+        for (n, &index) in open.choose_multiple(rng, population).enumerate() {
+            let cell = &mut cells[index];
+            cell.brain = Some(brain(rng, n));
+            cell.food += SPAWN_FOOD;
+            self.masks.occupied.insert(index);
+            spawned += 1;
+        }
+        self.population += spawned;
+        spawned
+    }
+}
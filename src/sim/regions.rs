@@ -0,0 +1,165 @@
+//! Parts of the world where some parameters differ from everywhere else, so that conditions can
+//! be compared side by side within one run, such as more food in the north against the south.
+
+use super::chance;
+use rand::distributions::Bernoulli;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The most regions a world can have, so that a tile can refer to its region in a byte.
+pub const MAX_REGIONS: usize = 255;
+
+/// A half or a quarter of the world, with north at the top.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Area {
+    North,
+    South,
+    East,
+    West,
+    Northeast,
+    Northwest,
+    Southeast,
+    Southwest,
+}
+
+impl Area {
+    /// Whether a tile is in the area. A middle row or column of an odd size goes south or east.
+    fn contains(self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        let north = y < height / 2;
+        let west = x < width / 2;
+        match self {
+            Area::North => north,
+            Area::South => !north,
+            Area::East => !west,
+            Area::West => west,
+            Area::Northeast => north && !west,
+            Area::Northwest => north && west,
+            Area::Southeast => !north && !west,
+            Area::Southwest => !north && west,
+        }
+    }
+}
+
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Area::North => "north",
+            Area::South => "south",
+            Area::East => "east",
+            Area::West => "west",
+            Area::Northeast => "northeast",
+            Area::Northwest => "northwest",
+            Area::Southeast => "southeast",
+            Area::Southwest => "southwest",
+        })
+    }
+}
+
+/// An area along with the parameters which differ in it, leaving the rest as they are
+/// everywhere else.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Region {
+    pub area: Area,
+    #[serde(default)]
+    pub spawn_chance: Option<f64>,
+    #[serde(default)]
+    pub mutation_chance: Option<f64>,
+    #[serde(default)]
+    pub general_food_chance: Option<f64>,
+    #[serde(default)]
+    pub cornacopia_chance: Option<f64>,
+    #[serde(default)]
+    pub cornacopia_bounty: Option<u32>,
+}
+
+impl Region {
+    /// The chances the region sets, by name, for checking that they are probabilities.
+    pub fn chances(&self) -> [(&'static str, Option<f64>); 4] {
+        [
+            ("regions.spawn_chance", self.spawn_chance),
+            ("regions.mutation_chance", self.mutation_chance),
+            ("regions.general_food_chance", self.general_food_chance),
+            ("regions.cornacopia_chance", self.cornacopia_chance),
+        ]
+    }
+}
+
+/// The distributions of a region, with `None` wherever the one in effect everywhere is used.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Overrides {
+    pub(super) spawn: Option<Bernoulli>,
+    pub(super) mutate: Option<Bernoulli>,
+    pub(super) food: Option<Bernoulli>,
+    /// The cornacopia chance, which each tier scales.
+    pub(super) cornacopia: Option<f64>,
+    pub(super) bounty: Option<u32>,
+}
+
+impl From<&Region> for Overrides {
+    fn from(region: &Region) -> Self {
+        Self {
+            spawn: region.spawn_chance.map(chance),
+            mutate: region.mutation_chance.map(chance),
+            food: region.general_food_chance.map(chance),
+            cornacopia: region.cornacopia_chance,
+            bounty: region.cornacopia_bounty,
+        }
+    }
+}
+
+/// Which region each tile is in, where a tile in several is in the last of them.
+#[derive(Clone, Debug, Default)]
+pub(super) struct RegionMap {
+    regions: Vec<Region>,
+    overrides: Vec<Overrides>,
+    /// One more than the index of the region of each tile, or zero for none.
+    tiles: Vec<u8>,
+}
+
+impl RegionMap {
+    pub(super) fn new(regions: Vec<Region>, width: usize, height: usize) -> Self {
+        let regions: Vec<Region> = regions.into_iter().take(MAX_REGIONS).collect();
+        if regions.is_empty() {
+            return Self::default();
+        }
+        let tiles = (0..width * height)
+            .map(|index| {
+                let (x, y) = (index % width, index / width);
+                regions
+                    .iter()
+                    .rposition(|region| region.area.contains(x, y, width, height))
+                    .map_or(0, |region| region as u8 + 1)
+            })
+            .collect();
+        Self {
+            overrides: regions.iter().map(Overrides::from).collect(),
+            regions,
+            tiles,
+        }
+    }
+
+    pub(super) fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// One more than the index of the region of each tile, or zero for none, and empty when there
+    /// are no regions.
+    pub(super) fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    /// The index of the region a tile is in, if any.
+    pub(super) fn region(&self, index: usize) -> Option<usize> {
+        match self.tiles.get(index) {
+            Some(&id) if id > 0 => Some(id as usize - 1),
+            _ => None,
+        }
+    }
+
+    /// What the region of a tile overrides, if it is in one.
+    pub(super) fn at(&self, index: usize) -> Option<&Overrides> {
+        self.region(index).map(|region| &self.overrides[region])
+    }
+}
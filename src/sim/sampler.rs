@@ -0,0 +1,345 @@
+use super::brain;
+use super::{Candle, CellType, DeathCauses, MoneyFlows, OrderCounts, Sanitized, Sim, Stats};
+use iced::Color;
+use rustc_hash::FxHashMap;
+
+/// The largest lineages whose prices are reported.
+const PRICED_LINEAGES: usize = 4;
+/// The most bands of age the living cells are counted in.
+const AGE_BANDS: u32 = 16;
+
+/// How many ticks each sample of stats covers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Sampling {
+    /// One sample for every so many ticks.
+    Ticks(usize),
+    /// One sample for all the ticks run in a frame, however many that is.
+    Frame,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Sampling::Ticks(1)
+    }
+}
+
+/// The prices one of the largest lineages traded food at over a sample.
+#[derive(Clone, Debug)]
+pub struct LineagePrices {
+    pub lineage: u64,
+    pub color: Color,
+    /// `None` if it did not trade.
+    pub prices: Option<Ohlc>,
+}
+
+/// The open, high, low and close of the prices of fills, along with the mean price of each side.
+#[derive(Copy, Clone, Debug)]
+pub struct Ohlc {
+    pub open: i32,
+    pub high: i32,
+    pub low: i32,
+    pub close: i32,
+    /// Money paid per food bought, if any was.
+    pub buy: Option<f64>,
+    /// Money received per food sold, if any was.
+    pub sell: Option<f64>,
+}
+
+/// How many living cells are of each age, in bands of equal width from the youngest up.
+#[derive(Clone, Debug, Default)]
+pub struct AgePyramid {
+    /// Ticks of age each band covers, a power of two so that the bands have round bounds.
+    pub width: u32,
+    pub counts: Vec<u32>,
+}
+
+impl AgePyramid {
+    /// Counts cells by the ticks they have lived, in as few bands as cover the oldest.
+    fn new(ages: impl Iterator<Item = u32> + Clone) -> Self {
+        let oldest = match ages.clone().max() {
+            Some(oldest) => oldest,
+            None => return Self::default(),
+        };
+        let width = (oldest / AGE_BANDS + 1).next_power_of_two();
+        let mut counts = vec![0; (oldest / width + 1) as usize];
+        for age in ages {
+            counts[(age / width) as usize] += 1;
+        }
+        Self { width, counts }
+    }
+}
+
+/// The fills of a lineage so far in a sample.
+struct Fills {
+    open: i32,
+    high: i32,
+    low: i32,
+    close: i32,
+    /// Money and food of each side.
+    bought: (i64, u64),
+    sold: (i64, u64),
+}
+
+impl Fills {
+    fn ohlc(&self) -> Ohlc {
+        let mean = |(money, food): (i64, u64)| match food {
+            0 => None,
+            food => Some(money as f64 / food as f64),
+        };
+        Ohlc {
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            buy: mean(self.bought),
+            sell: mean(self.sold),
+        }
+    }
+}
+
+/// Averages the stats of several ticks into one sample, so that graphs of a fixed number of
+/// samples cover a meaningful span at any speed.
+///
+/// Rates such as prices, volumes, money flows, sanitization counts, decisions and deaths are
+/// averaged over the ticks, while levels such as the reserve and the population are taken from
+/// the last tick.
+#[derive(Default)]
+pub(super) struct Sampler {
+    ticks: usize,
+    bids: (i64, i64),
+    asks: (i64, i64),
+    buy_volume: u64,
+    sell_volume: u64,
+    trades: u64,
+    reserve_purchases: u64,
+    reserve_sales: u64,
+    escheatment: u64,
+    demurrage: u64,
+    inheritance_tax: u64,
+    minted: u64,
+    sanitized_memory: u64,
+    sanitized_signals: u64,
+    sanitized_trades: u64,
+    /// Codons of sampled decisions, and how many of them did not run.
+    sampled_codons: usize,
+    idle_codons: usize,
+    choices: [u64; 6],
+    starved: u64,
+    signaling: u64,
+    spoiled: u64,
+    metabolized: u64,
+    died_in_walls: u64,
+    died_combining: u64,
+    fills: FxHashMap<u64, Fills>,
+    candle: Option<Candle>,
+    /// Food resting at each price, summed over the ticks.
+    depth: FxHashMap<i32, u64>,
+    halted: bool,
+    expired_orders: u64,
+    partial_orders: u64,
+}
+
+impl Sampler {
+    pub(super) fn add(&mut self, sim: &Sim) {
+        let market = &sim.market;
+        self.ticks += 1;
+        if let Some(bid) = market.last_bid {
+            self.bids.0 += bid as i64;
+            self.bids.1 += 1;
+        }
+        if let Some(ask) = market.last_ask {
+            self.asks.0 += ask as i64;
+            self.asks.1 += 1;
+        }
+        self.buy_volume += market.buy_volume as u64;
+        self.sell_volume += market.sell_volume as u64;
+        self.trades += market.flows.trades as u64;
+        self.reserve_purchases += market.flows.reserve_purchases as u64;
+        self.reserve_sales += market.flows.reserve_sales as u64;
+        self.escheatment += market.flows.escheatment as u64;
+        self.demurrage += market.flows.demurrage as u64;
+        self.inheritance_tax += market.flows.inheritance_tax as u64;
+        self.minted += market.flows.minted as u64;
+        self.sanitized_memory += sim.sanitized.memory as u64;
+        self.sanitized_signals += sim.sanitized.signals as u64;
+        self.sanitized_trades += sim.sanitized.trades as u64;
+        self.sampled_codons += sim.executed.codons;
+        self.idle_codons += sim.executed.idle;
+        for (sum, &count) in self.choices.iter_mut().zip(&sim.choices) {
+            *sum += count as u64;
+        }
+        self.starved += sim.death_causes.starved as u64;
+        self.signaling += sim.signaling as u64;
+        self.spoiled += sim.spoiled as u64;
+        self.metabolized += sim.metabolized as u64;
+        self.died_in_walls += sim.death_causes.walls as u64;
+        self.died_combining += sim.death_causes.combined as u64;
+        self.halted |= market.halted;
+        self.expired_orders += market.orders.expired as u64;
+        self.partial_orders += market.orders.partial as u64;
+        for &(price, food) in &market.depth {
+            *self.depth.entry(price).or_insert(0) += food as u64;
+        }
+        if let Some(candle) = market.candle {
+            self.candle = Some(match self.candle {
+                Some(earlier) => earlier.then(candle),
+                None => candle,
+            });
+        }
+        for fill in &market.fills {
+            let fills = self.fills.entry(fill.lineage).or_insert(Fills {
+                open: fill.price,
+                high: fill.price,
+                low: fill.price,
+                close: fill.price,
+                bought: (0, 0),
+                sold: (0, 0),
+            });
+            fills.high = fills.high.max(fill.price);
+            fills.low = fills.low.min(fill.price);
+            fills.close = fill.price;
+            let side = if fill.buy {
+                &mut fills.bought
+            } else {
+                &mut fills.sold
+            };
+            side.0 += fill.price as i64 * fill.food as i64;
+            side.1 += fill.food as u64;
+        }
+    }
+
+    pub(super) fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    /// The sample of the ticks added since the last one was taken.
+    pub(super) fn take(&mut self, sim: &Sim) -> Stats {
+        let sampler = std::mem::take(self);
+        let ticks = sampler.ticks.max(1) as u64;
+        let price = |(sum, count): (i64, i64)| match count {
+            0 => None,
+            count => Some((sum / count) as i32),
+        };
+        let mean = |sum: u64| (sum / ticks) as u32;
+        Stats {
+            tick: sim.tick,
+            bid: price(sampler.bids),
+            ask: price(sampler.asks),
+            reserve: sim.market.reserve,
+            buy_volume: mean(sampler.buy_volume),
+            sell_volume: mean(sampler.sell_volume),
+            money_flows: MoneyFlows {
+                trades: mean(sampler.trades),
+                reserve_purchases: mean(sampler.reserve_purchases),
+                reserve_sales: mean(sampler.reserve_sales),
+                escheatment: mean(sampler.escheatment),
+                demurrage: mean(sampler.demurrage),
+                inheritance_tax: mean(sampler.inheritance_tax),
+                minted: mean(sampler.minted),
+            },
+            mean_age: sim.mean_age,
+            max_age: sim.max_age,
+            params: sim.params,
+            population: sim.population,
+            unique_genomes: sim.unique_genomes,
+            interned_genomes: sim.interned_genomes,
+            separators: brain::mean_separators(
+                sim.masks
+                    .occupied
+                    .ones()
+                    .filter_map(|index| sim.grid.get_cells()[index].brain.as_ref()),
+            ),
+            junk: brain::junk(
+                sim.masks
+                    .occupied
+                    .ones()
+                    .filter_map(|index| sim.grid.get_cells()[index].brain.as_ref()),
+                brain::Executed {
+                    codons: sampler.sampled_codons,
+                    idle: sampler.idle_codons,
+                },
+            ),
+            choices: {
+                let mut choices = [0; 6];
+                for (choice, &sum) in choices.iter_mut().zip(&sampler.choices) {
+                    *choice = mean(sum);
+                }
+                choices
+            },
+            sanitized: Sanitized {
+                memory: mean(sampler.sanitized_memory),
+                signals: mean(sampler.sanitized_signals),
+                trades: mean(sampler.sanitized_trades),
+            },
+            deaths: DeathCauses {
+                starved: mean(sampler.starved),
+                walls: mean(sampler.died_in_walls),
+                combined: mean(sampler.died_combining),
+                hungry: sim.death_causes.hungry,
+            },
+            selection: sim.deaths.selection(),
+            ages: AgePyramid::new(
+                sim.masks
+                    .occupied
+                    .ones()
+                    .filter_map(|index| sim.grid.get_cells()[index].brain.as_ref())
+                    .map(|brain| brain.life.ticks),
+            ),
+            lineage_prices: sim
+                .lineages
+                .legend()
+                .into_iter()
+                .filter(|summary| summary.living > 0)
+                .take(PRICED_LINEAGES)
+                .map(|summary| LineagePrices {
+                    lineage: summary.lineage,
+                    color: summary.color,
+                    prices: sampler.fills.get(&summary.lineage).map(Fills::ohlc),
+                })
+                .collect(),
+            candle: sampler.candle,
+            depth: {
+                let mut depth: Vec<(i32, f32)> = sampler
+                    .depth
+                    .into_iter()
+                    .map(|(price, food)| (price, food as f32 / ticks as f32))
+                    .collect();
+                depth.sort_unstable_by_key(|&(price, _)| price);
+                depth
+            },
+            halted: sampler.halted,
+            orders: OrderCounts {
+                expired: mean(sampler.expired_orders),
+                partial: mean(sampler.partial_orders),
+            },
+            fertile: sim.grid.get_cells().len()
+                - sim.masks.walls.count()
+                - sim.masks.sources.count(),
+            sources: {
+                let cells = sim.grid.get_cells();
+                let mut sources = [0; 3];
+                for index in sim.masks.sources.ones() {
+                    if let CellType::Source(tier) = cells[index].ty {
+                        sources[tier.index()] += 1;
+                    }
+                }
+                sources
+            },
+            region_populations: {
+                let regions = sim.regions.regions();
+                let mut populations: Vec<_> =
+                    regions.iter().map(|region| (region.area, 0)).collect();
+                for index in sim.masks.occupied.ones() {
+                    if let Some(region) = sim.regions.region(index) {
+                        populations[region].1 += 1;
+                    }
+                }
+                populations
+            },
+            approximated: sim.approximated,
+            signaling: mean(sampler.signaling),
+            spoiled: mean(sampler.spoiled),
+            metabolized: mean(sampler.metabolized),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Correlations between the traits of cells and how well they did, over the cells which died most
+//! recently, to show what evolution is currently rewarding.
+
+use super::brain::Brain;
+use std::collections::VecDeque;
+
+/// Deaths the correlations are computed over.
+const WINDOW: usize = 4096;
+
+pub const TRAITS: [&str; 3] = ["genome length", "trade rate", "move rate"];
+pub const FITNESS: [&str; 2] = ["age at death", "offspring"];
+
+struct Death {
+    traits: [f64; 3],
+    fitness: [f64; 2],
+}
+
+/// The Pearson correlation of every trait with every fitness proxy.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Selection {
+    /// Deaths the correlations were computed over.
+    pub deaths: usize,
+    /// Indexed by trait then fitness proxy, or `None` where either never varied.
+    pub correlations: [[Option<f64>; 2]; 3],
+}
+
+/// The most recent deaths.
+#[derive(Default)]
+pub(super) struct Deaths {
+    window: VecDeque<Death>,
+}
+
+impl Deaths {
+    /// Records a brain which is about to die of starvation.
+    pub(super) fn record(&mut self, brain: &Brain) {
+        let life = brain.life;
+        let ticks = life.ticks.max(1) as f64;
+        self.window.push_back(Death {
+            traits: [
+                brain.genome_len() as f64,
+                life.trades as f64 / ticks,
+                life.moves as f64 / ticks,
+            ],
+            fitness: [life.ticks as f64, life.offspring as f64],
+        });
+        if self.window.len() > WINDOW {
+            self.window.pop_front();
+        }
+    }
+
+    pub(super) fn selection(&self) -> Selection {
+        let mut selection = Selection {
+            deaths: self.window.len(),
+            ..Selection::default()
+        };
+        for (t, row) in selection.correlations.iter_mut().enumerate() {
+            for (f, correlation) in row.iter_mut().enumerate() {
+                *correlation = pearson(
+                    self.window
+                        .iter()
+                        .map(|death| (death.traits[t], death.fitness[f])),
+                );
+            }
+        }
+        selection
+    }
+}
+
+fn pearson(pairs: impl Iterator<Item = (f64, f64)> + Clone) -> Option<f64> {
+    let n = pairs.clone().count() as f64;
+    if n < 2.0 {
+        return None;
+    }
+    let (sum_x, sum_y) = pairs
+        .clone()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let (mean_x, mean_y) = (sum_x / n, sum_y / n);
+    let (cov, var_x, var_y) = pairs.fold((0.0, 0.0, 0.0), |(c, vx, vy), (x, y)| {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        (c + dx * dy, vx + dx * dx, vy + dy * dy)
+    });
+    if var_x == 0.0 || var_y == 0.0 {
+        None
+    } else {
+        Some(cov / (var_x * var_y).sqrt())
+    }
+}
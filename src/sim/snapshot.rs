@@ -0,0 +1,990 @@
+//! The save format of a world.
+//!
+//! A snapshot starts with an uncompressed preamble so that it can be identified and inspected
+//! cheaply:
+//!
+//! - the magic bytes `EVONSNAP`
+//! - the format version as a little endian `u32`
+//! - since version 26, the names of the optional mechanics the world runs with, as a bincode
+//!   encoded list of strings, which every later version keeps in this place
+//! - the bincode encoded [`Header`]
+//! - since version 2, the bincode encoded [`Rules`], holding only direct exchange at first and
+//!   gaining with each version after:
+//!   - since version 3, the pipeline
+//!   - since version 7, the sanitization
+//!   - since version 8, the circuit breaker
+//!   - since version 9, the life inputs
+//!   - since version 10, the starvation grace
+//!   - since version 14, demurrage
+//!   - since version 15, the inheritance tax
+//!   - since version 16, the tiers of cornacopia
+//!   - since version 17, approximate stepping
+//!   - since version 18, the policy for the proceeds of starving cells
+//!   - since version 19, the cost of signaling
+//!   - since version 20, the part of the world held still
+//!   - since version 22, spoilage
+//!   - since version 23, the metabolism
+//!   - since version 24, the fill inputs
+//!   - since version 25, the schedules of the tiers of cornacopia
+//!   - since version 27, the policy for an exhausted reserve
+//!
+//! Everything after that is a single zstd frame holding, in bincode:
+//!
+//! - every distinct genome, each stored once no matter how many cells share it, without the
+//!   order of its entries before version 11
+//! - the cells in row-major chunks of `chunk_rows` rows, where each brain refers to its genome
+//!   by index, without what each brain did over its life before version 28
+//! - since version 5, the names given to lineages, keyed by the lineage of their founder
+//! - since version 12, the regions whose parameters differ, in the order they were given
+//! - since version 17, the ticks the world has run with approximate stepping
+//! - since version 21, the seed of the random streams of every tick, without which a loaded world
+//!   draws a new one
+//! - since version 29, the [`Sinks`] of the last tick, which the market leaves out
+//!
+//! Since version 6 a cell may be a portal. Portals are saved without their link, so a loaded
+//! world keeps its portal tiles but sends nothing through them.
+//!
+//! Since version 16 a cornacopia has a tier. Those of older worlds are all of the middle tier.
+//!
+//! Since version 13 an order carries its expiry and whether it is all-or-nothing, so that an
+//! order resting in the book when the world was saved keeps resting once it is loaded.
+//!
+//! Any change to the layout of these types must bump [`VERSION`] and keep loading the older
+//! versions, so that saved experiments are never lost to an upgrade. `fixtures/snapshots` holds
+//! a small world saved by the builds which wrote some of them, which the tests load.
+
+use super::brain::{Brain, Dna, DnaV10, Life};
+use super::{
+    Cell, CellType, Evonomics, FeatureSet, Lineages, Market, Masks, Mutations, Noise, Params,
+    Region, RegionMap, Rules, Sim, SimConfig, Tally, Tier, TierYield, Tiers, Trade,
+};
+use crate::rng;
+use arrayvec::ArrayVec;
+use gridsim::SquareGrid;
+use rand::Rng;
+use rustc_hash::FxHashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 8] = b"EVONSNAP";
+/// The version written by this build.
+const VERSION: u32 = 29;
+/// Rows of cells per chunk, which bounds the memory used to encode or decode a large world.
+const CHUNK_ROWS: usize = 16;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Everything about the world apart from its cells.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    width: usize,
+    height: usize,
+    tick: u64,
+    chunk_rows: usize,
+    genomes: usize,
+    mean_age: u64,
+    max_age: u64,
+    params: Params,
+    market: Market,
+}
+
+/// The money flows of the last tick which the market leaves out of the header, so that it keeps
+/// the layout older saves have.
+#[derive(Serialize, Deserialize)]
+struct Sinks {
+    demurrage: u32,
+    inheritance_tax: u32,
+    minted: u32,
+}
+
+/// The tiers of cornacopia as they were saved before they could yield on a schedule.
+#[derive(Deserialize)]
+struct TiersV24 {
+    small: TierYieldV24,
+    medium: TierYieldV24,
+    large: TierYieldV24,
+}
+
+#[derive(Deserialize)]
+struct TierYieldV24 {
+    proportion: f64,
+    chance: f64,
+    bounty: f64,
+}
+
+impl From<TiersV24> for Tiers {
+    fn from(tiers: TiersV24) -> Self {
+        let tier = |tier: TierYieldV24| TierYield {
+            proportion: tier.proportion,
+            chance: tier.chance,
+            bounty: tier.bounty,
+            every: 0,
+        };
+        Self {
+            small: tier(tiers.small),
+            medium: tier(tiers.medium),
+            large: tier(tiers.large),
+        }
+    }
+}
+
+/// The type of a tile as it was saved before cornacopias had tiers.
+#[derive(Deserialize)]
+enum CellTypeV15 {
+    Wall,
+    Source,
+    Empty,
+    Portal,
+}
+
+impl From<CellTypeV15> for CellType {
+    fn from(ty: CellTypeV15) -> Self {
+        match ty {
+            CellTypeV15::Wall => CellType::Wall,
+            CellTypeV15::Source => CellType::Source(Tier::Medium),
+            CellTypeV15::Empty => CellType::Empty,
+            CellTypeV15::Portal => CellType::Portal,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CellRecord {
+    food: u32,
+    money: u32,
+    ty: CellType,
+    signal: f64,
+    brain: Option<BrainRecord>,
+    trade: Option<Trade>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BrainRecord {
+    color: f64,
+    rotation: usize,
+    generation: usize,
+    lineage: u64,
+    memory: Vec<f64>,
+    /// Index into the genomes of the snapshot.
+    genome: usize,
+    life: Life,
+}
+
+/// A brain as it was saved before what it did over its life was kept.
+#[derive(Deserialize)]
+struct BrainRecordV27 {
+    color: f64,
+    rotation: usize,
+    generation: usize,
+    lineage: u64,
+    memory: Vec<f64>,
+    genome: usize,
+}
+
+impl From<BrainRecordV27> for BrainRecord {
+    fn from(record: BrainRecordV27) -> Self {
+        Self {
+            color: record.color,
+            rotation: record.rotation,
+            generation: record.generation,
+            lineage: record.lineage,
+            memory: record.memory,
+            genome: record.genome,
+            life: Life::default(),
+        }
+    }
+}
+
+/// A cell as it was saved before what its brain did over its life was kept.
+#[derive(Deserialize)]
+struct CellRecordV27 {
+    food: u32,
+    money: u32,
+    ty: CellType,
+    signal: f64,
+    brain: Option<BrainRecordV27>,
+    trade: Option<Trade>,
+}
+
+impl From<CellRecordV27> for CellRecord {
+    fn from(record: CellRecordV27) -> Self {
+        Self {
+            food: record.food,
+            money: record.money,
+            ty: record.ty,
+            signal: record.signal,
+            brain: record.brain.map(BrainRecord::from),
+            trade: record.trade,
+        }
+    }
+}
+
+/// An order as it was saved before orders could rest in the book.
+#[derive(Deserialize)]
+struct TradeV12 {
+    rate: i32,
+    food: i32,
+}
+
+impl From<TradeV12> for Trade {
+    fn from(trade: TradeV12) -> Self {
+        Self {
+            rate: trade.rate,
+            food: trade.food,
+            limit: Default::default(),
+        }
+    }
+}
+
+/// A cell as it was saved before cornacopias had tiers.
+#[derive(Deserialize)]
+struct CellRecordV15 {
+    food: u32,
+    money: u32,
+    ty: CellTypeV15,
+    signal: f64,
+    brain: Option<BrainRecordV27>,
+    trade: Option<Trade>,
+}
+
+impl From<CellRecordV15> for CellRecord {
+    fn from(record: CellRecordV15) -> Self {
+        Self {
+            food: record.food,
+            money: record.money,
+            ty: record.ty.into(),
+            signal: record.signal,
+            brain: record.brain.map(BrainRecord::from),
+            trade: record.trade,
+        }
+    }
+}
+
+/// A cell as it was saved before orders could rest in the book.
+#[derive(Deserialize)]
+struct CellRecordV12 {
+    food: u32,
+    money: u32,
+    ty: CellTypeV15,
+    signal: f64,
+    brain: Option<BrainRecordV27>,
+    trade: Option<TradeV12>,
+}
+
+impl From<CellRecordV12> for CellRecord {
+    fn from(record: CellRecordV12) -> Self {
+        Self {
+            food: record.food,
+            money: record.money,
+            ty: record.ty.into(),
+            signal: record.signal,
+            brain: record.brain.map(BrainRecord::from),
+            trade: record.trade.map(Trade::from),
+        }
+    }
+}
+
+/// A cell as it was saved before brains had a lineage.
+#[derive(Deserialize)]
+struct CellRecordV3 {
+    food: u32,
+    money: u32,
+    ty: CellTypeV15,
+    signal: f64,
+    brain: Option<BrainRecordV3>,
+    trade: Option<TradeV12>,
+}
+
+#[derive(Deserialize)]
+struct BrainRecordV3 {
+    color: f64,
+    rotation: usize,
+    generation: usize,
+    memory: Vec<f64>,
+    genome: usize,
+}
+
+impl From<CellRecordV3> for CellRecord {
+    fn from(record: CellRecordV3) -> Self {
+        Self {
+            food: record.food,
+            money: record.money,
+            ty: record.ty.into(),
+            signal: record.signal,
+            // Every brain becomes the founder of its own lineage.
+            brain: record.brain.map(|brain| BrainRecord {
+                color: brain.color,
+                rotation: brain.rotation,
+                generation: brain.generation,
+                lineage: unsafe { rng() }.gen(),
+                memory: brain.memory,
+                genome: brain.genome,
+                life: Life::default(),
+            }),
+            trade: record.trade.map(Trade::from),
+        }
+    }
+}
+
+/// Reads everything before the compressed cells, returning the version along with it.
+fn read_preamble(reader: &mut impl Read) -> Result<(u32, Header, Rules), Box<dyn Error>> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err("not an evonomics snapshot".into());
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    // The features come first, so that a save from a later build is refused for a mechanic
+    // this build lacks by its name rather than only by its version.
+    if version >= 26 {
+        let features: Vec<String> = bincode::deserialize_from(&mut *reader)?;
+        FeatureSet::from_names(&features).map_err(|e| format!("the snapshot {}", e))?;
+    }
+    if !(1..=VERSION).contains(&version) {
+        return Err(format!("unsupported snapshot version {}", version).into());
+    }
+    let header = bincode::deserialize_from(&mut *reader)?;
+    let rules = read_rules(version, reader)?;
+    Ok((version, header, rules))
+}
+
+/// Reads the rules one field at a time in the order [`Rules`] declares them, leaving each field
+/// which is newer than the version at its default.
+///
+/// A new field of [`Rules`] needs one more line here, in its place in that order, giving the
+/// version which first saved it.
+fn read_rules(version: u32, reader: &mut impl Read) -> Result<Rules, Box<dyn Error>> {
+    fn since<T: DeserializeOwned>(
+        first: u32,
+        version: u32,
+        reader: &mut impl Read,
+        field: &mut T,
+    ) -> bincode::Result<()> {
+        if version >= first {
+            *field = bincode::deserialize_from(reader)?;
+        }
+        Ok(())
+    }
+    let mut rules = Rules::default();
+    since(2, version, reader, &mut rules.direct_exchange)?;
+    since(3, version, reader, &mut rules.pipeline)?;
+    since(7, version, reader, &mut rules.sanitization)?;
+    since(8, version, reader, &mut rules.circuit_breaker)?;
+    since(9, version, reader, &mut rules.sense_life)?;
+    since(24, version, reader, &mut rules.sense_fills)?;
+    since(10, version, reader, &mut rules.starvation_grace)?;
+    since(14, version, reader, &mut rules.demurrage)?;
+    since(15, version, reader, &mut rules.inheritance_tax)?;
+    if (16..25).contains(&version) {
+        rules.tiers = bincode::deserialize_from::<_, TiersV24>(&mut *reader)?.into();
+    }
+    since(25, version, reader, &mut rules.tiers)?;
+    since(17, version, reader, &mut rules.approximation)?;
+    since(18, version, reader, &mut rules.proceeds)?;
+    since(27, version, reader, &mut rules.exhaustion)?;
+    since(19, version, reader, &mut rules.signal_cost)?;
+    since(23, version, reader, &mut rules.metabolism)?;
+    since(20, version, reader, &mut rules.freeze)?;
+    since(22, version, reader, &mut rules.spoilage)?;
+    Ok(rules)
+}
+
+impl Sim {
+    /// Writes the world to a snapshot file.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let cells = self.grid.get_cells();
+        let width = self.grid.get_width();
+
+        // Number the distinct genomes in the order they are first seen.
+        let mut genome_indices: FxHashMap<*const Dna, usize> = FxHashMap::default();
+        let mut genomes: Vec<&Dna> = vec![];
+        for brain in cells.iter().filter_map(|cell| cell.brain.as_ref()) {
+            genome_indices
+                .entry(&*brain.code as *const Dna)
+                .or_insert_with(|| {
+                    genomes.push(&brain.code);
+                    genomes.len() - 1
+                });
+        }
+
+        let header = Header {
+            width,
+            height: self.grid.get_height(),
+            tick: self.tick,
+            chunk_rows: CHUNK_ROWS,
+            genomes: genomes.len(),
+            mean_age: self.mean_age,
+            max_age: self.max_age,
+            params: self.params,
+            market: self.market.clone(),
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut writer, &self.features())?;
+        bincode::serialize_into(&mut writer, &header)?;
+        bincode::serialize_into(&mut writer, &self.rules)?;
+
+        let mut encoder = zstd::Encoder::new(writer, COMPRESSION_LEVEL)?;
+        for genome in &genomes {
+            bincode::serialize_into(&mut encoder, genome)?;
+        }
+        for chunk in cells.chunks(CHUNK_ROWS * width) {
+            let records: Vec<CellRecord> = chunk
+                .iter()
+                .map(|cell| {
+                    // Offers are not saved, so whatever they hold back is returned to the cell.
+                    let (escrow_food, escrow_money) =
+                        cell.offer.map(|offer| offer.escrow()).unwrap_or((0, 0));
+                    CellRecord {
+                        food: cell.food + escrow_food,
+                        money: cell.money + escrow_money,
+                        ty: cell.ty,
+                        signal: cell.signal,
+                        brain: cell.brain.as_ref().map(|brain| BrainRecord {
+                            color: brain.color,
+                            rotation: brain.rotation,
+                            generation: brain.generation,
+                            lineage: brain.lineage,
+                            memory: brain.memory.to_vec(),
+                            genome: genome_indices[&(&*brain.code as *const Dna)],
+                            life: brain.life,
+                        }),
+                        trade: cell.trade.clone(),
+                    }
+                })
+                .collect();
+            bincode::serialize_into(&mut encoder, &records)?;
+        }
+        let names: Vec<(u64, &str)> = self.lineages.names().collect();
+        bincode::serialize_into(&mut encoder, &names)?;
+        bincode::serialize_into(&mut encoder, self.regions.regions())?;
+        bincode::serialize_into(&mut encoder, &self.approximated)?;
+        bincode::serialize_into(&mut encoder, &self.seed)?;
+        let flows = &self.market.flows;
+        bincode::serialize_into(
+            &mut encoder,
+            &Sinks {
+                demurrage: flows.demurrage,
+                inheritance_tax: flows.inheritance_tax,
+                minted: flows.minted,
+            },
+        )?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Reads a world from a snapshot file and puts its parameters into effect.
+    pub fn load(
+        path: &Path,
+        control: Option<Arc<super::ControlLink>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (version, header, rules) = read_preamble(&mut reader)?;
+        if header.width == 0 || header.height == 0 || header.chunk_rows == 0 {
+            return Err("snapshot has an empty world".into());
+        }
+
+        let mut decoder = zstd::Decoder::with_buffer(reader)?;
+        let genomes = (0..header.genomes)
+            .map(|_| {
+                if version < 11 {
+                    bincode::deserialize_from::<_, DnaV10>(&mut decoder)
+                        .map(|dna| Arc::new(dna.into()))
+                } else {
+                    bincode::deserialize_from(&mut decoder).map(Arc::new)
+                }
+            })
+            .collect::<Result<Vec<Arc<Dna>>, _>>()?;
+
+        let mut grid = SquareGrid::<Evonomics>::new(header.width, header.height);
+        for chunk in grid
+            .get_cells_mut()
+            .chunks_mut(header.chunk_rows * header.width)
+        {
+            let records: Vec<CellRecord> = if version < 4 {
+                let records: Vec<CellRecordV3> = bincode::deserialize_from(&mut decoder)?;
+                records.into_iter().map(CellRecord::from).collect()
+            } else if version < 13 {
+                let records: Vec<CellRecordV12> = bincode::deserialize_from(&mut decoder)?;
+                records.into_iter().map(CellRecord::from).collect()
+            } else if version < 16 {
+                let records: Vec<CellRecordV15> = bincode::deserialize_from(&mut decoder)?;
+                records.into_iter().map(CellRecord::from).collect()
+            } else if version < 28 {
+                let records: Vec<CellRecordV27> = bincode::deserialize_from(&mut decoder)?;
+                records.into_iter().map(CellRecord::from).collect()
+            } else {
+                bincode::deserialize_from(&mut decoder)?
+            };
+            if records.len() != chunk.len() {
+                return Err("snapshot chunk does not match the world size".into());
+            }
+            for (cell, record) in chunk.iter_mut().zip(records) {
+                let brain = match record.brain {
+                    Some(brain) => Some(load_brain(brain, &genomes)?),
+                    None => None,
+                };
+                *cell = Cell {
+                    food: record.food,
+                    money: record.money,
+                    ty: record.ty,
+                    signal: record.signal,
+                    brain,
+                    trade: record.trade,
+                    offer: None,
+                    traded: false,
+                    proceeds: 0,
+                    harvest: 0,
+                    tile: 0,
+                };
+            }
+        }
+        for (tile, cell) in grid.get_cells_mut().iter_mut().enumerate() {
+            cell.tile = tile;
+        }
+
+        let mut lineages = Lineages::default();
+        if version >= 5 {
+            let names: Vec<(u64, String)> = bincode::deserialize_from(&mut decoder)?;
+            for (lineage, name) in names {
+                lineages.rename(lineage, Some(name));
+            }
+        }
+
+        let regions: Vec<Region> = if version >= 12 {
+            bincode::deserialize_from(&mut decoder)?
+        } else {
+            vec![]
+        };
+
+        let approximated: u64 = if version >= 17 {
+            bincode::deserialize_from(&mut decoder)?
+        } else {
+            0
+        };
+
+        let seed: u64 = if version >= 21 {
+            bincode::deserialize_from(&mut decoder)?
+        } else {
+            unsafe { rng() }.gen()
+        };
+
+        let mut market = header.market;
+        if version >= 29 {
+            let sinks: Sinks = bincode::deserialize_from(&mut decoder)?;
+            market.flows.demurrage = sinks.demurrage;
+            market.flows.inheritance_tax = sinks.inheritance_tax;
+            market.flows.minted = sinks.minted;
+        }
+
+        let masks = Masks::new(grid.get_cells());
+        lineages.update(grid.get_cells(), &masks.occupied, header.tick, &[]);
+        // The lineages were already alive when the world was saved.
+        lineages.take_events();
+        let portals = (0..grid.get_cells().len())
+            .filter(|&ix| grid.get_cells()[ix].ty == CellType::Portal)
+            .collect();
+        Ok(Self {
+            population: masks.occupied.count(),
+            masks,
+            lineages,
+            ancestry: None,
+            portals,
+            portal: None,
+            grid,
+            market,
+            mean_age: header.mean_age,
+            max_age: header.max_age,
+            unique_genomes: 0,
+            interned_genomes: 0,
+            sanitized: Default::default(),
+            tally: Arc::new(Tally::new(header.width * header.height)),
+            executed: Default::default(),
+            choices: [0; 6],
+            death_causes: Default::default(),
+            signaling: 0,
+            spoiled: 0,
+            metabolized: 0,
+            deaths: Default::default(),
+            tick: header.tick,
+            seed,
+            hand_spawns: 0,
+            controls: 0,
+            approximated,
+            params: header.params,
+            rules,
+            noise: Noise::default(),
+            mutations: Mutations::default(),
+            config: Arc::new(SimConfig::new(
+                &header.params,
+                &rules,
+                &Noise::default(),
+                Mutations::default(),
+            )),
+            regions: RegionMap::new(regions, header.width, header.height),
+            edges: None,
+            recovery: None,
+            recovered: None,
+            control,
+            possessing: false,
+        })
+    }
+}
+
+fn load_brain(record: BrainRecord, genomes: &[Arc<Dna>]) -> Result<Brain, Box<dyn Error>> {
+    let code = genomes
+        .get(record.genome)
+        .ok_or("snapshot brain refers to a missing genome")?
+        .clone();
+    let mut memory = ArrayVec::new();
+    for value in record.memory {
+        memory
+            .try_push(value)
+            .map_err(|_| "snapshot brain has too much memory")?;
+    }
+    if !memory.is_full() {
+        return Err("snapshot brain has too little memory".into());
+    }
+    Ok(Brain {
+        id: super::brain::next_id(),
+        color: record.color,
+        rotation: record.rotation % 4,
+        generation: record.generation,
+        lineage: record.lineage,
+        memory,
+        code,
+        life: record.life,
+        possessed: false,
+        forced: None,
+        expression: None,
+        execution: None,
+        parents: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::brain::Codons;
+    use super::super::{
+        Area, CircuitBreaker, Demurrage, Exhaustion, Freeze, Limit, Maze, Pipeline, Proceeds,
+        Sanitization, Spoilage,
+    };
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A snapshot checked in from the build which wrote the version it is named after.
+    fn fixture(version: u32) -> Sim {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("snapshots")
+            .join(format!("v{}.snap", version));
+        Sim::load(&path, None).unwrap()
+    }
+
+    /// Checks the cells every fixture was saved with: a wall, a cornacopia, a brain on a
+    /// resting order beside its offspring, which shares its genome, and money lying on a tile.
+    fn check_cells(sim: &Sim, version: u32) {
+        assert_eq!(sim.tick, 1234);
+        assert_eq!(sim.grid.get_width(), 4);
+        assert_eq!(sim.grid.get_height(), 3);
+        let cells = sim.grid.get_cells();
+        assert_eq!(cells[0].ty, CellType::Wall);
+        let tier = if version < 16 {
+            Tier::Medium
+        } else {
+            Tier::Large
+        };
+        assert_eq!(cells[1].ty, CellType::Source(tier));
+        assert_eq!(cells[1].food, 40);
+        let portal = if version < 6 {
+            CellType::Empty
+        } else {
+            CellType::Portal
+        };
+        assert_eq!(cells[2].ty, portal);
+
+        assert_eq!((cells[5].food, cells[5].money), (30, 12));
+        assert_eq!(cells[5].signal, 0.25);
+        let trade = cells[5].trade.as_ref().unwrap();
+        assert_eq!((trade.rate, trade.food), (3, -5));
+        let limit = if version < 13 {
+            Limit::default()
+        } else {
+            Limit {
+                expiry: 6,
+                all_or_nothing: true,
+            }
+        };
+        assert_eq!(trade.limit, limit);
+        let parent = cells[5].brain.as_ref().unwrap();
+        assert_eq!(parent.color, 1.5);
+        assert_eq!(parent.rotation, 2);
+        assert_eq!(parent.generation, 7);
+        for (i, &value) in parent.memory.iter().enumerate() {
+            assert_eq!(value, i as f64 / 4.0);
+        }
+
+        assert_eq!((cells[6].food, cells[6].money), (20, 3));
+        assert!(cells[6].trade.is_none());
+        let child = cells[6].brain.as_ref().unwrap();
+        assert_eq!(child.generation, 8);
+        assert!(Arc::ptr_eq(&parent.code, &child.code));
+        if version < 4 {
+            // Each brain founds a lineage of its own.
+            assert_ne!(parent.lineage, child.lineage);
+        } else {
+            assert_eq!((parent.lineage, child.lineage), (42, 42));
+        }
+
+        assert_eq!(cells[11].money, 9);
+        assert!(cells[11].brain.is_none());
+        let living = cells.iter().filter(|cell| cell.brain.is_some()).count();
+        assert_eq!(living, 2);
+        assert_eq!(sim.population, 2);
+        for (tile, cell) in cells.iter().enumerate() {
+            assert_eq!(cell.tile, tile);
+        }
+    }
+
+    fn names(sim: &Sim) -> Vec<(u64, String)> {
+        sim.lineages
+            .names()
+            .map(|(lineage, name)| (lineage, name.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn loads_version_1() {
+        let sim = fixture(1);
+        check_cells(&sim, 1);
+        assert_eq!(sim.rules, Rules::default());
+        assert!(names(&sim).is_empty());
+    }
+
+    #[test]
+    fn loads_version_2() {
+        let sim = fixture(2);
+        check_cells(&sim, 2);
+        let rules = Rules {
+            direct_exchange: true,
+            ..Rules::default()
+        };
+        assert_eq!(sim.rules, rules);
+    }
+
+    fn rules_v7() -> Rules {
+        Rules {
+            direct_exchange: true,
+            pipeline: Pipeline::MARKET_BEFORE_CONSUMPTION,
+            sanitization: Sanitization::Clamp,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn loads_version_7() {
+        let sim = fixture(7);
+        check_cells(&sim, 7);
+        assert_eq!(sim.rules, rules_v7());
+        assert_eq!(names(&sim), vec![(42, "Founders".to_owned())]);
+    }
+
+    fn rules_v10() -> Rules {
+        Rules {
+            circuit_breaker: Some(CircuitBreaker {
+                move_percent: 25.0,
+                window: 10,
+                halt: 5,
+            }),
+            sense_life: true,
+            starvation_grace: 3,
+            ..rules_v7()
+        }
+    }
+
+    #[test]
+    fn loads_version_10() {
+        let sim = fixture(10);
+        check_cells(&sim, 10);
+        assert_eq!(sim.rules, rules_v10());
+        assert!(sim.regions.regions().is_empty());
+    }
+
+    fn check_regions(sim: &Sim) {
+        let regions = sim.regions.regions();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].area, Area::North);
+        assert_eq!(regions[0].spawn_chance, Some(0.5));
+        assert_eq!(regions[0].mutation_chance, None);
+        assert_eq!(regions[0].cornacopia_bounty, Some(7));
+    }
+
+    #[test]
+    fn loads_version_13() {
+        let sim = fixture(13);
+        check_cells(&sim, 13);
+        assert_eq!(sim.rules, rules_v10());
+        check_regions(&sim);
+        assert_eq!(sim.approximated, 0);
+    }
+
+    fn rules_v18() -> Rules {
+        let mut tiers = Tiers::default();
+        tiers.large.bounty = 9.0;
+        Rules {
+            demurrage: Some(Demurrage {
+                percent: 2.0,
+                carried: true,
+            }),
+            inheritance_tax: 10.0,
+            tiers,
+            approximation: Some(0.5),
+            proceeds: Proceeds::Reserve,
+            ..rules_v10()
+        }
+    }
+
+    #[test]
+    fn loads_version_18() {
+        let sim = fixture(18);
+        check_cells(&sim, 18);
+        assert_eq!(sim.rules, rules_v18());
+        check_regions(&sim);
+        assert_eq!(sim.approximated, 77);
+    }
+
+    fn rules_v25() -> Rules {
+        let mut rules = Rules {
+            sense_fills: true,
+            signal_cost: 0.5,
+            metabolism: 0.01,
+            freeze: Freeze::Market,
+            spoilage: Spoilage {
+                capacity: Some(50),
+                above: 10,
+                percent: 5.0,
+            },
+            ..rules_v18()
+        };
+        rules.tiers.large.every = 4;
+        rules
+    }
+
+    #[test]
+    fn loads_version_25() {
+        let sim = fixture(25);
+        check_cells(&sim, 25);
+        assert_eq!(sim.rules, rules_v25());
+        check_regions(&sim);
+        assert_eq!(sim.approximated, 77);
+        assert_eq!(sim.seed, 99);
+    }
+
+    #[test]
+    fn round_trips_the_current_version() {
+        let mut sim = Sim::new(4, 3, Maze::new(0), 0.0, None);
+        sim.tick = 1234;
+        sim.rules = Rules {
+            exhaustion: Exhaustion::Ration,
+            ..rules_v25()
+        };
+        sim.approximated = 77;
+        sim.seed = 99;
+        sim.market.flows.demurrage = 4;
+        sim.market.flows.inheritance_tax = 5;
+        sim.market.flows.minted = 6;
+        let mut parent: Brain = unsafe { rng() }.sample(Codons {
+            direct_exchange: true,
+        });
+        parent.color = 1.5;
+        parent.rotation = 2;
+        parent.generation = 7;
+        parent.lineage = 42;
+        for (i, value) in parent.memory.iter_mut().enumerate() {
+            *value = i as f64 / 4.0;
+        }
+        parent.life.ticks = 55;
+        parent.life.offspring = 1;
+        parent.life.hungry = 2;
+        parent.life.filled = 0.5;
+        parent.life.fill_price = 3.0;
+        let mut child = parent.clone();
+        child.generation = 8;
+        child.life = Default::default();
+        for cell in sim.grid.get_cells_mut() {
+            cell.food = 0;
+            cell.money = 0;
+            cell.ty = CellType::Empty;
+            cell.signal = 0.0;
+            cell.brain = None;
+            cell.trade = None;
+        }
+        let cells = sim.grid.get_cells_mut();
+        cells[0].ty = CellType::Wall;
+        cells[1].ty = CellType::Source(Tier::Large);
+        cells[1].food = 40;
+        cells[2].ty = CellType::Portal;
+        cells[5].food = 30;
+        cells[5].money = 12;
+        cells[5].signal = 0.25;
+        cells[5].brain = Some(parent);
+        cells[5].trade = Some(Trade {
+            rate: 3,
+            food: -5,
+            limit: Limit {
+                expiry: 6,
+                all_or_nothing: true,
+            },
+        });
+        cells[6].food = 20;
+        cells[6].money = 3;
+        cells[6].brain = Some(child);
+        cells[11].money = 9;
+        sim.lineages.rename(42, Some("Founders".to_owned()));
+        sim.regions = RegionMap::new(
+            vec![Region {
+                area: Area::North,
+                spawn_chance: Some(0.5),
+                mutation_chance: None,
+                general_food_chance: None,
+                cornacopia_chance: None,
+                cornacopia_bounty: Some(7),
+            }],
+            4,
+            3,
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "evonomics-snapshot-test-{}.snap",
+            std::process::id()
+        ));
+        sim.save(&path).unwrap();
+        let loaded = Sim::load(&path, None);
+        std::fs::remove_file(&path).ok();
+        let loaded = loaded.unwrap();
+
+        check_cells(&loaded, VERSION);
+        assert_eq!(loaded.rules, sim.rules);
+        assert_eq!(names(&loaded), vec![(42, "Founders".to_owned())]);
+        check_regions(&loaded);
+        assert_eq!(loaded.approximated, 77);
+        assert_eq!(loaded.seed, 99);
+        let flows = loaded.market.flows;
+        assert_eq!(
+            (flows.demurrage, flows.inheritance_tax, flows.minted),
+            (4, 5, 6)
+        );
+        let cells = loaded.grid.get_cells();
+        let life = cells[5].brain.as_ref().unwrap().life;
+        assert_eq!((life.ticks, life.offspring, life.hungry), (55, 1, 2));
+        assert_eq!((life.filled, life.fill_price), (0.5, 3.0));
+        assert_eq!(cells[6].brain.as_ref().unwrap().life.ticks, 0);
+    }
+}
@@ -0,0 +1,51 @@
+//! Food spoiling in the stockpiles of cells, so that hoarding it has diminishing returns and
+//! trading a surplus away pays better than sitting on it.
+
+use super::Cell;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How much food a cell can keep, by a cap on what it carries, a share of a large stockpile
+/// spoiling every tick, or both.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spoilage {
+    /// The most food a cell can carry, beyond which the rest spoils at once, if capped.
+    pub capacity: Option<u32>,
+    /// Food a cell can carry before any of it starts to spoil.
+    pub above: u32,
+    /// The share of the food carried beyond `above` which spoils each tick, as a percentage.
+    pub percent: f64,
+}
+
+impl Spoilage {
+    /// Whether any food can spoil at all.
+    pub fn is_active(&self) -> bool {
+        self.capacity.is_some() || self.percent > 0.0
+    }
+
+    /// Spoils the food of every cell with a brain beyond what it can keep, returning how much
+    /// spoiled.
+    pub(super) fn spoil(&self, rng: &mut impl Rng, cells: &mut [Cell]) -> u32 {
+        let rate = self.percent.max(0.0).min(100.0) / 100.0;
+        let mut spoiled = 0;
+        for cell in cells.iter_mut().filter(|cell| cell.brain.is_some()) {
+            if let Some(capacity) = self.capacity {
+                let excess = cell.food.saturating_sub(capacity);
+                cell.food -= excess;
+                spoiled += excess;
+            }
+            let stockpile = cell.food.saturating_sub(self.above);
+            if stockpile == 0 || rate == 0.0 {
+                continue;
+            }
+            // Rounded up by chance, as demurrage is, so that small stockpiles spoil at the rate
+            // on average.
+            let decay = stockpile as f64 * rate;
+            let decay = (decay.floor() as u32 + rng.gen_bool(decay.fract()) as u32).min(stockpile);
+            cell.food -= decay;
+            spoiled += decay;
+        }
+        spoiled
+    }
+}
@@ -0,0 +1,70 @@
+//! The random numbers of a tick, which depend only on the seed of the world, the tick, what they
+//! are drawn for and where, so that a world runs the same however its tiles are spread over
+//! threads, and a seed replays a run bit for bit.
+//!
+//! Each draw reads its own part of a ChaCha stream. The seed of the world makes the key and the
+//! tick picks the stream, while what is drawn and the tile or chunk of tiles it is drawn for pick
+//! where in the stream reading starts. Starts lie far enough apart that no draw reads into the
+//! numbers of another, so nothing has to be shared between the threads stepping cells.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Blocks of sixteen words each draw may read before reaching the numbers of the next index.
+const INDEX_BLOCKS: u64 = 1 << 20;
+/// The block counter holds the draw above the index.
+const DRAW_SHIFT: u32 = 56;
+
+/// What random numbers are drawn for, each read from a part of the stream of its own.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum Draw {
+    /// A cell deciding what to do, by tile.
+    Step,
+    /// Brains combining on a tile, by tile.
+    Combining,
+    /// A cell paying for its signal, by tile.
+    Signaling,
+    /// A cell paying for the codons its brain ran, by tile.
+    Metabolism,
+    /// The phases which touch every tile, by chunk of tiles.
+    Mutation,
+    Spawning,
+    Feeding,
+    /// What happens to the world as a whole, at index zero.
+    Edges,
+    Market,
+    Travel,
+    /// Starting the world over, at index zero within a tick, and spawning cells by hand between
+    /// ticks, at a later index each time.
+    Recovery,
+    Demurrage,
+    Spoilage,
+    /// Possessing a cell, and deciding for it at random, for a controller, at a later index each
+    /// time.
+    Control,
+}
+
+/// The random streams of one tick of a world.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) struct Streams {
+    key: [u8; 32],
+    tick: u64,
+}
+
+impl Streams {
+    pub(super) fn new(seed: u64, tick: u64) -> Self {
+        Self {
+            key: ChaCha8Rng::seed_from_u64(seed).gen(),
+            tick,
+        }
+    }
+
+    /// The numbers drawn for something at an index, the same every time they are asked for.
+    pub(super) fn rng(self, draw: Draw, index: usize) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::from_seed(self.key);
+        rng.set_stream(self.tick);
+        let block = ((draw as u64) << DRAW_SHIFT) | (index as u64 * INDEX_BLOCKS);
+        rng.set_word_pos(block as u128 * 16);
+        rng
+    }
+}
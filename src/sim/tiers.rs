@@ -0,0 +1,175 @@
+//! Cornacopias of a few sizes, so that a world can have rare rich sources among many poor ones
+//! rather than sources which are all alike.
+//!
+//! Each tier yields relative to the cornacopia chance and bounty in effect, so the controls for
+//! them still scale every tier at once. A tier may also yield in harvests every so many ticks
+//! rather than by chance every tick, so that sources go through cycles cells can anticipate.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How big a cornacopia is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    Small,
+    Medium,
+    Large,
+}
+
+impl Tier {
+    /// Every tier from the smallest.
+    pub const ALL: [Tier; 3] = [Tier::Small, Tier::Medium, Tier::Large];
+
+    /// Where the tier is in `ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Tier::Small => "small",
+            Tier::Medium => "medium",
+            Tier::Large => "large",
+        })
+    }
+}
+
+/// How common a tier is among new cornacopias and how much it yields.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TierYield {
+    /// The weight of the tier when a new cornacopia is given one, against those of the others.
+    pub proportion: f64,
+    /// What the cornacopia chance is multiplied by on tiles of the tier.
+    pub chance: f64,
+    /// What the cornacopia bounty is multiplied by on tiles of the tier.
+    pub bounty: f64,
+    /// Ticks between the harvests of tiles of the tier, each yielding the food expected over
+    /// them all at once, or zero for food to grow by chance every tick.
+    #[serde(default)]
+    pub every: u32,
+}
+
+/// Every cornacopia is of the middle tier unless the others are given a proportion.
+pub(super) const DEFAULT_TIERS: Tiers = Tiers {
+    small: TierYield {
+        proportion: 0.0,
+        chance: 0.5,
+        bounty: 0.5,
+        every: 0,
+    },
+    medium: TierYield {
+        proportion: 1.0,
+        chance: 1.0,
+        bounty: 1.0,
+        every: 0,
+    },
+    large: TierYield {
+        proportion: 0.0,
+        chance: 1.0,
+        bounty: 4.0,
+        every: 0,
+    },
+};
+
+/// The tiers of cornacopia, of which a tier left out of a scenario keeps its default.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tiers {
+    pub small: TierYield,
+    pub medium: TierYield,
+    pub large: TierYield,
+}
+
+impl Default for Tiers {
+    fn default() -> Self {
+        DEFAULT_TIERS
+    }
+}
+
+impl Tiers {
+    pub fn get(&self, tier: Tier) -> TierYield {
+        match tier {
+            Tier::Small => self.small,
+            Tier::Medium => self.medium,
+            Tier::Large => self.large,
+        }
+    }
+
+    /// Why the tiers cannot be used, if they cannot: every number must be finite and none
+    /// negative, and some tier must have a proportion.
+    pub fn check(&self) -> Result<(), String> {
+        for &tier in &Tier::ALL {
+            let tier_yield = self.get(tier);
+            for &(name, value) in &[
+                ("proportion", tier_yield.proportion),
+                ("chance", tier_yield.chance),
+                ("bounty", tier_yield.bounty),
+            ] {
+                if !value.is_finite() || value < 0.0 {
+                    return Err(format!(
+                        "tiers.{}.{} must be 0 or more, not {}",
+                        tier, name, value
+                    ));
+                }
+            }
+        }
+        if self.total_proportion() == 0.0 {
+            return Err("some tier of cornacopia must have a proportion".to_owned());
+        }
+        Ok(())
+    }
+
+    fn total_proportion(&self) -> f64 {
+        Tier::ALL
+            .iter()
+            .map(|&tier| self.get(tier).proportion)
+            .sum()
+    }
+
+    /// Picks the tier of a new cornacopia by the proportions, or the middle tier if none has a
+    /// proportion.
+    pub(super) fn pick(&self, rng: &mut impl Rng) -> Tier {
+        let total = self.total_proportion();
+        if !total.is_finite() || total <= 0.0 {
+            return Tier::Medium;
+        }
+        let mut left = rng.gen_range(0.0, total);
+        for &tier in &Tier::ALL {
+            let proportion = self.get(tier).proportion;
+            if left < proportion {
+                return tier;
+            }
+            left -= proportion;
+        }
+        // Rounding can leave a sliver past the last tier.
+        Tier::ALL
+            .iter()
+            .rev()
+            .copied()
+            .find(|&tier| self.get(tier).proportion > 0.0)
+            .unwrap_or(Tier::Medium)
+    }
+
+    /// The food a tile of a tier is expected to yield each tick under a chance and bounty.
+    pub fn expected_food(&self, tier: Tier, chance: f64, bounty: u32) -> f64 {
+        let tier_yield = self.get(tier);
+        (chance * tier_yield.chance).max(0.0).min(1.0) * self.bounty(tier, bounty) as f64
+    }
+
+    /// The food a tile of a tier yields when it yields any.
+    pub(super) fn bounty(&self, tier: Tier, bounty: u32) -> u32 {
+        (bounty as f64 * self.get(tier).bounty).round() as u32
+    }
+
+    /// The food a tile of a tier which yields on a schedule yields at each harvest, the food it
+    /// would be expected to yield by chance over the ticks between harvests.
+    pub(super) fn harvest(&self, tier: Tier, chance: f64, bounty: u32) -> u32 {
+        let every = self.get(tier).every as f64;
+        (self.expected_food(tier, chance, bounty) * every).round() as u32
+    }
+}
@@ -0,0 +1,113 @@
+//! Following one individual as it moves through the world, recording its food and money every
+//! tick.
+
+use super::brain::Brain;
+use super::{BrainTrace, Expression, Sim};
+
+/// The followed cell at the end of a tick.
+#[derive(Copy, Clone, Debug)]
+pub struct TracePoint {
+    pub tick: u64,
+    pub x: usize,
+    pub y: usize,
+    pub food: u32,
+    pub money: u32,
+}
+
+/// Where the followed brain was last seen.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Follow {
+    id: u64,
+    index: usize,
+}
+
+impl Follow {
+    /// Starts following the brain at a tile, if there is one.
+    pub(super) fn new(sim: &Sim, index: usize) -> Option<Self> {
+        let id = sim.cell(index).brain.as_ref()?.id;
+        Some(Self { id, index })
+    }
+
+    /// Starts or stops keeping track of which parts of the genome of the brain run, for as
+    /// long as it lives.
+    pub(super) fn trace_expression(&self, sim: &mut Sim, tracing: bool) {
+        if let Some(brain) = self.brain_mut(sim) {
+            brain.trace_expression(tracing);
+        }
+    }
+
+    /// Starts or stops recording how each decision of the brain comes about, for as long as it
+    /// lives.
+    pub(super) fn trace_execution(&self, sim: &mut Sim, tracing: bool) {
+        if let Some(brain) = self.brain_mut(sim) {
+            brain.trace_execution(tracing);
+        }
+    }
+
+    /// The decisions of the brain traced since they were last taken, as of where it was last
+    /// found, marked with the tick the world is on.
+    pub(super) fn take_execution(&self, sim: &mut Sim) -> Vec<BrainTrace> {
+        let tick = sim.tick;
+        let mut decisions = self
+            .brain_mut(sim)
+            .map_or(vec![], |brain| brain.take_execution());
+        for decision in &mut decisions {
+            decision.tick = tick;
+        }
+        decisions
+    }
+
+    fn brain_mut<'a>(&self, sim: &'a mut Sim) -> Option<&'a mut Brain> {
+        let id = self.id;
+        sim.grid.get_cells_mut()[self.index]
+            .brain
+            .as_mut()
+            .filter(|brain| brain.id == id)
+    }
+
+    /// Which parts of the genome of the brain ran, as of where it was last found.
+    pub(super) fn expression(&self, sim: &Sim) -> Option<Expression> {
+        sim.cell(self.index)
+            .brain
+            .as_ref()
+            .filter(|brain| brain.id == self.id)?
+            .expression()
+    }
+
+    /// Finds the brain again after a tick, giving `None` once it died or combined with another.
+    ///
+    /// A brain moves at most one tile a tick, so the tiles around where it was are looked at
+    /// before the whole world.
+    pub(super) fn find(&mut self, sim: &Sim) -> Option<TracePoint> {
+        let (width, height) = (sim.width(), sim.height());
+        let (x, y) = (self.index % width, self.index / width);
+        // Where it was, then each way it could have moved, wrapping around the edges.
+        let offsets = [(0, 0), (1, 0), (width - 1, 0), (0, 1), (0, height - 1)];
+        let mut around = offsets
+            .iter()
+            .map(|&(dx, dy)| (y + dy) % height * width + (x + dx) % width);
+        let id = self.id;
+        let is_followed = |index: usize| {
+            sim.cell(index)
+                .brain
+                .as_ref()
+                .map_or(false, |brain| brain.id == id)
+        };
+        self.index = match around.find(|&index| is_followed(index)) {
+            Some(index) => index,
+            None => sim
+                .masks()
+                .occupied
+                .ones()
+                .find(|&index| is_followed(index))?,
+        };
+        let cell = sim.cell(self.index);
+        Some(TracePoint {
+            tick: sim.tick,
+            x: self.index % width,
+            y: self.index / width,
+            food: cell.food,
+            money: cell.money,
+        })
+    }
+}
@@ -0,0 +1,91 @@
+//! Sounding out the market as it clears, so that a long run can be kept an ear on while doing
+//! other work. Each sample with trades plays a tone whose pitch follows the price the last of
+//! them was executed at and whose loudness follows the food traded.
+//!
+//! Playing sound takes an audio backend, which is only built with the `audio` feature, so that
+//! default builds go without one.
+
+use crate::sim::Candle;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// How long each tone sounds, and so the least time between two of them.
+const TONE: Duration = Duration::from_millis(150);
+/// The pitch in hertz of a price of one money per food, the price of the reserve.
+const BASE_PITCH: f64 = 220.0;
+const HIGHEST_PITCH: f64 = 1760.0;
+/// How loud the sample which traded the most food so far sounds.
+const MAX_VOLUME: f64 = 0.25;
+
+pub struct Sonifier {
+    output: Output,
+    /// When the last tone started.
+    last: Option<Instant>,
+    /// The most food traded in a sample so far.
+    loudest: u64,
+}
+
+impl Sonifier {
+    /// Opens the default audio output, failing if there is none or sound was not built in.
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            output: Output::open()?,
+            last: None,
+            loudest: 0,
+        })
+    }
+
+    /// Plays the trades of a sample, unless the last tone is still sounding, so that a burst of
+    /// samples never queues up sound which lags behind the world.
+    pub fn play(&mut self, candle: Candle) {
+        if self.last.map_or(false, |last| last.elapsed() < TONE) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        self.loudest = self.loudest.max(candle.food);
+        // Loudness is heard roughly as the square root of the power.
+        let volume = (candle.food as f64 / self.loudest.max(1) as f64).sqrt() * MAX_VOLUME;
+        self.output.tone(pitch(candle.close), volume as f32);
+    }
+}
+
+/// Each doubling of the price raises the pitch by half an octave, up to the highest pitch.
+fn pitch(price: i32) -> u32 {
+    let pitch = BASE_PITCH * (price.max(1) as f64).sqrt();
+    pitch.min(HIGHEST_PITCH) as u32
+}
+
+#[cfg(feature = "audio")]
+struct Output {
+    sink: rodio::Sink,
+}
+
+#[cfg(feature = "audio")]
+impl Output {
+    fn open() -> Result<Self, Box<dyn Error>> {
+        let device = rodio::default_output_device().ok_or("there is no audio output device")?;
+        Ok(Self {
+            sink: rodio::Sink::new(&device),
+        })
+    }
+
+    fn tone(&self, pitch: u32, volume: f32) {
+        use rodio::Source;
+        let tone = rodio::source::SineWave::new(pitch)
+            .take_duration(TONE)
+            .amplify(volume);
+        self.sink.append(tone);
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+struct Output;
+
+#[cfg(not(feature = "audio"))]
+impl Output {
+    fn open() -> Result<Self, Box<dyn Error>> {
+        Err("evonomics was built without the audio feature".into())
+    }
+
+    fn tone(&self, _pitch: u32, _volume: f32) {}
+}
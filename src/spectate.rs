@@ -0,0 +1,277 @@
+//! Lets a long run be watched from another machine without being controlled from it.
+//!
+//! A running app started with `--spectators` sends every view of the grid and every sample of
+//! stats to whoever connects, and an app started with `--spectate` shows them. Each frame on the
+//! wire is its length as four little-endian bytes, followed by that many bytes of a zstd frame
+//! holding a `Frame` in bincode.
+
+use crate::sim::{View, ViewColors};
+use bincode::Options;
+use futures::channel::mpsc::{self, Receiver};
+use futures::executor::block_on;
+use futures::SinkExt;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// Lower than for snapshots, since a frame is compressed for every view sent.
+const COMPRESSION_LEVEL: i32 = 1;
+/// Frames waiting to be sent before new ones are dropped, so a slow spectator never holds up
+/// the window.
+const QUEUED_FRAMES: usize = 2;
+/// The largest frame a spectator accepts, both as sent and once decompressed, to fail cleanly on
+/// something which is not a server.
+const MAX_FRAME_BYTES: usize = 1 << 28;
+/// How long a spectator may go without reading before it is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Frame {
+    /// The colors of every tile, by row then column, as red, green and blue bytes.
+    View {
+        width: usize,
+        height: usize,
+        colors: Vec<[u8; 3]>,
+        cells: usize,
+        ticks: usize,
+    },
+    /// A sample of stats as the columns and values of a row of the exported stats.
+    Stats {
+        tick: u64,
+        header: Vec<String>,
+        values: Vec<String>,
+    },
+}
+
+impl Frame {
+    /// A view as it is sent, without the ancestry markings a full view has.
+    pub fn view(view: &View) -> Self {
+        let (height, width) = view.colors.dim();
+        let colors = match view.colors {
+            ViewColors::Compact(ref colors) => colors.iter().copied().collect(),
+            ViewColors::Full(ref colors) => colors
+                .iter()
+                .map(|&(color, _)| {
+                    let byte = |channel: f32| (channel * 255.0).round() as u8;
+                    [byte(color.r), byte(color.g), byte(color.b)]
+                })
+                .collect(),
+        };
+        Frame::View {
+            width,
+            height,
+            colors,
+            cells: view.cells,
+            ticks: view.ticks,
+        }
+    }
+}
+
+/// A TCP endpoint which sends frames to every spectator connected to it.
+pub struct Broadcast {
+    frames: std_mpsc::SyncSender<Frame>,
+    /// Spectators connected, kept apart from their streams so that it can be read while they
+    /// are being written to.
+    watching: Arc<AtomicUsize>,
+}
+
+impl Broadcast {
+    /// Start accepting spectators on the given address.
+    pub fn listen(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let spectators = Arc::new(Mutex::new(Vec::new()));
+        let watching = Arc::new(AtomicUsize::new(0));
+        let (accepted, counted) = (spectators.clone(), watching.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok() {
+                    let mut spectators = accepted.lock().unwrap();
+                    spectators.push(stream);
+                    counted.store(spectators.len(), Relaxed);
+                }
+            }
+        });
+        let (frames, queued) = std_mpsc::sync_channel::<Frame>(QUEUED_FRAMES);
+        let counted = watching.clone();
+        std::thread::spawn(move || {
+            for frame in queued {
+                let bytes = match encode(&frame) {
+                    Ok(bytes) => bytes,
+                    Err(_) => continue,
+                };
+                // Spectators which can no longer be written to have left.
+                let mut spectators = spectators.lock().unwrap();
+                spectators.retain(|mut stream| stream.write_all(&bytes).is_ok());
+                counted.store(spectators.len(), Relaxed);
+            }
+        });
+        Ok(Self { frames, watching })
+    }
+
+    /// Whether anyone is watching, so that frames are only made when they would be sent.
+    pub fn watched(&self) -> bool {
+        self.watching.load(Relaxed) > 0
+    }
+
+    pub fn send(&self, frame: Frame) {
+        // If the queue is full, dont send it.
+        self.frames.try_send(frame).ok();
+    }
+}
+
+/// A frame with its length in front, ready to be written.
+fn encode(frame: &Frame) -> Result<Vec<u8>, Box<dyn Error>> {
+    let compressed = zstd::encode_all(&frame_options().serialize(frame)?[..], COMPRESSION_LEVEL)?;
+    let mut bytes = (compressed.len() as u32).to_le_bytes().to_vec();
+    bytes.extend_from_slice(&compressed);
+    Ok(bytes)
+}
+
+fn read_frame(reader: &mut impl Read) -> Result<Frame, Box<dyn Error>> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_FRAME_BYTES {
+        return Err(format!("a frame of {} bytes is too large", length).into());
+    }
+    let mut compressed = vec![0; length];
+    reader.read_exact(&mut compressed)?;
+    // One byte past the limit is read, so that a frame which would decompress to more is
+    // refused rather than cut short.
+    let mut bytes = vec![];
+    zstd::stream::read::Decoder::new(&compressed[..])?
+        .take(MAX_FRAME_BYTES as u64 + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() > MAX_FRAME_BYTES {
+        return Err(format!(
+            "a frame decompressing to over {} bytes is too large",
+            MAX_FRAME_BYTES
+        )
+        .into());
+    }
+    Ok(frame_options().deserialize(&bytes)?)
+}
+
+/// The encoding of `bincode::serialize`, limited so that a corrupt frame fails instead of
+/// allocating without bound, and so that no frame is sent which a spectator would refuse.
+fn frame_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_FRAME_BYTES as u64)
+}
+
+/// Either a frame or why the connection ended, which is the last thing received.
+pub type Received = Result<Frame, String>;
+
+/// Connects to a running app and receives its frames until the connection ends.
+pub fn connect(addr: String) -> Receiver<Received> {
+    let (mut tx, rx) = mpsc::channel(QUEUED_FRAMES);
+    std::thread::spawn(move || {
+        let mut reader = match TcpStream::connect(&addr) {
+            Ok(stream) => BufReader::new(stream),
+            Err(e) => {
+                block_on(tx.send(Err(format!("failed to connect to {}: {}", addr, e)))).ok();
+                return;
+            }
+        };
+        loop {
+            let received = read_frame(&mut reader)
+                .map_err(|e| format!("lost the connection to {}: {}", addr, e));
+            let lost = received.is_err();
+            if block_on(tx.send(received)).is_err() || lost {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+/// Turns a received view back into one the grid can show.
+pub fn to_view(
+    width: usize,
+    height: usize,
+    colors: Vec<[u8; 3]>,
+    cells: usize,
+    ticks: usize,
+) -> View {
+    View {
+        colors: ViewColors::Compact(
+            Array2::from_shape_vec((height, width), colors).unwrap_or_default(),
+        ),
+        cells,
+        matches: None,
+        ticks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn frames_survive_the_wire() {
+        let bytes = encode(&Frame::View {
+            width: 2,
+            height: 1,
+            colors: vec![[1, 2, 3], [4, 5, 6]],
+            cells: 7,
+            ticks: 8,
+        })
+        .unwrap();
+        match read_frame(&mut Cursor::new(bytes)).unwrap() {
+            Frame::View {
+                width,
+                height,
+                colors,
+                cells,
+                ticks,
+            } => {
+                assert_eq!((width, height, cells, ticks), (2, 1, 7, 8));
+                assert_eq!(colors, vec![[1, 2, 3], [4, 5, 6]]);
+            }
+            frame => panic!("expected a view, got {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn a_frame_decompressing_past_the_limit_is_refused() {
+        let mut encoder = zstd::stream::write::Encoder::new(vec![], COMPRESSION_LEVEL).unwrap();
+        let zeros = vec![0; 1 << 20];
+        for _ in 0..=MAX_FRAME_BYTES / zeros.len() {
+            encoder.write_all(&zeros).unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() <= MAX_FRAME_BYTES);
+        let mut bytes = (compressed.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&compressed);
+        let error = read_frame(&mut Cursor::new(bytes)).unwrap_err();
+        assert!(error.to_string().contains("too large"), "{}", error);
+    }
+
+    #[test]
+    fn a_corrupt_length_inside_a_frame_is_refused() {
+        // A view claiming more colors than any frame may hold.
+        let mut serialized = frame_options()
+            .serialize(&Frame::View {
+                width: 0,
+                height: 0,
+                colors: vec![],
+                cells: 0,
+                ticks: 0,
+            })
+            .unwrap();
+        let colors = 4 + 8 + 8;
+        serialized[colors..colors + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let compressed = zstd::encode_all(&serialized[..], COMPRESSION_LEVEL).unwrap();
+        let mut bytes = (compressed.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&compressed);
+        assert!(read_frame(&mut Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! A binary file of stats for runs of millions of ticks, which is only ever appended to and can be
+//! read a few columns and a stretch of ticks at a time, so that analyzing a long run never loads
+//! all of it into memory.
+//!
+//! The file starts with the magic bytes `EVONSTAT`, the format version as a little endian `u32`
+//! and the names of the columns, each as a little endian `u32` length and its UTF-8 bytes. The
+//! rows follow in chunks of up to `CHUNK_ROWS`, each laid out column by column: the ticks of its
+//! rows as little endian `u64`, then the values of each column in turn as little endian `f64`,
+//! with NaN where a value is missing.
+//!
+//! The index beside it, at the same path with `.idx` appended, holds an entry for each chunk:
+//! where it starts, how many rows it has and its first and last tick. A chunk is only indexed once
+//! it was written in full, so a run which is cut short leaves a file which reads up to its last
+//! complete chunk.
+
+use std::convert::TryInto;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"EVONSTAT";
+/// The version written by this build.
+const VERSION: u32 = 1;
+/// Rows held in memory before they are written out as a chunk.
+const CHUNK_ROWS: usize = 4096;
+/// The bytes of an entry of the index: the offset, the rows, and the first and last tick.
+const ENTRY_BYTES: usize = 8 + 4 + 8 + 8;
+
+/// Where the index of a stats file is.
+fn index_path(path: &Path) -> PathBuf {
+    let mut index = path.as_os_str().to_owned();
+    index.push(".idx");
+    index.into()
+}
+
+/// Appends rows of stats to a new stats file.
+pub struct StatsWriter {
+    data: BufWriter<File>,
+    index: BufWriter<File>,
+    /// Where the next chunk starts in the data.
+    offset: u64,
+    ticks: Vec<u64>,
+    /// The values of the rows not yet written, column by column.
+    values: Vec<Vec<f64>>,
+}
+
+impl StatsWriter {
+    pub fn create(path: &Path, columns: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut data = BufWriter::new(File::create(path)?);
+        let index = BufWriter::new(File::create(index_path(path))?);
+        data.write_all(MAGIC)?;
+        data.write_all(&VERSION.to_le_bytes())?;
+        data.write_all(&(columns.len() as u32).to_le_bytes())?;
+        let mut offset = (MAGIC.len() + 8) as u64;
+        for column in columns {
+            data.write_all(&(column.len() as u32).to_le_bytes())?;
+            data.write_all(column.as_bytes())?;
+            offset += 4 + column.len() as u64;
+        }
+        Ok(Self {
+            data,
+            index,
+            offset,
+            ticks: Vec::with_capacity(CHUNK_ROWS),
+            values: vec![Vec::with_capacity(CHUNK_ROWS); columns.len()],
+        })
+    }
+
+    /// Adds the values of every column at a tick, writing out a chunk once enough have been added.
+    pub fn push(&mut self, tick: u64, values: &[Option<f64>]) -> Result<(), Box<dyn Error>> {
+        if values.len() != self.values.len() {
+            return Err(format!(
+                "expected {} values, not {}",
+                self.values.len(),
+                values.len()
+            )
+            .into());
+        }
+        self.ticks.push(tick);
+        for (column, value) in self.values.iter_mut().zip(values) {
+            column.push(value.unwrap_or(std::f64::NAN));
+        }
+        if self.ticks.len() == CHUNK_ROWS {
+            self.write_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out the rows added since the last chunk, then indexes them.
+    fn write_chunk(&mut self) -> Result<(), Box<dyn Error>> {
+        let (first, last) = match (self.ticks.first(), self.ticks.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return Ok(()),
+        };
+        let rows = self.ticks.len();
+        for tick in self.ticks.drain(..) {
+            self.data.write_all(&tick.to_le_bytes())?;
+        }
+        for column in &mut self.values {
+            for value in column.drain(..) {
+                self.data.write_all(&value.to_le_bytes())?;
+            }
+        }
+        // The chunk must be out before the index points at it.
+        self.data.flush()?;
+        self.index.write_all(&self.offset.to_le_bytes())?;
+        self.index.write_all(&(rows as u32).to_le_bytes())?;
+        self.index.write_all(&first.to_le_bytes())?;
+        self.index.write_all(&last.to_le_bytes())?;
+        self.index.flush()?;
+        self.offset += (rows * 8 * (1 + self.values.len())) as u64;
+        Ok(())
+    }
+
+    /// Writes out the rows still held, so that every row added can be read.
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        self.write_chunk()
+    }
+}
+
+/// A chunk of rows as the index has it.
+struct Chunk {
+    offset: u64,
+    rows: usize,
+    first: u64,
+    last: u64,
+}
+
+/// A stats file opened for reading, of which only the index is held in memory.
+pub struct StatsFile {
+    data: BufReader<File>,
+    columns: Vec<String>,
+    chunks: Vec<Chunk>,
+}
+
+impl StatsFile {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut data = BufReader::new(File::open(path)?);
+        let mut magic = [0; 8];
+        data.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("not an evonomics stats file".into());
+        }
+        let version = read_u32(&mut data)?;
+        if version != VERSION {
+            return Err(format!("unsupported stats file version {}", version).into());
+        }
+        let mut columns = vec![];
+        for _ in 0..read_u32(&mut data)? {
+            let mut name = vec![0; read_u32(&mut data)? as usize];
+            data.read_exact(&mut name)?;
+            columns.push(String::from_utf8(name)?);
+        }
+        let mut index = vec![];
+        File::open(index_path(path))?.read_to_end(&mut index)?;
+        // An entry cut off by a crash is left out, along with its chunk.
+        let chunks = index
+            .chunks_exact(ENTRY_BYTES)
+            .map(|entry| Chunk {
+                offset: u64::from_le_bytes(entry[0..8].try_into().unwrap()),
+                rows: u32::from_le_bytes(entry[8..12].try_into().unwrap()) as usize,
+                first: u64::from_le_bytes(entry[12..20].try_into().unwrap()),
+                last: u64::from_le_bytes(entry[20..28].try_into().unwrap()),
+            })
+            .collect();
+        Ok(Self {
+            data,
+            columns,
+            chunks,
+        })
+    }
+
+    /// The rows in the file.
+    pub fn rows(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.rows).sum()
+    }
+
+    /// Every `every`th row whose tick lies within `first..=last`, counting rows from the start of
+    /// the file, with the values of some columns, each `None` where it is missing or the file has
+    /// no such column. Only the chunks overlapping the ticks are read, and of them only the
+    /// columns asked for.
+    pub fn read(
+        &mut self,
+        columns: &[&str],
+        first: u64,
+        last: u64,
+        every: usize,
+    ) -> Result<Vec<(u64, Vec<Option<f64>>)>, Box<dyn Error>> {
+        let every = every.max(1);
+        let indices: Vec<Option<usize>> = columns
+            .iter()
+            .map(|&name| self.columns.iter().position(|column| column == name))
+            .collect();
+        let mut rows = vec![];
+        let mut start = 0;
+        for chunk in &self.chunks {
+            let skipped = chunk.first > last || chunk.last < first;
+            if !skipped {
+                let ticks = read_block(&mut self.data, chunk.offset, chunk.rows)?;
+                let mut values = vec![];
+                for index in &indices {
+                    values.push(match *index {
+                        Some(index) => {
+                            let offset = chunk.offset + ((1 + index) * chunk.rows * 8) as u64;
+                            Some(read_block(&mut self.data, offset, chunk.rows)?)
+                        }
+                        None => None,
+                    });
+                }
+                for (row, tick) in ticks.into_iter().map(u64::from_le_bytes).enumerate() {
+                    if (start + row) % every != 0 || tick < first || tick > last {
+                        continue;
+                    }
+                    let row = values
+                        .iter()
+                        .map(|column| {
+                            let value = f64::from_le_bytes(column.as_ref()?[row]);
+                            if value.is_nan() {
+                                None
+                            } else {
+                                Some(value)
+                            }
+                        })
+                        .collect();
+                    rows.push((tick, row));
+                }
+            }
+            start += chunk.rows;
+        }
+        Ok(rows)
+    }
+
+    /// Rows spread evenly over the whole file, at most `points` of them, so that a run of any
+    /// length can be charted from about the same amount of memory.
+    pub fn sample(
+        &mut self,
+        columns: &[&str],
+        points: usize,
+    ) -> Result<Vec<(u64, Vec<Option<f64>>)>, Box<dyn Error>> {
+        let every = (self.rows() + points.max(1) - 1) / points.max(1);
+        self.read(columns, 0, u64::MAX, every)
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, Box<dyn Error>> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// The eight byte words of a block of a chunk.
+fn read_block(
+    data: &mut BufReader<File>,
+    offset: u64,
+    rows: usize,
+) -> Result<Vec<[u8; 8]>, Box<dyn Error>> {
+    data.seek(SeekFrom::Start(offset))?;
+    let mut bytes = vec![0; rows * 8];
+    data.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(8)
+        .map(|word| word.try_into().unwrap())
+        .collect())
+}
@@ -1,35 +1,109 @@
+use crate::sim;
 use iced::{ Background, container, Color, button, slider };
+use serde::Deserialize;
+use std::path::Path;
 
 pub enum Theme {Default, Nested}
 
-pub const MAIN_MENU_COLLUMN_WIDTH: u32 = 350;
-pub const BUTTON_SIZE: u32 = 200;
-pub const PADDING: u16 = 10;
-pub const SPACING: u16 = 20;
+/// The colors and sizes of the GUI, which can be loaded from a TOML theme file.
+///
+/// Every field is optional in a theme file, falling back to the dark metallic theme.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Skin {
+    /// Text, slider rails and handles, as red, green and blue bytes.
+    pub accent: [u8; 3],
+    pub accent_hovered: [u8; 3],
+    pub accent_dragged: [u8; 3],
+    pub hovered_text: [u8; 3],
+    pub background: [u8; 3],
+    /// Panels inside the background, and the buttons on the background.
+    pub nested_background: [u8; 3],
+    /// Buttons inside panels.
+    pub nested_button: [u8; 3],
+    /// Walls on the grid, and the bricks drawn on them up close.
+    pub wall: [u8; 3],
+    /// Portals on the grid with no cell on them, and the rings drawn on them up close.
+    pub portal: [u8; 3],
+    /// The tints of cornacopias of each tier on the grid, from the smallest, which their food and
+    /// money are added to, and the diamonds drawn on them up close.
+    pub sources: [[u8; 3]; 3],
+    pub menu_column_width: u32,
+    pub button_size: u32,
+    pub padding: u16,
+    pub spacing: u16,
+}
 
-macro_rules! color_const {
-    ( $r:expr, $g:expr, $b:expr ) => {
-        Color { r: $r as f32 / 255.0, g: $g as f32 / 255.0, b: $b as f32 / 255.0, a: 1.0 }
+impl Skin {
+    pub const DARK_METALLIC: Skin = Skin {
+        accent: [ 0xD4, 0xAF, 0x37 ], // gold
+        accent_hovered: [ 0xE5, 0xE4, 0xE2 ], // platinum
+        accent_dragged: [ 0xE2, 0xE7, 0xE1 ], // rhodium
+        hovered_text: [ 0xFF, 0xFF, 0xFF ],
+        background: [ 0x90, 0x90, 0xA3 ], // osmium
+        nested_background: [ 0x4C, 0x55, 0x59 ], // tellurium
+        nested_button: [ 0x6F, 0x6A, 0x75 ], // palladium
+        wall: [ 0x66, 0x00, 0x00 ],
+        portal: [ 0x80, 0x00, 0xCC ],
+        sources: [ [ 0x00, 0x0D, 0x4D ], [ 0x59, 0x26, 0x00 ], [ 0x66, 0x66, 0x66 ] ],
+        menu_column_width: 350,
+        button_size: 200,
+        padding: 10,
+        spacing: 20,
     };
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&text)?)
+    }
+
+    /// The colors the sim draws the terrain of the grid in.
+    pub fn terrain(&self) -> sim::TerrainColors {
+        let [small, medium, large] = self.sources;
+        sim::TerrainColors {
+            wall: color(self.wall),
+            portal: color(self.portal),
+            sources: [color(small), color(medium), color(large)],
+        }
+    }
 }
 
-pub const COLOR_GOLD: Color = color_const!( 0xD4, 0xAF, 0x37 );
-pub const COLOR_RHODIUM: Color = color_const!( 0xE2, 0xE7, 0xE1 );
-pub const COLOR_PLATINUM: Color = color_const!( 0xE5, 0xE4, 0xE2 );
-pub const COLOR_PALLADIUM: Color = color_const!( 0x6F, 0x6A, 0x75 );
-// pub const COLOR_SILVER: Color = color_const!( 0xC0, 0xC0, 0xC0 );
-// pub const COLOR_MERCURY: Color = color_const!( 0xD5, 0xD2, 0xD1 );
-pub const COLOR_TELLURIUM: Color = color_const!( 0x4C, 0x55, 0x59 );
-pub const COLOR_OSMIUM: Color = color_const!( 0x90, 0x90, 0xA3 );
+impl Default for Skin {
+    fn default() -> Self {
+        Skin::DARK_METALLIC
+    }
+}
+
+/// The skin in use, which only the GUI thread reads or changes.
+static mut SKIN: Skin = Skin::DARK_METALLIC;
+
+pub fn skin() -> Skin {
+    unsafe { SKIN }
+}
+
+/// Changes the skin, which shows the next time the window is drawn.
+pub fn set_skin(skin: Skin) {
+    unsafe { SKIN = skin }
+}
+
+pub fn color([r, g, b]: [u8; 3]) -> Color {
+    Color { r: r as f32 / 255.0, g: g as f32 / 255.0, b: b as f32 / 255.0, a: 1.0 }
+}
+
+pub fn menu_column_width() -> u32 { skin().menu_column_width }
+pub fn button_size() -> u32 { skin().button_size }
+pub fn padding() -> u16 { skin().padding }
+pub fn spacing() -> u16 { skin().spacing }
 
 pub struct Slider;
 impl slider::StyleSheet for Slider {
     fn active(&self) -> slider::Style {
+        let accent = color(skin().accent);
         slider::Style {
-            rail_colors: (COLOR_GOLD, Color { a: 0.1, ..COLOR_GOLD }),
+            rail_colors: (accent, Color { a: 0.1, ..accent }),
             handle: slider::Handle {
                 shape: slider::HandleShape::Circle { radius: 9 },
-                color: COLOR_GOLD,
+                color: accent,
                 border_width: 0,
                 border_color: Color::TRANSPARENT,
             },
@@ -40,7 +114,7 @@ impl slider::StyleSheet for Slider {
 
         slider::Style {
             handle: slider::Handle {
-                color: COLOR_PLATINUM,
+                color: color(skin().accent_hovered),
                 ..active.handle
             },
             ..active
@@ -51,7 +125,7 @@ impl slider::StyleSheet for Slider {
 
         slider::Style {
             handle: slider::Handle {
-                color: COLOR_RHODIUM,
+                color: color(skin().accent_dragged),
                 ..active.handle
             },
             ..active
@@ -68,8 +142,8 @@ pub struct Container;
 impl container::StyleSheet for Container {
     fn style(&self) -> container::Style {
         container::Style {
-            background: Some(Background::Color(COLOR_OSMIUM)),
-            text_color: Some(COLOR_GOLD),
+            background: Some(Background::Color(color(skin().background))),
+            text_color: Some(color(skin().accent)),
             ..container::Style::default()
         }
     }
@@ -78,12 +152,24 @@ pub struct ContainerNested;
 impl container::StyleSheet for ContainerNested {
     fn style(&self) -> container::Style {
         container::Style {
-            background: Some(Background::Color(COLOR_TELLURIUM)),
-            text_color: Some(COLOR_GOLD),
+            background: Some(Background::Color(color(skin().nested_background))),
+            text_color: Some(color(skin().accent)),
             ..container::Style::default()
         }
     }
 }
+/// A note pointing something out, such as a callout of a demo, ringed in the accent.
+pub struct Callout;
+impl container::StyleSheet for Callout {
+    fn style(&self) -> container::Style {
+        container::Style {
+            border_radius: 8,
+            border_width: 2,
+            border_color: color(skin().accent),
+            ..ContainerNested.style()
+        }
+    }
+}
 impl From<Theme> for Box<dyn container::StyleSheet> {
     fn from(theme: Theme) -> Self {
         match theme {
@@ -97,14 +183,14 @@ pub struct Button;
 impl button::StyleSheet for Button {
     fn active(&self) -> button::Style {
         button::Style {
-            background: Some(Background::Color(COLOR_TELLURIUM)),
-            text_color: COLOR_GOLD,
+            background: Some(Background::Color(color(skin().nested_background))),
+            text_color: color(skin().accent),
             ..button::Style::default()
         }
     }
     fn hovered(&self) -> button::Style {
         button::Style {
-            text_color: Color::WHITE,
+            text_color: color(skin().hovered_text),
             ..self.active()
         }
     }
@@ -113,14 +199,14 @@ pub struct ButtonNested;
 impl button::StyleSheet for ButtonNested {
     fn active(&self) -> button::Style {
         button::Style {
-            background: Some(Background::Color(COLOR_PALLADIUM)),
-            text_color: COLOR_GOLD,
+            background: Some(Background::Color(color(skin().nested_button))),
+            text_color: color(skin().accent),
             ..button::Style::default()
         }
     }
     fn hovered(&self) -> button::Style {
         button::Style {
-            text_color: Color::WHITE,
+            text_color: color(skin().hovered_text),
             ..self.active()
         }
     }
@@ -0,0 +1,90 @@
+//! Records every trade the market executes as a zstd compressed CSV table, for studying how
+//! prices form offline. Each row is the tick, the price, the food traded and the lineages of the
+//! buyer and the seller, left empty for the reserve or a tile whose brain is gone.
+//!
+//! A long run trades far too much to keep all of it, so only the clearing of every so many ticks
+//! may be recorded, each in full.
+
+use crate::sim::Print;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Trades are written as they come, so speed matters more than size.
+const COMPRESSION_LEVEL: i32 = 3;
+const HEADER: &str = "tick,price,food,buyer,seller";
+
+pub struct Tape {
+    path: PathBuf,
+    encoder: zstd::Encoder<BufWriter<File>>,
+    /// Ticks between clearings recorded.
+    every: u64,
+    trades: usize,
+}
+
+// The encoder has nothing worth showing.
+impl fmt::Debug for Tape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tape")
+            .field("path", &self.path)
+            .field("every", &self.every)
+            .field("trades", &self.trades)
+            .finish()
+    }
+}
+
+impl Tape {
+    /// Creates the file and writes the header, so that a bad path fails before the sim is asked
+    /// to record anything.
+    pub fn create(path: &Path, every: u64) -> Result<Self, Box<dyn Error>> {
+        let mut encoder =
+            zstd::Encoder::new(BufWriter::new(File::create(path)?), COMPRESSION_LEVEL)?;
+        writeln!(encoder, "{}", HEADER)?;
+        Ok(Self {
+            path: path.to_owned(),
+            encoder,
+            every: every.max(1),
+            trades: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Trades written so far.
+    pub fn trades(&self) -> usize {
+        self.trades
+    }
+
+    /// Whether the clearing of a tick is recorded.
+    pub fn records(&self, tick: u64) -> bool {
+        tick % self.every == 0
+    }
+
+    /// Writes the trades of the clearing of a tick.
+    pub fn write(&mut self, tick: u64, prints: &[Print]) -> Result<(), Box<dyn Error>> {
+        let lineage = |lineage: Option<u64>| lineage.map(|l| l.to_string()).unwrap_or_default();
+        for print in prints {
+            writeln!(
+                self.encoder,
+                "{},{},{},{},{}",
+                tick,
+                print.price,
+                print.food,
+                lineage(print.buyer),
+                lineage(print.seller)
+            )?;
+        }
+        self.trades += prints.len();
+        Ok(())
+    }
+
+    /// Ends the zstd frame and flushes the file, without which the end of the tape is lost.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        self.encoder.finish()?.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+//! Saves a frame of the whole world every so many ticks as a numbered PNG sequence, which can be
+//! assembled into a timelapse afterwards, such as with
+//! `ffmpeg -i frame_%06d.png timelapse.mp4`.
+//!
+//! Frames are drawn from the same view the grid shows rather than captured from the screen, so a
+//! timelapse records the whole world at any zoom and can be made without a window at all.
+
+use crate::raster::Raster;
+use crate::sim::{Sim, ViewSettings};
+use std::error::Error;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+
+/// Where and how often frames are saved.
+#[derive(Clone, Debug)]
+pub struct Timelapse {
+    dir: PathBuf,
+    /// Ticks between frames.
+    every: usize,
+    /// Each side of a frame is this many times smaller than the world, averaging the tiles.
+    downscale: usize,
+    /// Ticks since the timelapse started.
+    ticks: usize,
+    frames: usize,
+}
+
+impl Timelapse {
+    pub fn new(dir: PathBuf, every: usize, downscale: usize) -> Self {
+        Self {
+            dir,
+            every: every.max(1),
+            downscale: downscale.max(1),
+            ticks: 0,
+            frames: 0,
+        }
+    }
+
+    /// Counts a tick, saving a frame of the world if one is due.
+    pub fn tick(&mut self, sim: &Sim, settings: &ViewSettings) -> Result<(), Box<dyn Error>> {
+        self.ticks += 1;
+        if self.ticks % self.every != 0 {
+            return Ok(());
+        }
+        self.frames += 1;
+        if self.frames == 1 {
+            create_dir_all(&self.dir)?;
+        }
+        let path = self.dir.join(format!("frame_{:06}.png", self.frames));
+        Raster::downscaled(&sim.view(0, settings), self.downscale).save(&path)
+    }
+
+    /// Frames saved so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+/// Runs a world for a number of ticks without a window, saving a timelapse of it.
+pub fn headless(
+    mut sim: Sim,
+    mut timelapse: Timelapse,
+    ticks: usize,
+) -> Result<(), Box<dyn Error>> {
+    let settings = ViewSettings::default();
+    for _ in 0..ticks {
+        sim = sim.tick();
+        timelapse.tick(&sim, &settings).map_err(|e| {
+            format!(
+                "failed to save a frame to {}: {}",
+                timelapse.dir.display(),
+                e
+            )
+        })?;
+    }
+    println!(
+        "saved {} frames to {}",
+        timelapse.frames(),
+        timelapse.dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Reloads a file, such as a scenario or a theme, whenever its modification time changes.
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    missing: bool,
+}
+
+impl FileWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+            missing: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns what `load` reads from the file if it changed since the last poll.
+    pub fn poll<T>(
+        &mut self,
+        load: impl FnOnce(&Path) -> Result<T, Box<dyn Error>>,
+    ) -> Result<Option<T>, Box<dyn Error>> {
+        let modified = match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            // Only report a missing file once until it reappears.
+            Err(_) if self.missing => return Ok(None),
+            Err(e) => {
+                self.missing = true;
+                self.modified = None;
+                return Err(e.into());
+            }
+        };
+        self.missing = false;
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+        // Record the time even if parsing fails so a broken file is only reported once.
+        self.modified = Some(modified);
+        load(&self.path).map(Some)
+    }
+}